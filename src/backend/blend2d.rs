@@ -5,8 +5,11 @@
  * Copyright (c) 2025 M.H.Fan, All rights reserved.             *
  ****************************************************************/
 
-use crate::core::{CompositeContext, helpers::{Vec2D, RGBA}, render::RenderContext,
-    schema::{FillRule, LineJoin, LineCap, MatteMode, MaskMode, VisualLayer},
+use crate::core::{CompositeContext, LayerEffect, StyleStage,
+    blend_pixels, apply_effects, apply_styles,
+    helpers::{Vec2D, RGBA},
+    render::RenderContext, schema::{FillRule, LineJoin, LineCap, MatteMode, MaskMode,
+    BlendMode, VisualLayer},
     style::{StyleConv, MatrixConv, TM2DwO, FSOpts},
     pathm::{PathBuilder, PathFactory, BezPath},
 };
@@ -137,9 +140,101 @@ impl CompositeContext for BLContext {
         let result = self.blit_image_d(BLPoint::new(), &content, &area);
         result.and(self.restore())
     }
+
+    fn present_blended(&mut self, content: Self::Image,
+        mode: BlendMode) -> Result<(), Self::Error> {
+        let op = match mode {
+            BlendMode::Normal     => BL_COMP_OP_SRC_OVER,
+            BlendMode::Multiply   => BL_COMP_OP_MULTIPLY,
+            BlendMode::Screen     => BL_COMP_OP_SCREEN,
+            BlendMode::Overlay    => BL_COMP_OP_OVERLAY,
+            BlendMode::Darken     => BL_COMP_OP_DARKEN,
+            BlendMode::Lighten    => BL_COMP_OP_LIGHTEN,
+            BlendMode::ColorDodge => BL_COMP_OP_COLOR_DODGE,
+            BlendMode::ColorBurn  => BL_COMP_OP_COLOR_BURN,
+            BlendMode::HardLight  => BL_COMP_OP_HARD_LIGHT,
+            BlendMode::SoftLight  => BL_COMP_OP_SOFT_LIGHT,
+            BlendMode::Difference => BL_COMP_OP_DIFFERENCE,
+            BlendMode::Exclusion  => BL_COMP_OP_EXCLUSION,
+            BlendMode::Add        => BL_COMP_OP_PLUS,
+            // Blend2D has no non-separable or hard-mix operators
+            BlendMode::Hue | BlendMode::Saturation | BlendMode::Color |
+            BlendMode::Luminosity | BlendMode::HardMix => return blend_on_cpu(self, content, mode),
+        };
+        let area: BLRectI = (0, 0, content.width(), content.height()).into();
+        self.save()?;   self.set_comp_op(op);
+        self.reset_transform(None);     self.set_global_alpha(1.);
+        let result = self.blit_image_d(BLPoint::new(), &content, &area);
+        result.and(self.restore())
+    }
+    fn apply_effects(&mut self, content: Self::Image,
+        effects: &[LayerEffect]) -> Result<Self::Image, Self::Error> {
+        process_pixels(content, |pixels, width, height, stride|
+            apply_effects(pixels, width, height, stride, true, effects))
+    }
+    fn apply_styles(&mut self, content: Self::Image,
+        styles: &[StyleStage]) -> Result<Self::Image, Self::Error> {
+        process_pixels(content, |pixels, width, height, stride|
+            apply_styles(pixels, width, height, stride, true, styles))
+    }
     fn discard(&mut self, _: Self::Image) {}
 }
 
+/// Runs a CPU stage over a copy of the PRGB32 pixels and copies the result back.
+fn process_pixels(content: BLImage, stage: impl FnOnce(&mut [u8],
+    usize, usize, usize)) -> Result<BLImage, BLErr> {
+    let (width, height) = (content.width() as usize, content.height() as usize);
+    let stride = content.stride() as usize;
+    let Some(mut pixels) = content.pixels().map(<[u8]>::to_vec)
+        else { return Ok(content) };
+    stage(&mut pixels, width, height, stride);
+
+    // SAFETY: `pixels` outlives the temporary image and synchronous blit.
+    let image = unsafe { BLImage::from_buffer(width as _, height as _,
+        BLFormat::BL_FORMAT_PRGB32, &mut pixels, stride as _)? };
+    let mut ctx = BLContext::from_image(content)?;
+    ctx.set_comp_op(BL_COMP_OP_SRC_COPY);
+    ctx.blit_image_d(BLPoint::new(), &image, &(0, 0, width as _, height as _).into())?;
+    ctx.end()
+}
+
+/// Flushes the target to blend its PRGB32 pixels with the layer in memory,
+/// then copies the blended backdrop back; the target stays attached throughout.
+fn blend_on_cpu(ctx: &mut BLContext, content: BLImage, mode: BlendMode) -> Result<(), BLErr> {
+    let state = ctx.save_state()?;
+    // Capture `?` errors instead of returning early, so the state is always restored.
+    let result = (|| {
+        ctx.flush()?;
+        let size  = ctx.get_target_size();
+        let (width, height) = (size.width() as usize, size.height() as usize);
+        let mut blended = vec![0; width * height * 4];
+        let readable = {
+            let target = ctx.get_target_image();
+            if let (Some(dst), Some(src)) = (target.pixels(), content.pixels()) {
+                let (dst_stride, src_stride) =
+                    (target.stride() as usize, content.stride() as usize);
+                for (row, out) in blended.chunks_exact_mut(width * 4).enumerate() {
+                    out.copy_from_slice(&dst[row * dst_stride..][..width * 4]);
+                    blend_pixels(out, &src[row * src_stride..][..width * 4], mode, true);
+                }   true
+            } else { false }
+        };
+
+        let area: BLRectI = (0, 0, width as _, height as _).into();
+        if readable {
+            // SAFETY: `blended` outlives the temporary image and synchronous blit.
+            let image = unsafe { BLImage::from_buffer(width as _, height as _,
+                BLFormat::BL_FORMAT_PRGB32, &mut blended, (width * 4) as _)? };
+            ctx.set_comp_op(BL_COMP_OP_SRC_COPY);
+            ctx.blit_image_d(BLPoint::new(), &image,   &area)
+        } else {
+            ctx.set_comp_op(BL_COMP_OP_SRC_OVER);
+            ctx.blit_image_d(BLPoint::new(), &content, &area)
+        }
+    })();
+    ctx.set_comp_op(BL_COMP_OP_SRC_OVER);   result.and(ctx.restore_state(state))
+}
+
 impl RenderContext for BLContext {
     type State = (Self::TM2D, f64);
     type TM2D = BLMatrix2D;
@@ -295,7 +390,12 @@ impl MatrixConv for BLMatrix2D {
     fn translate(&mut self, pos: Vec2D) { self.post_translate(pos.into()) }
     fn skew_x(&mut self, sk: f32) { self.post_skew((sk as _, 0.)) }
     fn scale(&mut self, sl: Vec2D) { self.post_scale((sl.x as _, sl.y as _)) }
-    fn premul(&mut self, tm: &Self) { self.transform(tm) }
+    fn premul(&mut self, tm: &Self) {
+        let mut result = Self::new(tm.get_values());
+        result.transform(self);     *self = result
+    }
+    fn coeffs(&self) -> [f64; 6] { self.get_values() }
+    fn from_coeffs(coeffs: [f64; 6]) -> Self { Self::new(coeffs) }
 }
 
 impl StyleConv for BLStyle {
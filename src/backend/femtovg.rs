@@ -6,13 +6,15 @@
  ****************************************************************/
 
 use std::collections::HashMap;
-use crate::core::{CompositeContext, helpers::{Vec2D, RGBA},
+use crate::core::{CompositeContext, LayerEffect, StyleStage,
+    blend_pixels, apply_effects, apply_styles,
+    helpers::{Vec2D, RGBA},
     pathm::{PathBuilder, BezPath, PathFactory},
-    schema::{VisualLayer, MatteMode, MaskMode, FillRule, LineJoin, LineCap},
+    schema::{VisualLayer, MatteMode, MaskMode, BlendMode, FillRule, LineJoin, LineCap},
     style::{StyleConv, MatrixConv, TM2DwO, FSOpts}, render::RenderContext
 };
 use femtovg::{PixelFormat, ImageId, ImageFlags, RenderTarget, Color as VGColor,
-    CompositeOperation as CompOp, renderer::SurfacelessRenderer};
+    CompositeOperation as CompOp, BlendFactor, renderer::SurfacelessRenderer};
 const CLEAR_COLOR: VGColor = VGColor::rgbaf(0., 0., 0., 0.);
 const  MASK_COLOR: VGColor = VGColor::rgbaf(1., 1., 1., 1.);
 
@@ -98,7 +100,9 @@ impl MatrixConv for femtovg::Transform2D {
     fn rotate(&mut self, angle: f32) { self.rotate(angle) }
     fn translate(&mut self, pos: Vec2D) { self.translate(pos.x, pos.y) }
     fn scale(&mut self, sl: Vec2D) { self.scale(sl.x, sl.y) }
-    fn premul(&mut self, tm: &Self) { self.premultiply(tm) }
+    fn premul(&mut self, tm: &Self) { *self *= *tm }
+    fn coeffs(&self) -> [f64; 6] { self.0.map(|v| v as _) }
+    fn from_coeffs(coeffs: [f64; 6]) -> Self { Self(coeffs.map(|v| v as _)) }
 }
 
 impl StyleConv for femtovg::Paint {
@@ -350,9 +354,97 @@ impl<T: SurfacelessRenderer> CompositeContext for FemtovgContext<'_, T> {
         self.flush(); self.restore();
         self.delete_image(image);   Ok(())
     }
+
+    fn present_blended(&mut self, image: Self::Image,
+        mode: BlendMode) -> Result<(), Self::Error> {
+        let (w, h) = (self.width(), self.height());
+        self.save(); self.reset_transform(); self.set_global_alpha(1.);
+        // only these are exact with fixed-function blending on premultiplied pixels
+        match mode {
+            BlendMode::Normal => self.global_composite_operation(CompOp::SourceOver),
+            BlendMode::Screen => self.global_composite_blend_func(
+                BlendFactor::One, BlendFactor::OneMinusSrcColor),
+            BlendMode::Add    => self.global_composite_operation(CompOp::Lighter),
+            _ => { self.restore();  return self.blend_on_cpu(image, mode) }
+        }
+        self.fill_path(&full_path(w, h), &Self::VGStyle::image(
+            image, 0., 0., w as _, h as _, 0., 1.));
+        self.flush(); self.restore();
+        self.delete_image(image);   Ok(())
+    }
+    fn apply_effects(&mut self, image: Self::Image,
+        effects: &[LayerEffect]) -> Result<Self::Image, Self::Error> {
+        self.process_pixels(image, |pixels, w, h, stride|
+            apply_effects(pixels, w, h, stride, false, effects))
+    }
+    fn apply_styles(&mut self, image: Self::Image,
+        styles: &[StyleStage]) -> Result<Self::Image, Self::Error> {
+        self.process_pixels(image, |pixels, w, h, stride|
+            apply_styles(pixels, w, h, stride, false, styles))
+    }
     fn discard(&mut self, image: Self::Image) { self.flush(); self.delete_image(image); }
 }
 
+impl<T: SurfacelessRenderer> FemtovgContext<'_, T> {
+    /// Reads the layer and its backdrop back, blends them exactly on the CPU,
+    /// and copies the result over the current target.
+    fn blend_on_cpu(&mut self, image: ImageId,
+        mode: BlendMode) -> Result<(), femtovg::ErrorKind> {
+        let parent = self.target;
+        let result = (|| {
+            self.flush();   let mut backdrop = self.screenshot()?;
+            self.set_target(RenderTarget::Image(image));
+            self.flush();   let layer = self.screenshot()?;
+            self.set_target(parent);
+
+            let (dst, src) = (backdrop.buf_mut(), layer.buf());
+            for (dst, src) in dst.iter_mut().zip(src) {
+                let mut pixel = [dst.r, dst.g, dst.b, dst.a];
+                blend_pixels(&mut pixel, &[src.r, src.g, src.b, src.a], mode, false);
+                *dst = pixel.into();
+            }   self.copy_pixels(backdrop.as_ref())
+        })();
+
+        self.set_target(parent);    self.flush();
+        self.delete_image(image);   result
+    }
+
+    /// Reads the image back for a CPU stage over its RGBA bytes, then writes it back.
+    fn process_pixels(&mut self, image: ImageId, stage: impl FnOnce(&mut [u8],
+        usize, usize, usize)) -> Result<ImageId, femtovg::ErrorKind> {
+        let parent = self.target;
+        let result = (|| {
+            self.set_target(RenderTarget::Image(image));
+            self.flush();   let mut layer = self.screenshot()?;
+            let (w, h, stride) = (layer.width(), layer.height(), layer.stride());
+            let mut pixels = layer.buf().iter()
+                .flat_map(|px| [px.r, px.g, px.b, px.a]).collect::<Vec<_>>();
+            stage(&mut pixels, w, h, stride * 4);
+            for (dst, src) in layer.buf_mut().iter_mut().zip(pixels.chunks_exact(4)) {
+                *dst = [src[0], src[1], src[2], src[3]].into();
+            }   self.copy_pixels(layer.as_ref())
+        })();
+
+        self.set_target(parent);    self.flush();
+        match result { Ok(_) => Ok(image),
+            Err(error) => { self.delete_image(image); Err(error) }
+        }
+    }
+
+    /// Replaces the current target with premultiplied pixels read back earlier.
+    fn copy_pixels<'a>(&mut self,
+        pixels: impl Into<femtovg::ImageSource<'a>>) -> Result<(), femtovg::ErrorKind> {
+        let (w, h) = (self.width(), self.height());
+        let copied = self.create_image(pixels, ImageFlags::PREMULTIPLIED)?;
+        self.save(); self.reset_transform(); self.set_global_alpha(1.);
+        self.global_composite_operation(CompOp::Copy);
+        self.fill_path(&full_path(w, h), &femtovg::Paint::image(
+            copied, 0., 0., w as _, h as _, 0., 1.));
+        self.flush(); self.restore();
+        self.delete_image(copied);  Ok(())
+    }
+}
+
 fn full_path(w: u32, h: u32) -> femtovg::Path {
     let mut path = femtovg::Path::new();
     path.rect(0., 0., w as _, h as _); path
@@ -1,2 +1,5 @@
 pub mod femtovg;
+pub mod svg;
+pub mod svg_anim;
 #[cfg(feature = "b2d")] pub mod blend2d;
+#[cfg(feature = "tiny-skia")] pub mod tiny_skia;
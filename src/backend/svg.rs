@@ -0,0 +1,496 @@
+/****************************************************************
+ * $ID: svg.rs  	Sun 18 Oct 2026 18:42:26+0800               *
+ *                                                              *
+ * Maintainer: 范美辉 (MeiHui FAN) <mhfan@ustc.edu>              *
+ * Copyright (c) 2026 M.H.Fan, All rights reserved.             *
+ ****************************************************************/
+
+//! Markup backend writing a static SVG document instead of rasterizing, every element
+//! carries its absolute transform, and offscreen images are fragments of markup.
+
+use std::{collections::HashMap, fmt::{self, Write}};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use crate::core::{CompositeContext,
+    helpers::{Vec2D, RGBA},
+    pathm::{PathBuilder, BezPath, PathFactory},
+    schema::{VisualLayer, MatteMode, MaskMode, BlendMode, FillRule, LineJoin, LineCap},
+    style::{StyleConv, TM2DwO, FSOpts}, render::{RenderContext, LottieRuntime}
+};
+
+#[derive(Clone)] pub enum SvgPaint {
+    Solid(RGBA),
+    Linear(Vec2D, Vec2D, Vec<(f32, RGBA)>),
+    /// Center, focal point, radius and color stops.
+    Radial(Vec2D, Vec2D, f32, Vec<(f32, RGBA)>),
+}
+
+impl StyleConv for SvgPaint {
+    fn solid_color(color: RGBA) -> Self { Self::Solid(color) }
+    fn linear_gradient(sp: Vec2D, ep: Vec2D, stops: &[(f32, RGBA)]) -> Self {
+        Self::Linear(sp, ep, stops.to_vec())
+    }
+    fn radial_gradient(cp: Vec2D, fp: Vec2D, radii: (f32, f32),
+            stops: &[(f32, RGBA)]) -> Self {
+        Self::Radial(cp, fp, radii.1, stops.to_vec())
+    }
+}
+
+/// Accumulates the body and `<defs>` of a document of `size`, see `finish`.
+pub struct SvgContext {
+    size: (u32, u32), transform: kurbo::Affine, opacity: f32,
+    pub(super) body: String, defs: String, next_id: u32,
+    gradients: HashMap<String, u32>,  // deduplicated by their markup
+}
+
+impl SvgContext {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self { size: (width, height), transform: kurbo::Affine::IDENTITY, opacity: 1.,
+            body: String::new(), defs: String::new(), next_id: 0, gradients: HashMap::new() }
+    }
+
+    /// The complete SVG document drawn so far.
+    pub fn finish(self) -> String {
+        let (w, h) = self.size;
+        let mut svg = format!(concat!(r#"<svg xmlns="http://www.w3.org/2000/svg" "#,
+            r#"width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#, "\n"), w = w, h = h);
+        if !self.defs.is_empty() { svg += "<defs>\n";  svg += &self.defs;  svg += "</defs>\n"; }
+        svg += &self.body;  svg += "</svg>\n";  svg
+    }
+
+    fn id(&mut self) -> u32 { self.next_id += 1;  self.next_id }
+
+    /// Defines a mask of the markup in user space over the whole canvas.
+    fn define_mask(&mut self, content: &str, alpha: bool) -> Result<u32, fmt::Error> {
+        let id = self.id();     let (w, h) = self.size;
+        writeln!(self.defs, r#"<mask id="m{id}" maskUnits="userSpaceOnUse" x="0" y="0" {}"#,
+            format_args!(r#"width="{w}" height="{h}"{}>"#,
+                if alpha { r#" style="mask-type:alpha""# } else { "" }))?;
+        self.defs += content;   self.defs += "</mask>\n";   Ok(id)
+    }
+
+    fn full_rect(&self, fill: &str) -> String {
+        format!(r#"<rect width="{}" height="{}" fill="{fill}"/>"#, self.size.0, self.size.1) + "\n"
+    }
+
+    /// Markup of `content` with luminance coverage inverted, by painting black over white
+    /// through a mask of it.
+    fn inverted(&mut self, content: &str, alpha: bool) -> Result<String, fmt::Error> {
+        let id = self.define_mask(content, alpha)?;
+        Ok(self.full_rect("#fff") + &format!(r#"<g mask="url(#m{id})">"#) + "\n" +
+            &self.full_rect("#000") + "</g>\n")
+    }
+
+    /// Id of the gradient definition of `paint`, shared by identical gradients.
+    fn gradient(&mut self, paint: &SvgPaint) -> Result<Option<u32>, fmt::Error> {
+        let mut key = String::new();    // the definition without its id
+        let (tag, stops) = match paint {
+            SvgPaint::Solid(_) => return Ok(None),
+            SvgPaint::Linear(sp, ep, stops) => {
+                write!(key, r#"gradientUnits="userSpaceOnUse" x1="{}" y1="{}" x2="{}" y2="{}">"#,
+                    Num(sp.x), Num(sp.y), Num(ep.x), Num(ep.y))?;   ("linearGradient", stops)
+            }
+            SvgPaint::Radial(cp, fp, radius, stops) => {
+                write!(key, r#"gradientUnits="userSpaceOnUse" cx="{}" cy="{}" r="{}" "#,
+                    Num(cp.x), Num(cp.y), Num(*radius))?;
+                write!(key, r#"fx="{}" fy="{}">"#, Num(fp.x), Num(fp.y))?;
+                ("radialGradient", stops)
+            }
+        };
+        key += "\n";
+        for (offset, color) in stops {
+            write!(key, r#"  <stop offset="{}" stop-color="{}""#, Num(*offset), Hex(*color))?;
+            if color.a < 255 { write!(key, r#" stop-opacity="{}""#, Num(color.a as f32 / 255.))?; }
+            key += "/>\n";
+        }
+
+        if let Some(&id) = self.gradients.get(&key) { return Ok(Some(id)) }
+        let id = self.id();
+        writeln!(self.defs, r#"<{tag} id="g{id}" {key}</{tag}>"#)?;
+        self.gradients.insert(key, id);     Ok(Some(id))
+    }
+
+    /// Paint attribute value and its opacity if not opaque.
+    fn paint(&mut self, paint: &SvgPaint) -> Result<(String, Option<f32>), fmt::Error> {
+        Ok(match (paint, self.gradient(paint)?) {
+            (SvgPaint::Solid(color), _) => (Hex(*color).to_string(),
+                (color.a < 255).then_some(color.a as f32 / 255.)),
+            (_, Some(id)) => (format!("url(#g{id})"), None),
+            (_, None) => unreachable!(),
+        })
+    }
+
+    /// Presentation attributes of the paint and fill/stroke options.
+    pub(super) fn style_attrs(&mut self,
+        style: &(SvgPaint, FSOpts)) -> Result<String, fmt::Error> {
+        let (paint, alpha) = self.paint(&style.0)?;
+        let mut out = String::new();
+        match &style.1 {
+            FSOpts::Fill(rule) => {
+                write!(out, r#" fill="{paint}""#)?;
+                if let Some(alpha) = alpha { write!(out, r#" fill-opacity="{}""#, Num(alpha))?; }
+                if matches!(rule, FillRule::EvenOdd) { out += r#" fill-rule="evenodd""#; }
+            }
+            FSOpts::Stroke { width, limit, join, cap, dash } => {
+                write!(out, r#" fill="none" stroke="{paint}" stroke-width="{}""#, Num(*width))?;
+                if let Some(alpha) = alpha {
+                    write!(out, r#" stroke-opacity="{}""#, Num(alpha))?;
+                }
+                match join {
+                    LineJoin::Miter => if *limit != 4. {
+                        write!(out, r#" stroke-miterlimit="{}""#, Num(*limit))?;
+                    }
+                    LineJoin::Round => out += r#" stroke-linejoin="round""#,
+                    LineJoin::Bevel => out += r#" stroke-linejoin="bevel""#,
+                }
+                match cap {
+                    LineCap::Butt   => (),
+                    LineCap::Round  => out += r#" stroke-linecap="round""#,
+                    LineCap::Square => out += r#" stroke-linecap="square""#,
+                }
+                if !dash.1.is_empty() {
+                    out += r#" stroke-dasharray=""#;
+                    for (index, length) in dash.1.iter().enumerate() {
+                        if 0 < index { out.push(' ') }  write!(out, "{}", Num(*length))?;
+                    }   out += "\"";
+                    if dash.0 != 0. { write!(out, r#" stroke-dashoffset="{}""#, Num(dash.0))?; }
+                }
+            }
+        }   Ok(out)
+    }
+}
+
+/// A number rounded to 3 decimals without trailing zeros, for stable and compact markup.
+pub(super) struct Num<T>(pub T);
+impl<T: Into<f64> + Copy> fmt::Display for Num<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let value = (self.0.into() * 1000.).round() / 1000.;
+        let text = format!("{:.3}", if value == 0. { 0. } else { value });
+        f.write_str(text.trim_end_matches('0').trim_end_matches('.'))
+    }
+}
+
+pub(super) struct Hex(pub RGBA);
+impl fmt::Display for Hex {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02x}{:02x}{:02x}", self.0.r, self.0.g, self.0.b)
+    }
+}
+
+pub(super) fn write_path(out: &mut String, path: &BezPath) -> fmt::Result {
+    use kurbo::PathEl::*;
+    let pt = |p: kurbo::Point| format!("{} {}", Num(p.x), Num(p.y));
+    for el in path.elements() { match *el {
+        MoveTo(p) => write!(out, "M{}", pt(p))?,
+        LineTo(p) => write!(out, "L{}", pt(p))?,
+        QuadTo(c, p) => write!(out, "Q{} {}", pt(c), pt(p))?,
+        CurveTo(c1, c2, p) => write!(out, "C{} {} {}", pt(c1), pt(c2), pt(p))?,
+        ClosePath => out.push('Z'),
+    } }     Ok(())
+}
+
+pub(super) fn write_transform(out: &mut String, tm: &kurbo::Affine) -> fmt::Result {
+    if *tm == kurbo::Affine::IDENTITY { return Ok(()) }
+    let [a, b, c, d, e, f] = tm.as_coeffs().map(Num);
+    write!(out, r#" transform="matrix({a} {b} {c} {d} {e} {f})""#)
+}
+
+/// Sniffs the MIME type of the image data for its data URI.
+fn mime_type(image: &[u8]) -> &'static str {
+    match image {
+        [0x89, b'P', b'N', b'G', ..] => "image/png",
+        [0xff, 0xd8, 0xff, ..] => "image/jpeg",
+        [b'G', b'I', b'F', b'8', ..] => "image/gif",
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => "image/webp",
+        _ if image.trim_ascii_start().starts_with(b"<") => "image/svg+xml",
+        _ => "application/octet-stream",
+    }
+}
+
+impl RenderContext for SvgContext {
+    type State = (kurbo::Affine, f32);
+    type TM2D = kurbo::Affine;
+    type VGStyle = SvgPaint;
+    type VGPath  = BezPath;
+    type Error = fmt::Error;
+
+    fn get_size(&self) -> (u32, u32) { self.size }
+    /// Clearing the whole canvas drops the markup drawn so far.
+    fn clear_rect_with(&mut self, x: u32, y: u32, w: u32, h: u32,
+        color: RGBA) -> Result<(), Self::Error> {
+        if (x, y) == (0, 0) && self.size.0 <= w && self.size.1 <= h { self.body.clear() }
+        if color.a == 0 { return Ok(()) }
+        write!(self.body, r#"<rect x="{x}" y="{y}" width="{w}" height="{h}" fill="{}""#,
+            Hex(color))?;
+        if color.a < 255 {
+            write!(self.body, r#" fill-opacity="{}""#, Num(color.a as f32 / 255.))?;
+        }   self.body += "/>\n";    Ok(())
+    }
+    fn save_state(&mut self) -> Result<Self::State, Self::Error> {
+        Ok((self.transform, self.opacity))
+    }
+    fn restore_state(&mut self, (transform, opacity): Self::State) -> Result<(), Self::Error> {
+        self.transform = transform;     self.opacity = opacity;     Ok(())
+    }
+    fn apply_transform(&mut self, trfm: &Self::TM2D,
+        opacity: Option<f32>) -> Result<(), Self::Error> {
+        if let Some(opacity) = opacity { self.opacity = opacity.clamp(0., 1.) }
+        self.transform = *trfm;     Ok(())
+    }
+
+    fn fill_stroke(&mut self, path: &Self::VGPath, relative: Option<&Self::TM2D>,
+        style: &(Self::VGStyle, FSOpts)) -> Result<(), Self::Error> {
+        let transformed = relative.map(|transform| *transform * path.clone());
+        let path = transformed.as_ref().unwrap_or(path);
+        if path.elements().is_empty() { return Ok(()) }
+
+        let mut out = String::from(r#"<path d=""#);
+        write_path(&mut out, path)?;    out += "\"";
+        out += &self.style_attrs(style)?;
+        write_transform(&mut out, &self.transform)?;
+        if self.opacity < 1. { write!(out, r#" opacity="{}""#, Num(self.opacity))?; }
+        self.body += &out;  self.body += "/>\n";    Ok(())
+    }
+
+    fn draw_image(&mut self, image: &[u8],
+        width: f32, height: f32) -> Result<(), Self::Error> {
+        write!(self.body, r#"<image width="{}" height="{}" preserveAspectRatio="none""#,
+            Num(width), Num(height))?;
+        let mut out = String::new();    write_transform(&mut out, &self.transform)?;
+        self.body += &out;
+        if self.opacity < 1. { write!(self.body, r#" opacity="{}""#, Num(self.opacity))?; }
+        write!(self.body, r#" href="data:{};base64,{}"/>"#, mime_type(image),
+            STANDARD.encode(image))?;   self.body += "\n";  Ok(())
+    }
+}
+
+impl CompositeContext for SvgContext {
+    type Offscreen = String;
+    type Image = String;
+
+    fn begin_offscreen(&mut self) -> Result<Self::Offscreen, Self::Error> {
+        Ok(core::mem::take(&mut self.body))
+    }
+    fn abort_offscreen(&mut self, target: Self::Offscreen) { self.body = target }
+    fn end_offscreen(&mut self, target: Self::Offscreen) -> Result<Self::Image, Self::Error> {
+        Ok(core::mem::replace(&mut self.body, target))
+    }
+
+    /// Combines the mask shapes as luminance markup, mirroring the compositing operators
+    /// of the other backends; `Lighten` and `Darken` act like `Add` and `Intersect`,
+    /// and `Difference` is exact for fully covered or uncovered pixels only.
+    fn apply_masks(&mut self, content: Self::Image, layer: &VisualLayer,
+        transform: &TM2DwO<Self::TM2D>, frame: f32) -> Result<Self::Image, Self::Error> {
+        let (mut accum, mut initialized) = (String::new(), false);
+        for item in &layer.masks {
+            if matches!(item.mode, MaskMode::None) { continue }
+            let mut path: BezPath = item.shape.to_path(frame);
+            if let Some(expand) = &item.expand {
+                path.offset_path(expand.get_value(frame), LineJoin::Round, 4.);
+            }
+            let mut shape = String::from(r#"<path d=""#);
+            write_path(&mut shape, &path)?;     shape += "\"";
+            write_transform(&mut shape, &transform.0)?;
+            // the coverage of the (inverted) shape painted in `fill` at `opacity`
+            let coverage = |this: &mut Self, fill: &str, opacity: f32| {
+                let opacity = if opacity < 1. {
+                    format!(r#" opacity="{}""#, Num(opacity)) } else { String::new() };
+                if !item.inv {
+                    return Ok(format!(r#"{shape} fill="{fill}"{opacity}/>"#) + "\n")
+                }
+                let id = this.define_mask(&(this.full_rect("#fff") +
+                    &format!(r##"{shape} fill="#000"/>"##) + "\n"), false)?;
+                Ok::<_, fmt::Error>(format!(r#"<rect width="{}" height="{}" fill="{fill}"{}"#,
+                    this.size.0, this.size.1, opacity) +
+                    &format!(r#" mask="url(#m{id})"/>"#) + "\n")
+            };
+
+            if !initialized && matches!(item.mode,
+                MaskMode::Subtract | MaskMode::Intersect | MaskMode::Darken) {
+                accum = self.full_rect("#fff");
+            }
+            let opacity = item.opacity.as_ref().map_or(1.,
+                |opacity| opacity.get_value(frame) / 100.).clamp(0., 1.);
+            match item.mode {
+                MaskMode::Add | MaskMode::Lighten => accum += &coverage(self, "#fff", opacity)?,
+                MaskMode::Subtract => accum += &coverage(self, "#000", opacity)?,
+                MaskMode::Intersect | MaskMode::Darken => {     // scaled by 1 - o + o * s
+                    let level = (255. * (1. - opacity)).round() as u8;
+                    let gray = Hex(RGBA::new_u8(level, level, level, 255)).to_string();
+                    let covered = coverage(self, "#fff", 1.)?;
+                    let id = self.define_mask(&(self.full_rect(&gray) + &covered), false)?;
+                    accum = format!(r#"<g mask="url(#m{id})">"#) + "\n" + &accum + "</g>\n";
+                }
+                MaskMode::Difference => {
+                    let outside = self.full_rect("#fff") + &coverage(self, "#000", opacity)?;
+                    let outside = self.define_mask(&outside, false)?;
+                    let uncovered = self.inverted(&accum, false)?;
+                    let uncovered = self.define_mask(&uncovered, false)?;
+                    accum = format!(r#"<g mask="url(#m{outside})">"#) + "\n" + &accum +
+                        &format!(r#"</g>{}<g mask="url(#m{uncovered})">"#, "\n") + "\n" +
+                        &coverage(self, "#fff", opacity)? + "</g>\n";
+                }
+                MaskMode::None => unreachable!(),
+            }   initialized = true;
+        }
+
+        if !initialized { return Ok(content) }
+        let id = self.define_mask(&accum, false)?;
+        Ok(format!(r#"<g mask="url(#m{id})">"#) + "\n" + &content + "</g>\n")
+    }
+
+    fn apply_matte(&mut self, content: Self::Image, matte: Self::Image,
+        mode: MatteMode) -> Result<Self::Image, Self::Error> {
+        let alpha = matches!(mode, MatteMode::Alpha | MatteMode::InvertedAlpha);
+        let id = match mode {
+            MatteMode::Normal => return Ok(content),
+            MatteMode::Alpha | MatteMode::Luma => self.define_mask(&matte, alpha)?,
+            MatteMode::InvertedAlpha | MatteMode::InvertedLuma => {
+                let inverted = self.inverted(&matte, alpha)?;
+                self.define_mask(&inverted, false)?
+            }
+        };  Ok(format!(r#"<g mask="url(#m{id})">"#) + "\n" + &content + "</g>\n")
+    }
+
+    fn present(&mut self, image: Self::Image) -> Result<(), Self::Error> {
+        self.body += &image;    Ok(())
+    }
+
+    fn present_blended(&mut self, image: Self::Image,
+        mode: BlendMode) -> Result<(), Self::Error> {
+        let name = match mode {
+            BlendMode::Multiply   => "multiply",
+            BlendMode::Screen     => "screen",
+            BlendMode::Overlay    => "overlay",
+            BlendMode::Darken     => "darken",
+            BlendMode::Lighten    => "lighten",
+            BlendMode::ColorDodge => "color-dodge",
+            BlendMode::ColorBurn  => "color-burn",
+            BlendMode::HardLight  => "hard-light",
+            BlendMode::SoftLight  => "soft-light",
+            BlendMode::Difference => "difference",
+            BlendMode::Exclusion  => "exclusion",
+            BlendMode::Hue        => "hue",
+            BlendMode::Saturation => "saturation",
+            BlendMode::Color      => "color",
+            BlendMode::Luminosity => "luminosity",
+            BlendMode::Add        => "plus-lighter",
+            // CSS has no hard-mix blending
+            BlendMode::Normal | BlendMode::HardMix => return self.present(image),
+        };
+        writeln!(self.body, r#"<g style="mix-blend-mode:{name}">"#)?;
+        self.body += &image;    self.body += "</g>\n";  Ok(())
+    }
+    fn discard(&mut self, _: Self::Image) {}
+
+    /// Keeps precomps as groups with their layer's `id`, `class` and name.
+    fn begin_group(&mut self, layer: &VisualLayer) -> Result<(), Self::Error> {
+        self.body += "<g";
+        if let Some(prop) = layer.svg_prop() {
+            if !prop.ln.is_empty() { write!(self.body, r#" id="{}""#, Escaped(&prop.ln))?; }
+            if !prop.cl.is_empty() { write!(self.body, r#" class="{}""#, Escaped(&prop.cl))?; }
+        }
+        if !layer.base.vo.nm.is_empty() {
+            write!(self.body, r#" data-name="{}""#, Escaped(&layer.base.vo.nm))?;
+        }   self.body += ">\n";     Ok(())
+    }
+    fn end_group(&mut self) -> Result<(), Self::Error> { self.body += "</g>\n";  Ok(()) }
+}
+
+impl LottieRuntime {
+    /// The SVG document of frame `fnth` at the composition size and view transform.
+    pub fn render_svg(&mut self, fnth: f32) -> String {
+        let animation = self.animation();
+        let mut svg = SvgContext::new(animation.w as _, animation.h as _);
+        self.render_frame(&mut svg, fnth, None).expect("formatting into a String");
+        svg.finish()
+    }
+}
+
+/// Text escaped for XML attribute values.
+pub(super) struct Escaped<'a>(pub &'a str);
+impl fmt::Display for Escaped<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for ch in self.0.chars() { match ch {
+            '&' => f.write_str("&amp;")?,   '<' => f.write_str("&lt;")?,
+            '>' => f.write_str("&gt;")?,    '"' => f.write_str("&quot;")?,
+            _ => f.write_char(ch)?,
+        } }     Ok(())
+    }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    fn render(json: &str) -> String {
+        LottieRuntime::from_reader(json.as_bytes()).unwrap().render_svg(0.)
+    }
+    const RECT: &str = r#"{"ty":"rc","p":{"a":0,"k":[5,5]},"s":{"a":0,"k":[10,10]},
+        "r":{"a":0,"k":0}}"#;
+
+    #[test] fn shapes_and_gradients_are_written_as_stable_markup() {
+        let svg = render(&format!(r#"{{"ip":0,"op":1,"fr":1,"w":20,"h":10,"layers":[
+            {{"ty":4,"ind":1,"ip":0,"op":1,"st":0,"ks":{{"p":{{"a":0,"k":[2,0]}},
+                "o":{{"a":0,"k":50}}}},"shapes":[{RECT},
+                {{"ty":"st","c":{{"a":0,"k":[1,0,0,1]}},"o":{{"a":0,"k":100}},
+                    "w":{{"a":0,"k":2}},"lc":2,"lj":1,"ml":4}},
+                {{"ty":"gf","t":1,"s":{{"a":0,"k":[0,0]}},"e":{{"a":0,"k":[10,0]}},
+                    "g":{{"p":2,"k":{{"a":0,"k":[0,0,0,0,1,1,1,1]}}}},"o":{{"a":0,"k":100}},
+                    "r":2}}]}}]}}"#));
+        assert_eq!(svg, concat!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="20" height="10" "#,
+            r#"viewBox="0 0 20 10">"#, "\n<defs>\n",
+            r#"<linearGradient id="g1" gradientUnits="userSpaceOnUse" "#,
+            r#"x1="0" y1="0" x2="10" y2="0">"#, "\n",
+            r##"  <stop offset="0" stop-color="#000000"/>"##, "\n",
+            r##"  <stop offset="1" stop-color="#ffffff"/>"##, "\n",
+            "</linearGradient>\n</defs>\n",
+            r#"<path d="M10 0L10 10L0 10L0 0Z" fill="url(#g1)" fill-rule="evenodd" "#,
+            r#"transform="matrix(1 0 0 1 2 0)" opacity="0.5"/>"#, "\n",
+            r##"<path d="M10 0L10 10L0 10L0 0Z" fill="none" stroke="#ff0000" "##,
+            r#"stroke-width="2" stroke-linecap="round" "#,
+            r#"transform="matrix(1 0 0 1 2 0)" opacity="0.5"/>"#, "\n</svg>\n"));
+    }
+
+    #[test] fn masks_mattes_and_precomps_nest_groups() {
+        let svg = render(&format!(r#"{{"ip":0,"op":1,"fr":1,"w":20,"h":20,
+            "assets":[{{"id":"comp","layers":[{{"ty":4,"ind":1,"ip":0,"op":1,"st":0,
+                "ks":{{}},"shapes":[{RECT},{{"ty":"fl","c":{{"a":0,"k":[0,0,1,1]}},
+                "o":{{"a":0,"k":100}}}}]}}]}}],
+            "layers":[
+            {{"ty":4,"ind":1,"ip":0,"op":1,"st":0,"td":1,"ks":{{}},"shapes":[{RECT},
+                {{"ty":"fl","c":{{"a":0,"k":[1,1,1,1]}},"o":{{"a":0,"k":100}}}}]}},
+            {{"ty":0,"ind":2,"ip":0,"op":1,"st":0,"tt":2,"refId":"comp","w":20,"h":20,
+                "nm":"Logo <1>","ln":"logo","ks":{{}},"hasMask":true,"masksProperties":[
+                {{"mode":"s","inv":true,"o":{{"a":0,"k":100}},"pt":{{"a":0,"k":{{"c":true,
+                "v":[[0,0],[4,0],[4,4]],"i":[[0,0],[0,0],[0,0]],"o":[[0,0],[0,0],[0,0]]}}}}}}]}}
+        ]}}"#));
+        // masked precomp group, then inverted by the alpha matte
+        assert!(svg.contains(r#"<g id="logo""#), "{svg}");
+        assert!(svg.contains(r#" data-name="Logo &lt;1&gt;">"#));
+        assert_eq!(svg.matches("<mask ").count(), 4);
+        assert!(svg.contains(concat!(r#"<mask id="m3" maskUnits="userSpaceOnUse" "#,
+            r#"x="0" y="0" width="20" height="20" style="mask-type:alpha">"#)));
+        let body = &svg[svg.find("</defs>").unwrap()..];
+        assert!(body.starts_with("</defs>\n<g mask=\"url(#m4)\">\n<g mask=\"url(#m2)\">\n"),
+            "{body}");
+        assert!(body.contains(r##"fill="#0000ff""##));
+        assert_eq!(body.matches("<g").count(), body.matches("</g>").count());
+    }
+
+    #[test] fn numbers_colors_and_images_are_formatted_for_markup() {
+        assert_eq!(Num(0.30000000000000004).to_string(), "0.3");
+        assert_eq!(Num(-0.0001f32).to_string(), "0");
+        assert_eq!(Num(12.).to_string(), "12");
+        assert_eq!(Escaped(r#"a&"b""#).to_string(), "a&amp;&quot;b&quot;");
+
+        let mut svg = SvgContext::new(4, 4);
+        svg.apply_transform(&kurbo::Affine::scale(2.), Some(0.25)).unwrap();
+        svg.draw_image(b"\x89PNG", 2., 2.).unwrap();
+        assert_eq!(svg.body, concat!(r#"<image width="2" height="2" preserveAspectRatio="none" "#,
+            r#"transform="matrix(2 0 0 2 0 0)" opacity="0.25" "#,
+            r#"href="data:image/png;base64,iVBORw=="/>"#, "\n"));
+        svg.clear_rect_with(0, 0, 4, 4, RGBA::new_u8(255, 255, 255, 128)).unwrap();
+        assert_eq!(svg.body, concat!(r##"<rect x="0" y="0" width="4" height="4" "##,
+            r##"fill="#ffffff" fill-opacity="0.502"/>"##, "\n"));
+    }
+}
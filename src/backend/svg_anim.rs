@@ -0,0 +1,729 @@
+/****************************************************************
+ * $ID: svg_anim.rs  	Sun 18 Oct 2026 21:05:47+0800           *
+ *                                                              *
+ * Maintainer: 范美辉 (MeiHui FAN) <mhfan@ustc.edu>              *
+ * Copyright (c) 2026 M.H.Fan, All rights reserved.             *
+ ****************************************************************/
+
+//! Self-animating SVG of the whole timeline: keyframes of transforms, opacity, colors
+//! and paths become SMIL `<animate>`, `<animateTransform>` and `<animateMotion>` elements
+//! eased by their bezier handles, and animations beyond that get baked frame by frame.
+
+use std::fmt::{self, Write};
+use kurbo::{Affine, ParamCurveArclen};
+use crate::core::{CompositeContext, helpers::{math::CubicBezierEasing, IntBool, Vec2D},
+    pathm::{BezPath, PathFactory}, render::LottieRuntime,
+    style::MatrixConv, schema::*,
+};
+use super::svg::{SvgContext, SvgPaint, Num, Hex, write_path, write_transform};
+
+/// SVG document of the animation, looping indefinitely.
+pub struct AnimatedSvg {
+    pub markup: String,
+    /// Feature that prevented converting the keyframes, if the frames were baked instead
+    pub baked: Option<&'static str>,
+}
+
+impl LottieRuntime {
+    /// Converts keyframes into SMIL animations, falling back to a baked SVG frame
+    /// for every composition frame if the animation needs more than that.
+    pub fn render_animated_svg(&mut self) -> AnimatedSvg {
+        let animation = self.animation();
+        let mut svg = SvgContext::new(animation.w as _, animation.h as _);
+        match Smil::new(animation, &mut svg).document(self.view_transform()) {
+            Ok(body) => { svg.body = body;
+                AnimatedSvg { markup: svg.finish(), baked: None }
+            }
+            Err(Unsupported(feature)) => {
+                let mut svg = SvgContext::new(animation.w as _, animation.h as _);
+                bake(self, &mut svg).expect("formatting into a String");
+                AnimatedSvg { markup: svg.finish(), baked: Some(feature) }
+            }
+        }
+    }
+}
+
+/// Shows each frame in its own group only for the duration of that frame.
+fn bake(runtime: &mut LottieRuntime, svg: &mut SvgContext) -> fmt::Result {
+    let timeline = Timeline::new(runtime.animation());
+    let (ip, op) = timeline.window;
+    let frames = (0..).map(|index| ip + index as f32).take_while(|&frame| frame < op);
+
+    for frame in frames.collect::<Vec<_>>() {
+        let target = svg.begin_offscreen()?;
+        if let Err(error) = runtime.render_frame(svg, frame, None) {
+            svg.abort_offscreen(target);    return Err(error)
+        }
+        let markup = svg.end_offscreen(target)?;
+        let (start, end) = (timeline.fraction(frame), timeline.fraction(frame + 1.).min(1.));
+        svg.body += "<g";   visibility(&mut svg.body, start, end, &timeline.dur)?;
+        svg.body += &markup;    svg.body += "</g>\n";
+    }   Ok(())
+}
+
+/// Static and discretely animated `visibility` showing the content within `[start, end)`
+/// of the normalized timeline, closing the start tag of the element.
+fn visibility(out: &mut String, start: f32, end: f32, dur: &str) -> fmt::Result {
+    if start <= 0. && 1. <= end { out.push_str(">\n");  return Ok(()) }
+    let (values, times) = match (0. < start, end < 1.) {
+        (true, true) => ("hidden;visible;hidden", format!("0;{};{}", Key(start), Key(end))),
+        (true, _) => ("hidden;visible", format!("0;{}", Key(start))),
+        _ => ("visible;hidden", format!("0;{}", Key(end))),
+    };
+    writeln!(out, r#" visibility="{}">"#, if 0. < start { "hidden" } else { "visible" })?;
+    writeln!(out, concat!(r#"<animate attributeName="visibility" dur="{}" "#,
+        r#"repeatCount="indefinite" calcMode="discrete" keyTimes="{}" values="{}"/>"#),
+        dur, times, values)
+}
+
+/// Normalized key time, finer than `Num` for long timelines.
+struct Key(f32);
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = format!("{:.5}", self.0.clamp(0., 1.));
+        f.write_str(text.trim_end_matches('0').trim_end_matches('.'))
+    }
+}
+
+/// Normalized time window of the composition and its duration attribute.
+struct Timeline { window: (f32, f32), dur: String }
+
+impl Timeline {
+    fn new(animation: &Animation) -> Self {
+        let dur = (animation.op - animation.ip).max(0.) / animation.fr.max(1.);
+        Self { window: (animation.ip, animation.op), dur: format!("{}s", Num(dur)) }
+    }
+
+    fn fraction(&self, frame: f32) -> f32 {
+        (frame - self.window.0) / (self.window.1 - self.window.0)
+    }
+
+    /// Writes the SMIL animation element if the values change, with `attrs` naming
+    /// the animated attribute and `key` the attribute listing the values.
+    fn animate(&self, out: &mut String, element: &str, attrs: &str, key: &str,
+        stops: &[Stop], values: &[String]) -> fmt::Result {
+        if values.iter().all(|value| *value == values[0]) { return Ok(()) }
+        write!(out, r#"<{element} {attrs} dur="{}" repeatCount="indefinite" "#, self.dur)?;
+        out.push_str(r#"calcMode="spline" keyTimes=""#);
+        for (index, stop) in stops.iter().enumerate() {
+            if 0 < index { out.push(';') }  write!(out, "{}", Key(self.fraction(stop.time)))?;
+        }
+        out.push_str(r#"" keySplines=""#);
+        for (index, stop) in stops[..stops.len() - 1].iter().enumerate() {
+            if 0 < index { out.push(';') }
+            let [x1, y1, x2, y2] = stop.spline.map(Num);    write!(out, "{x1} {y1} {x2} {y2}")?;
+        }   writeln!(out, r#"" {key}="{}"/>"#, values.join(";"))
+    }
+}
+
+/// Name of the feature without a keyframed SVG equivalent.
+struct Unsupported(&'static str);
+impl From<fmt::Error> for Unsupported {
+    fn from(_: fmt::Error) -> Self { Self("formatting") }
+}
+
+#[derive(Clone, Copy, PartialEq)] enum Ease {
+    Hold, Spline([f32; 4]),
+    /// Per-component or overshooting easing, which SMIL key splines can't express
+    Sampled,
+}
+const LINEAR: [f32; 4] = [0., 0., 1., 1.];
+
+/// Composition frames of the keyframes and their easing towards the next one.
+type Timing = Vec<(f32, Ease)>;
+
+fn keyframes<T>(prop: &AnimatedProperty<T>) -> Result<&AnimatedValue<T>, Unsupported> {
+    #[cfg(feature = "expression")] if prop.expr.as_ref().is_some_and(|expr| !expr.x.is_empty()) {
+        return Err(Unsupported("expressions"))
+    }
+    match &prop.source {
+        PropertySource::Inline(value) |
+        PropertySource::Slot { fallback: Some(value), .. } => Ok(value),
+        PropertySource::Slot { fallback: None, .. } => Err(Unsupported("unresolved slots")),
+    }
+}
+
+fn is_animated<T>(prop: &AnimatedProperty<T>) -> Result<bool, Unsupported> {
+    Ok(matches!(keyframes(prop)?, AnimatedValue::Animated(_)))
+}
+
+/// Timing of the keyframes of a layer starting at `st`, `None` if the property is static.
+fn timing<T>(prop: &AnimatedProperty<T>, st: f32) -> Result<Option<Timing>, Unsupported> {
+    let AnimatedValue::Animated(coll) = keyframes(prop)? else { return Ok(None) };
+    Ok(Some(coll.iter().enumerate().filter(|(_, keyframe)| keyframe.value.is_some())
+        .map(|(index, keyframe)| {
+            let next = coll.get(index + 1).filter(|next| next.value.is_some());
+            let ease = if keyframe.hold.as_bool() || next.is_none() { Ease::Hold } else {
+                keyframe.easing.as_deref().map_or(Ease::Spline(LINEAR), spline)
+            };  (keyframe.start + st, ease)
+        }).collect()))
+}
+
+fn spline(easing: &EasingHandle) -> Ease {
+    fn uniform(value: &ArrayScalar<f32>) -> Option<f32> { match value {
+        ArrayScalar::Scalar(value) => Some(*value),
+        ArrayScalar::Array(values) =>
+            values.iter().all(|value| *value == values[0]).then_some(values[0]),
+    } }
+    let handles = [&easing.to.time, &easing.to.factor, &easing.ti.time, &easing.ti.factor];
+    match handles.map(uniform) {
+        [Some(x1), Some(y1), Some(x2), Some(y2)] if [x1, y1, x2, y2].iter()
+            .all(|value| (0. ..=1.).contains(value)) => Ease::Spline([x1, y1, x2, y2]),
+        _ => Ease::Sampled,
+    }
+}
+
+/// Timing shared by several properties driving one attribute, sampled every frame
+/// within the `window` if they aren't keyed alike.
+fn merge(timings: Vec<Timing>, (ip, op): (f32, f32)) -> Option<Timing> {
+    let mut timings = timings.into_iter();
+    let first = timings.next()?;
+    if timings.all(|timing| timing == first) { return Some(first) }
+    Some(vec![(ip, Ease::Sampled), (op, Ease::Hold)])
+}
+
+/// Key of an SMIL animation at `time`, valued at the composition frame `at`
+/// and eased by `spline` towards the next key.
+struct Stop { time: f32, at: f32, spline: [f32; 4] }
+
+/// Keys of the timing clipped to the `window`. Holds end by a key repeating the held
+/// value at the time of the next one, and segments cut by the window or with easing
+/// beyond key splines are sampled linearly once per frame.
+fn schedule(timing: &[(f32, Ease)], (ip, op): (f32, f32)) -> Vec<Stop> {
+    let first = timing.first().map_or(op, |key| key.0);
+    let segments = std::iter::once((f32::NEG_INFINITY, first, Ease::Hold))
+        .chain(timing.iter().enumerate().map(|(index, &(start, ease))|
+            (start, timing.get(index + 1).map_or(f32::INFINITY, |next| next.0), ease)));
+    let (mut stops, mut pending) = (Vec::new(), None::<Stop>);
+
+    for (start, end, ease) in segments {
+        let (from, to) = (start.max(ip), end.min(op));
+        if to <= from { continue }
+        if let Some(last) = pending.take() { if last.at != from { stops.push(last) } }
+        match ease {
+            Ease::Hold => {
+                stops.push(Stop { time: from, at: from, spline: LINEAR });
+                pending = Some(Stop { time: to, at: from, spline: LINEAR });   continue
+            }
+            Ease::Spline(spline) if (from, to) == (start, end) =>
+                stops.push(Stop { time: from, at: from, spline }),
+            _ => {
+                let steps = ((to - from).ceil() as usize).max(2);
+                stops.extend((0..steps).map(|step| {
+                    let at = from + (to - from) * step as f32 / steps as f32;
+                    Stop { time: at, at, spline: LINEAR }
+                }));
+            }
+        }   pending = Some(Stop { time: to, at: to, spline: LINEAR });
+    }   stops.extend(pending);  stops
+}
+
+/// Writes path data with every segment as a cubic curve, keeping the commands
+/// of a path the same whenever only its points are animated.
+fn write_cubic_path(out: &mut String, path: &BezPath) -> fmt::Result {
+    use kurbo::PathEl::*;
+    let (mut last, mut start) = (kurbo::Point::ZERO, kurbo::Point::ZERO);
+    for el in path.elements() {
+        let (c1, c2, p) = match *el {
+            MoveTo(p) => { write!(out, "M{} {}", Num(p.x), Num(p.y))?;
+                (last, start) = (p, p);    continue
+            }
+            LineTo(p) => (last, p, p),
+            QuadTo(c, p) => (last + (c - last) * (2. / 3.), p + (c - p) * (2. / 3.), p),
+            CurveTo(c1, c2, p) => (c1, c2, p),
+            ClosePath => { out.push('Z');   last = start;   continue }
+        };
+        write!(out, "C{} {} {} {} {} {}", Num(c1.x), Num(c1.y),
+            Num(c2.x), Num(c2.y), Num(p.x), Num(p.y))?;    last = p;
+    }   Ok(())
+}
+
+/// Motion path through the keyframed positions, with its arc length up to each of them.
+struct Motion { path: BezPath, lengths: Vec<f64> }
+
+impl Motion {
+    fn new(keys: &[&KeyframeBase<Vec2D>]) -> Self {
+        let point = |value: Vec2D| kurbo::Point::new(value.x as _, value.y as _);
+        let (mut path, mut lengths) = (BezPath::new(), vec![0.]);
+        let Some(first) = keys.first() else { return Self { path, lengths } };
+        path.move_to(point(*first.as_scalar()));
+        for pair in keys.windows(2) {
+            let (from, to) = (*pair[0].as_scalar(), *pair[1].as_scalar());
+            let length = match &pair[0].pextra {
+                Some(extra) if !pair[0].hold.as_bool() => {
+                    let curve = kurbo::CubicBez::new(point(from), point(from + extra.to),
+                        point(to + extra.ti), point(to));
+                    path.curve_to(curve.p1, curve.p2, curve.p3);
+                    curve.arclen(1e-3)
+                }
+                _ => { path.line_to(point(to));   point(from).distance(point(to)) }
+            };  lengths.push(lengths.last().unwrap() + length);
+        }   Self { path, lengths }
+    }
+
+    /// Fraction of the path length reached at `frame` of the keyframes' timeline.
+    fn progress(&self, keys: &[&KeyframeBase<Vec2D>], st: f32, frame: f32) -> f32 {
+        let total = *self.lengths.last().unwrap();
+        if total <= 0. { return 0. }
+        let local = frame - st;
+        let index = keys.partition_point(|key| key.start <= local).saturating_sub(1);
+        let (key, length) = (keys[index], self.lengths[index]);
+        let Some(next) = keys.get(index + 1).filter(|_|
+            !key.hold.as_bool() && key.start < local) else { return (length / total) as _ };
+
+        let time = ((local - key.start) / (next.start - key.start).max(f32::EPSILON))
+            .clamp(0., 1.);
+        let factor = key.easing.as_deref().map_or(time, |easing| {
+            let first = |value: &ArrayScalar<f32>| match value {
+                ArrayScalar::Scalar(value) => *value, ArrayScalar::Array(values) => values[0],
+            };
+            CubicBezierEasing::new((first(&easing.to.time), first(&easing.to.factor)),
+                (first(&easing.ti.time), first(&easing.ti.factor))).get_y(time)
+        });
+        let segment = self.lengths[index + 1] - length;
+        ((length + segment * factor as f64) / total).clamp(0., 1.) as _
+    }
+}
+
+enum Content<'a> { Shapes(&'a [ShapeItem]), Solid(&'a SolidLayer) }
+
+/// Writer of the animated document body, defining gradients through `svg`.
+struct Smil<'a> {
+    animation: &'a Animation, svg: &'a mut SvgContext, out: String, timeline: Timeline,
+}
+
+impl<'a> Smil<'a> {
+    fn new(animation: &'a Animation, svg: &'a mut SvgContext) -> Self {
+        Self { animation, svg, out: String::new(), timeline: Timeline::new(animation) }
+    }
+
+    fn document(mut self, view: [f64; 6]) -> Result<String, Unsupported> {
+        let view = Affine::new(view);
+        let grouped = view != Affine::IDENTITY;
+        if grouped {
+            self.out += "<g";   write_transform(&mut self.out, &view)?;  self.out += ">\n";
+        }
+        let layers = &self.animation.layers;
+        for layer in layers.iter().rev() { self.layer(layer, layers)? }
+        if grouped { self.out += "</g>\n" }     Ok(self.out)
+    }
+
+    fn layer(&mut self, layer: &LayerItem, layers: &[LayerItem]) -> Result<(), Unsupported> {
+        let (vl, content) = match layer {
+            LayerItem::Shape(layer) => (&layer.vl, Content::Shapes(&layer.shapes)),
+            LayerItem::SolidColor(layer) => (&layer.vl, Content::Solid(layer)),
+            LayerItem::Null(_) | LayerItem::Audio(_) | LayerItem::Data(_) => return Ok(()),
+            LayerItem::PrecompLayer(_) => return Err(Unsupported("precomps")),
+            LayerItem::Image(_) => return Err(Unsupported("image layers")),
+            LayerItem::Text(_)  => return Err(Unsupported("text layers")),
+            LayerItem::Camera(_) => return Err(Unsupported("cameras")),
+        };
+        let base = &vl.base;
+        if base.hd { return Ok(()) }
+        if vl.tt.is_some() || vl.td.is_some_and(|td| td.as_bool()) {
+            return Err(Unsupported("track mattes"))
+        }
+        if !vl.masks.is_empty() { return Err(Unsupported("masks")) }
+        if !vl.ef.is_empty() || !vl.sy.is_empty() {
+            return Err(Unsupported("layer effects and styles"))
+        }
+        if vl.bm != BlendMode::Normal { return Err(Unsupported("blend modes")) }
+        if base.ddd.as_bool() { return Err(Unsupported("3D layers")) }
+        if base.sr != 1. { return Err(Unsupported("time stretch")) }
+
+        let (ip, op) = self.timeline.window;
+        let (start, end) = (base.ip.max(ip), base.op.min(op));
+        if end <= start { return Ok(()) }
+        let timeline = &self.timeline;
+        self.out += "<g";   visibility(&mut self.out,
+            timeline.fraction(start), timeline.fraction(end), &timeline.dur)?;
+
+        let mut chain = vec![vl];   // parents don't pass on their opacity
+        while let Some(parent) = chain.last().and_then(|vl| vl.base.parent) {
+            let Some(parent) = layers.iter().filter_map(LayerItem::visual_layer)
+                .find(|vl| vl.base.ind == Some(parent)) else { break };
+            if layers.len() < chain.len() { return Err(Unsupported("cyclic parenting")) }
+            chain.push(parent);
+        }
+        let mut opened = 1;
+        for vl in chain.iter().rev() { opened += self.transform(&vl.ks, vl.base.st, vl.ao)? }
+        if let Some(opacity) = &vl.ks.opacity { opened += self.opacity(opacity, base.st)? }
+
+        match content {
+            Content::Shapes(shapes) => self.shapes(shapes, base.st, vl.ao)?,
+            Content::Solid(solid) => {
+                write!(self.out, r#"<rect width="{}" height="{}" fill="{}""#,
+                    Num(solid.sw), Num(solid.sh), Hex(solid.sc))?;
+                if solid.sc.a < 255 {
+                    write!(self.out, r#" fill-opacity="{}""#, Num(solid.sc.a as f32 / 255.))?;
+                }   self.out += "/>\n";
+            }
+        }
+        for _ in 0..opened { self.out += "</g>\n" }     Ok(())
+    }
+
+    /// SMIL animation of the attribute valued at composition frames, empty if it's static.
+    fn attribute(&self, name: &str, timing: Option<Timing>,
+        value: impl Fn(f32) -> String) -> Result<String, Unsupported> {
+        let mut out = String::new();
+        let Some(timing) = timing else { return Ok(out) };
+        let stops = schedule(&timing, self.timeline.window);
+        let values = stops.iter().map(|stop| value(stop.at)).collect::<Vec<_>>();
+        self.timeline.animate(&mut out, "animate",
+            &format!(r#"attributeName="{name}""#), "values", &stops, &values)?;   Ok(out)
+    }
+
+    fn opacity(&mut self, opacity: &Value, st: f32) -> Result<usize, Unsupported> {
+        let timing = timing(opacity, st)?;
+        let value = |frame: f32| Num(opacity.get_value(frame - st) / 100.).to_string();
+        let initial = value(self.timeline.window.0);
+        let animation = self.attribute("opacity", timing, value)?;
+        if animation.is_empty() && initial == "1" { return Ok(0) }
+        writeln!(self.out, r#"<g opacity="{initial}">"#)?;
+        self.out += &animation;     Ok(1)
+    }
+
+    /// Opens the groups of transform components, animated ones each in its own group,
+    /// and returns the number of them.
+    fn transform(&mut self, trfm: &Transform, st: f32,
+        ao: IntBool) -> Result<usize, Unsupported> {
+        let rotation = match &trfm.extra {
+            TransRotation::Normal2D { rotation } => rotation.as_ref(),
+            TransRotation::Split3D(_) => return Err(Unsupported("3D rotation")),
+        };
+        if trfm.skew.as_ref().map_or(Ok(false), is_animated)? ||
+            trfm.skew_axis.as_ref().map_or(Ok(false), is_animated)? {
+            return Err(Unsupported("animated skew"))
+        }
+        let position = match &trfm.position {
+            Some(Translation::Normal(position)) => Some(position),
+            Some(Translation::Split(split)) => {
+                if is_animated(&split.x)? || is_animated(&split.y)? {
+                    return Err(Unsupported("animated split position"))
+                }   None
+            }   None => None,
+        };
+        let anchor_timing   = trfm.anchor.as_ref().map_or(Ok(None), |v| timing(v, st))?;
+        let position_timing = position.map_or(Ok(None), |v| timing(v, st))?;
+        let scale_timing    = trfm.scale.as_ref().map_or(Ok(None), |v| timing(v, st))?;
+        let rotation_timing = rotation.map_or(Ok(None), |v| timing(v, st))?;
+
+        if anchor_timing.is_none() && position_timing.is_none() &&
+            scale_timing.is_none() && rotation_timing.is_none() {
+            let matrix = trfm.to_matrix::<Affine>(self.timeline.window.0 - st, ao).0;
+            if matrix == Affine::IDENTITY { return Ok(0) }
+            self.out += "<g";   write_transform(&mut self.out, &matrix)?;
+            self.out += ">\n";  return Ok(1)
+        }
+
+        let mut opened = 0;
+        match (&trfm.position, position_timing) {
+            (Some(Translation::Normal(position)), Some(timing)) => {
+                if ao.as_bool() { return Err(Unsupported("auto-orient")) }
+                opened += self.position(position, timing, st)?;
+            }
+            (Some(Translation::Normal(position)), None) => opened += self.component("translate",
+                None, |frame| { let pos = position.get_value(frame - st);
+                    format!("{} {}", Num(pos.x), Num(pos.y)) }, "0 0")?,
+            (Some(Translation::Split(split)), _) => opened += self.component("translate",
+                None, |frame| format!("{} {}", Num(split.x.get_value(frame - st)),
+                    Num(split.y.get_value(frame - st))), "0 0")?,
+            (None, _) => (),
+        }
+        if let Some(rotation) = rotation {
+            opened += self.component("rotate", rotation_timing,
+                |frame| Num(rotation.get_value(frame - st)).to_string(), "0")?;
+        }
+        if let Some(skew) = &trfm.skew {
+            let local = self.timeline.window.0 - st;
+            let axis = trfm.skew_axis.as_ref().map(|axis| axis.get_value(local).to_radians());
+            let mut matrix = Affine::IDENTITY;
+            if let Some(axis) = axis { matrix.rotate(-axis) }
+            matrix.skew_x(-skew.get_value(local).clamp(-85., 85.).to_radians());
+            if let Some(axis) = axis { matrix.rotate(axis) }
+            if matrix != Affine::IDENTITY {
+                self.out += "<g";   write_transform(&mut self.out, &matrix)?;
+                self.out += ">\n";  opened += 1;
+            }
+        }
+        if let Some(scale) = &trfm.scale {
+            opened += self.component("scale", scale_timing, |frame| {
+                let scale = scale.get_value(frame - st) / 100.;
+                format!("{} {}", Num(scale.x), Num(scale.y))
+            }, "1 1")?;
+        }
+        if let Some(anchor) = &trfm.anchor {
+            opened += self.component("translate", anchor_timing, |frame| {
+                let anchor = anchor.get_value(frame - st);
+                format!("{} {}", Num(-anchor.x), Num(-anchor.y))
+            }, "0 0")?;
+        }   Ok(opened)
+    }
+
+    /// Opens a group with a transform component of `kind`, unless it stays `identity`.
+    fn component(&mut self, kind: &str, timing: Option<Timing>,
+        value: impl Fn(f32) -> String, identity: &str) -> Result<usize, Unsupported> {
+        let initial = value(self.timeline.window.0);
+        let (stops, values) = timing.map_or((Vec::new(), Vec::new()), |timing| {
+            let stops = schedule(&timing, self.timeline.window);
+            let values = stops.iter().map(|stop| value(stop.at)).collect();
+            (stops, values)
+        });
+        if values.iter().all(|value| *value == initial) && initial == identity { return Ok(0) }
+        writeln!(self.out, r#"<g transform="{kind}({initial})">"#)?;
+        if !stops.is_empty() {
+            self.timeline.animate(&mut self.out, "animateTransform", &format!(
+                r#"attributeName="transform" type="{kind}""#), "values", &stops, &values)?;
+        }   Ok(1)
+    }
+
+    /// Opens a group translated by the keyframed position, moving along the motion path
+    /// by arc length if its keyframes have spatial tangents.
+    fn position(&mut self, position: &Position, timing: Timing,
+        st: f32) -> Result<usize, Unsupported> {
+        let AnimatedValue::Animated(coll) = keyframes(position)? else { unreachable!() };
+        let keys = coll.iter().filter(|key| key.value.is_some()).collect::<Vec<_>>();
+        let curved = keys.iter().any(|key| key.pextra.as_ref().is_some_and(|extra|
+            [extra.to, extra.ti].iter().any(|tangent| tangent.x != 0. || tangent.y != 0.)));
+        if !curved {
+            return self.component("translate", Some(timing), |frame| {
+                let pos = position.get_value(frame - st);
+                format!("{} {}", Num(pos.x), Num(pos.y))
+            }, "0 0")
+        }
+
+        let motion = Motion::new(&keys);
+        let stops = schedule(&timing, self.timeline.window);
+        let points = stops.iter().map(|stop|
+            Key(motion.progress(&keys, st, stop.at)).to_string()).collect::<Vec<_>>();
+        if points.iter().all(|point| *point == points[0]) {   // at rest on the path
+            return self.component("translate", None, |frame| {
+                let pos = position.get_value(frame - st);
+                format!("{} {}", Num(pos.x), Num(pos.y))
+            }, "0 0")
+        }
+        let mut path = String::new();   write_path(&mut path, &motion.path)?;
+        self.out += "<g>\n";   // the motion adds to the (absent) transform attribute
+        self.timeline.animate(&mut self.out, "animateMotion",
+            &format!(r#"path="{path}""#), "keyPoints", &stops, &points)?;   Ok(1)
+    }
+
+    fn shapes(&mut self, items: &[ShapeItem], st: f32,
+        ao: IntBool) -> Result<(), Unsupported> {
+        for (index, item) in items.iter().enumerate().rev() { match item {
+            ShapeItem::Fill(style) | ShapeItem::Stroke(style) |
+            ShapeItem::GradientFill(style) | ShapeItem::GradientStroke(style)
+                if !style.elem.hd => self.style(style, &items[..index], st, ao)?,
+            ShapeItem::Group(group) if !group.elem.hd => {
+                let opened = self.group(group, st, ao)?;
+                self.shapes(&group.shapes, st, ao)?;
+                for _ in 0..opened { self.out += "</g>\n" }
+            }
+            ShapeItem::Repeater(repeater) if !repeater.elem.hd =>
+                return Err(Unsupported("repeaters")),
+            ShapeItem::RoundedCorners(RoundedCorners { elem, .. }) |
+            ShapeItem::PuckerBloat(PuckerBloat { elem, .. }) |
+            ShapeItem::OffsetPath(OffsetPath { elem, .. }) |
+            ShapeItem::Trim(TrimPath { elem, .. }) | ShapeItem::Twist(Twist { elem, .. }) |
+            ShapeItem::Merge(Merge { elem, .. }) | ShapeItem::ZigZag(ZigZag { elem, .. })
+                if !elem.hd => return Err(Unsupported("path modifiers")),
+            _ => (),
+        } }     Ok(())
+    }
+
+    /// Opens the transform and opacity groups of a shape group.
+    fn group(&mut self, group: &Group, st: f32, ao: IntBool) -> Result<usize, Unsupported> {
+        if group.elem.bm.is_some_and(|bm| bm != BlendMode::Normal) {
+            return Err(Unsupported("blend modes"))
+        }
+        let Some(trfm) = group.shapes.iter().rev().find_map(|item| match item {
+            ShapeItem::Transform(trfm) if !trfm.elem.hd => Some(&trfm.trfm), _ => None,
+        }) else { return Ok(0) };
+        let opened = self.transform(trfm, st, ao)?;
+        Ok(opened + trfm.opacity.as_ref().map_or(Ok(0), |opacity| self.opacity(opacity, st))?)
+    }
+
+    /// Paints the preceding shapes, also those in preceding groups, as the renderer does.
+    fn style(&mut self, style: &FillStrokeGrad, shapes: &[ShapeItem], st: f32,
+        ao: IntBool) -> Result<(), Unsupported> {
+        if style.elem.bm.is_some_and(|bm| bm != BlendMode::Normal) {
+            return Err(Unsupported("blend modes"))
+        }
+        let fill = matches!(style.base, FillStroke::FillRule { .. });
+        let color_timing = match &style.grad {
+            ColorGrad::Color { color } => timing(color, st)?,
+            ColorGrad::Gradient(grad) => {
+                let animated = is_animated(&grad.sp)? || is_animated(&grad.ep)? ||
+                    is_animated(&grad.stops.cl)? || is_animated(&style.opacity)? ||
+                    grad.hl.as_ref().map_or(Ok(false), is_animated)? ||
+                    grad.ha.as_ref().map_or(Ok(false), is_animated)?;
+                if animated { return Err(Unsupported("animated gradients")) }   None
+            }
+        };
+        let width_timing = match &style.base {
+            FillStroke::Stroke(stroke) => {
+                if stroke.ml2.as_ref().map_or(Ok(false), is_animated)? ||
+                    stroke.dash.iter().try_fold(false, |animated, dash|
+                        Ok::<_, Unsupported>(animated || is_animated(&dash.value)?))? {
+                    return Err(Unsupported("animated dashes and miter limits"))
+                }   timing(&stroke.width, st)?
+            }   FillStroke::FillRule { .. } => None,
+        };
+        let opacity_timing = if matches!(style.grad, ColorGrad::Color { .. }) {
+            timing(&style.opacity, st)? } else { None };
+
+        let attrs = self.svg.style_attrs(&style.to_style::<SvgPaint>(self.timeline.window.0 - st))?;
+        let mut animations = String::new();
+        if let ColorGrad::Color { color } = &style.grad {
+            animations += &self.attribute(if fill { "fill" } else { "stroke" }, color_timing,
+                |frame| Hex(color.get_value(frame - st)).to_string())?;
+        }
+        animations += &self.attribute(if fill { "fill-opacity" } else { "stroke-opacity" },
+            opacity_timing, |frame| Num(style.opacity.get_value(frame - st) / 100.).to_string())?;
+        if let FillStroke::Stroke(stroke) = &style.base {
+            animations += &self.attribute("stroke-width", width_timing,
+                |frame| Num(stroke.width.get_value(frame - st)).to_string())?;
+        }
+        writeln!(self.out, "<g{attrs}>")?;     self.out += &animations;
+        self.geometry(shapes, st, ao)?;     self.out += "</g>\n";   Ok(())
+    }
+
+    fn geometry(&mut self, items: &[ShapeItem], st: f32,
+        ao: IntBool) -> Result<(), Unsupported> {
+        for item in items.iter().rev() { match item {
+            ShapeItem::Path(curve) if !curve.base.elem.hd =>
+                self.path(curve, timing(&curve.shape, st)?, st)?,
+            ShapeItem::Rectangle(rect) if !rect.base.elem.hd => {
+                let mut timings = vec![timing(&rect.pos, st)?, timing(&rect.size, st)?];
+                if let Some(rcr) = &rect.rcr { timings.push(timing(rcr, st)?) }
+                let timing = merge(timings.into_iter().flatten().collect(), self.timeline.window);
+                self.path(rect, timing, st)?
+            }
+            ShapeItem::Ellipse(elps) if !elps.base.elem.hd => {
+                let timings = [timing(&elps.pos, st)?, timing(&elps.size, st)?];
+                let timing = merge(timings.into_iter().flatten().collect(), self.timeline.window);
+                self.path(elps, timing, st)?
+            }
+            ShapeItem::Polystar(star) if !star.base.elem.hd => {
+                let animated = is_animated(&star.pos)? || is_animated(&star.pt)? ||
+                    is_animated(&star.or)? || is_animated(&star.os)? ||
+                    is_animated(&star.rotation)? ||
+                    star.ir.as_ref().map_or(Ok(false), is_animated)? ||
+                    star.is.as_ref().map_or(Ok(false), is_animated)?;
+                if animated { return Err(Unsupported("animated polystars")) }
+                self.path(star.as_ref(), None, st)?
+            }
+            ShapeItem::Group(group) if !group.elem.hd => {
+                let opened = self.group(group, st, ao)?;
+                self.geometry(&group.shapes, st, ao)?;
+                for _ in 0..opened { self.out += "</g>\n" }
+            }   _ => (),
+        } }     Ok(())
+    }
+
+    fn path(&mut self, shape: &impl PathFactory, timing: Option<Timing>,
+        st: f32) -> Result<(), Unsupported> {
+        let path: BezPath = shape.to_path(self.timeline.window.0 - st);
+        self.out += r#"<path d=""#;
+        let Some(timing) = timing else {
+            write_path(&mut self.out, &path)?;  self.out += "\"/>\n";   return Ok(())
+        };
+
+        let stops = schedule(&timing, self.timeline.window);
+        let values = stops.iter().map(|stop| {
+            let mut data = String::new();
+            write_cubic_path(&mut data, &shape.to_path(stop.at - st)).map(|_| data)
+        }).collect::<Result<Vec<_>, _>>()?;
+        let commands = |data: &str| data.chars()
+            .filter(char::is_ascii_alphabetic).collect::<String>();
+        if values.iter().any(|data| commands(data) != commands(&values[0])) {
+            return Err(Unsupported("changing path topology"))
+        }
+        write_cubic_path(&mut self.out, &path)?;    self.out += "\">\n";
+        self.timeline.animate(&mut self.out, "animate",
+            r#"attributeName="d""#, "values", &stops, &values)?;
+        self.out += "</path>\n";    Ok(())
+    }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    fn render(json: &str) -> AnimatedSvg {
+        LottieRuntime::from_reader(json.as_bytes()).unwrap().render_animated_svg()
+    }
+
+    #[test] fn holds_and_clipped_segments_become_smil_keys() {
+        let ease = Ease::Spline([0.5, 0., 0.5, 1.]);
+        let stops = schedule(&[(0., ease), (10., Ease::Hold), (20., Ease::Sampled),
+            (40., Ease::Hold)], (0., 24.));
+        let keys = stops.iter().map(|stop| (stop.time, stop.at)).collect::<Vec<_>>();
+        assert_eq!(keys, [(0., 0.), (10., 10.), (20., 10.), (20., 20.),
+            (21., 21.), (22., 22.), (23., 23.), (24., 24.)]);
+        assert_eq!(stops[0].spline, [0.5, 0., 0.5, 1.]);
+        assert_eq!(stops[4].spline, LINEAR);
+
+        // before the first keyframe and after the last one values hold
+        let stops = schedule(&[(5., Ease::Hold)], (0., 10.));
+        let keys = stops.iter().map(|stop| (stop.time, stop.at)).collect::<Vec<_>>();
+        assert_eq!(keys, [(0., 0.), (5., 0.), (5., 5.), (10., 5.)]);
+    }
+
+    #[test] fn keyframes_are_converted_with_their_easing() {
+        let svg = render(r#"{"ip":0,"op":20,"fr":10,"w":20,"h":20,"layers":[
+            {"ty":4,"ind":1,"ip":0,"op":20,"st":0,"ks":{
+                "o":{"a":1,"k":[{"t":0,"s":[0],"o":{"x":[0.4],"y":[0]},"i":{"x":[0.6],"y":[1]}},
+                    {"t":10,"s":[100]}]},
+                "p":{"a":1,"k":[{"t":0,"s":[0,0],"h":1},{"t":10,"s":[10,5]}]}},
+            "shapes":[{"ty":"rc","p":{"a":0,"k":[0,0]},"s":{"a":0,"k":[4,4]},"r":{"a":0,"k":0}},
+                {"ty":"fl","o":{"a":0,"k":100},"c":{"a":1,"k":[
+                    {"t":0,"s":[1,0,0,1]},{"t":20,"s":[0,0,1,1]}]}}]}]}"#);
+        assert_eq!(svg.baked, None);
+        let markup = &svg.markup;
+        assert!(markup.contains(concat!(r#"<g transform="translate(0 0)">"#, "\n",
+            r#"<animateTransform attributeName="transform" type="translate" dur="2s" "#,
+            r#"repeatCount="indefinite" calcMode="spline" keyTimes="0;0.5;0.5;1" "#,
+            r#"keySplines="0 0 1 1;0 0 1 1;0 0 1 1" values="0 0;0 0;10 5;10 5"/>"#)), "{markup}");
+        assert!(markup.contains(concat!(r#"<g opacity="0">"#, "\n",
+            r#"<animate attributeName="opacity" dur="2s" repeatCount="indefinite" "#,
+            r#"calcMode="spline" keyTimes="0;0.5;1" keySplines="0.4 0 0.6 1;0 0 1 1" "#,
+            r#"values="0;1;1"/>"#)));
+        assert!(markup.contains(concat!(r##"<g fill="#ff0000">"##, "\n",
+            r#"<animate attributeName="fill" dur="2s" repeatCount="indefinite" "#,
+            r#"calcMode="spline" keyTimes="0;1" keySplines="0 0 1 1" "#,
+            r##"values="#ff0000;#0000ff"/>"##, "\n<path d=")));
+    }
+
+    #[test] fn curved_motion_and_animated_paths_keep_their_keys() {
+        let svg = render(r#"{"ip":0,"op":10,"fr":10,"w":20,"h":20,"layers":[
+            {"ty":4,"ind":1,"ip":0,"op":10,"st":0,"ks":{"p":{"a":1,"k":[
+                {"t":0,"s":[0,0],"to":[0,10],"ti":[0,10]},{"t":10,"s":[10,0]}]}},
+            "shapes":[{"ty":"sh","ks":{"a":1,"k":[
+                {"t":0,"s":[{"c":true,"v":[[0,0],[1,0],[1,1]],"i":[[0,0],[0,0],[0,0]],
+                    "o":[[0,0],[0,0],[0,0]]}]},
+                {"t":10,"s":[{"c":true,"v":[[0,0],[2,0],[2,2]],"i":[[0,0],[0,0],[0,0]],
+                    "o":[[0,0],[0,0],[0,0]]}]}]}},
+                {"ty":"fl","o":{"a":0,"k":100},"c":{"a":0,"k":[0,0,0,1]}}]}]}"#);
+        assert_eq!(svg.baked, None);
+        assert!(svg.markup.contains(concat!(r#"<animateMotion path="M0 0C0 10 10 10 10 0" "#,
+            r#"dur="1s" repeatCount="indefinite" calcMode="spline" keyTimes="0;1" "#,
+            r#"keySplines="0 0 1 1" keyPoints="0;1"/>"#)), "{}", svg.markup);
+        assert!(svg.markup.contains(concat!(r#"values="M0 0C0 0 1 0 1 0C1 0 1 1 1 1"#,
+            r#"C1 1 0 0 0 0Z;M0 0C0 0 2 0 2 0C2 0 2 2 2 2C2 2 0 0 0 0Z"/>"#)));
+    }
+
+    #[test] fn unsupported_features_bake_every_frame() {
+        let svg = render(r#"{"ip":0,"op":3,"fr":3,"w":8,"h":8,"layers":[
+            {"ty":4,"ind":1,"ip":0,"op":3,"st":0,"ks":{},"hasMask":true,
+            "masksProperties":[{"mode":"a","o":{"a":0,"k":100},"pt":{"a":0,"k":{"c":true,
+                "v":[[0,0],[4,0],[4,4]],"i":[[0,0],[0,0],[0,0]],"o":[[0,0],[0,0],[0,0]]}}}],
+            "shapes":[{"ty":"rc","p":{"a":0,"k":[4,4]},"s":{"a":0,"k":[8,8]},"r":{"a":0,"k":0}},
+                {"ty":"fl","o":{"a":0,"k":100},"c":{"a":0,"k":[0,1,0,1]}}]}]}"#);
+        assert_eq!(svg.baked, Some("masks"));
+        assert_eq!(svg.markup.matches(r#"<g mask="#).count(), 3);
+        assert!(svg.markup.contains(concat!(r#"<g visibility="hidden">"#, "\n",
+            r#"<animate attributeName="visibility" dur="1s" repeatCount="indefinite" "#,
+            r#"calcMode="discrete" keyTimes="0;0.33333;0.66667" "#,
+            r#"values="hidden;visible;hidden"/>"#)), "{}", svg.markup);
+    }
+}
@@ -0,0 +1,512 @@
+/****************************************************************
+ * $ID: tiny_skia.rs  	Sun 18 Oct 2026 14:20:37+0800           *
+ *                                                              *
+ * Maintainer: 范美辉 (MeiHui FAN) <mhfan@ustc.edu>              *
+ * Copyright (c) 2026 M.H.Fan, All rights reserved.             *
+ ****************************************************************/
+
+//! Pure-Rust CPU backend rasterizing into a premultiplied RGBA `Pixmap` by tiny-skia,
+//! for headless rendering and deterministic golden images without any GPU.
+
+use crate::core::{CompositeContext, LayerEffect, StyleStage,
+    blend_pixels, apply_effects, apply_styles,
+    helpers::{Vec2D, RGBA},
+    pathm::{PathBuilder, BezPath, PathFactory},
+    schema::{VisualLayer, MatteMode, MaskMode, BlendMode, FillRule, LineJoin, LineCap},
+    style::{StyleConv, TM2DwO, FSOpts}, render::RenderContext
+};
+use std::{collections::HashMap, hash::{DefaultHasher, Hash, Hasher}};
+use tiny_skia::{Pixmap, PixmapPaint, Paint, Shader, Color, GradientStop, Transform,
+    Mask, Rect, Stroke, StrokeDash, SpreadMode, FilterQuality, Point,
+    BlendMode as SkBlend};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkiaError { InvalidSize }
+
+impl core::fmt::Display for SkiaError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self { Self::InvalidSize => f.write_str("invalid pixmap size") }
+    }
+}
+impl std::error::Error for SkiaError {}
+
+/// Render target with the current transform and global opacity of the drawing state,
+/// and the images decoded so far, keyed by a hash of their data (`None` if undecodable).
+pub struct SkiaContext { pixmap: Pixmap, transform: Transform, opacity: f32,
+    images: HashMap<(u64, usize), Option<Pixmap>>,
+}
+
+/// Decoded images kept at most, the cache is emptied beyond.
+const MAX_IMAGES: usize = 64;
+
+impl SkiaContext {
+    pub fn new(width: u32, height: u32) -> Result<Self, SkiaError> {
+        Pixmap::new(width, height).map(Self::from_pixmap).ok_or(SkiaError::InvalidSize)
+    }
+    pub fn from_pixmap(pixmap: Pixmap) -> Self {
+        Self { pixmap, transform: Transform::identity(), opacity: 1., images: HashMap::new() }
+    }
+    pub fn pixmap(&self) -> &Pixmap { &self.pixmap }
+    pub fn pixmap_mut(&mut self) -> &mut Pixmap { &mut self.pixmap }
+    pub fn into_pixmap(self) -> Pixmap { self.pixmap }
+
+    fn blank(&self) -> Result<Pixmap, SkiaError> {
+        Pixmap::new(self.pixmap.width(), self.pixmap.height()).ok_or(SkiaError::InvalidSize)
+    }
+
+    /// Draws `image` over the whole target with `mode`, ignoring the drawing state.
+    fn blit(&mut self, image: &Pixmap, mode: SkBlend) {
+        self.pixmap.draw_pixmap(0, 0, image.as_ref(), &PixmapPaint {
+            blend_mode: mode, ..Default::default() }, Transform::identity(), None);
+    }
+}
+
+fn to_skia_path(path: &BezPath) -> Option<tiny_skia::Path> {
+    use kurbo::PathEl::*;
+    let mut pb = tiny_skia::PathBuilder::with_capacity(path.elements().len(),
+        path.elements().len() * 3);
+    let point = |pt: kurbo::Point| (pt.x as f32, pt.y as f32);
+    for el in path.elements() { match *el {
+        MoveTo(p) => { let p = point(p); pb.move_to(p.0, p.1) }
+        LineTo(p) => { let p = point(p); pb.line_to(p.0, p.1) }
+        QuadTo(c, p) => {
+            let (c, p) = (point(c), point(p));  pb.quad_to(c.0, c.1, p.0, p.1)
+        }
+        CurveTo(c1, c2, p) => {
+            let (c1, c2, p) = (point(c1), point(c2), point(p));
+            pb.cubic_to(c1.0, c1.1, c2.0, c2.1, p.0, p.1)
+        }
+        ClosePath => pb.close(),
+    } }     pb.finish()
+}
+
+fn to_skia_transform(tm: &kurbo::Affine) -> Transform {
+    let [a, b, c, d, e, f] = tm.as_coeffs().map(|v| v as f32);
+    Transform::from_row(a, b, c, d, e, f)
+}
+
+impl StyleConv for Shader<'static> {
+    fn solid_color(color: RGBA) -> Self { Self::SolidColor(color.into()) }
+    fn linear_gradient(sp: Vec2D, ep: Vec2D, stops: &[(f32, RGBA)]) -> Self {
+        tiny_skia::LinearGradient::new(sp.into(), ep.into(), gradient_stops(stops),
+            SpreadMode::Pad, Transform::identity()).unwrap_or_else(|| degenerate(stops))
+    }
+
+    /// The focal point is the start of a two-point conical gradient of zero radius.
+    fn radial_gradient(cp: Vec2D, fp: Vec2D, radii: (f32, f32),
+            stops: &[(f32, RGBA)]) -> Self {
+        tiny_skia::RadialGradient::new(fp.into(), cp.into(), radii.1,
+            gradient_stops(stops), SpreadMode::Pad, Transform::identity())
+            .unwrap_or_else(|| degenerate(stops))
+    }
+}
+
+fn gradient_stops(stops: &[(f32, RGBA)]) -> Vec<GradientStop> {
+    stops.iter().map(|&(offset, color)| GradientStop::new(offset, color.into())).collect()
+}
+/// Paints a gradient of zero extent with its last color, as Skia does.
+fn degenerate(stops: &[(f32, RGBA)]) -> Shader<'static> {
+    Shader::SolidColor(stops.last().map_or(Color::TRANSPARENT, |&(_, color)| color.into()))
+}
+
+impl From<RGBA> for Color {
+    fn from(color: RGBA) -> Self { Self::from_rgba8(color.r, color.g, color.b, color.a) }
+}
+impl From<Vec2D> for Point { fn from(pt: Vec2D) -> Self { Self::from_xy(pt.x, pt.y) } }
+
+impl RenderContext for SkiaContext {
+    type State = (Transform, f32);
+    type TM2D = kurbo::Affine;
+    type VGStyle = Shader<'static>;
+    type VGPath  = BezPath;
+    type Error = SkiaError;
+
+    fn get_size(&self) -> (u32, u32) { (self.pixmap.width(), self.pixmap.height()) }
+    fn clear_rect_with(&mut self, x: u32, y: u32, w: u32, h: u32,
+        color: RGBA) -> Result<(), Self::Error> {
+        let Some(rect) = Rect::from_xywh(x as _, y as _, w as _, h as _)
+            else { return Ok(()) };
+        let paint = Paint { shader: Shader::SolidColor(color.into()),
+            blend_mode: SkBlend::Source, anti_alias: false, ..Default::default() };
+        self.pixmap.fill_rect(rect, &paint, Transform::identity(), None);   Ok(())
+    }
+    fn save_state(&mut self) -> Result<Self::State, Self::Error> {
+        Ok((self.transform, self.opacity))
+    }
+    fn restore_state(&mut self, (transform, opacity): Self::State) -> Result<(), Self::Error> {
+        self.transform = transform;     self.opacity = opacity;     Ok(())
+    }
+    fn apply_transform(&mut self, trfm: &Self::TM2D,
+        opacity: Option<f32>) -> Result<(), Self::Error> {
+        if let Some(opacity) = opacity { self.opacity = opacity.clamp(0., 1.) }
+        self.transform = to_skia_transform(trfm);   Ok(())
+    }
+
+    fn fill_stroke(&mut self, path: &Self::VGPath, relative: Option<&Self::TM2D>,
+        style: &(Self::VGStyle, FSOpts)) -> Result<(), Self::Error> {
+        let transformed = relative.map(|transform| *transform * path.clone());
+        let Some(path) = to_skia_path(transformed.as_ref().unwrap_or(path))
+            else { return Ok(()) };     // nothing to draw of an empty path
+
+        let mut paint = Paint { shader: style.0.clone(), ..Default::default() };
+        if  self.opacity < 1. { paint.shader.apply_opacity(self.opacity) }
+        match &style.1 {
+            FSOpts::Fill(rule) => self.pixmap.fill_path(&path, &paint, match rule {
+                FillRule::NonZero => tiny_skia::FillRule::Winding,
+                FillRule::EvenOdd => tiny_skia::FillRule::EvenOdd,
+            }, self.transform, None),
+
+            FSOpts::Stroke { width, limit, join, cap, dash } => {
+                let stroke = Stroke { width: *width, miter_limit: *limit,
+                    line_join: match join {
+                        LineJoin::Miter => tiny_skia::LineJoin::MiterClip,
+                        LineJoin::Round => tiny_skia::LineJoin::Round,
+                        LineJoin::Bevel => tiny_skia::LineJoin::Bevel,
+                    },
+                    line_cap: match cap {
+                        LineCap::Butt   => tiny_skia::LineCap::Butt,
+                        LineCap::Round  => tiny_skia::LineCap::Round,
+                        LineCap::Square => tiny_skia::LineCap::Square,
+                    },
+                    dash: if dash.1.is_empty() { None } else {
+                        StrokeDash::new(dash.1.clone(), dash.0)
+                    },
+                };  self.pixmap.stroke_path(&path, &paint, &stroke, self.transform, None)
+            }
+        }   Ok(())
+    }
+
+    /// Images are decoded once; undecodable ones are skipped rather than failing the frame.
+    fn draw_image(&mut self, image: &[u8],
+        width: f32, height: f32) -> Result<(), Self::Error> {
+        let mut hasher = DefaultHasher::new();  image.hash(&mut hasher);
+        let key = (hasher.finish(), image.len());
+        if MAX_IMAGES <= self.images.len() && !self.images.contains_key(&key) {
+            self.images.clear();
+        }
+        let Some(image) = self.images.entry(key)
+            .or_insert_with(|| decode_image(image)) else { return Ok(()) };
+        let (iw, ih) = (image.width() as f32, image.height() as f32);
+        let width  = if 0. < width  { width  } else { iw };
+        let height = if 0. < height { height } else { ih };
+        self.pixmap.draw_pixmap(0, 0, image.as_ref(), &PixmapPaint {
+            opacity: self.opacity, quality: FilterQuality::Bilinear, ..Default::default()
+        }, self.transform.pre_scale(width / iw, height / ih), None);    Ok(())
+    }
+}
+
+impl CompositeContext for SkiaContext {
+    type Offscreen = Pixmap;
+    type Image = Pixmap;
+
+    fn begin_offscreen(&mut self) -> Result<Self::Offscreen, Self::Error> {
+        let layer = self.blank()?;  Ok(core::mem::replace(&mut self.pixmap, layer))
+    }
+    fn abort_offscreen(&mut self, target: Self::Offscreen) { self.pixmap = target }
+    fn end_offscreen(&mut self, target: Self::Offscreen) -> Result<Self::Image, Self::Error> {
+        Ok(core::mem::replace(&mut self.pixmap, target))
+    }
+
+    /// Combines the coverage of all masks like Blend2D's compositing operators
+    /// at the mask opacity, then keeps the content inside the accumulated coverage.
+    fn apply_masks(&mut self, mut content: Self::Image, layer: &VisualLayer,
+        transform: &TM2DwO<Self::TM2D>, frame: f32) -> Result<Self::Image, Self::Error> {
+        let (w, h) = (content.width(), content.height());
+        let mut accum = Mask::new(w, h).ok_or(SkiaError::InvalidSize)?;
+        let (ltm, mut initialized) = (to_skia_transform(&transform.0), false);
+
+        for item in &layer.masks {
+            if matches!(item.mode, MaskMode::None) { continue }
+            let mut part = Mask::new(w, h).ok_or(SkiaError::InvalidSize)?;
+            let mut path: Self::VGPath = item.shape.to_path(frame);
+            if let Some(expand) = &item.expand {
+                path.offset_path(expand.get_value(frame), LineJoin::Round, 4.);
+            }
+            if let Some(path) = to_skia_path(&path) {
+                part.fill_path(&path, tiny_skia::FillRule::Winding, true, ltm);
+            }
+            if item.inv { part.invert() }
+
+            if !initialized && matches!(item.mode,
+                MaskMode::Subtract | MaskMode::Intersect | MaskMode::Darken) {
+                accum.data_mut().fill(u8::MAX);
+            }
+            let opacity = item.opacity.as_ref().map_or(1.,
+                |opacity| opacity.get_value(frame) / 100.).clamp(0., 1.);
+            for (dst, &src) in accum.data_mut().iter_mut().zip(part.data()) {
+                let (da, sa) = (*dst as f32 / 255., src as f32 / 255.);
+                let value = match item.mode {   // (source, destination) operators
+                    MaskMode::Add        => sa * opacity + da * (1. - sa * opacity),
+                    MaskMode::Subtract   => da * (1. - sa * opacity),
+                    MaskMode::Intersect  => da * (1. - opacity + sa * opacity),
+                    MaskMode::Lighten    => da + (da.max(sa) - da) * opacity,
+                    MaskMode::Darken     => da + (da.min(sa) - da) * opacity,
+                    MaskMode::Difference => sa * opacity * (1. - da) + da * (1. - sa * opacity),
+                    MaskMode::None => unreachable!(),
+                };  *dst = (value * 255.).round() as u8;
+            }   initialized = true;
+        }
+
+        if initialized {
+            for (px, &coverage) in content.data_mut().chunks_exact_mut(4).zip(accum.data()) {
+                px.iter_mut().for_each(|ch| *ch = mul_u8(*ch, coverage));
+            }
+        }   Ok(content)
+    }
+
+    fn apply_matte(&mut self, mut content: Self::Image, matte: Self::Image,
+        mode: MatteMode) -> Result<Self::Image, Self::Error> {
+        // Rec.709/sRGB luminance weights of premultiplied RGBA
+        const LUMA_RGB: [f32; 3] = [0.2126, 0.7152, 0.0722];
+
+        if matches!(mode, MatteMode::Normal) { return Ok(content) }
+        for (px, src) in content.data_mut().chunks_exact_mut(4)
+            .zip(matte.data().chunks_exact(4)) {
+            let coverage = match mode {
+                MatteMode::Alpha | MatteMode::InvertedAlpha => src[3],
+                _ => (LUMA_RGB[0] * src[0] as f32 + LUMA_RGB[1] * src[1] as f32 +
+                      LUMA_RGB[2] * src[2] as f32).round() as u8,
+            };
+            let coverage = if matches!(mode,
+                MatteMode::InvertedAlpha | MatteMode::InvertedLuma) {
+                u8::MAX - coverage } else { coverage };
+            px.iter_mut().for_each(|ch| *ch = mul_u8(*ch, coverage));
+        }   Ok(content)
+    }
+
+    fn present(&mut self, image: Self::Image) -> Result<(), Self::Error> {
+        self.blit(&image, SkBlend::SourceOver);     Ok(())
+    }
+
+    fn present_blended(&mut self, image: Self::Image,
+        mode: BlendMode) -> Result<(), Self::Error> {
+        let op = match mode {
+            BlendMode::Normal     => SkBlend::SourceOver,
+            BlendMode::Multiply   => SkBlend::Multiply,
+            BlendMode::Screen     => SkBlend::Screen,
+            BlendMode::Overlay    => SkBlend::Overlay,
+            BlendMode::Darken     => SkBlend::Darken,
+            BlendMode::Lighten    => SkBlend::Lighten,
+            BlendMode::ColorDodge => SkBlend::ColorDodge,
+            BlendMode::ColorBurn  => SkBlend::ColorBurn,
+            BlendMode::HardLight  => SkBlend::HardLight,
+            BlendMode::SoftLight  => SkBlend::SoftLight,
+            BlendMode::Difference => SkBlend::Difference,
+            BlendMode::Exclusion  => SkBlend::Exclusion,
+            BlendMode::Hue        => SkBlend::Hue,
+            BlendMode::Saturation => SkBlend::Saturation,
+            BlendMode::Color      => SkBlend::Color,
+            BlendMode::Luminosity => SkBlend::Luminosity,
+            BlendMode::Add        => SkBlend::Plus,
+            // tiny-skia has no hard-mix operator
+            BlendMode::HardMix => {
+                blend_pixels(self.pixmap.data_mut(), image.data(), mode, false);
+                return Ok(())
+            }
+        };  self.blit(&image, op);  Ok(())
+    }
+    fn apply_effects(&mut self, mut image: Self::Image,
+        effects: &[LayerEffect]) -> Result<Self::Image, Self::Error> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        apply_effects(image.data_mut(), width, height, width * 4, false, effects);
+        Ok(image)
+    }
+    fn apply_styles(&mut self, mut image: Self::Image,
+        styles: &[StyleStage]) -> Result<Self::Image, Self::Error> {
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        apply_styles(image.data_mut(), width, height, width * 4, false, styles);
+        Ok(image)
+    }
+    fn discard(&mut self, _: Self::Image) {}
+}
+
+/// PNG is decoded natively, other formats by the `image` crate if enabled.
+fn decode_image(data: &[u8]) -> Option<Pixmap> {
+    if let Ok(pixmap) = Pixmap::decode_png(data) { return Some(pixmap) }
+    #[cfg(feature = "image")] {
+        let image = image::load_from_memory(data).ok()?.into_rgba8();
+        let size = tiny_skia::IntSize::from_wh(image.width(), image.height())?;
+        let mut pixels = image.into_raw();
+        for pixel in pixels.chunks_exact_mut(4) {
+            let alpha = pixel[3];
+            pixel[..3].iter_mut().for_each(|value| *value = mul_u8(*value, alpha));
+        }   Pixmap::from_vec(pixels, size)
+    }
+    #[cfg(not(feature = "image"))] None
+}
+
+fn mul_u8(value: u8, coverage: u8) -> u8 {
+    ((value as u32 * coverage as u32 + 127) / 255) as u8
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+    use crate::core::render::LottieRuntime;
+    use kurbo::Shape;
+
+    fn render(json: &str, size: (u32, u32), frame: f32) -> SkiaContext {
+        let mut runtime = LottieRuntime::from_reader(json.as_bytes()).unwrap();
+        let mut context = SkiaContext::new(size.0, size.1).unwrap();
+        runtime.render_frame(&mut context, frame, Some(RGBA::new_u8(0, 0, 0, 0))).unwrap();
+        context
+    }
+    fn pixel(context: &SkiaContext, x: u32, y: u32) -> [u8; 4] {
+        let px = context.pixmap().pixel(x, y).unwrap();
+        [px.red(), px.green(), px.blue(), px.alpha()]
+    }
+
+    /// A shape layer drawing `shapes` into a 20x20 composition.
+    fn shape_layer(ind: u32, extra: &str, shapes: &str) -> String {
+        format!(r#"{{"ty":4,"ind":{ind},"ip":0,"op":1,"st":0,{extra}
+            "ks":{{}},"shapes":[{shapes}]}}"#)
+    }
+    fn composition(layers: &[String]) -> String {
+        format!(r#"{{"ip":0,"op":1,"fr":1,"w":20,"h":20,"layers":[{}]}}"#, layers.join(","))
+    }
+    const RED_FILL: &str = r#"{"ty":"fl","c":{"a":0,"k":[1,0,0,1]},"o":{"a":0,"k":100}}"#;
+    fn rect(x: f32, y: f32, w: f32, h: f32) -> String {
+        format!(r#"{{"ty":"rc","p":{{"a":0,"k":[{x},{y}]}},"s":{{"a":0,"k":[{w},{h}]}},
+            "r":{{"a":0,"k":0}}}}"#)
+    }
+
+    #[test] fn fills_with_anti_aliasing_and_both_fill_rules() {
+        let json = composition(&[shape_layer(1, "",
+            &format!("{},{RED_FILL}", rect(10., 10., 10.5, 10.)))]);
+        let context = render(&json, (20, 20), 0.);
+        assert_eq!(pixel(&context, 10, 10), [255, 0, 0, 255]);
+        assert_eq!(pixel(&context, 1, 1), [0; 4]);
+        let edge = pixel(&context, 4, 10)[3];   // a quarter covered from x = 4.75
+        assert!((48..=80).contains(&edge), "edge coverage {edge}");
+
+        let mut context = SkiaContext::new(10, 10).unwrap();
+        let mut path = BezPath::new();
+        path.extend(kurbo::Rect::new(1., 1., 9., 9.).path_elements(0.1));
+        path.extend(kurbo::Rect::new(3., 3., 7., 7.).path_elements(0.1));
+        let red = Shader::solid_color(RGBA::new_u8(255, 0, 0, 255));
+        context.fill_stroke(&path, None,
+            &(red.clone(), FSOpts::Fill(FillRule::EvenOdd))).unwrap();
+        assert_eq!(pixel(&context, 5, 5), [0; 4]);
+        assert_eq!(pixel(&context, 2, 2), [255, 0, 0, 255]);
+        context.fill_stroke(&path, None, &(red, FSOpts::Fill(FillRule::NonZero))).unwrap();
+        assert_eq!(pixel(&context, 5, 5), [255, 0, 0, 255]);
+    }
+
+    #[test] fn gradients_interpolate_and_honor_the_focal_point() {
+        let mut context = SkiaContext::new(100, 1).unwrap();
+        let stops = [(0., RGBA::new_u8(0, 0, 0, 255)), (1., RGBA::new_u8(255, 255, 255, 255))];
+        let linear = Shader::linear_gradient((0., 0.).into(), (100., 0.).into(), &stops);
+        let mut path = BezPath::new();
+        path.extend(kurbo::Rect::new(0., 0., 100., 1.).path_elements(0.1));
+        context.fill_stroke(&path, None, &(linear, FSOpts::Fill(FillRule::NonZero))).unwrap();
+        let (left, mid) = (pixel(&context, 2, 0)[0], pixel(&context, 50, 0)[0]);
+        assert!(left < 10 && (120..=136).contains(&mid), "{left} {mid}");
+
+        let radial = |fp: Vec2D| Shader::radial_gradient((50., 0.).into(), fp, (0., 50.), &stops);
+        context.fill_stroke(&path, None,
+            &(radial((50., 0.).into()), FSOpts::Fill(FillRule::NonZero))).unwrap();
+        let centered = pixel(&context, 30, 0)[0];
+        assert!((95..=110).contains(&centered), "{centered}");
+        context.fill_stroke(&path, None,
+            &(radial((25., 0.).into()), FSOpts::Fill(FillRule::NonZero))).unwrap();
+        assert!(pixel(&context, 30, 0)[0] < centered / 2);
+    }
+
+    #[test] fn strokes_apply_width_caps_and_dashes() {
+        let mut context = SkiaContext::new(40, 10).unwrap();
+        let mut path = BezPath::new();
+        path.move_to((5., 5.));  path.line_to((35., 5.));
+        let stroke = |cap, dash| FSOpts::Stroke { width: 4., limit: 4.,
+            join: LineJoin::Miter, cap, dash };
+        let red = Shader::solid_color(RGBA::new_u8(255, 0, 0, 255));
+        context.fill_stroke(&path, None,
+            &(red.clone(), stroke(LineCap::Butt, (0., vec![5., 5.])))).unwrap();
+        assert_eq!(pixel(&context, 7, 5)[3], 255);
+        assert_eq!(pixel(&context, 12, 5)[3], 0);
+        assert_eq!(pixel(&context, 17, 4)[3], 255);
+        assert_eq!(pixel(&context, 7, 8)[3], 0);
+        assert_eq!(pixel(&context, 3, 5)[3], 0);
+
+        context.fill_stroke(&path, None,
+            &(red, stroke(LineCap::Square, (0., Vec::new())))).unwrap();
+        assert_eq!(pixel(&context, 12, 5)[3], 255);
+        assert_eq!(pixel(&context, 3, 5)[3], 255);
+    }
+
+    #[test] fn masks_combine_coverage_by_mode_and_inversion() {
+        let mask = |mode: &str, inv: bool, [x0, y0, x1, y1]: [u32; 4]| format!(
+            r#"{{"mode":"{mode}","inv":{inv},"o":{{"a":0,"k":100}},"pt":{{"a":0,"k":
+            {{"c":true,"v":[[{x0},{y0}],[{x1},{y0}],[{x1},{y1}],[{x0},{y1}]],
+            "i":[[0,0],[0,0],[0,0],[0,0]],"o":[[0,0],[0,0],[0,0],[0,0]]}}}}}}"#);
+        let layer = |masks: &[String]| composition(&[shape_layer(1,
+            &format!(r#""hasMask":true,"masksProperties":[{}],"#, masks.join(",")),
+            &format!("{},{RED_FILL}", rect(10., 10., 20., 20.)))]);
+
+        let context = render(&layer(&[mask("a", false, [0, 0, 10, 20]),
+            mask("s", false, [0, 0, 10, 10])]), (20, 20), 0.);
+        assert_eq!(pixel(&context, 5, 15)[3], 255);
+        assert_eq!(pixel(&context, 5, 5)[3], 0);
+        assert_eq!(pixel(&context, 15, 15)[3], 0);
+
+        let context = render(&layer(&[mask("i", true, [0, 0, 10, 20])]), (20, 20), 0.);
+        assert_eq!(pixel(&context, 5, 5)[3], 0);
+        assert_eq!(pixel(&context, 15, 5), [255, 0, 0, 255]);
+    }
+
+    #[test] fn track_mattes_keep_content_by_alpha_or_luma() {
+        let matte = |tt: u8| composition(&[
+            shape_layer(1, r#""td":1,"#, &format!(
+                r#"{},{{"ty":"fl","c":{{"a":0,"k":[0.5,0.5,0.5,1]}},"o":{{"a":0,"k":100}}}}"#,
+                rect(5., 10., 10., 20.))),
+            shape_layer(2, &format!(r#""tt":{tt},"#),
+                &format!("{},{RED_FILL}", rect(10., 10., 20., 20.))),
+        ]);
+        let context = render(&matte(1), (20, 20), 0.);
+        assert_eq!(pixel(&context, 5, 10), [255, 0, 0, 255]);
+        assert_eq!(pixel(&context, 15, 10), [0; 4]);
+
+        let context = render(&matte(2), (20, 20), 0.);
+        assert_eq!(pixel(&context, 5, 10), [0; 4]);
+        assert_eq!(pixel(&context, 15, 10), [255, 0, 0, 255]);
+
+        let luma = pixel(&render(&matte(3), (20, 20), 0.), 5, 10);
+        assert!((120..=136).contains(&luma[3]) && luma[0] == luma[3], "{luma:?}");
+    }
+
+    #[test] fn blended_layers_use_the_separable_and_cpu_operators() {
+        let blended = |mode| {
+            let mut context = SkiaContext::new(1, 1).unwrap();
+            context.clear_rect_with(0, 0, 1, 1, RGBA::new_u8(100, 200, 50, 255)).unwrap();
+            let mut image = Pixmap::new(1, 1).unwrap();
+            image.fill(RGBA::new_u8(200, 100, 50, 255).into());
+            context.present_blended(image, mode).unwrap();  pixel(&context, 0, 0)
+        };
+        assert_eq!(blended(BlendMode::Multiply), [78, 78, 10, 255]);
+        assert_eq!(blended(BlendMode::HardMix),  [255, 255, 0, 255]);
+    }
+
+    #[test] fn images_decode_once_and_skip_undecodable_data() {
+        let mut image = Pixmap::new(2, 2).unwrap();
+        image.fill(Color::from_rgba8(0, 0, 255, 255));
+        let png = image.encode_png().unwrap();
+        let mut context = SkiaContext::new(4, 4).unwrap();
+        context.draw_image(&png, 4., 4.).unwrap();
+        context.draw_image(&png, 0., 0.).unwrap();
+        assert_eq!(context.images.len(), 1);
+        assert_eq!(pixel(&context, 3, 3), [0, 0, 255, 255]);
+
+        let mut context = SkiaContext::new(4, 4).unwrap();
+        context.draw_image(b"\xff\xd8\xff\xe0", 4., 4.).unwrap();
+        assert!(context.images.values().all(Option::is_none));
+        assert_eq!(pixel(&context, 3, 3), [0; 4]);
+    }
+
+    #[cfg(feature = "image")] #[test] fn images_decode_other_formats_by_the_image_crate() {
+        let mut jpeg = std::io::Cursor::new(Vec::new());
+        image::RgbImage::from_pixel(2, 2, image::Rgb([0, 0, 255]))
+            .write_to(&mut jpeg, image::ImageFormat::Jpeg).unwrap();
+        let mut context = SkiaContext::new(2, 2).unwrap();
+        context.draw_image(jpeg.get_ref(), 0., 0.).unwrap();
+        let [r, g, b, a] = pixel(&context, 1, 1);
+        assert!(r < 16 && g < 16 && 240 < b && a == 255, "{:?}", [r, g, b, a]);
+    }
+}
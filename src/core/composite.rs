@@ -1,4 +1,7 @@
-use super::{render::RenderContext, schema::{MatteMode, VisualLayer}, style::TM2DwO};
+use super::{render::RenderContext, helpers::{Vec2D, RGBA}, style::{MatrixConv, TM2DwO},
+    schema::{BlendMode, MatteMode, VisualLayer, LayerStyleItem, InnerShadowStyle,
+        OuterGlowStyle, GradientType, Value, ColorValue},
+    effects::{LayerEffect, resolve_effects, blur}};
 
 /// Backend operations needed only for layer masks and track mattes.
 /// Image arguments transfer ownership and must be released even when an operation fails.
@@ -15,10 +18,35 @@ pub trait CompositeContext: RenderContext {
         mode: MatteMode) -> Result<Self::Image, Self::Error>;
     fn present(&mut self, image: Self::Image) -> Result<(), Self::Error>;
     fn discard(&mut self, image: Self::Image);
+
+    /// Presents with a non-normal layer blend mode, which motion blur relies on for `Add`;
+    /// backends without the operator can read both targets back and use `blend_pixels`.
+    fn present_blended(&mut self, image: Self::Image,
+        mode: BlendMode) -> Result<(), Self::Error>;
+
+    /// Runs layer effects on the masked image; backends opt in by reading pixels back
+    /// and calling `apply_effects`, the default leaves the image unaffected.
+    fn apply_effects(&mut self, image: Self::Image,
+        effects: &[LayerEffect]) -> Result<Self::Image, Self::Error> {
+        let _ = effects;    Ok(image)
+    }
+
+    /// Runs layer styles after the effects; backends opt in by reading pixels back
+    /// and calling `apply_styles`, the default leaves the image unstyled.
+    fn apply_styles(&mut self, image: Self::Image,
+        styles: &[StyleStage]) -> Result<Self::Image, Self::Error> {
+        let _ = styles;     Ok(image)
+    }
+
+    /// Brackets the layers of a precomp, for backends keeping the layer hierarchy.
+    fn begin_group(&mut self, layer: &VisualLayer) -> Result<(), Self::Error> {
+        let _ = layer;  Ok(())
+    }
+    fn end_group(&mut self) -> Result<(), Self::Error> { Ok(()) }
 }
 
 pub(super) struct Compositor<I> { pending: Vec<Pending<I>> }
-struct Pending<I> { mode: MatteMode, source: Option<u32>, image: I }
+struct Pending<I> { mode: MatteMode, source: Option<u32>, blend: BlendMode, image: I }
 
 impl<I> Default for Compositor<I> { fn default() -> Self { Self { pending: Vec::new() } } }
 
@@ -29,27 +57,38 @@ impl<I> Compositor<I> {
         layer: &VisualLayer, transform: &TM2DwO<RC::TM2D>, frame: f32,
         draw: impl FnOnce(&mut RC) -> Result<(), RC::Error>) -> Result<(), RC::Error> {
         let pending = self.pending.iter().rposition(|matte| accepts(layer, matte));
-        if  pending.is_none() && layer.tt.is_none() && layer.masks.is_empty() {
+        let scale = || {
+            let [a, b, c, d, ..] = transform.0.coeffs();
+            (a * d - b * c).abs().sqrt() as _
+        };
+        let effects = if layer.ef.is_empty() { Vec::new() } else {
+            resolve_effects(&layer.ef, frame, scale())
+        };
+        let styles  = if layer.sy.is_empty() { Vec::new() } else {
+            resolve_styles (&layer.sy, frame, scale())
+        };
+        if  pending.is_none() && layer.tt.is_none() && layer.masks.is_empty() &&
+            layer.bm == BlendMode::Normal && effects.is_empty() && styles.is_empty() {
             return draw(context)
         }
 
-        let target = context.begin_offscreen()?;
-        if let Err(error) = draw(context) {
-            context.abort_offscreen(target);
-            return Err(error)
-        }
-        let mut image = context.end_offscreen(target)?;
+        let mut image = offscreen(context, draw)?;
         if !layer.masks.is_empty() {
             image = context.apply_masks(image, layer, transform, frame)?;
         }
+        if !effects.is_empty() { image = context.apply_effects(image, &effects)?; }
+        if !styles .is_empty() { image = context.apply_styles (image, &styles)?; }
+        // a resolved matte presents the matted content with the content layer's mode
+        let mut blend = layer.bm;
         if let Some(index) = pending {
-            let matte = self.pending.remove(index);
+            let matte = self.pending.remove(index);     blend = matte.blend;
             image = context.apply_matte(matte.image, image, matte.mode)?;
         }
 
         if let Some(mode) = layer.tt {
-            self.pending.push(Pending { mode, source: layer.tp, image }); Ok(())
-        } else { context.present(image) }
+            self.pending.push(Pending { mode, source: layer.tp, blend, image }); Ok(())
+        } else if blend == BlendMode::Normal { context.present(image)
+        } else { context.present_blended(image, blend) }
     }
 
     /// A hidden or unsupported matte source contributes transparent coverage and
@@ -66,7 +105,501 @@ impl<I> Compositor<I> {
     }
 }
 
+/// Averages the layer drawn at every motion-blur sample by adding their offscreen
+/// images with the sample opacity divided by their count. Without samples the layer
+/// is drawn once at `transform`.
+pub(super) fn accumulate<RC: CompositeContext>(context: &mut RC,
+    transform: &TM2DwO<RC::TM2D>, samples: &[TM2DwO<RC::TM2D>],
+    mut draw: impl FnMut(&mut RC, &TM2DwO<RC::TM2D>) -> Result<(), RC::Error>)
+    -> Result<(), RC::Error> {
+    if samples.is_empty() { return draw(context, transform) }
+    let sum = offscreen(context, |context| {
+        for sample in samples {
+            let image = offscreen(context, |context| draw(context, sample))?;
+            context.present_blended(image, BlendMode::Add)?;
+        }   Ok(())
+    })?;    context.present(sum)
+}
+
+fn offscreen<RC: CompositeContext>(context: &mut RC,
+    draw: impl FnOnce(&mut RC) -> Result<(), RC::Error>) -> Result<RC::Image, RC::Error> {
+    let target = context.begin_offscreen()?;
+    if let Err(error) = draw(context) {
+        context.abort_offscreen(target);    return Err(error)
+    }   context.end_offscreen(target)
+}
+
 fn accepts<I>(layer: &VisualLayer, matte: &Pending<I>) -> bool {
     !layer.td.is_some_and(|td| !td.as_bool()) && layer.base.ind.is_none_or(|id|
         matte.source.is_none_or(|source| id == source))
 }
+
+/// Blends premultiplied 8-bit source pixels onto the destination in place, following
+/// the W3C compositing formulas (`Add` saturates like `lighter`). `bgra` selects the
+/// channel order used by the luminosity weights of the non-separable modes.
+pub fn blend_pixels(dst: &mut [u8], src: &[u8], mode: BlendMode, bgra: bool) {
+    let (r, b) = if bgra { (2, 0) } else { (0, 2) };
+    for (dp, sp) in dst.chunks_exact_mut(4).zip(src.chunks_exact(4)) {
+        if sp[3] == 0 { continue }
+        let (sa, da) = (sp[3] as f32 / 255., dp[3] as f32 / 255.);
+        let unpremul = |px: &[u8], alpha: f32| if 0. < alpha {
+            [r, 1, b].map(|i| (px[i] as f32 / 255. / alpha).min(1.))
+        } else { [0.; 3] };
+        let (cs, cb) = (unpremul(sp, sa), unpremul(dp, da));
+
+        let mixed = match mode {
+            BlendMode::Normal => cs,
+            BlendMode::Hue => set_lum(set_sat(cs, sat(cb)), lum(cb)),
+            BlendMode::Saturation => set_lum(set_sat(cb, sat(cs)), lum(cb)),
+            BlendMode::Color => set_lum(cs, lum(cb)),
+            BlendMode::Luminosity => set_lum(cb, lum(cs)),
+            BlendMode::Add => {
+                for i in 0..4 { dp[i] = dp[i].saturating_add(sp[i]); }  continue
+            }
+            _ => [0, 1, 2].map(|i| blend_channel(cb[i], cs[i], mode)),
+        };
+
+        let alpha = sa + da - sa * da;
+        for (i, ch) in [r, 1, b].into_iter().enumerate() {
+            let value = sp[ch] as f32 / 255. * (1. - da) +
+                dp[ch] as f32 / 255. * (1. - sa) + sa * da * mixed[i];
+            dp[ch] = (value.min(alpha) * 255.).round() as u8;
+        }   dp[3] = (alpha * 255.).round() as u8;
+    }
+}
+
+fn blend_channel(cb: f32, cs: f32, mode: BlendMode) -> f32 {
+    match mode {
+        BlendMode::Multiply => cb * cs,
+        BlendMode::Screen   => cb + cs - cb * cs,
+        BlendMode::Overlay  => blend_channel(cs, cb, BlendMode::HardLight),
+        BlendMode::Darken   => cb.min(cs),
+        BlendMode::Lighten  => cb.max(cs),
+        BlendMode::ColorDodge => if cb <= 0. { 0. } else if 1. <= cs { 1. }
+            else { (cb / (1. - cs)).min(1.) },
+        BlendMode::ColorBurn  => if 1. <= cb { 1. } else if cs <= 0. { 0. }
+            else { 1. - ((1. - cb) / cs).min(1.) },
+        BlendMode::HardLight  => if cs <= 0.5 { cb * 2. * cs }
+            else { blend_channel(cb, 2. * cs - 1., BlendMode::Screen) },
+        BlendMode::SoftLight  => if cs <= 0.5 {
+            cb - (1. - 2. * cs) * cb * (1. - cb)
+        } else {
+            let d = if cb <= 0.25 { ((16. * cb - 12.) * cb + 4.) * cb } else { cb.sqrt() };
+            cb + (2. * cs - 1.) * (d - cb)
+        },
+        BlendMode::Difference => (cb - cs).abs(),
+        BlendMode::Exclusion  => cb + cs - 2. * cb * cs,
+        BlendMode::HardMix    => if 1. <= cb + cs { 1. } else { 0. },
+        BlendMode::Add => (cb + cs).min(1.),
+        BlendMode::Normal | BlendMode::Hue | BlendMode::Saturation |
+        BlendMode::Color  | BlendMode::Luminosity => cs,
+    }
+}
+
+fn lum(c: [f32; 3]) -> f32 { 0.3 * c[0] + 0.59 * c[1] + 0.11 * c[2] }
+fn sat(c: [f32; 3]) -> f32 { c[0].max(c[1]).max(c[2]) - c[0].min(c[1]).min(c[2]) }
+
+fn set_lum(c: [f32; 3], l: f32) -> [f32; 3] {
+    let d = l - lum(c);     let c = c.map(|v| v + d);
+    let (l, n, x) = (lum(c), c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    c.map(|v| if n < 0. { l + (v - l) * l / (l - n) }
+         else if 1. < x { l + (v - l) * (1. - l) / (x - l) } else { v })
+}
+
+fn set_sat(c: [f32; 3], s: f32) -> [f32; 3] {
+    let (min, max) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+    if max <= min { return [0.; 3] }
+    c.map(|v| (v - min) * s / (max - min))
+}
+
+/// Layer style parameters at one frame, with distances in device pixels and the
+/// opacity folded into color alpha. Style blend modes composite as normal.
+#[derive(Clone)] pub enum StyleStage {
+    /// Offset and softened layer alpha behind the layer, or its inverse atop it
+    Shadow { inner: bool, color: RGBA, offset: Vec2D, size: f32, spread: f32 },
+    /// Softened layer alpha behind the layer, or inside from its edge or center
+    Glow { inner: bool, edge: bool, color: RGBA, size: f32, spread: f32 },
+    /// Outside stroke of the layer alpha
+    Stroke { color: RGBA, size: f32 },
+    /// Difference of two offset and blurred copies of the layer alpha
+    Satin { color: RGBA, offset: Vec2D, size: f32, invert: bool },
+    /// Inner bevel lit from `angle` at `altitude` (radians) over a `size` wide slope
+    BevelEmboss { highlight: RGBA, shadow: RGBA, angle: f32, altitude: f32,
+        size: f32, depth: f32 },
+    ColorOverlay(RGBA),
+    /// Gradient at `angle` (radians) over the layer bounds (`align`) or the target
+    GradientOverlay { stops: Vec<(f32, RGBA)>, radial: bool, angle: f32,
+        scale: f32, reverse: bool, align: bool },
+}
+
+impl StyleStage {
+    /// Photoshop stacking, from fill overlays up to the inner styles, then the
+    /// stroke and outer styles going successively below the layer.
+    fn rank(&self) -> u8 {
+        match self {
+            Self::ColorOverlay(_) => 0, Self::GradientOverlay { .. } => 1,
+            Self::Satin { .. } => 2,    Self::Glow { inner: true, .. } => 3,
+            Self::Shadow { inner: true, .. } => 4,  Self::BevelEmboss { .. } => 5,
+            Self::Stroke { .. } => 6,   Self::Glow { inner: false, .. } => 7,
+            Self::Shadow { inner: false, .. } => 8,
+        }
+    }
+}
+
+/// Resolves layer styles with Photoshop defaults for absent values, `scale`
+/// converts layer units.
+pub(super) fn resolve_styles(styles: &[LayerStyleItem],
+    fnth: f32, scale: f32) -> Vec<StyleStage> {
+    let value = |value: &Option<Value>, default: f32|
+        value.as_ref().map_or(default, |value| value.get_value(fnth));
+    let color = |color: &Option<ColorValue>, default: RGBA, opacity: f32| {
+        let mut color = color.as_ref().map_or(default, |color| color.get_value(fnth));
+        color.a = (color.a as f32 * (opacity / 100.).clamp(0., 1.)).round() as _;  color
+    };
+    let offset = |angle: &Option<Value>, distance: f32| {
+        let (angle, distance) = (value(angle, 120.).to_radians(), distance * scale);
+        Vec2D { x: -angle.cos() * distance, y: angle.sin() * distance }
+    };
+    let (black, white) = (RGBA::default(), RGBA::new_u8(255, 255, 255, 255));
+    let shadow = |inner, shadow: &InnerShadowStyle| StyleStage::Shadow { inner,
+        color: color(&shadow.color, black, value(&shadow.opacity, 75.)),
+        offset: offset(&shadow.angle, value(&shadow.distance, 5.)),
+        size: value(&shadow.bs, 5.) * scale, spread: value(&shadow.ch, 0.) / 100.,
+    };
+    // glows have no size property, their range is taken in pixels instead
+    let glow = |inner, edge, glow: &OuterGlowStyle| StyleStage::Glow { inner, edge,
+        color: color(&glow.color, white, value(&glow.opacity, 75.)),
+        size: value(&glow.range, 5.) * scale, spread: value(&glow.ch, 0.) / 100.,
+    };
+
+    styles.iter().map(|style| match style {
+        LayerStyleItem::DropShadow(style)  => shadow(false, &style.inner),
+        LayerStyleItem::InnerShadow(style) => shadow(true,  style),
+        LayerStyleItem::OuterGlow(style) => glow(false, false, style),
+        // the source popup is 1 for the center and 2 for the edge
+        LayerStyleItem::InnerGlow(style) =>
+            glow(true, value(&style.sr, 2.) != 1., &style.outer),
+        LayerStyleItem::Stroke(stroke) => StyleStage::Stroke {
+            color: color(&stroke.color, black, 100.), size: value(&stroke.size, 3.) * scale,
+        },
+        LayerStyleItem::Satin(satin) => StyleStage::Satin {
+            color: color(&satin.color, black, value(&satin.opacity, 50.)),
+            offset: offset(&satin.angle, value(&satin.distance, 11.)),
+            size: value(&satin.size, 14.) * scale, invert: value(&satin.invert, 1.) != 0.,
+        },
+        LayerStyleItem::BevelEmboss(bevel) => {
+            let size = value(&bevel.size, 5.) * scale;
+            StyleStage::BevelEmboss { size, depth: size * value(&bevel.sr, 100.) / 100.,
+                highlight: color(&bevel.hc, white, value(&bevel.ho, 75.)),
+                shadow: color(&bevel.sc, black, value(&bevel.so, 75.)),
+                angle: value(&bevel.angle, 120.).to_radians(),
+                altitude: value(&bevel.ll, 30.).clamp(1., 90.).to_radians(),
+            }
+        }
+        LayerStyleItem::ColorOverlay(overlay) => StyleStage::ColorOverlay(
+            color(&overlay.color, black, value(&overlay.opacity, 100.))),
+        LayerStyleItem::GradientOverlay(overlay) => StyleStage::GradientOverlay {
+            stops: overlay.gf.as_ref().map_or_else(Vec::new, |colors|
+                colors.resolve(fnth, value(&overlay.opacity, 100.) / 100.)),
+            radial: matches!(overlay.gt, Some(GradientType::Radial)),
+            angle: value(&overlay.angle, 90.).to_radians(),
+            scale: value(&overlay.size, 100.) / 100.,
+            reverse: value(&overlay.reverse, 0.) != 0.,
+            align: value(&overlay.align, 1.) != 0.,
+        },
+    }).collect()
+}
+
+/// Renders layer styles on premultiplied 8-bit pixels, `stride` in bytes, and
+/// `bgra` selects the channel order of the buffer. Every stage derives its coverage
+/// from the alpha of the unstyled layer.
+pub fn apply_styles(pixels: &mut [u8], width: usize, height: usize,
+    stride: usize, bgra: bool, styles: &[StyleStage]) {
+    if width == 0 || height == 0 { return }
+    let order = if bgra { [2, 1, 0] } else { [0, 1, 2] };
+    let alpha = (0..height).flat_map(|y| (0..width).map(move |x| (y, x)))
+        .map(|(y, x)| pixels[y * stride + x * 4 + 3]).collect::<Vec<_>>();
+    let mut stages = styles.iter().collect::<Vec<_>>();
+    stages.sort_by_key(|stage| stage.rank());
+
+    // composites per-pixel colors and coverage atop (source-atop) or behind the layer
+    let mut paint = |behind: bool, shade: &dyn Fn(usize, usize) -> (RGBA, f32)| {
+        for y in 0..height { for x in 0..width {
+            let (color, cover) = shade(x, y);
+            let cover = cover.clamp(0., 1.) * color.a as f32 / 255.;
+            if cover <= 0. { continue }
+            let pixel = &mut pixels[y * stride + x * 4..][..4];
+            let da = pixel[3] as f32 / 255.;
+            let (keep, add) = if behind { (1., cover * (1. - da)) }
+                else { (1. - cover, cover * da) };
+            for (i, c) in order.into_iter().zip([color.r, color.g, color.b]) {
+                pixel[i] = (pixel[i] as f32 * keep + c as f32 * add)
+                    .round().min(255.) as u8;
+            }
+            if behind { pixel[3] = (pixel[3] as f32 + 255. * add).round().min(255.) as u8; }
+        } }
+    };
+    let at = |map: &[u8], x: usize, y: usize| map[y * width + x] as f32 / 255.;
+    let inverse = || alpha.iter().map(|&a| 255 - a).collect::<Vec<_>>();
+
+    for stage in stages { match stage {
+        &StyleStage::Shadow { inner, color, offset, size, spread } => {
+            let map = shifted(&alpha, width, height, offset, inner);
+            let map = soften(map, width, height, size, spread, inner);
+            paint(!inner, &|x, y| (color, at(&map, x, y)));
+        }
+        &StyleStage::Glow { inner, edge, color, size, spread } => {
+            let map = soften(if inner { inverse() } else { alpha.clone() },
+                width, height, size, spread, inner);
+            let center = inner && !edge;
+            paint(!inner, &|x, y|
+                (color, if center { 1. - at(&map, x, y) } else { at(&map, x, y) }));
+        }
+        &StyleStage::Stroke { color, size } => {
+            let map = dilate(&alpha, width, height, size);
+            paint(true, &|x, y| (color, at(&map, x, y)));
+        }
+        &StyleStage::Satin { color, offset, size, invert } => {
+            let sigma = Vec2D { x: size / 2., y: size / 2. };
+            let [mut lead, mut lag] = [1., -1.].map(|sign|
+                shifted(&alpha, width, height,
+                    Vec2D { x: offset.x * sign, y: offset.y * sign }, false));
+            blur(&mut lead, width, height, width, 1, sigma, false);
+            blur(&mut lag,  width, height, width, 1, sigma, false);
+            paint(false, &|x, y| {
+                let value = (at(&lead, x, y) - at(&lag, x, y)).abs();
+                (color, if invert { 1. - value } else { value })
+            });
+        }
+        &StyleStage::BevelEmboss { highlight, shadow, angle, altitude, size, depth } => {
+            let mut height_map = alpha.clone();
+            blur(&mut height_map, width, height, width, 1,
+                Vec2D { x: size / 2., y: size / 2. }, true);
+            let light = [angle.cos() * altitude.cos(), -angle.sin() * altitude.cos(),
+                altitude.sin()];
+            let shade = |x: usize, y: usize| {
+                let slope = |a: (usize, usize), b: (usize, usize)|
+                    (at(&height_map, b.0, b.1) - at(&height_map, a.0, a.1)) / 2. * depth;
+                let gx = slope((x.saturating_sub(1), y), ((x + 1).min(width  - 1), y));
+                let gy = slope((x, y.saturating_sub(1)), (x, (y + 1).min(height - 1)));
+                let normal = (gx * gx + gy * gy + 1.).sqrt();
+                (-gx * light[0] - gy * light[1] + light[2]) / normal - light[2]
+            };
+            let (lit, dark) = ((1. - light[2]).max(f32::EPSILON), light[2]);
+            paint(false, &|x, y| (highlight,  shade(x, y) / lit));
+            paint(false, &|x, y| (shadow,    -shade(x, y) / dark));
+        }
+        &StyleStage::ColorOverlay(color) => paint(false, &|_, _| (color, 1.)),
+        StyleStage::GradientOverlay { stops, radial, angle, scale, reverse, align } => {
+            let Some(&(_, last)) = stops.last() else { continue };
+            let (mut min, mut max) = ((0, 0), (width - 1, height - 1));
+            if *align {
+                (min, max) = ((width, height), (0, 0));
+                for (index, _) in alpha.iter().enumerate().filter(|(_, &a)| a != 0) {
+                    let (x, y) = (index % width, index / width);
+                    min = (min.0.min(x), min.1.min(y));
+                    max = (max.0.max(x), max.1.max(y));
+                }   if max.0 < min.0 { continue }
+            }
+            let (w, h) = ((max.0 - min.0 + 1) as f32, (max.1 - min.1 + 1) as f32);
+            let center = ((min.0 + max.0 + 1) as f32 / 2., (min.1 + max.1 + 1) as f32 / 2.);
+            let dir = (angle.cos(), -angle.sin());
+            let length = if *radial { w.hypot(h) / 2. }
+                else { (w * dir.0).abs() + (h * dir.1).abs() } * scale.max(f32::EPSILON);
+
+            paint(false, &|x, y| {
+                let (px, py) = (x as f32 + 0.5 - center.0, y as f32 + 0.5 - center.1);
+                let t = if *radial { px.hypot(py) / length }
+                    else { (px * dir.0 + py * dir.1) / length + 0.5 };
+                let t = if *reverse { 1. - t } else { t }.clamp(0., 1.);
+                (match stops.iter().position(|&(offset, _)| t <= offset) {
+                    Some(0) => stops[0].1,  None => last,
+                    Some(index) => {
+                        let ((lo, from), (hi, to)) = (stops[index - 1], stops[index]);
+                        let factor = if hi <= lo { 1. } else { (t - lo) / (hi - lo) };
+                        let mix = |a: u8, b: u8|
+                            (a as f32 + (b as f32 - a as f32) * factor).round() as u8;
+                        RGBA::new_u8(mix(from.r, to.r), mix(from.g, to.g),
+                            mix(from.b, to.b), mix(from.a, to.a))
+                    }
+                }, 1.)
+            });
+        }
+    } }
+}
+
+/// Alpha map moved by `offset`, the uncovered area reads as transparent, or as
+/// opaque for the inverted map of inner styles.
+fn shifted(alpha: &[u8], width: usize, height: usize,
+    offset: Vec2D, invert: bool) -> Vec<u8> {
+    let (dx, dy) = (offset.x.round() as isize, offset.y.round() as isize);
+    let (w, h) = (width as isize, height as isize);
+    (0..h).flat_map(|y| (0..w).map(move |x| (x - dx, y - dy))).map(|(x, y)| {
+        let value = if (0..w).contains(&x) && (0..h).contains(&y) {
+            alpha[(y * w + x) as usize] } else { 0 };
+        if invert { 255 - value } else { value }
+    }).collect()
+}
+
+/// Spreads a hard edge by `spread` of the size and blurs over the remaining size.
+fn soften(map: Vec<u8>, width: usize, height: usize,
+    size: f32, spread: f32, wrap: bool) -> Vec<u8> {
+    let spread = spread.clamp(0., 1.);
+    let mut map = dilate(&map, width, height, size * spread);
+    let sigma = size * (1. - spread) / 2.;
+    blur(&mut map, width, height, width, 1, Vec2D { x: sigma, y: sigma }, wrap);    map
+}
+
+/// Maximum over a disk of `radius`, i.e. morphological dilation. The disk is a stack of
+/// horizontal spans, so rows are maximized over each span width first, then combined
+/// vertically, in O(W·H·r) instead of over every point of the disk.
+fn dilate(map: &[u8], width: usize, height: usize, radius: f32) -> Vec<u8> {
+    let radius = radius.round() as usize;
+    if radius < 1 || width == 0 { return map.to_vec() }
+    let (mut dilated, mut spans) = (vec![0; map.len()], vec![0; map.len()]);
+    let (mut prefix, mut suffix) = (Vec::new(), Vec::new());
+    let half = |dy: usize| (radius * radius - dy * dy).isqrt();
+    for span in 0..=radius {
+        if (0..=radius).all(|dy| half(dy) != span) { continue }
+        for (row, out) in map.chunks_exact(width).zip(spans.chunks_exact_mut(width)) {
+            row_max(row, span, out, &mut prefix, &mut suffix);
+        }
+        for dy in (0..=radius).filter(|&dy| half(dy) == span) {
+            for y in 0..height {
+                for sy in [y.checked_sub(dy), (0 < dy).then_some(y + dy)] {
+                    let Some(sy) = sy.filter(|&sy| sy < height) else { continue };
+                    let (src, dst) = (&spans[sy * width..][..width],
+                        &mut dilated[y * width..][..width]);
+                    dst.iter_mut().zip(src).for_each(|(dst, &src)| *dst = (*dst).max(src));
+                }
+            }
+        }
+    }   dilated
+}
+
+/// Maximum of `row` over `[x - half, x + half]` for each `x`, by van Herk/Gil-Werman:
+/// the window spans at most two blocks of its size, maximized from either end.
+fn row_max(row: &[u8], half: usize, out: &mut [u8], prefix: &mut Vec<u8>, suffix: &mut Vec<u8>) {
+    let (size, len) = (half * 2 + 1, row.len() + half * 2);
+    prefix.clear();     prefix.resize(len, 0);
+    suffix.clear();     suffix.resize(len, 0);
+    prefix[half..][..row.len()].copy_from_slice(row);
+    suffix[half..][..row.len()].copy_from_slice(row);
+    for i in 1..len { if i % size != 0 { prefix[i] = prefix[i].max(prefix[i - 1]); } }
+    for i in (0..len - 1).rev() {
+        if (i + 1) % size != 0 { suffix[i] = suffix[i].max(suffix[i + 1]); }
+    }
+    for (x, out) in out.iter_mut().enumerate() { *out = suffix[x].max(prefix[x + half * 2]); }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    fn blend(dst: [u8; 4], src: [u8; 4], mode: BlendMode) -> [u8; 4] {
+        let mut out = dst;  blend_pixels(&mut out, &src, mode, false); out
+    }
+
+    #[test] fn separable_modes_match_reference_values_on_opaque_pixels() {
+        let (dst, src) = ([200, 100, 50, 255], [100, 100, 200, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Multiply), [78, 39, 39, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Screen),  [222, 161, 211, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Darken),  [100, 100, 50, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Lighten), [200, 100, 200, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Difference), [100, 0, 150, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Add), [255, 200, 250, 255]);
+        assert_eq!(blend(dst, src, BlendMode::HardMix), [255, 0, 0, 255]);
+        assert_eq!(blend(dst, src, BlendMode::Overlay), blend(src, dst, BlendMode::HardLight));
+    }
+
+    #[test] fn blending_keeps_uncovered_backdrop_and_source_over_alpha() {
+        assert_eq!(blend([10, 20, 30, 255], [0, 0, 0, 0], BlendMode::Multiply),
+            [10, 20, 30, 255]);
+        // an empty backdrop leaves the source unchanged for every mode
+        assert_eq!(blend([0, 0, 0, 0], [60, 30, 0, 128], BlendMode::Hue), [60, 30, 0, 128]);
+        assert_eq!(blend([0, 0, 0, 0], [60, 30, 0, 128], BlendMode::Screen), [60, 30, 0, 128]);
+        // half-covered source over opaque backdrop: premultiplied W3C result
+        assert_eq!(blend([255, 255, 255, 255], [0, 0, 0, 128], BlendMode::Multiply),
+            [127, 127, 127, 255]);
+    }
+
+    #[test] fn non_separable_modes_swap_hue_saturation_and_luminosity() {
+        let (red, gray) = ([255, 0, 0, 255], [128, 128, 128, 255]);
+        let color = blend(gray, red, BlendMode::Color);
+        let luminosity = |px: [u8; 4]| lum([px[0] as f32, px[1] as f32, px[2] as f32]);
+        assert!((luminosity(color) - 128.).abs() < 1.);
+        assert!(color[0] > color[1] && color[1] == color[2]);
+        assert_eq!(blend(red, gray, BlendMode::Luminosity), color);
+        assert_eq!(blend(gray, red, BlendMode::Saturation), gray);
+        assert_eq!(blend(red, [0, 0, 255, 255], BlendMode::Hue), [54, 54, 255, 255]);
+
+        let mut bgra = [0, 0, 255, 255];
+        blend_pixels(&mut bgra, &[128, 128, 128, 255], BlendMode::Luminosity, true);
+        assert_eq!(bgra, [color[2], color[1], color[0], 255]);
+    }
+
+    #[test] fn layer_styles_resolve_photoshop_angles_and_defaults() {
+        let styles = serde_json::from_str::<Vec<LayerStyleItem>>(r#"[
+            {"ty":1,"a":{"k":180},"d":{"k":4},"s":{"k":2}},
+            {"ty":0,"c":{"k":[1,0,0,1]},"s":{"k":1}},
+            {"ty":8,"gf":{"p":2,"k":{"k":[0,0,0,0,1,1,1,1]}},"a":{"k":0}}
+        ]"#).unwrap();
+        let stages = resolve_styles(&styles, 0., 2.);
+        let StyleStage::Shadow { inner, color, offset, size, spread } = stages[0]
+            else { panic!() };
+        assert_eq!((inner, color.a, size, spread), (false, 191, 4., 0.));
+        assert!((offset.x - 8.).abs() < 1e-5 && offset.y.abs() < 1e-5);
+        let StyleStage::Stroke { color, size } = stages[1] else { panic!() };
+        assert_eq!((color.r, color.a, size), (255, 255, 2.));
+        let StyleStage::GradientOverlay { ref stops, radial, .. } = stages[2]
+            else { panic!() };
+        assert_eq!((stops.len(), radial), (2, false));
+    }
+
+    #[test] fn styles_stack_inside_atop_and_outside_behind_the_layer() {
+        let (red, blue) = (RGBA::new_u8(255, 0, 0, 255), RGBA::new_u8(0, 0, 255, 255));
+        let mut pixels = [0, 0, 0, 0, 255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0];
+        apply_styles(&mut pixels, 4, 1, 16, false, &[
+            StyleStage::Stroke { color: blue, size: 1. }, StyleStage::ColorOverlay(red)]);
+        assert_eq!(pixels, [0, 0, 255, 255, 255, 0, 0, 255, 0, 0, 255, 255, 0, 0, 0, 0]);
+
+        let mut pixels = [255, 255, 255, 255, 0, 0, 0, 0, 0, 0, 0, 0];
+        apply_styles(&mut pixels, 3, 1, 12, true, &[StyleStage::Shadow { inner: false,
+            color: red, offset: Vec2D { x: 1., y: 0. }, size: 0., spread: 0. }]);
+        assert_eq!(pixels, [255, 255, 255, 255, 0, 0, 255, 255, 0, 0, 0, 0]);
+
+        let mut pixels = [255; 12];
+        apply_styles(&mut pixels, 3, 1, 12, false, &[StyleStage::Shadow { inner: true,
+            color: blue, offset: Vec2D { x: 1., y: 0. }, size: 0., spread: 0. }]);
+        assert_eq!(pixels, [0, 0, 255, 255, 255, 255, 255, 255, 255, 255, 255, 255]);
+    }
+
+    #[test] fn gradient_overlay_spans_the_layer_bounds() {
+        let mut pixels = [0, 0, 0, 0, 255, 255, 255, 255, 255, 255, 255, 255,
+            255, 255, 255, 255, 0, 0, 0, 0];
+        apply_styles(&mut pixels, 5, 1, 20, false, &[StyleStage::GradientOverlay {
+            stops: vec![(0., RGBA::new_u8(0, 0, 0, 255)),
+                        (1., RGBA::new_u8(240, 0, 0, 255))],
+            radial: false, angle: 0., scale: 1., reverse: false, align: true }]);
+        assert_eq!(pixels.chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>(),
+            [0, 40, 120, 200, 0]);
+        assert!(pixels.chunks(4).all(|pixel| pixel[1] == pixel[2]));
+    }
+
+    #[test] fn dilation_takes_the_maximum_over_a_disk() {
+        let (width, height) = (13, 11);
+        let map = (0..width * height).map(|i| (i * 37 % 101) as u8 * (i % 7 == 0) as u8)
+            .collect::<Vec<_>>();
+        for radius in [1., 2.4, 3., 5.] {
+            let r = f32::round(radius) as isize;
+            let expected = (0..height as isize).flat_map(|y| (0..width as isize)
+                .map(move |x| (x, y))).map(|(x, y)| (-r..=r).flat_map(|dy| (-r..=r)
+                    .map(move |dx| (dx, dy))).filter(|&(dx, dy)| dx * dx + dy * dy <= r * r)
+                .filter_map(|(dx, dy)| {
+                    let (x, y) = (x + dx, y + dy);
+                    (0 <= x && x < width as isize && 0 <= y && y < height as isize)
+                        .then(|| map[y as usize * width + x as usize])
+                }).max().unwrap()).collect::<Vec<_>>();
+            assert_eq!(dilate(&map, width, height, radius), expected, "radius {radius}");
+        }
+    }
+}
@@ -0,0 +1,457 @@
+/****************************************************************
+ * $ID: dotlottie.rs  	Sun 18 Oct 2026 22:14:09+0800           *
+ *                                                              *
+ * Maintainer: 范美辉 (MeiHui FAN) <mhfan@ustc.edu>              *
+ * Copyright (c) 2026 M.H.Fan, All rights reserved.             *
+ ****************************************************************/
+
+//! dotLottie (`.lottie`) packages, ZIP archives bundling a `manifest.json` with animations,
+//! themes, state machines and the image/audio files those animations reference.
+//! Both layouts are understood: v1 (`animations/`, `images/`, `audio/`) and
+//! v2 (`a/`, `i/`, `u/`, `t/`, `s/`).
+
+use std::{error::Error as StdError, fmt, fs::File, io::{self, BufReader, Read, Seek}};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Deserializer};
+use serde_json::{Value as Json, json};
+use zip::{ZipArchive, result::ZipError};
+
+use super::{playback::LoopMode, render::LottieRuntime, schema::Animation};
+
+const ANIMATIONS: [&str; 2] = ["a/", "animations/"];
+const THEMES: [&str; 2] = ["t/", "themes/"];
+const STATES: [&str; 2] = ["s/", "states/"];
+const MEDIA:  [&str; 4] = ["i/", "images/", "u/", "audio/"];
+
+/// Decompressed bytes read at most from one file, and for loading one animation with its
+/// theme and assets, against ZIP bombs; header sizes aren't trusted.
+const MAX_FILE_SIZE: u64 = if cfg!(test) { 1 << 10 } else { 64 << 20 };
+const MAX_LOAD_SIZE: u64 = if cfg!(test) { 4 << 10 } else { 256 << 20 };
+
+pub type Result<T> = std::result::Result<T, DotLottieError>;
+
+#[derive(Debug)] pub enum DotLottieError {
+    Zip(ZipError), Json(serde_json::Error), Io(io::Error), NoAnimation, TooLarge(String),
+    AnimationNotFound(String), ThemeNotFound(String), StateMachineNotFound(String),
+}
+
+impl fmt::Display for DotLottieError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { match self {
+        Self::Zip(error)  => write!(f, "dotLottie archive: {error}"),
+        Self::Json(error) => write!(f, "dotLottie JSON: {error}"),
+        Self::Io(error) => error.fmt(f),
+        Self::NoAnimation => f.write_str("dotLottie package contains no animation"),
+        Self::TooLarge(path) => write!(f, "dotLottie file `{path}` decompresses too large"),
+        Self::AnimationNotFound(id) => write!(f, "dotLottie animation `{id}` does not exist"),
+        Self::ThemeNotFound(id) => write!(f, "dotLottie theme `{id}` does not exist"),
+        Self::StateMachineNotFound(id) =>
+            write!(f, "dotLottie state machine `{id}` does not exist"),
+    } }
+}
+
+impl StdError for DotLottieError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> { match self {
+        Self::Zip(error) => Some(error), Self::Json(error) => Some(error),
+        Self::Io(error)  => Some(error), _ => None,
+    } }
+}
+
+impl From<ZipError> for DotLottieError {
+    fn from(error: ZipError) -> Self { Self::Zip(error) }
+}
+impl From<serde_json::Error> for DotLottieError {
+    fn from(error: serde_json::Error) -> Self { Self::Json(error) }
+}
+impl From<io::Error> for DotLottieError {
+    fn from(error: io::Error) -> Self { Self::Io(error) }
+}
+
+/// Package index, `manifest.json`; synthesized from the animation files if missing.
+#[derive(Debug, Default, Deserialize)] pub struct Manifest {
+    #[serde(default, deserialize_with = "des_version")] pub version: String,
+    #[serde(default)] pub generator: String,
+    #[serde(default)] pub author: String,
+    #[serde(default)] pub animations: Vec<ManifestAnimation>,
+    #[serde(default)] pub themes: Vec<ManifestItem>,
+    #[serde(default, rename = "stateMachines", alias = "states")]
+    pub state_machines: Vec<ManifestItem>,
+    #[serde(default)] pub initial: Option<Initial>,
+    /// v1 counterpart of `initial.animation`
+    #[serde(default, rename = "activeAnimationId")] pub active: String,
+}
+
+#[derive(Debug, Default, Deserialize)] pub struct ManifestAnimation {
+    pub id: String,
+    #[serde(default, rename = "initialTheme")] pub initial_theme: String,
+    #[serde(default)] pub themes: Vec<String>,
+    #[serde(default)] pub background: String,
+
+    // v1 playback hints, superseded by state machines in v2
+    #[serde(default)] pub speed: Option<f32>,
+    #[serde(default, rename = "loop", deserialize_with = "des_loop")] pub looping: Option<bool>,
+    #[serde(default, rename = "playMode")] pub play_mode: String, // "normal" or "bounce"
+    #[serde(default)] pub autoplay: Option<bool>,
+}
+
+/// Theme or state machine entry, v1 lists bare ids.
+#[derive(Debug, Default, Deserialize)] #[serde(from = "ItemRepr")] pub struct ManifestItem {
+    pub id: String, pub name: String,
+}
+
+#[derive(Deserialize)] #[serde(untagged)] enum ItemRepr {
+    Id(String), Item { id: String, #[serde(default)] name: String },
+}
+
+impl From<ItemRepr> for ManifestItem {
+    fn from(repr: ItemRepr) -> Self { match repr {
+        ItemRepr::Id(id) => Self { id, name: String::new() },
+        ItemRepr::Item { id, name } => Self { id, name },
+    } }
+}
+
+#[derive(Debug, Default, Deserialize)] pub struct Initial {
+    #[serde(default)] pub animation: String,
+    #[serde(default, rename = "stateMachine")] pub state_machine: String,
+}
+
+fn des_version<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<String, D::Error> {
+    Ok(match Json::deserialize(d)? {
+        Json::String(version) => version, Json::Null => String::new(), json => json.to_string(),
+    })
+}
+
+fn des_loop<'de, D: Deserializer<'de>>(d: D) -> std::result::Result<Option<bool>, D::Error> {
+    Ok(match Json::deserialize(d)? {
+        Json::Bool(looping) => Some(looping),
+        Json::Number(count) => Some(count.as_f64().is_some_and(|count| count != 0.)),
+        _ => None,
+    })
+}
+
+pub struct DotLottie<R> { archive: ZipArchive<R>, manifest: Manifest,
+    budget: u64,    // bytes left to read for the current load
+}
+
+impl DotLottie<BufReader<File>> {
+    pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self> {
+        Self::new(BufReader::new(File::open(path)?))
+    }
+}
+
+impl<R: Read + Seek> DotLottie<R> {
+    pub fn new(reader: R) -> Result<Self> {
+        let mut archive = ZipArchive::new(reader)?;
+        let mut budget = MAX_LOAD_SIZE;
+        let manifest = if let Some(data) =
+            read_file(&mut archive, "manifest.json", &mut budget)? {
+            serde_json::from_slice(&data)?
+        } else {
+            let animations = archive.file_names().filter_map(|name|
+                ANIMATIONS.iter().find_map(|dir| name.strip_prefix(dir)?
+                    .strip_suffix(".json").filter(|id| !id.contains('/'))))
+                .map(|id| ManifestAnimation { id: id.to_owned(), ..Default::default() })
+                .collect();     Manifest { animations, ..Default::default() }
+        };
+        if manifest.animations.is_empty() { return Err(DotLottieError::NoAnimation) }
+        Ok(Self { archive, manifest, budget })
+    }
+
+    pub fn manifest(&self) -> &Manifest { &self.manifest }
+
+    /// Animation to start with: the manifest's `initial`/`activeAnimationId`, else the first.
+    pub fn initial_animation(&self) -> &ManifestAnimation {
+        let manifest = &self.manifest;
+        let id = manifest.initial.as_ref()
+            .map_or(manifest.active.as_str(), |initial| initial.animation.as_str());
+        manifest.animations.iter().find(|animation| animation.id == id)
+            .unwrap_or(&manifest.animations[0])
+    }
+
+    pub fn animation(&mut self, id: &str) -> Result<Animation> {
+        self.themed_animation(id, None)
+    }
+
+    /// Load an animation with a theme's slot overrides applied, and every image/audio
+    /// asset found in the package embedded as a data URL.
+    pub fn themed_animation(&mut self, id: &str, theme: Option<&str>) -> Result<Animation> {
+        self.budget = MAX_LOAD_SIZE;
+        let mut animation: Json = serde_json::from_slice(&self.find(&ANIMATIONS, id)?
+            .ok_or_else(|| DotLottieError::AnimationNotFound(id.to_owned()))?)?;
+        if let Some(theme) = theme { apply_theme(&mut animation, &self.load_theme(theme)?, id) }
+
+        if let Some(assets) = animation.get_mut("assets").and_then(Json::as_array_mut) {
+            for asset in assets { self.embed(asset)? }
+        }
+        if let Some(slots) = animation.get_mut("slots").and_then(Json::as_object_mut) {
+            for asset in slots.values_mut().filter_map(|slot| slot.get_mut("p")) {
+                self.embed(asset)?
+            }
+        }   Ok(Animation::from_value(animation)?)
+    }
+
+    /// Theme JSON, `rules` overriding slots of the animations.
+    pub fn theme(&mut self, id: &str) -> Result<Json> {
+        self.budget = MAX_LOAD_SIZE;    self.load_theme(id)
+    }
+
+    fn load_theme(&mut self, id: &str) -> Result<Json> {
+        Ok(serde_json::from_slice(&self.find(&THEMES, id)?
+            .ok_or_else(|| DotLottieError::ThemeNotFound(id.to_owned()))?)?)
+    }
+
+    /// State machine JSON, left to the player to interpret.
+    pub fn state_machine(&mut self, id: &str) -> Result<Json> {
+        self.budget = MAX_LOAD_SIZE;
+        Ok(serde_json::from_slice(&self.find(&STATES, id)?
+            .ok_or_else(|| DotLottieError::StateMachineNotFound(id.to_owned()))?)?)
+    }
+
+    /// Runtime of the initial animation in its initial theme, with v1 playback hints.
+    pub fn runtime(&mut self) -> Result<LottieRuntime> {
+        let initial = self.initial_animation();
+        let (id, speed, looping, bounce, autoplay) = (initial.id.clone(),
+            initial.speed, initial.looping, initial.play_mode == "bounce", initial.autoplay);
+        let theme = Some(initial.initial_theme.clone()).filter(|theme| !theme.is_empty());
+
+        let mut runtime = LottieRuntime::from_animation(
+            self.themed_animation(&id, theme.as_deref())?);
+        if let Some(speed) = speed { runtime.set_speed(speed) }
+        if looping == Some(false) { runtime.set_loop_mode(LoopMode::Once) }
+        else if bounce { runtime.set_loop_mode(LoopMode::PingPong) }
+        if autoplay == Some(false) { runtime.pause() }  Ok(runtime)
+    }
+
+    fn find(&mut self, dirs: &[&str], id: &str) -> Result<Option<Vec<u8>>> {
+        self.read_any(dirs.iter().map(|dir| format!("{dir}{id}.json")))
+    }
+
+    /// The first of `paths` packaged.
+    fn read_any(&mut self, paths: impl IntoIterator<Item = String>) -> Result<Option<Vec<u8>>> {
+        for path in paths {
+            if let Some(data) = read_file(&mut self.archive, &path, &mut self.budget)? {
+                return Ok(Some(data))
+            }
+        }   Ok(None)
+    }
+
+    /// Replace a file asset's `u` + `p` reference by the packaged file as a data URL.
+    fn embed(&mut self, asset: &mut Json) -> Result<()> {
+        let Some(name) = asset.get("p").and_then(Json::as_str)
+            .filter(|name| !name.starts_with("data:")) else { return Ok(()) };
+        let dir = asset.get("u").and_then(Json::as_str).unwrap_or_default();
+        let path = format!("{dir}{name}");
+        let path = path.trim_start_matches('/');
+        let base = name.rsplit('/').next().unwrap_or(name);
+
+        let paths = [path.to_owned()].into_iter()
+            .chain(MEDIA.iter().map(|dir| format!("{dir}{base}")));
+        let Some(data) = self.read_any(paths)? else { return Ok(()) };
+        let ext = base.rsplit_once('.').map_or("", |(_, ext)| ext).to_ascii_lowercase();
+        let mime = match ext.as_str() {
+            "png" => "image/png", "jpg" | "jpeg" => "image/jpeg", "gif" => "image/gif",
+            "webp" => "image/webp", "svg" => "image/svg+xml", "mp3" => "audio/mpeg",
+            "wav" => "audio/wav", "ogg" => "audio/ogg", _ => "application/octet-stream",
+        };
+        asset["p"] = format!("data:{mime};base64,{}", STANDARD.encode(data)).into();
+        asset["u"] = "".into();     asset["e"] = 1.into();  Ok(())
+    }
+}
+
+/// Read the file at `path` if packaged, within the per-file limit and the `budget` left.
+fn read_file<R: Read + Seek>(archive: &mut ZipArchive<R>, path: &str,
+    budget: &mut u64) -> Result<Option<Vec<u8>>> {
+    let Ok(file) = archive.by_name(path) else { return Ok(None) };
+    let (limit, mut data) = (MAX_FILE_SIZE.min(*budget), Vec::new());
+    file.take(limit + 1).read_to_end(&mut data)?;
+    if limit < data.len() as u64 { return Err(DotLottieError::TooLarge(path.to_owned())) }
+    *budget -= data.len() as u64;   Ok(Some(data))
+}
+
+/// Turn the theme's rules for `id` into slots of the animation JSON. `Color`, `Scalar`,
+/// `Vector`, `Position` and `Gradient` rules become (keyframed) properties; `Image` rules
+/// retarget the slotted image assets in place. Other rule types are left alone.
+fn apply_theme(animation: &mut Json, theme: &Json, id: &str) {
+    let Some(rules) = theme.get("rules").and_then(Json::as_array) else { return };
+    for rule in rules {
+        let (Some(sid), Some(kind)) = (rule.get("id").and_then(Json::as_str),
+            rule.get("type").and_then(Json::as_str)) else { continue };
+        if rule.get("animations").and_then(Json::as_array)
+            .is_some_and(|ids| !ids.iter().any(|target| target == id)) { continue }
+
+        let property = match kind.to_ascii_lowercase().as_str() {
+            "color" | "scalar" | "vector" | "position" => theme_property(rule, Json::clone),
+            "gradient" => theme_property(rule, gradient_stops),
+            "image" => { retarget_images(animation, sid, &rule["value"]);  continue }
+            _ => continue,
+        };
+        let Some(object) = animation.as_object_mut() else { return };
+        let slots = object.entry("slots").or_insert_with(|| json!({}));
+        if let Some(slots) = slots.as_object_mut() {
+            slots.insert(sid.to_owned(), json!({ "p": property }));
+        }
+    }
+}
+
+fn theme_property(rule: &Json, convert: impl Fn(&Json) -> Json) -> Json {
+    let Some(keyframes) = rule.get("keyframes").and_then(Json::as_array) else {
+        return json!({ "a": 0, "k": convert(&rule["value"]) })
+    };
+    json!({ "a": 1, "k": keyframes.iter().map(|keyframe| {
+        let value = match convert(&keyframe["value"]) {
+            Json::Array(value) => value, value => vec![value],
+        };
+        let mut lottie = json!({ "t": keyframe["frame"], "s": value });
+        if let Some(tangent) = keyframe.get("inTangent")  { lottie["i"] = tangent.clone() }
+        if let Some(tangent) = keyframe.get("outTangent") { lottie["o"] = tangent.clone() }
+        if keyframe.get("hold").and_then(Json::as_bool) == Some(true) { lottie["h"] = 1.into() }
+        lottie
+    }).collect::<Vec<_>>() })
+}
+
+/// `[{ color: [r, g, b, a?], offset }]` to flat Lottie stops, alpha stops appended if any.
+fn gradient_stops(value: &Json) -> Json {
+    let stops = value.as_array().map_or(&[][..], Vec::as_slice);
+    let channel = |stop: &Json, index| stop["color"].get(index).and_then(Json::as_f64);
+    let mut flat = Vec::with_capacity(stops.len() * 6);
+    for stop in stops {
+        flat.push(stop["offset"].as_f64().unwrap_or_default());
+        flat.extend((0..3).map(|index| channel(stop, index).unwrap_or_default()));
+    }
+    if stops.iter().any(|stop| channel(stop, 3).is_some()) {
+        for stop in stops {
+            flat.push(stop["offset"].as_f64().unwrap_or_default());
+            flat.push(channel(stop, 3).unwrap_or(1.));
+        }
+    }   flat.into()
+}
+
+fn retarget_images(animation: &mut Json, sid: &str, value: &Json) {
+    let Some(assets) = animation.get_mut("assets").and_then(Json::as_array_mut) else { return };
+    for asset in assets.iter_mut().filter(|asset| asset["sid"] == sid) {
+        if let Some(url) = value.get("url").and_then(Json::as_str) {
+            asset["p"] = url.into();    asset["u"] = "".into();
+        } else if let Some(id) = value.get("id").and_then(Json::as_str) {
+            asset["p"] = id.into();     asset["u"] = "/i/".into();
+        }
+        if let Some(w) = value.get("width")  { asset["w"] = w.clone() }
+        if let Some(h) = value.get("height") { asset["h"] = h.clone() }
+        if let Some(asset) = asset.as_object_mut() { asset.remove("sid"); }
+    }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+    use std::io::{Cursor, Write};
+    use zip::write::{SimpleFileOptions, ZipWriter};
+    use crate::core::schema::AssetItem;
+
+    const ANIMATION: &str = r#"{ "fr": 30, "ip": 0, "op": 30, "w": 10, "h": 10,
+        "layers": [ { "ty": 4, "ip": 0, "op": 30, "st": 0, "ks": {}, "shapes": [
+            { "ty": "fl", "c": { "sid": "fg", "a": 0, "k": [1, 0, 0, 1] },
+              "o": { "a": 0, "k": 100 } } ] } ],
+        "assets": [ { "id": "img", "w": 1, "h": 1, "u": "/i/", "p": "dot.png", "e": 0 } ]
+    }"#;
+
+    fn package(files: &[(&str, &[u8])]) -> DotLottie<Cursor<Vec<u8>>> {
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, data) in files {
+            zip.start_file(*name, SimpleFileOptions::default()).unwrap();
+            zip.write_all(data).unwrap();
+        }   DotLottie::new(Cursor::new(zip.finish().unwrap().into_inner())).unwrap()
+    }
+
+    fn image_url(animation: &Animation) -> &str {
+        let AssetItem::Image(image) = &animation.assets[0] else { panic!("image asset") };
+        &image.file.url
+    }
+
+    #[test] fn v2_packages_list_contents_embed_assets_and_apply_themes() {
+        let mut dot = package(&[
+            ("manifest.json", br#"{ "version": "2", "generator": "test",
+                "animations": [ { "id": "intro" }, { "id": "main", "initialTheme": "dark" } ],
+                "themes": [ { "id": "dark" } ], "stateMachines": [ { "id": "sm" } ],
+                "initial": { "animation": "main" } }"#),
+            ("a/intro.json", ANIMATION.as_bytes()), ("a/main.json", ANIMATION.as_bytes()),
+            ("i/dot.png", b"PNG"), ("s/sm.json", br#"{ "initial": "idle" }"#),
+            ("t/dark.json", br#"{ "rules": [
+                { "id": "fg", "type": "Color", "value": [0, 0, 1, 1] },
+                { "id": "fg", "type": "Color", "value": [0, 1, 0, 1],
+                  "animations": [ "intro" ] } ] }"#),
+        ]);
+        let manifest = dot.manifest();
+        assert_eq!((manifest.version.as_str(), manifest.animations.len()), ("2", 2));
+        assert_eq!((manifest.themes[0].id.as_str(), manifest.state_machines[0].id.as_str()),
+                   ("dark", "sm"));
+        assert_eq!(dot.initial_animation().id, "main");
+        assert_eq!(dot.state_machine("sm").unwrap()["initial"], "idle");
+        assert!(matches!(dot.theme("light"), Err(DotLottieError::ThemeNotFound(_))));
+        assert!(matches!(dot.animation("outro"), Err(DotLottieError::AnimationNotFound(_))));
+
+        let plain = dot.animation("main").unwrap();
+        assert_eq!(image_url(&plain),
+                   format!("data:image/png;base64,{}", STANDARD.encode("PNG")));
+        let color = |animation: &Animation| serde_json::to_value(animation).unwrap()
+            .pointer("/layers/0/shapes/0/c/k").cloned().unwrap();
+        assert_eq!(color(&plain), json!([1., 0., 0.]));
+        assert_eq!(color(&dot.themed_animation("main", Some("dark")).unwrap()),
+                   json!([0., 0., 1.]));
+        let runtime = dot.runtime().unwrap();
+        assert_eq!(runtime.animation().slots.as_ref().unwrap()["fg"].p["k"], json!([0, 0, 1, 1]));
+    }
+
+    #[test] fn v1_packages_and_missing_manifests_fall_back_to_the_directory_layout() {
+        let v1 = ANIMATION.replace("/i/", "images/");
+        let mut dot = package(&[
+            ("manifest.json", br#"{ "version": 1.0, "activeAnimationId": "b",
+                "animations": [ { "id": "a" }, { "id": "b", "loop": false, "speed": 2 } ],
+                "states": [ "s1" ] }"#),
+            ("animations/a.json", v1.as_bytes()), ("animations/b.json", v1.as_bytes()),
+            ("images/dot.png", b"PNG"),
+        ]);
+        assert_eq!((dot.manifest().version.as_str(), dot.manifest().state_machines[0].id.as_str()),
+                   ("1.0", "s1"));
+        let runtime = dot.runtime().unwrap();
+        assert_eq!((runtime.playback().speed(), runtime.playback().loop_mode()),
+                   (2., LoopMode::Once));
+        assert!(image_url(runtime.animation()).starts_with("data:image/png;base64,"));
+
+        let mut bare = package(&[("a/only.json", ANIMATION.as_bytes())]);
+        assert_eq!(bare.initial_animation().id, "only");
+        assert_eq!(image_url(&bare.animation("only").unwrap()), "dot.png");
+    }
+
+    #[test] fn oversized_files_and_loads_are_rejected() {
+        let bomb = vec![0; MAX_FILE_SIZE as usize + 1];
+        let mut dot = package(&[("a/bomb.json", &bomb), ("a/main.json", ANIMATION.as_bytes()),
+            ("i/dot.png", &bomb[.. MAX_FILE_SIZE as usize])]);
+        assert!(matches!(dot.animation("bomb"), Err(DotLottieError::TooLarge(path))
+            if path == "a/bomb.json"));
+        assert!(dot.animation("main").is_ok(), "the budget is reset per load");
+
+        let many = ANIMATION.replace(r#""e": 0 }"#, r#""e": 0 }, { "id": "b", "p": "dot.png" },
+            { "id": "c", "p": "dot.png" }, { "id": "d", "p": "dot.png" }"#);
+        dot = package(&[("a/main.json", many.as_bytes()),
+            ("i/dot.png", &bomb[.. MAX_FILE_SIZE as usize])]);
+        assert!(matches!(dot.animation("main"), Err(DotLottieError::TooLarge(_))));
+    }
+
+    #[test] fn theme_rules_become_keyframed_slots_and_gradient_stops() {
+        let mut animation = json!({ "assets": [ { "id": "img", "sid": "pic", "p": "a.png" } ] });
+        apply_theme(&mut animation, &json!({ "rules": [
+            { "id": "size", "type": "Scalar", "keyframes": [
+                { "frame": 0, "value": 1, "outTangent": { "x": 0.5, "y": 0 }, "hold": true },
+                { "frame": 9, "value": 2 } ] },
+            { "id": "ramp", "type": "Gradient", "value": [
+                { "color": [1, 0, 0], "offset": 0 }, { "color": [0, 0, 1, 0.5], "offset": 1 } ] },
+            { "id": "pic", "type": "Image", "value": { "id": "b.png", "width": 4 } },
+            { "id": "label", "type": "Text", "value": { "text": "hi" } } ] }), "x");
+
+        assert_eq!(animation["slots"], json!({
+            "size": { "p": { "a": 1, "k": [
+                { "t": 0, "s": [1], "o": { "x": 0.5, "y": 0 }, "h": 1 }, { "t": 9, "s": [2] } ] } },
+            "ramp": { "p": { "a": 0, "k": [0., 1., 0., 0., 1., 0., 0., 1., 0., 1., 1., 0.5] } },
+        }));
+        assert_eq!(animation["assets"][0],
+                   json!({ "id": "img", "p": "b.png", "u": "/i/", "w": 4 }));
+    }
+}
@@ -0,0 +1,317 @@
+//! Layer effects resolved per frame, with a CPU fallback over premultiplied pixels.
+
+use super::{helpers::{Vec2D, RGBA}, schema::{Effect, EffectType, EffectValueItem}};
+
+/// Effect parameters at one frame, with distances already scaled to device pixels.
+#[derive(Clone, Copy)] pub enum LayerEffect {
+    /// Replaces the color of opaque areas, scaling coverage by the opacity
+    Fill { color: RGBA, opacity: f32 },
+    /// Maps luminance from black to white colors, mixed by the amount (0~1)
+    Tint { black: RGBA, white: RGBA, amount: f32 },
+    /// Maps luminance through dark/mid/bright colors, mixed back by `original` (0~1)
+    Tritone { bright: RGBA, mid: RGBA, dark: RGBA, original: f32 },
+    /// Levels for the composite RGB, then red, green, blue and alpha channels
+    ProLevels([Levels; 5]),
+    /// Standard deviations per axis, `wrap` repeats the edge pixels
+    GaussianBlur { sigma: Vec2D, wrap: bool },
+    /// Blurred alpha, tinted and offset below the layer
+    DropShadow { color: RGBA, offset: Vec2D, sigma: f32 },
+}
+
+/// Input black/white points, gamma and output black/white points, in 0~1
+#[derive(Clone, Copy)] pub struct Levels {
+    pub in_black: f32, pub in_white: f32, pub gamma: f32,
+    pub out_black: f32, pub out_white: f32,
+}
+
+impl Levels {
+    /// Lookup table of lottie-web's SVGProLevelsFilter
+    fn table(&self) -> [u8; 256] {
+        let (min, max) = (self.in_black.min(self.in_white),
+                          self.in_black.max(self.in_white));
+        let (input, output) = (self.in_white  - self.in_black,
+                               self.out_white - self.out_black);
+        core::array::from_fn(|index| {
+            let value = index as f32 / 255.;
+            let value = if value <= min {
+                if input < 0. { self.out_white } else { self.out_black }
+            } else if max <= value {
+                if input < 0. { self.out_black } else { self.out_white }
+            } else {
+                let ratio = (value - self.in_black) / input;
+                self.out_black + output * ratio.powf(1. / self.gamma)
+            };  (value.clamp(0., 1.) * 255.).round() as u8
+        })
+    }
+    fn is_identity(&self) -> bool {
+        self.in_black == 0. && self.in_white == 1. && self.gamma == 1. &&
+            self.out_black == 0. && self.out_white == 1.
+    }
+}
+
+/// Resolves the enabled effects that have a rendering, `scale` converts layer units.
+pub(crate) fn resolve_effects(effects: &[Effect],
+    fnth: f32, scale: f32) -> Vec<LayerEffect> {
+    effects.iter().filter(|effect| effect.en.as_bool()).filter_map(|effect| {
+        let (ef, white) = (&effect.ef, RGBA::new_u8(255, 255, 255, 255));
+        let scalar = |index: usize, default: f32| ef.get(index).and_then(|item| match item {
+            EffectValueItem::Slider(value)   | EffectValueItem::Angle(value) |
+            EffectValueItem::Checkbox(value) | EffectValueItem::DropDown(value) =>
+                value.value.as_ref().map(|value| value.get_value(fnth)),
+            _ => None,
+        }).unwrap_or(default);
+        let color = |index: usize, default: RGBA| ef.get(index).and_then(|item| match item {
+            EffectValueItem::EffectColor(value) =>
+                value.value.as_ref().map(|value| value.get_value(fnth)),
+            _ => None,
+        }).unwrap_or(default);
+
+        // parameter indices and units follow lottie-web's SVG effect filters
+        Some(match effect.ty {
+            EffectType::Fill => LayerEffect::Fill {
+                color: color(2, RGBA::default()), opacity: scalar(6, 1.),
+            },
+            EffectType::Tint => LayerEffect::Tint { black: color(0, RGBA::default()),
+                white: color(1, white), amount: scalar(2, 100.) / 100.,
+            },
+            EffectType::Tritone => LayerEffect::Tritone { bright: color(0, white),
+                mid: color(1, RGBA::new_u8(128, 128, 128, 255)),
+                dark: color(2, RGBA::default()), original: scalar(3, 0.) / 100.,
+            },
+            EffectType::ProLevels => LayerEffect::ProLevels(core::array::from_fn(|channel| {
+                let base = 3 + channel * 7;
+                Levels { in_black: scalar(base, 0.), in_white: scalar(base + 1, 1.),
+                    gamma: scalar(base + 2, 1.).max(f32::EPSILON),
+                    out_black: scalar(base + 3, 0.), out_white: scalar(base + 4, 1.),
+                }
+            })),
+            EffectType::GaussianBlur => {
+                let sigma = scalar(0, 0.) * 0.3 * scale;
+                if sigma <= 0. { return None }
+                let dimensions = scalar(1, 1.) as u8;
+                LayerEffect::GaussianBlur {
+                    sigma: Vec2D { x: if dimensions == 3 { 0. } else { sigma },
+                                   y: if dimensions == 2 { 0. } else { sigma } },
+                    wrap: scalar(2, 0.) != 0.,
+                }
+            }
+            EffectType::DropShadow => {
+                let mut color = color(0, RGBA::default());
+                color.a = (scalar(1, 255.).clamp(0., 255.) * color.a as f32 / 255.) as _;
+                let angle = (scalar(2, 0.) - 90.).to_radians();
+                let distance = scalar(3, 0.) * scale;
+                LayerEffect::DropShadow { color, sigma: scalar(4, 0.) / 4. * scale,
+                    offset: Vec2D { x: distance * angle.cos(), y: distance * angle.sin() },
+                }
+            }
+            _ => return None,
+        })
+    }).collect()
+}
+
+/// Runs effects in order on premultiplied 8-bit pixels, `stride` in bytes, and
+/// `bgra` selects the channel order of the buffer.
+pub fn apply_effects(pixels: &mut [u8], width: usize, height: usize,
+    stride: usize, bgra: bool, effects: &[LayerEffect]) {
+    let (r, b) = if bgra { (2, 0) } else { (0, 2) };
+    let channels = |color: RGBA| [color.r, color.g, color.b].map(|c| c as f32 / 255.);
+    let luma = |rgb: [f32; 3]| 0.3 * rgb[0] + 0.59 * rgb[1] + 0.11 * rgb[2];
+    for effect in effects { match *effect {
+        LayerEffect::Fill { color, opacity } => map_colors(pixels, width, height, stride,
+            |_, alpha| (channels(color), alpha * opacity.clamp(0., 1.)), r, b),
+        LayerEffect::Tint { black, white, amount } => {
+            let (black, white) = (channels(black), channels(white));
+            map_colors(pixels, width, height, stride, |rgb, alpha| {
+                let luma = luma(rgb);
+                (core::array::from_fn(|i| rgb[i] +
+                    (black[i] + (white[i] - black[i]) * luma - rgb[i]) * amount), alpha)
+            }, r, b)
+        }
+        LayerEffect::Tritone { bright, mid, dark, original } => {
+            let (bright, mid, dark) = (channels(bright), channels(mid), channels(dark));
+            map_colors(pixels, width, height, stride, |rgb, alpha| {
+                let luma = luma(rgb);
+                (core::array::from_fn(|i| {
+                    let mapped = if luma < 0.5 { dark[i] + (mid[i] - dark[i]) * luma * 2.
+                    } else { mid[i] + (bright[i] - mid[i]) * (luma - 0.5) * 2. };
+                    mapped + (rgb[i] - mapped) * original
+                }), alpha)
+            }, r, b)
+        }
+        LayerEffect::ProLevels(levels) => {
+            if levels.iter().all(Levels::is_identity) { continue }
+            let tables = levels.map(|levels| levels.table());
+            let lookup = |table: &[u8; 256], value: f32|
+                table[(value * 255.).round() as usize] as f32 / 255.;
+            map_colors(pixels, width, height, stride, |rgb, alpha| {
+                let rgb = core::array::from_fn(|i|
+                    lookup(&tables[1 + i], lookup(&tables[0], rgb[i])));
+                (rgb, lookup(&tables[4], alpha))
+            }, r, b)
+        }
+        LayerEffect::GaussianBlur { sigma, wrap } =>
+            blur(pixels, width, height, stride, 4, sigma, wrap),
+        LayerEffect::DropShadow { color, offset, sigma } => {
+            let mut ordered = [color.r, color.g, color.b, color.a];
+            ordered.swap(0, r);     // color in the channel order of the buffer
+            drop_shadow(pixels, width, height, stride, ordered, offset, sigma)
+        }
+    } }
+}
+
+/// Maps unpremultiplied colors (in RGB order) and alpha of every covered pixel.
+fn map_colors(pixels: &mut [u8], width: usize, height: usize, stride: usize,
+    f: impl Fn([f32; 3], f32) -> ([f32; 3], f32), r: usize, b: usize) {
+    for row in pixels.chunks_mut(stride).take(height) {
+        for pixel in row[..width * 4].chunks_exact_mut(4) {
+            if pixel[3] == 0 { continue }
+            let alpha = pixel[3] as f32 / 255.;
+            let rgb = [r, 1, b].map(|i| (pixel[i] as f32 / 255. / alpha).min(1.));
+            let (rgb, alpha) = f(rgb, alpha);
+            let alpha = alpha.clamp(0., 1.);
+            for (value, i) in rgb.into_iter().zip([r, 1, b]) {
+                pixel[i] = (value.clamp(0., 1.) * alpha * 255.).round() as u8;
+            }   pixel[3] = (alpha * 255.).round() as u8;
+        }
+    }
+}
+
+/// Separable Gaussian convolution over `channels` interleaved bytes per pixel.
+pub(super) fn blur(pixels: &mut [u8], width: usize, height: usize, stride: usize,
+    channels: usize, sigma: Vec2D, wrap: bool) {
+    let kernel = |sigma: f32| {
+        let radius = (sigma * 3.).ceil() as isize;
+        let weights = (-radius..=radius).map(|x|
+            (-(x * x) as f32 / (2. * sigma * sigma)).exp()).collect::<Vec<_>>();
+        let total: f32 = weights.iter().sum();
+        (radius, weights.into_iter().map(|weight| weight / total).collect::<Vec<_>>())
+    };
+    let mut line = Vec::new();
+    let mut pass = |count: usize, lines: usize, sigma: f32,
+        index: &dyn Fn(usize, usize) -> usize| {
+        if sigma <= 0. || count == 0 { return }
+        let (radius, weights) = kernel(sigma);
+        for other in 0..lines {
+            line.clear();
+            for pos in 0..count {
+                let at = index(pos, other);     line.extend_from_slice(&pixels[at..at + channels]);
+            }
+            for pos in 0..count {
+                let mut sum = [0f32; 4];
+                for (offset, weight) in (-radius..=radius).zip(&weights) {
+                    let source = pos as isize + offset;
+                    let source = if wrap { source.clamp(0, count as isize - 1) as usize }
+                        else if source < 0 || count as isize <= source { continue }
+                        else { source as usize };
+                    for (c, sum) in sum.iter_mut().take(channels).enumerate() {
+                        *sum += line[source * channels + c] as f32 * weight;
+                    }
+                }
+                let at = index(pos, other);
+                for (pixel, sum) in pixels[at..at + channels].iter_mut().zip(sum) {
+                    *pixel = sum.round().clamp(0., 255.) as u8;
+                }
+            }
+        }
+    };
+    pass(width, height, sigma.x, &|x, y| y * stride + x * channels);
+    pass(height, width, sigma.y, &|y, x| y * stride + x * channels);
+}
+
+fn drop_shadow(pixels: &mut [u8], width: usize, height: usize, stride: usize,
+    color: [u8; 4], offset: Vec2D, sigma: f32) {
+    let (dx, dy) = (offset.x.round() as isize, offset.y.round() as isize);
+    let mut alpha = vec![0u8; width * height];
+    for y in 0..height { for x in 0..width {
+        let (sx, sy) = (x as isize - dx, y as isize - dy);
+        if (0..width as isize).contains(&sx) && (0..height as isize).contains(&sy) {
+            alpha[y * width + x] = pixels[sy as usize * stride + sx as usize * 4 + 3];
+        }
+    } }
+    blur(&mut alpha, width, height, width, 1, Vec2D { x: sigma, y: sigma }, false);
+
+    // the layer stays on top of its shadow (destination-over)
+    let opacity = color[3] as f32 / 255.;
+    for y in 0..height { for x in 0..width {
+        let pixel = &mut pixels[y * stride + x * 4..][..4];
+        let shadow = alpha[y * width + x] as f32 / 255. * opacity;
+        let below = shadow * (1. - pixel[3] as f32 / 255.);
+        for (value, c) in pixel[..3].iter_mut().zip(color) {
+            *value = (*value as f32 + c as f32 * below).round().min(255.) as u8;
+        }   pixel[3] = (pixel[3] as f32 + 255. * below).round().min(255.) as u8;
+    } }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    fn effects(json: &str, scale: f32) -> Vec<LayerEffect> {
+        resolve_effects(&serde_json::from_str::<Vec<Effect>>(json).unwrap(), 0., scale)
+    }
+
+    #[test] fn resolves_enabled_effects_with_lottie_web_parameters() {
+        let resolved = effects(r#"[
+            {"ty":29,"ef":[{"ty":0,"v":{"k":10}},{"ty":7,"v":{"k":2}},
+                {"ty":4,"v":{"k":1}}]},
+            {"ty":25,"ef":[{"ty":2,"v":{"k":[1,0,0,1]}},{"ty":0,"v":{"k":127.5}},
+                {"ty":1,"v":{"k":180}},{"ty":0,"v":{"k":5}},{"ty":0,"v":{"k":8}}]},
+            {"ty":20,"en":0,"ef":[]},
+            {"ty":32,"ef":[]}
+        ]"#, 2.);
+        assert_eq!(resolved.len(), 2);
+        let LayerEffect::GaussianBlur { sigma, wrap } = resolved[0] else { panic!() };
+        assert_eq!((sigma.x, sigma.y, wrap), (6., 0., true));
+        let LayerEffect::DropShadow { color, offset, sigma } = resolved[1]
+            else { panic!() };
+        assert_eq!((color.r, color.a, sigma), (255, 127, 4.));
+        assert!(offset.x.abs() < 1e-5 && (offset.y - 10.).abs() < 1e-5);
+    }
+
+    #[test] fn color_effects_map_unpremultiplied_colors() {
+        let mut pixel = [100, 50, 0, 128];
+        apply_effects(&mut pixel, 1, 1, 4, false, &[LayerEffect::Fill {
+            color: RGBA::new_u8(0, 0, 255, 255), opacity: 0.5 }]);
+        assert_eq!(pixel, [0, 0, 64, 64]);
+
+        let (mut white, mut black) = ([255; 4], [0, 0, 0, 255]);
+        let tint = [LayerEffect::Tint { black: RGBA::new_u8(0, 0, 255, 255),
+            white: RGBA::new_u8(255, 0, 0, 255), amount: 1. }];
+        apply_effects(&mut white, 1, 1, 4, false, &tint);
+        apply_effects(&mut black, 1, 1, 4, true,  &tint);
+        assert_eq!((white, black), ([255, 0, 0, 255], [255, 0, 0, 255]));
+
+        let mut gray = [127, 127, 127, 255];
+        apply_effects(&mut gray, 1, 1, 4, false, &[LayerEffect::Tritone {
+            bright: RGBA::new_u8(255, 255, 255, 255), mid: RGBA::new_u8(0, 255, 0, 255),
+            dark: RGBA::default(), original: 0. }]);
+        assert_eq!(gray, [0, 254, 0, 255]);
+    }
+
+    #[test] fn pro_levels_remap_channels_through_lookup_tables() {
+        let identity = Levels { in_black: 0., in_white: 1., gamma: 1.,
+            out_black: 0., out_white: 1. };
+        let invert = Levels { out_black: 1., out_white: 0., ..identity };
+        let mut pixel = [255, 51, 0, 255];
+        apply_effects(&mut pixel, 1, 1, 4, false,
+            &[LayerEffect::ProLevels([identity, invert, identity, identity, identity])]);
+        assert_eq!(pixel, [0, 51, 0, 255]);
+    }
+
+    #[test] fn blur_spreads_coverage_and_shadow_stays_below_the_layer() {
+        let (width, height) = (9, 1);
+        let mut pixels = vec![0u8; width * 4];
+        pixels[16..20].copy_from_slice(&[255; 4]);
+        apply_effects(&mut pixels, width, height, width * 4, false,
+            &[LayerEffect::GaussianBlur { sigma: Vec2D { x: 1., y: 0. }, wrap: false }]);
+        let alpha = pixels.chunks(4).map(|pixel| pixel[3]).collect::<Vec<_>>();
+        assert!(alpha[4] < 255 && 0 < alpha[2] && alpha[3] == alpha[5]);
+        assert!(alpha.iter().map(|&a| a as u32).sum::<u32>().abs_diff(255) <= 2);
+
+        let mut pixels = vec![0u8; 3 * 4];
+        pixels[..4].copy_from_slice(&[255, 255, 255, 255]);
+        apply_effects(&mut pixels, 3, 1, 12, false, &[LayerEffect::DropShadow {
+            color: RGBA::new_u8(0, 0, 255, 255), offset: Vec2D { x: 1., y: 0. },
+            sigma: 0. }]);
+        assert_eq!(pixels, [255, 255, 255, 255, 0, 0, 255, 255, 0, 0, 0, 0]);
+    }
+}
@@ -0,0 +1,1054 @@
+//! Sandboxed evaluator for the common subset of After Effects expressions, in plain Rust.
+//!
+//! Statements are limited to `var`/`let`/`const`, assignments, `if`/`else` and `return`
+//! (no loops or user functions), so evaluation always terminates. Anything beyond the
+//! subset fails to parse or evaluate, and the property keeps its keyframed value.
+
+use std::{cell::{Cell, RefCell}, collections::HashMap, rc::Rc};
+use super::{helpers::math::{self, Tween},
+    schema::{Animation, AssetItem, AnimatedProperty, AnimatedValue, PropertySource,
+        Expression, LayerItem, SplitVector, Translation, TransRotation,
+        EffectValueItem, VisualObject}};
+
+/// Composition (root, or the precomp `asset`) seen by the expressions evaluated within `f`
+pub(crate) fn with_composition<R>(animation: &Rc<Animation>, asset: Option<usize>,
+    size: (u32, u32), f: impl FnOnce() -> R) -> R {
+    struct Restore(Option<Rc<Scope>>);
+    impl Drop for Restore { fn drop(&mut self) { SCOPE.set(self.0.take()) } }
+
+    let scope = Scope { animation: Rc::clone(animation), asset, size, layer: Cell::new(None) };
+    let _restore = Restore(SCOPE.replace(Some(Rc::new(scope))));
+    f()
+}
+
+/// Selects the layer (`thisLayer`) whose properties are evaluated next
+pub(crate) fn select_layer(index: usize) {
+    with_scope(|scope| if let Some(scope) = scope { scope.layer.set(Some(index)) })
+}
+
+/// Result of the expression on the property at (layer local) frame `fnth`,
+/// `None` to keep the keyframed `value`.
+pub(crate) fn evaluate<T: Clone + Tween>(property: &AnimatedProperty<T>,
+    expr: &Expression, fnth: f32, value: &T) -> Option<T> {
+    if expr.x.is_empty() { return None }
+    let mut current = value.to_components()?;
+    if let Some(len) = expr.len.filter(|&len| 1 < len && len <= 4) {
+        current.resize(len as _, 0.);
+    }
+    let program = expr.program.get_or_init(|| Program::parse(&expr.x)).as_ref()?;
+
+    let depth = DEPTH.get();    if MAX_DEPTH <= depth { return None }
+    DEPTH.set(depth + 1);
+    let result = with_scope(|scope| {
+        let layer = scope.and_then(|scope| scope.layer.get());
+        let mut env = Env::new(scope, Prop { keyed: property, layer }, fnth, current);
+        env.seed = hash(expr.x.bytes().fold(layer.unwrap_or(0) as u64, |seed, byte|
+            hash(seed ^ byte as u64)));
+        env.rng = hash(env.seed ^ (env.comp_frame as f64).to_bits());
+        env.run(program)
+    });
+    DEPTH.set(depth);
+    value.with_components(&result?)
+}
+
+const MAX_DEPTH: u8 = 4;    // nested evaluation of properties referenced by expressions
+thread_local! {
+    static SCOPE: RefCell<Option<Rc<Scope>>> = const { RefCell::new(None) };
+    static DEPTH: Cell<u8> = const { Cell::new(0) };
+}
+
+struct Scope { animation: Rc<Animation>, asset: Option<usize>, size: (u32, u32),
+    layer: Cell<Option<usize>>,
+}
+
+impl Scope {
+    fn layers(&self) -> &[LayerItem] {
+        match self.asset.map(|asset| &self.animation.assets[asset]) {
+            Some(AssetItem::Precomp(precomp)) => &precomp.layers,
+            Some(_) => &[],     None => &self.animation.layers,
+        }
+    }
+}
+
+/// The scope is cloned out first, so evaluation may re-enter `with_composition`.
+fn with_scope<R>(f: impl FnOnce(Option<&Scope>) -> R) -> R {
+    let scope = SCOPE.with_borrow(Option::clone);     f(scope.as_deref())
+}
+
+/// Property as seen from expressions, in components
+trait Keyed {
+    /// Value at (layer local) `fnth`, with or without its own expression
+    fn value_at(&self, fnth: f32, expression: bool) -> Option<Vec<f64>>;
+    fn key_times(&self) -> Vec<f32>;
+}
+
+impl<T: Clone + Tween> Keyed for AnimatedProperty<T> {
+    fn value_at(&self, fnth: f32, expression: bool) -> Option<Vec<f64>> {
+        let value = if expression { self.try_get_value_cow(fnth) }
+            else { self.keyframed_cow(fnth) };  value.ok()?.to_components()
+    }
+
+    fn key_times(&self) -> Vec<f32> {
+        match &self.source {
+            PropertySource::Inline(AnimatedValue::Animated(keyframes)) |
+            PropertySource::Slot { fallback: Some(AnimatedValue::Animated(keyframes)), .. } =>
+                keyframes.iter().filter(|kf| kf.value.is_some()).map(|kf| kf.start).collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+impl Keyed for SplitVector {
+    fn value_at(&self, fnth: f32, expression: bool) -> Option<Vec<f64>> {
+        let mut value = self.x.value_at(fnth, expression)?;
+        value.extend(self.y.value_at(fnth, expression)?);   Some(value)
+    }
+
+    fn key_times(&self) -> Vec<f32> {
+        let mut times = self.x.key_times();     times.extend(self.y.key_times());
+        times.sort_by(f32::total_cmp);  times.dedup();  times
+    }
+}
+
+#[derive(Clone, Copy)] struct Prop<'s> { keyed: &'s dyn Keyed, layer: Option<usize> }
+
+#[derive(Clone)] enum Val<'s> {
+    Undef, Num(f64), Bool(bool), Str(String), Arr(Vec<f64>),
+    Math, Comp, Layer(usize), Transform(usize), Effect(usize, usize),
+    Prop(Prop<'s>), Key(Prop<'s>, usize),
+    /// Built-in function, with the receiver of a method
+    Func(&'static str, Option<Box<Val<'s>>>),
+}
+
+impl Val<'_> {
+    fn from_components(mut comps: Vec<f64>) -> Self {
+        if comps.len() == 1 { Self::Num(comps.remove(0)) } else { Self::Arr(comps) }
+    }
+
+    fn num(&self) -> Option<f64> {
+        match self {
+            Self::Num(v) => Some(*v),
+            Self::Bool(v) => Some(*v as u8 as _),
+            _ => None,
+        }
+    }
+
+    fn components(&self) -> Option<Vec<f64>> {
+        match self { Self::Arr(v) => Some(v.clone()), _ => Some(vec![self.num()?]) }
+    }
+
+    fn truthy(&self) -> bool {
+        match self {
+            Self::Undef => false,
+            Self::Num(v) => *v != 0. && !v.is_nan(),
+            Self::Bool(v) => *v,
+            Self::Str(v) => !v.is_empty(),
+            _ => true,
+        }
+    }
+}
+
+fn binary<'s>(op: &str, lhs: Val<'s>, rhs: Val<'s>) -> Option<Val<'s>> {
+    use Val::{Num, Bool, Str, Arr};
+    let arith = |a: f64, b: f64| match op {
+        "+" => Some(a + b), "-" => Some(a - b), "*" => Some(a * b),
+        "/" => Some(a / b), "%" => Some(a % b), _ => None,
+    };
+    Some(match (lhs, rhs) {
+        (Str(a), b) if op == "+" => Str(a + &to_string(&b)?),
+        (a, Str(b)) if op == "+" => Str(to_string(&a)? + &b),
+        (a, b) if matches!(op, "==" | "===" | "!=" | "!==") => {
+            let equal = match (&a, &b) {
+                (Str(a), Str(b)) => a == b,
+                (Arr(a), Arr(b)) => a == b,
+                (Val::Undef, Val::Undef) => true,
+                _ => a.num().zip(b.num()).is_some_and(|(a, b)| a == b),
+            };  Bool(equal == op.starts_with('='))
+        }
+        (a, b) if matches!(op, "<" | ">" | "<=" | ">=") => {
+            let (a, b) = (a.num()?, b.num()?);
+            Bool(match op { "<" => a < b, ">" => a > b, "<=" => a <= b, _ => a >= b })
+        }
+
+        (Arr(mut a), Arr(b)) if matches!(op, "+" | "-") => {
+            if a.len() < b.len() { a.resize(b.len(), 0.); }
+            a.iter_mut().zip(b.iter().chain(core::iter::repeat(&0.)))
+                .for_each(|(a, &b)| *a = arith(*a, b).unwrap());    Arr(a)
+        }
+        (Arr(mut a), b) => { let b = b.num()?;
+            match op {  // lottie-web adds/subtracts scalars to the first component only
+                "+" | "-" => if let Some(a) = a.first_mut() { *a = arith(*a, b)? },
+                "*" | "/" => for a in a.iter_mut() { *a = arith(*a, b)? },
+                _ => return None,
+            }   Arr(a)
+        }
+        (a, Arr(mut b)) => { let a = a.num()?;
+            match op {
+                "+" | "-" => if let Some(b) = b.first_mut() { *b = arith(a, *b)? },
+                "*" | "/" => for b in b.iter_mut() { *b = arith(a, *b)? },
+                _ => return None,
+            }   Arr(b)
+        }
+        (a, b) => Num(arith(a.num()?, b.num()?)?),
+    })
+}
+
+fn to_string(value: &Val) -> Option<String> {
+    Some(match value {
+        Val::Str(v) => v.clone(),
+        Val::Num(v) => v.to_string(),
+        Val::Bool(v) => v.to_string(),
+        Val::Arr(v) => v.iter().map(f64::to_string).collect::<Vec<_>>().join(","),
+        _ => return None,
+    })
+}
+
+enum Flow<'s> { Next, Return(Val<'s>) }
+
+struct Env<'s> {
+    scope: Option<&'s Scope>, fr: f32,
+    this: Prop<'s>, frame: f32, comp_frame: f32,
+    value: Vec<f64>, vars: HashMap<&'s str, Val<'s>>,
+    seed: u64, rng: u64,
+}
+
+impl<'s> Env<'s> {
+    fn new(scope: Option<&'s Scope>, this: Prop<'s>, frame: f32,
+        value: Vec<f64>) -> Self {
+        let fr = scope.map_or(60., |scope| scope.animation.fr);   // defaults::animation_fr
+        let mut env = Self { scope, fr, this, frame, comp_frame: frame, value,
+            vars: HashMap::new(), seed: 0, rng: 0 };
+        env.comp_frame = env.to_comp_frame(this.layer, frame);  env
+    }
+
+    /// Result components, from `return`, `$bm_rt` (as exported by Bodymovin),
+    /// or the last expression statement
+    fn run(&mut self, program: &'s Program) -> Option<Vec<f64>> {
+        let mut last = Val::Undef;
+        let result = match self.exec(&program.0, &mut last)? {
+            Flow::Return(value) => value,
+            Flow::Next => match self.vars.get("$bm_rt") {
+                Some(value) if !matches!(value, Val::Undef) => value.clone(),
+                _ => last,
+            },
+        };  self.resolve(result)?.components()
+    }
+
+    fn exec(&mut self, stmts: &'s [Stmt], last: &mut Val<'s>) -> Option<Flow<'s>> {
+        for stmt in stmts { match stmt {
+            Stmt::Expr(expr) => *last = self.eval(expr)?,
+            Stmt::Var(decls) => for (name, init) in decls {
+                let value = match init {
+                    Some(init) => self.operand(init)?, None => Val::Undef,
+                };
+                self.vars.insert(name, value);
+            }
+            Stmt::If(cond, then, other) => {
+                let branch = if self.operand(cond)?.truthy() { Some(&**then) }
+                    else { other.as_deref() };
+                if let Some(branch) = branch {
+                    if let Flow::Return(value) =
+                        self.exec(core::slice::from_ref(branch), last)? {
+                        return Some(Flow::Return(value))
+                    }
+                }
+            }
+            Stmt::Block(body) => if let Flow::Return(value) = self.exec(body, last)? {
+                return Some(Flow::Return(value))
+            }
+            Stmt::Return(expr) => return Some(Flow::Return(match expr {
+                Some(expr) => self.operand(expr)?, None => Val::Undef,
+            })),
+        } }     Some(Flow::Next)
+    }
+
+    /// Evaluates with properties collapsed into their current values
+    fn operand(&mut self, expr: &'s Expr) -> Option<Val<'s>> {
+        let value = self.eval(expr)?;   self.resolve(value)
+    }
+
+    fn resolve(&self, value: Val<'s>) -> Option<Val<'s>> {
+        match value {
+            Val::Prop(prop) => self.sample(&prop, self.comp_frame),
+            Val::Key(prop, index) => self.key_value(&prop, index),
+            value => Some(value),
+        }
+    }
+
+    fn eval(&mut self, expr: &'s Expr) -> Option<Val<'s>> {
+        Some(match expr {
+            Expr::Num(v) => Val::Num(*v),
+            Expr::Str(v) => Val::Str(v.clone()),
+            Expr::Ident(name) => self.ident(name)?,
+            Expr::Array(items) => {
+                let mut comps = Vec::with_capacity(items.len());
+                for item in items { comps.push(self.operand(item)?.num()?); }
+                Val::Arr(comps)
+            }
+            Expr::Unary(op, operand) => {
+                let value = self.operand(operand)?;
+                match *op {
+                    "!" => Val::Bool(!value.truthy()),
+                    "-" => binary("*", value, Val::Num(-1.))?,
+                    _ => Val::Num(value.num()?),
+                }
+            }
+            Expr::Binary("&&", lhs, rhs) => {
+                let lhs = self.operand(lhs)?;
+                if lhs.truthy() { self.operand(rhs)? } else { lhs }
+            }
+            Expr::Binary("||", lhs, rhs) => {
+                let lhs = self.operand(lhs)?;
+                if lhs.truthy() { lhs } else { self.operand(rhs)? }
+            }
+            Expr::Binary(op, lhs, rhs) => {
+                let (lhs, rhs) = (self.operand(lhs)?, self.operand(rhs)?);
+                binary(op, lhs, rhs)?
+            }
+            Expr::Cond(cond, then, other) => {
+                let branch = if self.operand(cond)?.truthy() { then } else { other };
+                self.eval(branch)?
+            }
+            Expr::Member(object, name) => {
+                let object = self.eval(object)?;    self.member(object, name)?
+            }
+            Expr::Index(object, index) => {
+                let (object, index) = (self.operand(object)?, self.operand(index)?.num()?);
+                match object {
+                    Val::Arr(comps) if 0. <= index =>
+                        comps.get(index as usize).map_or(Val::Undef, |&v| Val::Num(v)),
+                    Val::Num(v) if index == 0. => Val::Num(v),
+                    _ => Val::Undef,
+                }
+            }
+            Expr::Call(callee, args) => {
+                let callee = self.eval(callee)?;
+                let args = args.iter().map(|arg| self.operand(arg))
+                    .collect::<Option<Vec<_>>>()?;
+                self.call(callee, &args)?
+            }
+            Expr::Assign(target, op, value) => {
+                let value = self.operand(value)?;
+                match &**target {
+                    Expr::Ident(name) => {
+                        let value = match op {
+                            Some(op) =>
+                                binary(op, self.vars.get(name.as_str())?.clone(), value)?,
+                            None => value,
+                        };  self.vars.insert(name, value.clone());  value
+                    }
+                    Expr::Index(object, index) => {
+                        let Expr::Ident(name) = &**object else { return None };
+                        let index = self.operand(index)?.num()?;
+                        if !(0. ..16.).contains(&index) { return None }
+                        let Some(Val::Arr(comps)) = self.vars.get_mut(name.as_str())
+                            else { return None };
+                        let index = index as usize;
+                        if comps.len() <= index { comps.resize(index + 1, 0.); }
+                        let value = match op {
+                            Some(op) => binary(op, Val::Num(comps[index]), value)?,
+                            None => value,
+                        }.num()?;   comps[index] = value;   Val::Num(value)
+                    }
+                    _ => return None,
+                }
+            }
+        })
+    }
+
+    fn ident(&self, name: &str) -> Option<Val<'s>> {
+        if let Some(value) = self.vars.get(name) { return Some(value.clone()) }
+        Some(match name {
+            "time"  => Val::Num(self.comp_frame as f64 / self.fr as f64),
+            "value" => Val::from_components(self.value.clone()),
+            "thisProperty" => Val::Prop(self.this),
+            "thisLayer" => Val::Layer(self.this.layer?),
+            "thisComp"  => Val::Comp,
+            "Math" => Val::Math,
+            "true" => Val::Bool(true), "false" => Val::Bool(false),
+            "undefined" | "null" => Val::Undef,
+            "numKeys" => self.member(Val::Prop(self.this), name)?,
+            "transform" | "position" | "anchorPoint" | "scale" | "rotation" | "opacity" |
+            "index" | "inPoint" | "outPoint" | "startTime" =>
+                self.member(Val::Layer(self.this.layer?), name)?,
+            _ => Val::Func(GLOBALS.iter().find(|&&func| func == name).copied()?, None),
+        })
+    }
+
+    fn member(&self, object: Val<'s>, name: &str) -> Option<Val<'s>> {
+        let method = |methods: &[&'static str], receiver: Option<Val<'s>>|
+            methods.iter().find(|&&method| method == name)
+                .map(|&method| Val::Func(method, receiver.map(Box::new)));
+        Some(match object {
+            Val::Math => match name {
+                "PI" => Val::Num(core::f64::consts::PI),
+                "E"  => Val::Num(core::f64::consts::E),
+                _ => method(MATH, None)?,
+            },
+            Val::Comp => { let scope = self.scope?;
+                match name {
+                    "width"  => Val::Num(scope.size.0 as _),
+                    "height" => Val::Num(scope.size.1 as _),
+                    "frameDuration" => Val::Num(1. / self.fr as f64),
+                    "numLayers" => Val::Num(scope.layers().len() as _),
+                    _ => method(&["layer"], None)?,
+                }
+            }
+            Val::Layer(index) => {
+                let base = self.scope?.layers()[index].parentable()?;
+                match name {
+                    "transform" => Val::Transform(index),
+                    "index" => Val::Num(base.ind.map_or(index as u32 + 1, |ind| ind) as _),
+                    "name"  => Val::Str(base.vo.nm.clone()),
+                    "inPoint"   => Val::Num((base.ip / self.fr) as _),
+                    "outPoint"  => Val::Num((base.op / self.fr) as _),
+                    "startTime" => Val::Num((base.st / self.fr) as _),
+                    _ => method(&["effect"], Some(Val::Layer(index)))
+                        .or_else(|| self.member(Val::Transform(index), name))?,
+                }
+            }
+            Val::Transform(index) => Val::Prop(Prop { layer: Some(index),
+                keyed: self.transform_property(index, name)? }),
+            Val::Prop(prop) => match name {
+                "value" => self.sample(&prop, self.comp_frame)?,
+                "numKeys" => Val::Num(prop.keyed.key_times().len() as _),
+                _ => method(&["valueAtTime", "key", "nearestKey"], Some(Val::Prop(prop)))
+                    .or_else(|| self.member(self.sample(&prop, self.comp_frame)?, name))?,
+            },
+            Val::Key(prop, index) => match name {
+                "time"  => Val::Num(self.to_comp_frame(prop.layer,
+                    *prop.keyed.key_times().get(index)?) as f64 / self.fr as f64),
+                "value" => self.key_value(&prop, index)?,
+                "index" => Val::Num(index as f64 + 1.),
+                _ => return None,
+            },
+            Val::Arr(comps) if name == "length" => Val::Num(comps.len() as _),
+            Val::Str(text) if name == "length" => Val::Num(text.chars().count() as _),
+            _ => return None,
+        })
+    }
+
+    fn transform_property(&self, index: usize, name: &str) -> Option<&'s dyn Keyed> {
+        let ks = match &self.scope?.layers()[index] {
+            LayerItem::Camera(camera) => &camera.ks,
+            layer => &layer.visual_layer()?.ks,
+        };
+        let split = || match ks.position.as_ref()? {
+            Translation::Split(sv) => Some(&**sv), Translation::Normal(_) => None,
+        };
+        let rotation = || match &ks.extra {
+            TransRotation::Split3D(rot) => Some(&**rot),
+            TransRotation::Normal2D { .. } => None,
+        };
+        let keyed: &'s dyn Keyed = match name {
+            "position" => match ks.position.as_ref()? {
+                Translation::Normal(position) => position as &dyn Keyed,
+                Translation::Split(sv) => &**sv,
+            },
+            "xPosition" => &split()?.x,
+            "yPosition" => &split()?.y,
+            "zPosition" => split()?.z.as_ref()?,
+            "anchorPoint" => ks.anchor.as_ref()?,
+            "scale"   => ks.scale.as_ref()?,
+            "opacity" => ks.opacity.as_ref()?,
+            "rotation" | "zRotation" => match &ks.extra {
+                TransRotation::Normal2D { rotation } => rotation.as_ref()?,
+                TransRotation::Split3D(rot) => &rot.rz,
+            },
+            "xRotation" => rotation()?.rx.as_ref()?,
+            "yRotation" => rotation()?.ry.as_ref()?,
+            "orientation" => rotation()?.or.as_ref()?,
+            "skew" => ks.skew.as_ref()?,
+            "skewAxis" => ks.skew_axis.as_ref()?,
+            _ => return None,
+        };  Some(keyed)
+    }
+
+    fn call(&mut self, callee: Val<'s>, args: &[Val<'s>]) -> Option<Val<'s>> {
+        let (name, receiver) = match callee {
+            Val::Func(name, receiver) => (name, receiver.map(|receiver| *receiver)),
+            Val::Effect(layer, effect) =>
+                return self.effect_param(layer, effect, args.first()?),
+            _ => return None,
+        };
+        let num = |index: usize| args.get(index).and_then(Val::num);
+        let prop = match &receiver { Some(Val::Prop(prop)) => *prop, _ => self.this };
+
+        Some(match name {
+            "abs" | "floor" | "ceil" | "round" | "sqrt" | "sin" | "cos" | "tan" | "asin" |
+            "acos" | "atan" | "exp" | "log" => { let v = num(0)?;
+                Val::Num(match name {
+                    "abs" => v.abs(), "floor" => v.floor(), "ceil" => v.ceil(),
+                    "round" => (v + 0.5).floor(),   // JavaScript rounds half up
+                    "sqrt" => v.sqrt(), "sin" => v.sin(), "cos" => v.cos(), "tan" => v.tan(),
+                    "asin" => v.asin(), "acos" => v.acos(), "atan" => v.atan(),
+                    "exp" => v.exp(), _ => v.ln(),
+                })
+            }
+            "atan2" => Val::Num(num(0)?.atan2(num(1)?)),
+            "pow" => Val::Num(num(0)?.powf(num(1)?)),
+            "min" | "max" => Val::Num(args.iter().try_fold(
+                if name == "min" { f64::INFINITY } else { f64::NEG_INFINITY }, |acc, v| {
+                    let v = v.num()?;
+                    Some(if name == "min" { acc.min(v) } else { acc.max(v) })
+                })?),
+
+            "layer" => self.layer(args.first()?)?,
+            "effect" => {
+                let layer = match receiver { Some(Val::Layer(layer)) => layer,
+                    _ => self.this.layer? };    self.effect(layer, args.first()?)?
+            }
+            "valueAtTime" => self.sample(&prop, num(0)? as f32 * self.fr)?,
+            "key" => {
+                let index = num(0)? as usize;
+                if index < 1 || prop.keyed.key_times().len() < index { return None }
+                Val::Key(prop, index - 1)
+            }
+            "nearestKey" => {
+                let frame = self.to_local_frame(prop.layer, num(0)? as f32 * self.fr)?;
+                let (index, _) = prop.keyed.key_times().into_iter().enumerate()
+                    .min_by(|(_, a), (_, b)|
+                        (a - frame).abs().total_cmp(&(b - frame).abs()))?;
+                Val::Key(prop, index)
+            }
+
+            "wiggle" => self.wiggle(args)?,
+            "loopOut" | "loopIn" | "loopOutDuration" | "loopInDuration" =>
+                self.looped(name, args)?,
+            "linear" | "ease" | "easeIn" | "easeOut" => interpolate(name, args)?,
+            "clamp" => {
+                let (value, min, max) = (args.first()?, args.get(1)?, args.get(2)?);
+                match (value, min.num(), max.num()) {
+                    (Val::Arr(comps), Some(min), Some(max)) =>
+                        Val::Arr(comps.iter().map(|v| v.clamp(min, max)).collect()),
+                    _ => Val::Num(value.num()?.clamp(min.num()?, max.num()?)),
+                }
+            }
+            "length" => {
+                let mut comps = args.first()?.components()?;
+                if let Some(other) = args.get(1) {
+                    let other = other.components()?;
+                    comps.iter_mut().zip(other).for_each(|(a, b)| *a -= b);
+                }   Val::Num(comps.iter().map(|v| v * v).sum::<f64>().sqrt())
+            }
+            "normalize" => {
+                let comps = args.first()?.components()?;
+                let length = comps.iter().map(|v| v * v).sum::<f64>().sqrt();
+                Val::Arr(comps.iter().map(|v| if length == 0. { 0. } else { v / length })
+                    .collect())
+            }
+            "add" | "sum" | "$bm_sum" | "sub" | "$bm_sub" | "mul" | "$bm_mul" |
+            "div" | "$bm_div" | "$bm_mod" => {
+                let op = match name.trim_start_matches("$bm_") {
+                    "add" | "sum" => "+", "sub" => "-", "mul" => "*", "div" => "/", _ => "%",
+                };  binary(op, args.first()?.clone(), args.get(1)?.clone())?
+            }
+            "$bm_neg" => binary("*", args.first()?.clone(), Val::Num(-1.))?,
+            "degreesToRadians" => Val::Num(num(0)?.to_radians()),
+            "radiansToDegrees" => Val::Num(num(0)?.to_degrees()),
+            "timeToFrames" => Val::Num(num(0).unwrap_or(self.comp_frame as f64 /
+                self.fr as f64) * num(1).unwrap_or(self.fr as _)),
+            "framesToTime" => Val::Num(num(0)? / num(1).unwrap_or(self.fr as _)),
+
+            "seedRandom" => {
+                let timeless = args.get(1).is_some_and(Val::truthy);
+                self.seed = hash(num(0)?.to_bits());
+                self.rng = hash(self.seed ^ if timeless { 0 }
+                    else { (self.comp_frame as f64).to_bits() });  Val::Undef
+            }
+            "random" => match (args.first(), args.get(1)) {
+                (None, _) => Val::Num(self.random()),
+                (Some(max), None) => self.random_between(&Val::Num(0.), max)?,
+                (Some(min), Some(max)) => self.random_between(min, max)?,
+            },
+            _ => return None,
+        })
+    }
+
+    fn layer(&self, key: &Val) -> Option<Val<'s>> {
+        let layers = self.scope?.layers();
+        layers.iter().position(|layer| layer.parentable().is_some_and(|base| match key {
+            Val::Str(name) => base.vo.nm == *name,
+            Val::Num(index) => base.ind.is_some_and(|ind| ind as f64 == *index),
+            _ => false,
+        })).or_else(|| key.num().and_then(|index| (index as usize).checked_sub(1))
+            .filter(|&index| index < layers.len())).map(Val::Layer)
+    }
+
+    fn effect(&self, layer: usize, key: &Val) -> Option<Val<'s>> {
+        let effects = &self.scope?.layers()[layer].visual_layer()?.ef;
+        let index = match key {
+            Val::Str(name) => effects.iter().position(|effect| named(&effect.vo, name))?,
+            _ => (key.num()? as usize).checked_sub(1).filter(|&index| index < effects.len())?,
+        };  Some(Val::Effect(layer, index))
+    }
+
+    fn effect_param(&self, layer: usize, effect: usize, key: &Val) -> Option<Val<'s>> {
+        let params = &self.scope?.layers()[layer].visual_layer()?.ef.get(effect)?.ef;
+        let param = |item: &'s EffectValueItem| -> Option<(&'s VisualObject, &'s dyn Keyed)> {
+            Some(match item {
+                EffectValueItem::Slider(value) | EffectValueItem::Angle(value) |
+                EffectValueItem::Checkbox(value) | EffectValueItem::DropDown(value) |
+                EffectValueItem::EffectLayer(value) => (&value.vo, value.value.as_ref()?),
+                EffectValueItem::Point(value) => (&value.vo, value.value.as_ref()? as _),
+                EffectValueItem::EffectColor(value) =>
+                    (&value.vo, value.value.as_ref()? as _),
+                _ => return None,
+            })
+        };
+        let (_, keyed) = match key {
+            Val::Str(name) => params.iter().filter_map(param)
+                .find(|(vo, _)| named(vo, name))?,
+            _ => param(params.get((key.num()? as usize).checked_sub(1)?)?)?,
+        };  Some(Val::Prop(Prop { keyed, layer: Some(layer) }))
+    }
+
+    /// Value of `prop` at composition frame `frame`; other properties are evaluated
+    /// with their own expressions, in the scope of their layers.
+    fn sample(&self, prop: &Prop<'s>, frame: f32) -> Option<Val<'s>> {
+        let fnth = self.to_local_frame(prop.layer, frame)?;
+        let this = core::ptr::addr_eq(prop.keyed, self.this.keyed);
+        let comps = match self.scope.filter(|_| !this) {
+            Some(scope) => {
+                let previous = scope.layer.replace(prop.layer);
+                let comps = prop.keyed.value_at(fnth, true);
+                scope.layer.set(previous);  comps
+            }
+            None => prop.keyed.value_at(fnth, !this),
+        };  comps.map(Val::from_components)
+    }
+
+    fn key_value(&self, prop: &Prop<'s>, index: usize) -> Option<Val<'s>> {
+        prop.keyed.value_at(*prop.keyed.key_times().get(index)?, false)
+            .map(Val::from_components)
+    }
+
+    fn to_comp_frame(&self, layer: Option<usize>, fnth: f32) -> f32 {
+        self.scope.zip(layer).and_then(|(scope, layer)| scope.layers()[layer].parentable())
+            .map_or(fnth, |base| (fnth + base.st) * base.sr)
+    }
+
+    fn to_local_frame(&self, layer: Option<usize>, frame: f32) -> Option<f32> {
+        self.scope.zip(layer).and_then(|(scope, layer)| scope.layers()[layer].parentable())
+            .map_or(Some(frame), |base| base.local_frame(frame))
+    }
+
+    /// `wiggle(freq, amp, octaves = 1, amp_mult = .5, t = time)`,
+    /// smooth value noise, reproducible for the same expression and layer
+    fn wiggle(&self, args: &[Val]) -> Option<Val<'s>> {
+        let num = |index: usize| args.get(index).and_then(Val::num);
+        let (freq, amp) = (num(0)?, num(1)?);
+        let octaves = num(2).unwrap_or(1.).clamp(1., 10.) as u32;
+        let (amp_mult, time) = (num(3).unwrap_or(0.5),
+            num(4).unwrap_or(self.comp_frame as f64 / self.fr as f64));
+
+        Some(Val::from_components(self.value.iter().enumerate().map(|(comp, value)| {
+            let (mut sum, mut scale, mut freq) = (0., 1., freq);
+            for octave in 0..octaves {
+                let seed = hash(self.seed ^ ((comp as u64) << 8 | octave as u64));
+                sum += noise(time * freq, seed) * scale;
+                scale *= amp_mult;  freq *= 2.;
+            }   value + amp * sum
+        }).collect()))
+    }
+
+    /// `loopOut`/`loopIn` (`cycle`, `pingpong`, `offset`, `continue`) over the last/first
+    /// `numKeyframes` segments, or over a duration in seconds for the `*Duration` variants
+    fn looped(&self, name: &str, args: &[Val]) -> Option<Val<'s>> {
+        let current = || Some(Val::from_components(self.value.clone()));
+        let times = self.this.keyed.key_times();
+        let (count, frame) = (times.len(), self.frame);
+        if count < 2 { return current() }
+        let kind = match args.first() {
+            Some(Val::Str(kind)) => kind.as_str(), None | Some(Val::Undef) => "cycle",
+            _ => return None,
+        };
+
+        let out = name.starts_with("loopOut");
+        let (first, last) = (times[0], times[count - 1]);
+        let amount = args.get(1).and_then(Val::num).filter(|&amount| 0. < amount);
+        let (first, last) = match amount {
+            None => (first, last),
+            Some(span) if name.ends_with("Duration") => { let span = span as f32 * self.fr;
+                if out { ((last - span).max(first), last) }
+                else { (first, (first + span).min(last)) }
+            }
+            Some(keys) => { let keys = (keys as usize).min(count - 1);
+                if out { (times[count - 1 - keys], last) } else { (first, times[keys]) }
+            }
+        };
+        let duration = last - first;
+        if duration <= 0. || out && frame <= last || !out && first <= frame {
+            return current()
+        }
+
+        let at = |fnth: f32| self.this.keyed.value_at(fnth, false);
+        let elapsed = if out { frame - first } else { last - frame };
+        let (cycles, phase) = ((elapsed / duration).floor(), elapsed.rem_euclid(duration));
+        let comps = match kind {
+            "cycle" => at(if out { first + phase } else { last - phase })?,
+            "pingpong" => at(if (cycles as i64 % 2 == 0) == out { first + phase }
+                else { last - phase })?,
+            "offset" => {
+                let (base, start, end) = (at(if out { first + phase } else { last - phase })?,
+                    at(first)?, at(last)?);
+                let cycles = if out { cycles } else { -cycles } as f64;
+                base.iter().zip(start.iter().zip(&end))
+                    .map(|(base, (start, end))| base + (end - start) * cycles).collect()
+            }
+            "continue" => {
+                let (edge, near) = if out { (at(last)?, at(last - 1.)?) }
+                    else { (at(first)?, at(first + 1.)?) };
+                let frames = if out { frame - last } else { first - frame } as f64;
+                edge.iter().zip(&near).map(|(edge, near)| edge + (edge - near) * frames)
+                    .collect()
+            }
+            _ => return None,
+        };  Some(Val::from_components(comps))
+    }
+
+    fn random(&mut self) -> f64 {
+        self.rng = hash(self.rng);  (self.rng >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    fn random_between(&mut self, min: &Val, max: &Val) -> Option<Val<'s>> {
+        let (min, max) = (min.components()?, max.components()?);
+        let len = min.len().max(max.len());
+        let comps = (0..len).map(|index| {
+            let (min, max) = (min.get(index).copied().unwrap_or(0.),
+                max.get(index).copied().unwrap_or(0.));
+            min + (max - min) * self.random()
+        }).collect();   Some(Val::from_components(comps))
+    }
+}
+
+fn named(vo: &VisualObject, name: &str) -> bool { vo.nm == name || vo.mn == name }
+
+/// `linear`/`ease`/`easeIn`/`easeOut` `(t, [tMin, tMax,] value1, value2)`
+fn interpolate<'s>(name: &str, args: &[Val<'s>]) -> Option<Val<'s>> {
+    let (time, (start, end), (from, to)) = match args {
+        [time, from, to] => (time.num()?, (0., 1.), (from, to)),
+        [time, start, end, from, to] => (time.num()?, (start.num()?, end.num()?), (from, to)),
+        _ => return None,
+    };
+    let t = if start == end { if time < start { 0. } else { 1. } }
+        else { ((time - start) / (end - start)).clamp(0., 1.) };
+    let t = match name {
+        "ease"   => math::CubicBezierEasing::new((0.333, 0.), (0.667, 1.)).get_y(t as _),
+        "easeIn" => math::CubicBezierEasing::new((0.333, 0.), (1., 1.)).get_y(t as _),
+        "easeOut" => math::CubicBezierEasing::new((0., 0.), (0.667, 1.)).get_y(t as _),
+        _ => t as f32,
+    } as f64;
+
+    Some(match (from, to) {
+        (Val::Arr(_), _) | (_, Val::Arr(_)) => {
+            let (from, to) = (from.components()?, to.components()?);
+            Val::Arr((0..from.len().max(to.len())).map(|index| {
+                let (a, b) = (from.get(index).copied().unwrap_or(0.),
+                    to.get(index).copied().unwrap_or(0.));
+                a + (b - a) * t
+            }).collect())
+        }
+        _ => { let (a, b) = (from.num()?, to.num()?);  Val::Num(a + (b - a) * t) }
+    })
+}
+
+/// Gradient-free 1D value noise in `-1~1`
+fn noise(x: f64, seed: u64) -> f64 {
+    let lattice = |cell: f64| (hash(seed ^ (cell as i64 as u64)
+        .wrapping_mul(0x9E37_79B9_7F4A_7C15)) >> 11) as f64 / (1u64 << 52) as f64 - 1.;
+    let (cell, t) = (x.floor(), x - x.floor());
+    let t = t * t * (3. - 2. * t);
+    lattice(cell) + (lattice(cell + 1.) - lattice(cell)) * t
+}
+
+fn hash(mut x: u64) -> u64 {    // SplitMix64
+    x = x.wrapping_add(0x9E37_79B9_7F4A_7C15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);    x ^ (x >> 31)
+}
+
+const GLOBALS: &[&str] = &["wiggle", "loopOut", "loopIn", "loopOutDuration", "loopInDuration",
+    "linear", "ease", "easeIn", "easeOut", "valueAtTime", "key", "nearestKey", "effect",
+    "clamp", "length", "normalize", "add", "sum", "sub", "mul", "div",
+    "$bm_sum", "$bm_sub", "$bm_mul", "$bm_div", "$bm_mod", "$bm_neg",
+    "degreesToRadians", "radiansToDegrees", "timeToFrames", "framesToTime",
+    "seedRandom", "random"];
+const MATH: &[&str] = &["abs", "floor", "ceil", "round", "sqrt", "sin", "cos", "tan",
+    "asin", "acos", "atan", "atan2", "exp", "log", "pow", "min", "max", "random"];
+
+pub(crate) struct Program(Vec<Stmt>);
+
+enum Stmt {
+    Expr(Expr), Var(Vec<(String, Option<Expr>)>),
+    If(Expr, Box<Stmt>, Option<Box<Stmt>>), Block(Vec<Stmt>), Return(Option<Expr>),
+}
+
+enum Expr {
+    Num(f64), Str(String), Ident(String), Array(Vec<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+    Member(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Call(Box<Expr>, Vec<Expr>),
+    Assign(Box<Expr>, Option<&'static str>, Box<Expr>),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token { Num(f64), Str(String), Ident(String), Punct(&'static str) }
+
+const PUNCTS: &[&str] = &["===", "!==", "==", "!=", "<=", ">=", "&&", "||",
+    "+=", "-=", "*=", "/=", "(", ")", "[", "]", "{", "}", ",", ";", ".", "?", ":",
+    "+", "-", "*", "/", "%", "=", "<", ">", "!"];
+const MAX_NESTING: u32 = 64;
+
+impl Program {
+    pub(crate) fn parse(source: &str) -> Option<Self> {
+        let mut parser = Parser { tokens: tokenize(source)?, pos: 0, depth: 0 };
+        let mut stmts = Vec::new();
+        while parser.pos < parser.tokens.len() { stmts.push(parser.statement()?); }
+        Some(Self(stmts))
+    }
+}
+
+fn tokenize(source: &str) -> Option<Vec<Token>> {
+    let (bytes, mut tokens, mut pos) = (source.as_bytes(), Vec::new(), 0);
+    while let Some(&byte) = bytes.get(pos) {
+        let rest = &source[pos..];
+        if byte.is_ascii_whitespace() { pos += 1;   continue }
+        if rest.starts_with("//") { pos += rest.find('\n').unwrap_or(rest.len());  continue }
+        if rest.starts_with("/*") { pos += rest.find("*/")? + 2;    continue }
+
+        let digit = |pos: usize| bytes.get(pos).is_some_and(u8::is_ascii_digit);
+        if digit(pos) || byte == b'.' && digit(pos + 1) {
+            let start = pos;
+            while digit(pos) || bytes.get(pos) == Some(&b'.') { pos += 1; }
+            if matches!(bytes.get(pos), Some(b'e' | b'E')) {
+                let sign = matches!(bytes.get(pos + 1), Some(b'+' | b'-')) as usize;
+                if digit(pos + 1 + sign) { pos += 1 + sign;   while digit(pos) { pos += 1; } }
+            }   tokens.push(Token::Num(source[start..pos].parse().ok()?));
+        } else if byte == b'"' || byte == b'\'' {
+            let (mut text, mut chars) = (String::new(), rest[1..].char_indices());
+            loop { match chars.next()? {
+                (end, ch) if ch as u32 == byte as u32 => { pos += end + 2;  break }
+                (_, '\\') => text.push(match chars.next()?.1 {
+                    'n' => '\n', 't' => '\t', 'r' => '\r', ch => ch }),
+                (_, ch) => text.push(ch),
+            } } tokens.push(Token::Str(text));
+        } else if byte.is_ascii_alphabetic() || byte == b'_' || byte == b'$' {
+            let len = rest.find(|ch: char|
+                !(ch.is_ascii_alphanumeric() || ch == '_' || ch == '$'))
+                .unwrap_or(rest.len());
+            tokens.push(Token::Ident(rest[..len].to_owned()));  pos += len;
+        } else {
+            let punct = PUNCTS.iter().find(|punct| rest.starts_with(**punct))?;
+            tokens.push(Token::Punct(punct));   pos += punct.len();
+        }
+    }   Some(tokens)
+}
+
+struct Parser { tokens: Vec<Token>, pos: usize, depth: u32 }
+
+impl Parser {
+    fn eat(&mut self, punct: &str) -> bool {
+        let matched = matches!(self.tokens.get(self.pos),
+            Some(Token::Punct(token)) if *token == punct);
+        if matched { self.pos += 1; }   matched
+    }
+
+    fn expect(&mut self, punct: &str) -> Option<()> { self.eat(punct).then_some(()) }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        let matched = matches!(self.tokens.get(self.pos),
+            Some(Token::Ident(token)) if token == keyword);
+        if matched { self.pos += 1; }   matched
+    }
+
+    fn ident(&mut self) -> Option<String> {
+        let Some(Token::Ident(name)) = self.tokens.get(self.pos) else { return None };
+        self.pos += 1;  Some(name.clone())
+    }
+
+    fn statement(&mut self) -> Option<Stmt> {
+        self.depth += 1;    if MAX_NESTING < self.depth { return None }
+        let stmt = if self.eat(";") { Stmt::Block(Vec::new()) }
+        else if self.eat("{") {
+            let mut body = Vec::new();
+            while !self.eat("}") { body.push(self.statement()?); }  Stmt::Block(body)
+        } else if self.keyword("var") || self.keyword("let") || self.keyword("const") {
+            let mut decls = Vec::new();
+            loop {
+                let name = self.ident()?;
+                let init = if self.eat("=") { Some(self.assignment()?) } else { None };
+                decls.push((name, init));   if !self.eat(",") { break }
+            }   self.eat(";");  Stmt::Var(decls)
+        } else if self.keyword("if") {
+            self.expect("(")?;  let cond = self.expression()?;   self.expect(")")?;
+            let then = Box::new(self.statement()?);
+            let other = if self.keyword("else") { Some(Box::new(self.statement()?)) }
+                else { None };  Stmt::If(cond, then, other)
+        } else if self.keyword("return") {
+            let value = match self.tokens.get(self.pos) {
+                None | Some(Token::Punct(";" | "}")) => None,
+                _ => Some(self.expression()?),
+            };  self.eat(";");  Stmt::Return(value)
+        } else { let expr = self.expression()?;  self.eat(";");  Stmt::Expr(expr) };
+        self.depth -= 1;    Some(stmt)
+    }
+
+    fn expression(&mut self) -> Option<Expr> { self.assignment() }
+
+    fn assignment(&mut self) -> Option<Expr> {
+        let target = self.conditional()?;
+        for op in ["=", "+=", "-=", "*=", "/="] {
+            if !self.eat(op) { continue }
+            if !matches!(target, Expr::Ident(_) | Expr::Index(..)) { return None }
+            let op = PUNCTS.iter().find(|punct| **punct == &op[..op.len() - 1]).copied();
+            return Some(Expr::Assign(Box::new(target), op, Box::new(self.assignment()?)))
+        }   Some(target)
+    }
+
+    fn conditional(&mut self) -> Option<Expr> {
+        let cond = self.binary(0)?;
+        if !self.eat("?") { return Some(cond) }
+        let then = self.assignment()?;  self.expect(":")?;
+        Some(Expr::Cond(Box::new(cond), Box::new(then), Box::new(self.assignment()?)))
+    }
+
+    fn binary(&mut self, min: u8) -> Option<Expr> {
+        let mut lhs = self.unary()?;
+        while let Some(&Token::Punct(op)) = self.tokens.get(self.pos) {
+            let prec = match op {
+                "||" => 1, "&&" => 2, "==" | "!=" | "===" | "!==" => 3,
+                "<" | ">" | "<=" | ">=" => 4, "+" | "-" => 5, "*" | "/" | "%" => 6,
+                _ => break,
+            };  if prec < min { break }
+            self.pos += 1;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(self.binary(prec + 1)?));
+        }   Some(lhs)
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        self.depth += 1;    if MAX_NESTING < self.depth { return None }
+        let expr = match ["-", "+", "!"].into_iter().find(|op| self.eat(op)) {
+            Some(op) => Expr::Unary(op, Box::new(self.unary()?)),
+            None => self.postfix()?,
+        };  self.depth -= 1;    Some(expr)
+    }
+
+    fn postfix(&mut self) -> Option<Expr> {
+        let mut expr = self.primary()?;
+        loop {
+            expr = if self.eat(".") { Expr::Member(Box::new(expr), self.ident()?) }
+            else if self.eat("[") {
+                let index = self.expression()?;     self.expect("]")?;
+                Expr::Index(Box::new(expr), Box::new(index))
+            } else if self.eat("(") { Expr::Call(Box::new(expr), self.list(")")?) }
+            else { break Some(expr) };
+        }
+    }
+
+    fn list(&mut self, close: &str) -> Option<Vec<Expr>> {
+        let mut items = Vec::new();
+        while !self.eat(close) {
+            items.push(self.assignment()?);
+            if !self.eat(",") { self.expect(close)?;    break }
+        }   Some(items)
+    }
+
+    fn primary(&mut self) -> Option<Expr> {
+        if self.eat("(") {
+            let expr = self.expression()?;  self.expect(")")?;  return Some(expr)
+        }
+        if self.eat("[") { return Some(Expr::Array(self.list("]")?)) }
+        let expr = match self.tokens.get(self.pos)? {
+            Token::Num(v) => Expr::Num(*v),
+            Token::Str(v) => Expr::Str(v.clone()),
+            Token::Ident(name) if !matches!(name.as_str(), "function" | "for" | "while" |
+                "do" | "new" | "var" | "let" | "const" | "if" | "else" | "return") =>
+                Expr::Ident(name.clone()),
+            _ => return None,
+        };  self.pos += 1;  Some(expr)
+    }
+}
+
+#[cfg(test)] mod tests { use super::*;
+    use crate::core::schema::{Animation, Value, Animated2D};
+
+    fn scalar(json: &str) -> Value { serde_json::from_str(json).unwrap() }
+
+    #[test] fn parses_the_supported_subset_only() {
+        assert!(Program::parse("var $bm_rt; $bm_rt = value * 2 + [1, 2][0];").is_some());
+        assert!(Program::parse("if (time > 1) { 5 } else 3; // comment").is_some());
+        for source in ["while (true) {}", "function f() {}", "x = ", "(1", "'open"] {
+            assert!(Program::parse(source).is_none(), "{source}");
+        }
+        assert!(Program::parse(&"-".repeat(1000)).is_none());
+    }
+
+    #[test] fn evaluates_time_value_and_interpolation_helpers() {
+        let prop = scalar(r#"{"k":10,"x":"var $bm_rt; $bm_rt = value + time * 60;"}"#);
+        assert_eq!(prop.get_value(30.), 40.);   // default 60 fps outside a composition
+
+        let prop = scalar(r#"{"k":0,"x":"linear(time, 0, 1, 0, 100)"}"#);
+        assert_eq!(prop.get_value(30.), 50.);
+        let prop = scalar(r#"{"k":0,"x":"ease(time, 0, 1, [0, 0], [100, 200])[1]"}"#);
+        assert_eq!(prop.get_value(60.), 200.);
+        let prop = scalar(r#"{"k":7,"x":"if (time < 1) { return 1 } value"}"#);
+        assert_eq!((prop.get_value(0.), prop.get_value(60.)), (1., 7.));
+
+        // unsupported or failing expressions keep the keyframed value
+        assert_eq!(scalar(r#"{"k":3,"x":"for (;;) {}"}"#).get_value(0.), 3.);
+        assert_eq!(scalar(r#"{"k":3,"x":"undefinedName * 2"}"#).get_value(0.), 3.);
+    }
+
+    #[test] fn loops_keyframes_out_and_in() {
+        let keys = r#"[{"t":10,"s":[0],"o":{"x":[0],"y":[0]},"i":{"x":[1],"y":[1]}},
+            {"t":20,"s":[10]}]"#;
+        let looped = |x: &str| scalar(&format!(r#"{{"a":1,"k":{keys},"x":"{x}"}}"#));
+
+        let cycle = looped("loopOut()");
+        assert_eq!((cycle.get_value(15.), cycle.get_value(25.)), (5., 5.));
+        assert_eq!(looped("loopOut('pingpong')").get_value(27.), 3.);
+        assert_eq!(looped("loopOut('offset')").get_value(35.), 25.);
+        assert_eq!(looped("loopOut('continue')").get_value(25.), 15.);
+        assert_eq!(looped("loopIn('cycle')").get_value(3.), 3.);
+        assert_eq!(looped("loopIn('offset')").get_value(3.), -7.);
+    }
+
+    #[test] fn wiggle_is_smooth_and_reproducible() {
+        let prop: Animated2D = serde_json::from_str(
+            r#"{"k":[50,50],"x":"wiggle(2, 10)"}"#).unwrap();
+        let (a, b) = (prop.get_value(30.), prop.get_value(30.));
+        assert_eq!((a.x, a.y), (b.x, b.y));
+        assert!((a.x - 50.).abs() <= 10. && (a.y - 50.).abs() <= 10.);
+        assert!((a.x - prop.get_value(31.).x).abs() < 2.);
+    }
+
+    #[test] fn reads_other_layers_and_effect_controls() {
+        let animation: Animation = serde_json::from_str(r#"{ "fr":30, "layers": [
+            {"ty":3,"nm":"Control","ind":1,"ip":0,"op":60,"ks":{"p":{"k":[30,40]}},
+                "ef":[{"ty":5,"nm":"Speed","ef":[{"ty":0,"nm":"Slider","v":{"k":4}}]}]},
+            {"ty":3,"ind":2,"ip":0,"op":60,"st":10,"ks":{
+                "o":{"k":100,
+                    "x":"thisComp.layer('Control').effect('Speed')('Slider') * time"},
+                "p":{"k":[0,0],"x":"thisComp.layer(1).transform.position + [1, 2]"},
+                "r":{"k":0,"x":"effect('Missing')(1)"}}}
+        ] }"#).unwrap();
+        let animation = Rc::new(animation);
+        let ks = &animation.layers[1].visual_layer().unwrap().ks;
+        let Some(Translation::Normal(position)) = &ks.position else { panic!() };
+
+        with_composition(&animation, None, (100, 100), || {
+            select_layer(1);    // local frame 20 is composition frame 30, i.e. 1 second
+            assert_eq!(ks.opacity.as_ref().unwrap().get_value(20.), 4.);
+            let position = position.get_value(20.);
+            assert_eq!((position.x, position.y), (31., 42.));
+            let TransRotation::Normal2D { rotation: Some(rotation) } = &ks.extra
+                else { panic!() };
+            assert_eq!(rotation.get_value(20.), 0.);
+        });
+        assert_eq!(ks.opacity.as_ref().unwrap().get_value(20.), 100.);   // no layers in scope
+    }
+}
@@ -164,6 +164,22 @@ pub use super::schema_impl::{AnyAsset, AnyValue, UnresolvedSlot};
 
 pub const ACCURACY_TOLERANCE: f64 = 1e-2;
 
+/// Output size and the view transform mapping content of `natural` size into it, by
+/// `scale`, or fitted into `size` if only that is given; the content is centered.
+pub fn fit_viewport(natural: (f32, f32), size: Option<(u32, u32)>,
+    scale: Option<f32>) -> ((u32, u32), [f64; 6]) {
+    let (cw, ch) = (natural.0.max(1.), natural.1.max(1.));
+    let (size, scale) = match (size, scale) {
+        (Some(size), Some(scale)) => (size, scale),
+        (Some((w, h)), None) => ((w, h), (w as f32 / cw).min(h as f32 / ch)),
+        (None, scale) => { let scale = scale.unwrap_or(1.);
+            (((cw * scale).ceil() as _, (ch * scale).ceil() as _), scale)
+        }
+    };
+    let offset = ((size.0 as f32 - cw * scale) / 2., (size.1 as f32 - ch * scale) / 2.);
+    (size, [scale, 0., 0., scale, offset.0, offset.1].map(|v| v as _))
+}
+
 pub mod math {  use super::*;
 
 /** Fast arctangent approximations by iterative algorithms, the coordinated rotation
@@ -359,11 +375,23 @@ pub trait Tween: Sized { fn lerp(&self, other: &Self, t: f32) -> Self; // Linear
     }
     fn bezc(&self, _: &Self, _: f32, _: &PositionExtra) -> Self
         where Self: Sized { unreachable!() }    // Cubic Bezier interpolation
+
+    /// Components as seen from expressions, `None` if not scriptable
+    #[cfg(feature = "expression")] fn to_components(&self) -> Option<Vec<f64>> { None }
+    /// Value of the same kind (`self` as the template) from expression result components
+    #[cfg(feature = "expression")]
+    fn with_components(&self, _: &[f64]) -> Option<Self> { None }
 }
 
 impl Tween for f32 {
     fn lerp(&self, other: &Self, t: f32) -> Self { self + (other - self) * t }
     // fn lerp(&self, other: &Self, t: f32) -> Self { self * (1. - t) + other * t }
+
+    #[cfg(feature = "expression")]
+    fn to_components(&self) -> Option<Vec<f64>> { Some(vec![*self as _]) }
+    #[cfg(feature = "expression")] fn with_components(&self, comps: &[f64]) -> Option<Self> {
+        comps.first().map(|&v| v as _)
+    }
 }
 
 impl Tween for Vec2D {
@@ -377,6 +405,13 @@ impl Tween for Vec2D {
                y: self.y + (other.y - self.y) * factor(1) }
     }
 
+    #[cfg(feature = "expression")] fn to_components(&self) -> Option<Vec<f64>> {
+        Some(vec![self.x as _, self.y as _])
+    }
+    #[cfg(feature = "expression")] fn with_components(&self, comps: &[f64]) -> Option<Self> {
+        Some(Self { x: *comps.first()? as _, y: comps.get(1).map_or(self.y, |&y| y as _) })
+    }
+
     fn bezc(&self, other: &Self, t: f32, extra: &PositionExtra) -> Self {
         /* impl From<&Vec2D> for Coord2 {
             fn from(val: &Vec2D) -> Self { Self { x: val.x as _, y: val.y as _ } }
@@ -423,6 +458,18 @@ impl Tween for RGBA {
                b: channel(self.b, other.b, factor(2)),
                a: channel(self.a, other.a, factor(3)) }
     }
+
+    /// Color channels are in 0~1 for expressions
+    #[cfg(feature = "expression")] fn to_components(&self) -> Option<Vec<f64>> {
+        Some([self.r, self.g, self.b, self.a].map(|v| v as f64 / 255.).to_vec())
+    }
+    #[cfg(feature = "expression")] fn with_components(&self, comps: &[f64]) -> Option<Self> {
+        if comps.len() < 3 { return None }
+        let channel = |index: usize, fallback: u8| comps.get(index)
+            .map_or(fallback, |v| (v.clamp(0., 1.) * 255.).round() as u8);
+        Some(Self { r: channel(0, 0), g: channel(1, 0), b: channel(2, 0),
+                    a: channel(3, self.a) })
+    }
 }
 
 impl Tween for Bezier {
@@ -454,6 +501,14 @@ impl Tween for Vec<f32> {   // aka MultiD
         self.iter().zip(other).enumerate().map(|(index, (first, second))|
             first + (second - first) * factor(index)).collect()
     }
+
+    #[cfg(feature = "expression")] fn to_components(&self) -> Option<Vec<f64>> {
+        Some(self.iter().map(|&v| v as _).collect())
+    }
+    #[cfg(feature = "expression")] fn with_components(&self, comps: &[f64]) -> Option<Self> {
+        Some(self.iter().enumerate().map(|(index, &v)|
+            comps.get(index).map_or(v, |&v| v as _)).collect())
+    }
 }
 
 }
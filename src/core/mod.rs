@@ -2,9 +2,17 @@
 pub mod helpers;
 pub mod schema;
 mod schema_impl;
+#[cfg(feature = "expression")] mod expression;
 pub mod render;
+#[cfg(feature = "dotlottie")] pub mod dotlottie;
+mod playback;
+pub use playback::{Playback, LoopMode, Segment, PlaybackEvent};
 mod composite;
-pub use composite::CompositeContext;
+pub use composite::{CompositeContext, StyleStage, blend_pixels, apply_styles};
+mod effects;
+pub use effects::{LayerEffect, Levels, apply_effects};
 pub mod pathm;
 mod path_ops;
 pub mod style;
+pub mod text;
+mod text_range;
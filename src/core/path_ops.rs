@@ -69,39 +69,62 @@ pub(super) fn round_contour(elements: &[kurbo::PathEl], closed: bool, radius: f6
     }
 }
 
-pub(super) fn flatten_contour(elements: &[kurbo::PathEl],
-    tolerance: f64, points: &mut Vec<kurbo::Point>) {
-    fn flatten(segment: kurbo::PathSeg, tolerance: f64,
-        points: &mut Vec<kurbo::Point>, depth: u8) {
-        let (start, end, middle) =
-            (segment.eval(0.), segment.eval(1.), segment.eval(0.5));
-        let chord = end - start;
-        let distance = if chord.hypot2() == 0. { (middle - start).hypot()
-        } else { chord.cross(middle - start).abs() / chord.hypot() };
-        if  distance <= tolerance || depth == 12 { points.push(end); } else {
-            flatten(segment.subsegment(0. .. 0.5), tolerance, points, depth + 1);
-            flatten(segment.subsegment(0.5 .. 1.), tolerance, points, depth + 1);
-        }
+/// Subdivides `segment` over `range` until its midpoint deviates from the chord by
+/// no more than `tolerance`, reporting each piece's end point and parameter range.
+fn subdivide(segment: kurbo::PathSeg, range: (f64, f64), tolerance: f64, depth: u8,
+    emit: &mut impl FnMut(kurbo::Point, (f64, f64))) {
+    let middle = (range.0 + range.1) / 2.;
+    let (start, end) = (segment.eval(range.0), segment.eval(range.1));
+    let (chord, offset) = (end - start, segment.eval(middle) - start);
+    let distance = if chord.hypot2() == 0. { offset.hypot()
+    } else { chord.cross(offset).abs() / chord.hypot() };
+    if  distance <= tolerance || depth == 12 { emit(end, range) } else {
+        subdivide(segment, (range.0, middle), tolerance, depth + 1, emit);
+        subdivide(segment, (middle, range.1), tolerance, depth + 1, emit);
     }
+}
 
+/// Flattens a contour, passing each curve with the points approximating it.
+fn flatten_segments(elements: &[kurbo::PathEl], tolerance: f64,
+    mut emit: impl FnMut(kurbo::Point, Option<(kurbo::PathSeg, (f64, f64))>)) {
     let mut current = None;
-    for &element in elements { match element {
-        MoveTo(point) => { points.push(point); current = Some(point); }
-        LineTo(point) => { points.push(point); current = Some(point); }
-        QuadTo(control, point) => {
-            if let Some(start) = current {
-                flatten(kurbo::QuadBez::new(start, control, point).into(),
-                    tolerance, points, 0);
-            }   current = Some(point);
-        }
-        CurveTo(first, second, point) => {
-            if let Some(start) = current {
-                flatten(kurbo::CubicBez::new(start, first, second, point).into(),
-                    tolerance, points, 0);
-            }   current = Some(point);
-        }
-        ClosePath => {}
-    } } use kurbo::PathEl::*;
+    for &element in elements {
+        let segment: kurbo::PathSeg = match (element, current) {
+            (MoveTo(point) | LineTo(point), _) => {
+                emit(point, None);  current = Some(point);  continue
+            }
+            (QuadTo(control, point), Some(start)) =>
+                kurbo::QuadBez::new(start, control, point).into(),
+            (CurveTo(first, second, point), Some(start)) =>
+                kurbo::CubicBez::new(start, first, second, point).into(),
+            _ => continue,
+        };
+        subdivide(segment, (0., 1.), tolerance, 0,
+            &mut |point, range| emit(point, Some((segment, range))));
+        current = Some(segment.end());
+    }   use kurbo::PathEl::*;
+}
+
+pub(super) fn flatten_contour(elements: &[kurbo::PathEl],
+    tolerance: f64, points: &mut Vec<kurbo::Point>) {
+    flatten_segments(elements, tolerance, |point, _| points.push(point));
+}
+
+/// A curve by index with the parameter range that a polygon edge approximates.
+type CurveRange = (usize, f64, f64);
+/// A polygon vertex with the curve range of the edge to the next vertex,
+/// `None` for a straight edge.
+pub(super) type CurveVertex = (kurbo::Point, Option<CurveRange>);
+
+/// Flattens a contour into a polygon whose curved edges remember their source in `curves`.
+pub(super) fn flatten_curves(elements: &[kurbo::PathEl], tolerance: f64,
+    curves: &mut Vec<kurbo::PathSeg>, polygon: &mut Vec<CurveVertex>) {
+    flatten_segments(elements, tolerance, |point, curve| {
+        if let Some((segment, (t0, t1))) = curve {
+            if t0 == 0. { curves.push(segment) }
+            if let Some(last) = polygon.last_mut() { last.1 = Some((curves.len() - 1, t0, t1)) }
+        }   polygon.push((point, None));
+    });
 }
 
 pub(super) fn offset_contour(points: &mut Vec<kurbo::Point>, closed: bool,
@@ -174,6 +197,316 @@ pub(super) fn offset_contour(points: &mut Vec<kurbo::Point>, closed: bool,
     }
 }
 
+/// Contour segments as cubics, with lines keeping their control points on the
+/// vertices as Lottie bezier data does, so modifiers can bend them.
+fn cubic_segments(elements: &[kurbo::PathEl]) -> Vec<kurbo::CubicBez> {
+    kurbo::segments(elements.iter().copied()).map(|segment| match segment {
+        kurbo::PathSeg::Line(line) =>
+            kurbo::CubicBez::new(line.p0, line.p0, line.p1, line.p1),
+        kurbo::PathSeg::Quad(quad) => quad.raise(),
+        kurbo::PathSeg::Cubic(cubic) => cubic,
+    }).collect()
+}
+
+fn append_cubics(segments: &[kurbo::CubicBez], closed: bool, output: &mut BezPath) {
+    let Some(first) = segments.first() else { return };
+    output.move_to(first.p0);
+    segments.iter().for_each(|cubic| output.curve_to(cubic.p1, cubic.p2, cubic.p3));
+    if closed { output.close_path(); }
+}
+
+// https://github.com/airbnb/lottie-web/blob/master/player/js/utils/shapes/PuckerAndBloatModifier.js
+pub(super) fn pucker_contour(elements: &[kurbo::PathEl], closed: bool,
+    amount: f64, output: &mut BezPath) {
+    let mut segments = cubic_segments(elements);
+    let Some(last) = segments.last() else { return };
+    let mut vertices = segments.iter().map(|cubic| cubic.p0.to_vec2()).collect::<Vec<_>>();
+    if !closed || last.p3 != segments[0].p0 { vertices.push(last.p3.to_vec2()); }
+    let center = vertices.iter().copied().sum::<kurbo::Vec2>() / vertices.len() as f64;
+    let center = center.to_point();
+
+    // vertices move towards the center while their tangents move away from it
+    let towards = |point: kurbo::Point, amount: f64| point + (center - point) * amount;
+    for cubic in &mut segments {
+        *cubic = kurbo::CubicBez::new(towards(cubic.p0, amount), towards(cubic.p1, -amount),
+            towards(cubic.p2, -amount), towards(cubic.p3, amount));
+    }   append_cubics(&segments, closed, output);
+}
+
+/// Rotates points around the center by the full angle there, easing to none at `radius`.
+pub(super) fn twist_contour(elements: &[kurbo::PathEl], closed: bool, angle: f64,
+    center: kurbo::Point, radius: f64, output: &mut BezPath) {
+    let rotate = |point: kurbo::Point| {
+        let offset = point - center;
+        let angle  = angle * (1. - offset.hypot() / radius).max(0.);
+        let (sin, cos) = angle.sin_cos();
+        center + kurbo::Vec2::new(offset.x * cos - offset.y * sin,
+                                  offset.x * sin + offset.y * cos)
+    };
+    let mut segments = cubic_segments(elements);
+    for cubic in &mut segments {
+        *cubic = kurbo::CubicBez::new(rotate(cubic.p0), rotate(cubic.p1),
+            rotate(cubic.p2), rotate(cubic.p3));
+    }   append_cubics(&segments, closed, output);
+}
+
+// https://github.com/airbnb/lottie-web/blob/master/player/js/utils/shapes/ZigZagModifier.js
+pub(super) fn zigzag_contour(elements: &[kurbo::PathEl], closed: bool, ridges: u32,
+    amplitude: f64, smooth: bool, output: &mut BezPath) {
+    use kurbo::{ParamCurveDeriv, Point, Vec2};
+    let segments = kurbo::segments(elements.iter().copied()).collect::<Vec<_>>();
+    let Some(last) = segments.last() else { return };
+    let count = segments.len();
+    let unit = |v: Vec2| if v.hypot2() == 0. { v } else { v.normalize() };
+
+    // (point, unit tangent, in/out handle lengths), alternating sides from vertex 0
+    let mut points = Vec::<(Point, Vec2, f64, f64)>::new();
+    let step = ((ridges + 1) * 2) as f64;
+    let corner = |index: usize| {
+        let (prev, next) = if closed {
+            (segments[(index + count - 1) % count].start(), segments[index % count].end())
+        } else {
+            (segments[index.saturating_sub(1)].start(), segments[index.min(count - 1)].end())
+        };
+        let point = if index == count { last.end() } else { segments[index].start() };
+        let tangent = unit(next - prev);
+        let before = if index == 0 && !closed { 0. } else { (point - prev).hypot() };
+        let after  = if index == count { 0. } else { (next - point).hypot() };
+        (point, tangent, before / step, after / step)
+    };
+    for (index, segment) in segments.iter().enumerate() {
+        points.push(corner(index));
+        let chord = (segment.end() - segment.start()).hypot() / step;
+        for ridge in 1..=ridges {
+            let t = ridge as f64 / (ridges + 1) as f64;
+            let deriv = match segment {
+                kurbo::PathSeg::Line(line) => line.p1 - line.p0,
+                kurbo::PathSeg::Quad(quad) => quad.deriv().eval(t).to_vec2(),
+                kurbo::PathSeg::Cubic(cubic) => cubic.deriv().eval(t).to_vec2(),
+            };
+            points.push((segment.eval(t), unit(deriv), chord, chord));
+        }
+    }   if !closed { points.push(corner(count)); }
+
+    let (mut direction, mut previous) = (-1., None::<(Point, Vec2)>);
+    let mut first = None;
+    for &(point, tangent, before, after) in &points {
+        let point = point + Vec2::new(tangent.y, -tangent.x) * direction * amplitude;
+        match previous {
+            None => { output.move_to(point);  first = Some((point, tangent * before)); }
+            Some((from, out)) if smooth =>
+                output.curve_to(from + out, point - tangent * before, point),
+            Some(_) => output.line_to(point),
+        }   previous = Some((point, tangent * after));  direction = -direction;
+    }
+    if closed {
+        if let (true, Some((from, out)), Some((point, into))) = (smooth, previous, first) {
+            output.curve_to(from + out, point - into, point);
+        }   output.close_path();
+    }
+}
+
+const EPSILON: f64 = 1e-9;
+
+/// Combines two sets of closed polygons under the nonzero rule. Edges are split at
+/// every crossing, and a piece is kept (inside on its left) when `keep` of the
+/// subject/clip coverage differs on its two sides. Kept runs of a curved edge are
+/// restored as the matching part of their source curve.
+pub(super) fn boolean_contours(subject: &[Vec<CurveVertex>], clip: &[Vec<CurveVertex>],
+    curves: &[kurbo::PathSeg], keep: fn(bool, bool) -> bool) -> BezPath {
+    use kurbo::Point;
+    let mut edges = Vec::new();     // (start, end, operand, curve)
+    for (operand, polygons) in [subject, clip].into_iter().enumerate() {
+        for polygon in polygons {
+            for (index, &(start, curve)) in polygon.iter().enumerate() {
+                let end = polygon[(index + 1) % polygon.len()].0;
+                if start != end { edges.push((start, end, operand, curve)); }
+            }
+        }
+    }
+
+    // sweep along x, testing only edges with overlapping bounding boxes
+    let bounds = |(a, b): (Point, Point)| (a.x.min(b.x), a.x.max(b.x), a.y.min(b.y), a.y.max(b.y));
+    let mut order = (0..edges.len()).collect::<Vec<_>>();
+    order.sort_by(|&a, &b| bounds((edges[a].0, edges[a].1)).0
+        .total_cmp(&bounds((edges[b].0, edges[b].1)).0));
+    let mut splits = vec![Vec::<(f64, Point)>::new(); edges.len()];
+    for (rank, &i) in order.iter().enumerate() { for &j in &order[rank + 1..] {
+        let ((a1, a2, ..), (b1, b2, ..)) = (edges[i], edges[j]);
+        let (ab, bb) = (bounds((a1, a2)), bounds((b1, b2)));
+        if ab.1 + EPSILON < bb.0 { break }
+        if ab.3 + EPSILON < bb.2 || bb.3 + EPSILON < ab.2 { continue }
+
+        let (r, s, d) = (a2 - a1, b2 - b1, b1 - a1);
+        let denom = r.cross(s);
+        if denom.abs() <= EPSILON * r.hypot() * s.hypot() {
+            if EPSILON * r.hypot() < d.cross(r).abs() { continue }   // parallel apart
+            // collinear overlap: split each edge at the other's inner endpoints
+            for (index, (from, dir), points) in [(i, (a1, r), [b1, b2]), (j, (b1, s), [a1, a2])] {
+                for point in points {
+                    let t = (point - from).dot(dir) / dir.hypot2();
+                    if EPSILON < t && t < 1. - EPSILON { splits[index].push((t, point)); }
+                }
+            }   continue
+        }
+        let (t, u) = (d.cross(s) / denom, d.cross(r) / denom);
+        let range = -EPSILON..=1. + EPSILON;
+        if !range.contains(&t) || !range.contains(&u) { continue }
+        let point = if u.abs() <= EPSILON { b1 } else if (1. - u).abs() <= EPSILON { b2 }
+            else if t.abs() <= EPSILON { a1 } else if (1. - t).abs() <= EPSILON { a2 }
+            else { a1 + r * t };
+        if EPSILON < t && t < 1. - EPSILON { splits[i].push((t, point)); }
+        if EPSILON < u && u < 1. - EPSILON { splits[j].push((u, point)); }
+    } }
+
+    let mut pieces = Vec::with_capacity(edges.len());
+    for (&(start, end, operand, curve), splits) in edges.iter().zip(&mut splits) {
+        splits.sort_by(|a, b| a.0.total_cmp(&b.0));
+        let (mut from, mut at) = (start, 0.);
+        for (t, point) in splits.iter().copied().chain([(1., end)]) {
+            let curve = curve.map(|(index, t0, t1)|
+                (index, t0 + (t1 - t0) * at, t0 + (t1 - t0) * t));
+            if EPSILON < (point - from).hypot() { pieces.push((from, point, operand, curve)); }
+            (from, at) = (point, t);
+        }
+    }
+
+    // coincident pieces from either operand form one boundary with summed windings
+    let key = |point: Point| (point.x.to_bits(), point.y.to_bits());
+    let (mut groups, mut lookup) =
+        (Vec::<Vec<usize>>::new(), std::collections::HashMap::new());
+    for (index, &(start, end, ..)) in pieces.iter().enumerate() {
+        let (lo, hi) = if (start.x, start.y) < (end.x, end.y)
+            { (start, end) } else { (end, start) };
+        let group = *lookup.entry((key(lo), key(hi))).or_insert_with(|| {
+            groups.push(Vec::new()); groups.len() - 1 });
+        groups[group].push(index);
+    }
+
+    // bucket pieces by their y (and x, for rays along +y) spans to cast rays locally
+    let buckets = |axis: fn(Point) -> f64| {
+        let (min, max) = pieces.iter().flat_map(|&(a, b, ..)| [axis(a), axis(b)])
+            .fold((f64::MAX, f64::MIN), |(min, max), v| (min.min(v), max.max(v)));
+        let count = (pieces.len() as f64).sqrt().ceil().max(1.) as usize;
+        let scale = if min < max { count as f64 / (max - min) } else { 0. };
+        let slot = move |v: f64| (((v - min) * scale) as usize).min(count - 1);
+        let mut buckets = vec![Vec::new(); count];
+        for (index, &(a, b, ..)) in pieces.iter().enumerate() {
+            let (lo, hi) = (axis(a).min(axis(b)), axis(a).max(axis(b)));
+            (slot(lo)..=slot(hi)).for_each(|bucket| buckets[bucket].push(index));
+        }   (buckets, slot)
+    };
+    let (rows, row) = buckets(|point| point.y);
+    let (columns, column) = buckets(|point| point.x);
+
+    let mut output = Vec::new();
+    for members in &groups {
+        let (start, end, _, curve) = pieces[members[0]];
+        let (lo, hi) = if (start.x, start.y) < (end.x, end.y)
+            { (start, end) } else { (end, start) };
+        // cast along +x, or along +y with swapped axes for horizontal pieces
+        let horizontal = lo.y == hi.y;
+        let axes = |point: Point| if horizontal { Point::new(point.y, point.x) } else { point };
+        let middle = lo.midpoint(hi);
+        let candidates = if horizontal { &columns[column(middle.x)] } else { &rows[row(middle.y)] };
+        let middle = axes(middle);
+
+        let (mut behind, mut ahead) = ([0i32; 2], [0i32; 2]);
+        for &index in candidates {
+            let (start, end, operand, _) = pieces[index];
+            let (a, b) = (axes(start), axes(end));
+            if (a.y <= middle.y) == (b.y <= middle.y) { continue }
+            let winding = if a.y < b.y { 1 } else { -1 };
+            if members.contains(&index) { behind[operand] += winding; continue }
+            if middle.x < a.x + (middle.y - a.y) * (b.x - a.x) / (b.y - a.y) {
+                behind[operand] += winding;     ahead[operand] += winding;
+            }
+        }
+        let inside = |winding: [i32; 2]| keep(winding[0] != 0, winding[1] != 0);
+        let (behind, ahead) = (inside(behind), inside(ahead));
+        if behind == ahead { continue }
+
+        // the left side of lo -> hi lies behind the crossing point of the cast ray
+        let left_behind = if horizontal { hi.x < lo.x } else { lo.y < hi.y };
+        let (from, to) = if left_behind == behind { (lo, hi) } else { (hi, lo) };
+        output.push((from, to, curve.map(|(index, t0, t1)|
+            if from == start { (index, t0, t1) } else { (index, t1, t0) })));
+    }
+    chain_edges(output, curves)
+}
+
+/// Links directed boundary edges into closed contours, dropping collinear vertices
+/// and joining consecutive parts of one curve back into a single curve.
+fn chain_edges(edges: Vec<(kurbo::Point, kurbo::Point, Option<CurveRange>)>,
+    curves: &[kurbo::PathSeg]) -> BezPath {
+    let key = |point: kurbo::Point| (point.x.to_bits(), point.y.to_bits());
+    let mut outgoing = std::collections::HashMap::<_, Vec<usize>>::new();
+    for (index, &(start, ..)) in edges.iter().enumerate() {
+        outgoing.entry(key(start)).or_default().push(index);
+    }
+
+    let (mut used, mut path) = (vec![false; edges.len()], BezPath::new());
+    for first in 0..edges.len() {
+        if used[first] { continue }
+        let (mut contour, mut current) = (vec![first], first);
+        loop {
+            used[current] = true;
+            let (start, end, _) = edges[current];
+            // turning leftmost keeps pinched regions in separate contours
+            let incoming = end - start;
+            let next = outgoing.get(&key(end)).and_then(|candidates| candidates.iter()
+                .copied().filter(|&index| !used[index]).max_by(|&a, &b| {
+                    let turn = |index: usize| {
+                        let outgoing = edges[index].1 - end;
+                        incoming.cross(outgoing).atan2(incoming.dot(outgoing))
+                    };  turn(a).total_cmp(&turn(b))
+                }));
+            if end == edges[first].0 || next.is_none() { break }
+            current = next.unwrap();    contour.push(current);
+        }
+        if contour.len() < 3 { continue }
+
+        // a vertex is kept where the curve changes or straight edges make a corner
+        let count = contour.len();
+        let kept = (0..count).map(|index| {
+            let (prev, edge) = (edges[contour[(index + count - 1) % count]], edges[contour[index]]);
+            match (prev.2, edge.2) {
+                (Some((c0, _, t0)), Some((c1, t1, _))) => c0 != c1 || EPSILON < (t0 - t1).abs(),
+                (None, None) => {
+                    let (incoming, outgoing) = (prev.1 - prev.0, edge.1 - edge.0);
+                    EPSILON * incoming.hypot() * outgoing.hypot() <
+                        incoming.cross(outgoing).abs() || incoming.dot(outgoing) < 0.
+                }   _ => true,
+            }
+        }).collect::<Vec<_>>();
+        let Some(origin) = kept.iter().position(|&kept| kept) else { continue };
+        if kept.iter().filter(|&&kept| kept).count() < 2 &&
+            contour.iter().all(|&index| edges[index].2.is_none()) { continue }
+
+        let mut index = origin;
+        path.move_to(edges[contour[origin]].0);
+        loop {
+            let (_, mut end, curve) = edges[contour[index]];
+            let mut last = curve;
+            loop {
+                index = (index + 1) % count;
+                if kept[index] { break }
+                (end, last) = (edges[contour[index]].1, edges[contour[index]].2);
+            }
+            match curve.zip(last) {
+                Some(((curve, t0, _), (_, _, t1))) => match curves[curve].subsegment(t0..t1) {
+                    kurbo::PathSeg::Line(_) => path.line_to(end),
+                    kurbo::PathSeg::Quad(quad) => path.quad_to(quad.p1, end),
+                    kurbo::PathSeg::Cubic(cubic) => path.curve_to(cubic.p1, cubic.p2, end),
+                },
+                None if index == origin => break,  // closing the path draws it
+                None => path.line_to(end),
+            }
+            if index == origin { break }
+        }   path.close_path();
+    }   path
+}
+
 pub(crate) struct MeasuredPath {
     path: BezPath, tolerance: f64, pub length: f64,
     segments: Vec<(kurbo::PathSeg, f64)>,
@@ -223,6 +556,40 @@ impl MeasuredPath {
         }   BezPath::from_path_segments(output.into_iter())
     }
 
+    /// Point and unit tangent at the arc length `distance` over all contours,
+    /// extrapolated along the end tangents beyond either end of the path.
+    pub fn sample(&self, distance: f64) -> Option<(kurbo::Point, kurbo::Vec2)> {
+        let measured = |&&(_, len): &&(kurbo::PathSeg, f64)| 0. < len;
+        let (first, last) = (self.segments.iter().find(measured)?,
+                             self.segments.iter().rev().find(measured)?);
+        let tangent = |seg: &kurbo::PathSeg, t: f64| {
+            use kurbo::ParamCurveDeriv;
+            let deriv = match seg {
+                kurbo::PathSeg::Line(line) => line.p1 - line.p0,
+                kurbo::PathSeg::Quad(quad) => quad.deriv().eval(t).to_vec2(),
+                kurbo::PathSeg::Cubic(cubic) => cubic.deriv().eval(t).to_vec2(),
+            };  // coincident control points have no derivative at the ends
+            let deriv = if deriv.hypot2() <= f64::EPSILON {
+                seg.eval((t + 1e-3).min(1.)) - seg.eval((t - 1e-3).max(0.))
+            } else { deriv };
+            deriv.normalize()
+        };
+
+        if distance <= 0. {
+            let tangent = tangent(&first.0, 0.);
+            return Some((first.0.start() + tangent * distance, tangent))
+        }
+        let mut offset = 0.;
+        for (seg, len) in self.segments.iter().filter(measured) {
+            if distance <= offset + len {
+                let t = seg.inv_arclen(distance - offset, self.tolerance);
+                return Some((seg.eval(t), tangent(seg, t)))
+            }   offset += len;
+        }
+        let tangent = tangent(&last.0, 1.);
+        Some((last.0.end() + tangent * (distance - self.length), tangent))
+    }
+
     /// Apply a dash pattern using the segment lengths already measured by this path.
     pub fn dash(&self, offset: f64, pattern: &[f64]) -> BezPath {
         let period: f64 = pattern.iter().sum();
@@ -348,6 +715,19 @@ fn append_range(output: &mut BezPath, segments: &[(kurbo::PathSeg, f64)],
         assert!(unbroken.length > 0.);
     }
 
+    #[test] fn samples_points_and_tangents_by_arc_length() {
+        let mut path = BezPath::new();
+        path.move_to((0., 0.)); path.line_to((10., 0.)); path.line_to((10., 10.));
+        let measured = MeasuredPath::new(path, TOLERANCE);
+        let sample = |distance| measured.sample(distance)
+            .map(|(point, tangent)| (point.x, point.y, tangent.x, tangent.y));
+        assert_eq!(sample(4.), Some((4., 0., 1., 0.)));
+        assert_eq!(sample(15.), Some((10., 5., 0., 1.)));
+        assert_eq!(sample(-2.), Some((-2., 0., 1., 0.)));
+        assert_eq!(sample(22.), Some((10., 12., 0., 1.)));
+        assert!(MeasuredPath::new(BezPath::new(), TOLERANCE).sample(0.).is_none());
+    }
+
     #[test] fn measured_dash_preserves_an_unbroken_closed_contour() {
         let mut path = BezPath::new();
         path.move_to((0., 0.)); path.line_to((10., 0.));
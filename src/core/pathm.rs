@@ -5,10 +5,13 @@
  * Copyright (c) 2025 M.H.Fan, All rights reserved.             *
  ****************************************************************/
 
-use core::{mem, f32::consts::PI};
+use core::{mem, cell::RefCell, f32::consts::PI};
+use std::collections::HashMap;
 use super::{helpers::{Vec2D, ACCURACY_TOLERANCE},
-    path_ops::{flatten_contour, for_each_contour, offset_contour, round_contour},
-    schema::{Rectangle, Polystar, Ellipse, FreePath, ShapeProperty, StarType, LineJoin}
+    path_ops::{boolean_contours, flatten_contour, flatten_curves, for_each_contour, offset_contour,
+        round_contour, pucker_contour, twist_contour, zigzag_contour},
+    schema::{Rectangle, Polystar, Ellipse, FreePath, ShapeProperty, StarType, LineJoin,
+        MergeMode},
 };
 pub(crate) use super::path_ops::MeasuredPath;
 
@@ -118,6 +121,24 @@ pub trait PathBuilder {     //type Point; type Path;
         self.offset_path_with_tolerance(amount, join, miter_limit, ACCURACY_TOLERANCE);
     }
 
+    /// `amount` in percent: vertices move towards the center and tangents away
+    fn pucker_bloat(&mut self, amount: f32) where Self: Sized {
+        if amount == 0. { return }
+        modify_kurbo(self, |path| pucker_kurbo(path, amount));
+    }
+
+    /// `angle` in degrees, applied fully at `center` and fading out to the farthest point
+    fn twist(&mut self, angle: f32, center: Vec2D) where Self: Sized {
+        if angle == 0. { return }
+        modify_kurbo(self, |path| twist_kurbo(path, angle, center));
+    }
+
+    /// `ridges` per segment, `amplitude` from the path, smooth or corner points
+    fn zig_zag(&mut self, ridges: f32, amplitude: f32, smooth: bool) where Self: Sized {
+        if amplitude == 0. && ridges < 1. { return }
+        modify_kurbo(self, |path| zigzag_kurbo(path, ridges, amplitude, smooth));
+    }
+
     fn offset_path_with_tolerance(&mut self, amount: f32, join: LineJoin,
         miter_limit: f32, tolerance: f64) where Self: Sized {
         if amount == 0. { return }
@@ -152,6 +173,36 @@ pub(crate) fn round_kurbo(path: &mut BezPath, radius: f32) {
     *path = output;
 }
 
+pub(crate) fn pucker_kurbo(path: &mut BezPath, amount: f32) {
+    let (source, mut output) = (mem::take(path), BezPath::new());
+    for_each_contour(&source, |elements, closed|
+        pucker_contour(elements, closed, amount as f64 / 100., &mut output));
+    *path = output;
+}
+
+pub(crate) fn twist_kurbo(path: &mut BezPath, angle: f32, center: Vec2D) {
+    let (source, mut output) = (mem::take(path), BezPath::new());
+    let center = kurbo::Point::new(center.x as _, center.y as _);
+    let radius = source.elements().iter().flat_map(|element| match *element {
+        kurbo::PathEl::MoveTo(p) | kurbo::PathEl::LineTo(p) => vec![p],
+        kurbo::PathEl::QuadTo(c, p) => vec![c, p],
+        kurbo::PathEl::CurveTo(c1, c2, p) => vec![c1, c2, p],
+        kurbo::PathEl::ClosePath => vec![],
+    }).map(|point| (point - center).hypot()).fold(0., f64::max);
+    if radius <= 0. { *path = source; return }
+    for_each_contour(&source, |elements, closed| twist_contour(elements, closed,
+        (angle as f64).to_radians(), center, radius, &mut output));
+    *path = output;
+}
+
+pub(crate) fn zigzag_kurbo(path: &mut BezPath, ridges: f32, amplitude: f32, smooth: bool) {
+    let (source, mut output) = (mem::take(path), BezPath::new());
+    let ridges = ridges.round().clamp(0., u16::MAX as _) as u32;
+    for_each_contour(&source, |elements, closed| zigzag_contour(elements,
+        closed, ridges, amplitude as _, smooth, &mut output));
+    *path = output;
+}
+
 pub(crate) fn offset_kurbo(path: &mut BezPath, amount: f32,
     join: LineJoin, miter_limit: f32, tolerance: f64) {
     let (source, mut points) = (mem::take(path), Vec::new());
@@ -164,6 +215,60 @@ pub(crate) fn offset_kurbo(path: &mut BezPath, amount: f32,
     }); *path = output;
 }
 
+thread_local! {
+    /// Boolean results by their exact operands, so merges of static shapes
+    /// aren't recomputed on every frame.
+    static MERGED: RefCell<HashMap<(u8, Vec<u64>), BezPath>> = RefCell::default();
+}
+const MERGED_LIMIT: usize = 64;
+
+/// Merge Paths over shapes in stacking order: like lottie-android/skottie, the last
+/// (bottom-most) path is the operand that the union of the others is combined with.
+/// Operands are flattened under the nonzero fill rule, and boolean results keep
+/// the parts of their source curves.
+pub(crate) fn merge_kurbo(paths: &[BezPath], mode: MergeMode) -> BezPath {
+    let Some((first, rest)) = paths.split_last() else { return BezPath::new() };
+    let keep: fn(bool, bool) -> bool = match mode {
+        MergeMode::Normal => return paths.iter()
+            .flat_map(|path| path.elements().iter().copied()).collect(),
+        MergeMode::Add       => |first, rest| first || rest,
+        MergeMode::Subtract  => |first, rest| first && !rest,
+        MergeMode::Intersect => |first, rest| first && rest,
+        MergeMode::ExcludeIntersect => |first, rest| first != rest,
+    };
+    let mut key = Vec::new();
+    for path in paths {
+        key.push(path.elements().len() as _);
+        for &element in path.elements() {
+            let (tag, points) = match element {
+                MoveTo(point) => (0, [point; 3]), LineTo(point) => (1, [point; 3]),
+                QuadTo(control, point) => (2, [control, point, point]),
+                CurveTo(first, second, point) => (3, [first, second, point]),
+                ClosePath => (4, [kurbo::Point::ZERO; 3]),
+            };  key.push(tag);
+            key.extend(points.iter().flat_map(|point| [point.x.to_bits(), point.y.to_bits()]));
+        }
+    }   use kurbo::PathEl::*;
+    let key = (mode as u8, key);
+    if let Some(merged) = MERGED.with_borrow(|cache| cache.get(&key).cloned()) { return merged }
+
+    let mut curves = Vec::new();
+    let mut polygons = |paths: &[BezPath]| {
+        let mut polygons = Vec::new();
+        for path in paths { for_each_contour(path, |elements, _| {
+            let mut polygon = Vec::new();
+            flatten_curves(elements, ACCURACY_TOLERANCE, &mut curves, &mut polygon);
+            if 2 < polygon.len() { polygons.push(polygon); }
+        }) }    polygons
+    };
+    let (subject, clip) = (polygons(core::slice::from_ref(first)), polygons(rest));
+    let merged = boolean_contours(&subject, &clip, &curves, keep);
+    MERGED.with_borrow_mut(|cache| {
+        if MERGED_LIMIT <= cache.len() { cache.clear() }
+        cache.insert(key, merged.clone());
+    }); merged
+}
+
 pub trait PathFactory { fn to_path<PB: PathBuilder>(&self, fnth: f32) -> PB; }
 
 impl PathFactory for Rectangle { #[allow(unreachable_code)]
@@ -417,6 +522,44 @@ fn bezier_path<PB: PathBuilder>(curve: &super::schema::Bezier, reversed: bool) -
         assert_eq!(rounded.bounding_box(), kurbo::Rect::new(0., 0., 10., 10.));
     }
 
+    #[test] fn pucker_bloat_moves_vertices_and_tangents_in_opposite_directions() {
+        let mut path = square(); path.pucker_bloat(50.);
+        use kurbo::{PathEl::*, Point};
+        assert_eq!(path.elements().len(), 1 + 4 + 1);
+        assert_eq!(path.elements()[0], MoveTo(Point::new(2.5, 2.5)));
+        assert_eq!(path.elements()[1], CurveTo(Point::new(-2.5, -2.5),
+            Point::new(12.5, -2.5), Point::new(7.5, 2.5)));
+        assert!(matches!(path.elements().last(), Some(ClosePath)));
+    }
+
+    #[test] fn twist_rotates_most_at_the_center_and_keeps_the_farthest_point() {
+        let mut path = BezPath::new();
+        path.move_to((0., 0.)); path.line_to((5., 0.)); path.line_to((10., 0.));
+        path.twist(90., (0., 0.).into());
+        let ends = path.segments().map(|segment| segment.end()).collect::<Vec<_>>();
+        let diagonal = 5. * core::f64::consts::FRAC_1_SQRT_2;
+        assert!((ends[0] - kurbo::Point::new(diagonal, diagonal)).hypot() < 1e-9);
+        assert!((ends[1] - kurbo::Point::new(10., 0.)).hypot() < 1e-9);
+    }
+
+    #[test] fn zig_zag_alternates_ridges_with_corner_or_smooth_points() {
+        let mut line = BezPath::new();
+        line.move_to((0., 0.)); line.line_to((10., 0.));
+        let (mut corner, mut smooth) = (line.clone(), line);
+        corner.zig_zag(1., 2., false);
+        smooth.zig_zag(1., 2., true);
+
+        use kurbo::{PathEl::*, Point};
+        assert_eq!(corner.elements(), &[MoveTo(Point::new(0., 2.)),
+            LineTo(Point::new(5., -2.)), LineTo(Point::new(10., 2.))]);
+        assert_eq!(smooth.elements()[1], CurveTo(Point::new(2.5, 2.),
+            Point::new(2.5, -2.), Point::new(5., -2.)));
+
+        let mut closed = square(); closed.zig_zag(2., 1., false);
+        assert_eq!(closed.elements().len(), 1 + 4 * 3 - 1 + 1);
+        assert!(matches!(closed.elements().last(), Some(ClosePath)));
+    }
+
     #[test] fn offset_path_expands_and_contracts_closed_contours() {
         let (mut expanded, mut contracted) = (square(), square());
         expanded.offset_path(2., LineJoin::Miter, 4.);
@@ -460,6 +603,71 @@ fn bezier_path<PB: PathBuilder>(curve: &super::schema::Bezier, reversed: bool) -
         path.offset_path_with_tolerance(1., LineJoin::Miter, 4., f64::NAN);
     }
 
+    fn rect(x0: f64, y0: f64, x1: f64, y1: f64) -> BezPath {
+        kurbo::Rect::new(x0, y0, x1, y1).to_path(ACCURACY_TOLERANCE)
+    }
+
+    #[test] fn merge_paths_combines_overlapping_squares_for_every_mode() {
+        let (top, bottom) = (rect(0., 0., 10., 10.), rect(5., 5., 15., 15.));
+        let merge = |mode| merge_kurbo(&[top.clone(), bottom.clone()], mode);
+        let contours = |path: &BezPath| path.elements().iter()
+            .filter(|element| matches!(element, kurbo::PathEl::MoveTo(_))).count();
+
+        let union = merge(MergeMode::Add);
+        assert_eq!((union.area().abs(), contours(&union)), (175., 1));
+        assert_eq!(union.bounding_box(), kurbo::Rect::new(0., 0., 15., 15.));
+        assert_eq!(union.elements().len(), 8 + 1);  // eight corners, no split points
+
+        let subtract = merge(MergeMode::Subtract);
+        assert_eq!(subtract.area().abs(), 75.);
+        assert_eq!(subtract.bounding_box(), kurbo::Rect::new(5., 5., 15., 15.));
+
+        let intersect = merge(MergeMode::Intersect);
+        assert_eq!(intersect.area().abs(), 25.);
+        assert_eq!(intersect.bounding_box(), kurbo::Rect::new(5., 5., 10., 10.));
+
+        let exclude = merge(MergeMode::ExcludeIntersect);
+        assert_eq!((exclude.area().abs(), contours(&exclude)), (150., 2));
+
+        let normal = merge(MergeMode::Normal);
+        assert_eq!(normal.elements().len(), top.elements().len() + bottom.elements().len());
+    }
+
+    #[test] fn merge_paths_handles_holes_shared_edges_and_disjoint_shapes() {
+        let (outer, inner) = (kurbo::Circle::new((0., 0.), 20.).to_path(0.1),
+                              kurbo::Circle::new((0., 0.), 10.).to_path(0.1));
+        let ring = merge_kurbo(&[inner, outer], MergeMode::Subtract);
+        let expected = PI as f64 * (20. * 20. - 10. * 10.);
+        assert!((ring.area().abs() - expected).abs() < expected * 1e-2);
+        assert!(ring.contains((15., 0.).into()) && !ring.contains((0., 0.).into()));
+
+        // squares sharing a full edge merge into one rectangle
+        let joined = merge_kurbo(&[rect(0., 0., 10., 10.), rect(10., 0., 20., 10.)],
+            MergeMode::Add);
+        assert_eq!((joined.area().abs(), joined.elements().len()), (200., 4 + 1));
+
+        let apart = merge_kurbo(&[rect(0., 0., 1., 1.), rect(5., 5., 6., 6.)],
+            MergeMode::Intersect);
+        assert!(apart.is_empty());
+        assert!(merge_kurbo(&[], MergeMode::Add).is_empty());
+    }
+
+    #[test] fn merge_paths_keeps_the_curves_of_circles() {
+        let circle = |x| kurbo::Circle::new((x, 0.), 10.).to_path(1e-3);
+        let (first, second) = (circle(0.), circle(10.));
+        let union = merge_kurbo(&[first.clone(), second.clone()], MergeMode::Add);
+        let curves = union.elements().iter()
+            .filter(|element| matches!(element, kurbo::PathEl::CurveTo(..))).count();
+        assert!(curves <= first.elements().len() + second.elements().len(), "{curves}");
+        assert!(union.elements().len() < curves + 4, "{:?}", union.elements());
+
+        // circles of radius 10 and 10 apart overlap by a lens
+        let lens = 2. * 100. * (0.5f64).acos() - 5. * (400f64 - 100.).sqrt();
+        let expected = 2. * core::f64::consts::PI * 100. - lens;
+        assert!((union.area().abs() - expected).abs() < expected * 1e-4, "{}", union.area());
+        assert_eq!(merge_kurbo(&[first, second], MergeMode::Add), union);
+    }
+
     #[test] fn rounded_rectangle_has_four_quarter_curves() {
         let rect: Rectangle = serde_json::from_str(
             r#"{"ty":"rc","s":{"k":[100,80]},"p":{"k":[0,0]},"r":{"k":10}}"#,
@@ -0,0 +1,235 @@
+//! Playback clock of a timeline, independent of any renderer.
+
+use std::collections::VecDeque;
+
+/// How playback continues past the ends of the timeline.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LoopMode { Once, #[default] Loop, PingPong }
+
+/// Frames from `start` up to the (exclusive) `end`, named after its marker if any.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Segment { pub name: Option<String>, pub start: f32, pub end: f32 }
+
+impl Segment {
+    pub fn new(start: f32, end: f32) -> Self { Self { name: None, start, end } }
+}
+
+#[derive(Clone, Debug, PartialEq)] #[non_exhaustive] pub enum PlaybackEvent {
+    /// The segment played to its end, or wrapped/bounced back to its start when looping.
+    SegmentComplete(Segment),
+}
+
+/// Events kept for the application to take, dropping the oldest ones beyond.
+const MAX_EVENTS: usize = 32;
+
+/// Converts wall-clock time into whole frames to present within the active segment
+/// (the whole timeline by default), at `fr` frames per second scaled by `speed`.
+/// Queued segments take over in turn whenever the active one completes.
+#[derive(Clone, Debug)]
+pub struct Playback {
+    timeline: (f32, f32), fr: f32,
+    segment: Segment, queue: VecDeque<Segment>,
+    events: VecDeque<PlaybackEvent>,
+    frame: f32,     // the next frame to present
+    elapsed: f32,   // accumulated frames not yet presented
+    speed: f32, mode: LoopMode,
+    bounced: bool,  // on the way back in `PingPong` mode
+    paused: bool, finished: bool,
+    due: bool,      // present `frame` without waiting, after start or seeking
+}
+
+impl Playback {
+    pub fn new(ip: f32, op: f32, fr: f32) -> Self {
+        Self { timeline: (ip, op), fr, segment: Segment::new(ip, op),
+            queue: VecDeque::new(), events: VecDeque::new(),
+            frame: ip, elapsed: 0., speed: 1., mode: LoopMode::Loop,
+            bounced: false, paused: false, finished: false, due: true }
+    }
+
+    /// Frame to be presented next, in composition frames.
+    pub fn frame(&self) -> f32 { self.frame }
+    pub fn speed(&self) -> f32 { self.speed }
+    pub fn loop_mode(&self) -> LoopMode { self.mode }
+    pub fn is_paused(&self) -> bool { self.paused }
+    pub fn segment(&self) -> &Segment { &self.segment }
+    /// Whether `Once` playback has run past either end of the segment, with none queued.
+    pub fn is_finished(&self) -> bool { self.finished }
+
+    /// Last frame presented by whole-frame steps from the start of the segment.
+    fn last(&self) -> f32 { (self.segment.end - 1.).max(self.segment.start) }
+
+    /// Jump to `frame` (clamped into the segment), presented by the next `advance`.
+    pub fn seek(&mut self, frame: f32) {
+        self.frame = if self.segment.end <= frame { self.last() }
+            else { frame.max(self.segment.start) };
+        self.elapsed = 0.;  self.bounced = false;
+        self.finished = false;  self.due = true;
+    }
+
+    /// Jump to composition time `seconds`, i.e. frame `seconds * fr`.
+    pub fn seek_time(&mut self, seconds: f32) { self.seek(seconds * self.fr) }
+
+    /// Scale the frame rate, negative values play backwards and zero holds the frame.
+    pub fn set_speed(&mut self, speed: f32) {
+        if speed.is_finite() { self.speed = speed }
+    }
+
+    pub fn set_loop_mode(&mut self, mode: LoopMode) {
+        self.mode = mode;   self.finished = false;
+    }
+
+    pub fn pause (&mut self) { self.paused = true  }
+    pub fn resume(&mut self) { self.paused = false }
+
+    /// Play `segment` (clamped into the timeline) right away, dropping any queued ones.
+    pub fn play_segment(&mut self, segment: Segment) {
+        self.queue.clear();     self.enter(segment);
+        self.elapsed = 0.;      self.due = true;
+    }
+
+    /// Play `segment` once the active one completes, or right away if playback finished.
+    pub fn queue_segment(&mut self, segment: Segment) {
+        if self.finished { self.play_segment(segment) } else { self.queue.push_back(segment) }
+    }
+
+    /// Play the whole timeline again, dropping any queued segments.
+    pub fn reset_segment(&mut self) {
+        self.play_segment(Segment::new(self.timeline.0, self.timeline.1))
+    }
+
+    /// Take the events raised since the last call, in order.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = PlaybackEvent> + '_ {
+        self.events.drain(..)
+    }
+
+    /// Make `segment` active from the end it's played from, by the direction of playback.
+    fn enter(&mut self, mut segment: Segment) {
+        let (ip, op) = self.timeline;
+        segment.start = segment.start.clamp(ip, (op - 1.).max(ip));
+        segment.end = segment.end.min(op).max(segment.start + 1.);
+        self.segment = segment;     self.bounced = false;   self.finished = false;
+        self.frame = if self.speed < 0. { self.last() } else { self.segment.start };
+    }
+
+    fn complete(&mut self) {
+        if self.events.len() == MAX_EVENTS { self.events.pop_front(); }
+        self.events.push_back(PlaybackEvent::SegmentComplete(self.segment.clone()));
+        if let Some(segment) = self.queue.pop_front() { self.enter(segment) }
+    }
+
+    /// Account for `elapsed` seconds of wall-clock time, yielding the frame to present
+    /// if at least one is due, and skipping the frames that are too late to present.
+    pub fn advance(&mut self, elapsed: f32) -> Option<f32> {
+        if self.paused || self.finished { return None }
+        self.elapsed += elapsed * self.fr * self.speed.abs();
+        if  self.elapsed < 1. && !self.due { return None }
+
+        if  2. <= self.elapsed && !self.due {  // skip elapsed frames
+            let skipped = (self.elapsed - 1.).floor();
+            self.step(skipped);     self.elapsed -= skipped;
+        }
+        let frame = self.frame;
+        self.elapsed = (self.elapsed - 1.).max(0.);
+        self.due = false;   self.step(1.);  Some(frame)
+    }
+
+    /// Move `count` frames along the direction of playback.
+    fn step(&mut self, count: f32) {
+        let count = if self.speed < 0. { -count } else { count };
+        let (ip, last) = (self.segment.start, self.last());
+        let completed = match self.mode {
+            LoopMode::Loop => {
+                let (duration, offset) = (self.segment.end - ip, self.frame - ip + count);
+                if duration <= 0. { return }
+                self.frame = ip + offset.rem_euclid(duration);
+                !(0. ..duration).contains(&offset)
+            }
+            LoopMode::Once => {
+                let frame = self.frame + count;
+                self.finished = !(ip..=last).contains(&frame);
+                self.frame = frame.clamp(ip, last);     self.finished
+            }
+            LoopMode::PingPong => {
+                let span = last - ip;
+                if span <= 0. { self.frame = ip;  return }
+                // unfold the bounce into a cycle of twice the span
+                let offset = self.frame - ip;
+                let phase = if self.bounced { span * 2. - offset } else { offset } + count;
+                let cycle = phase.rem_euclid(span * 2.);
+                self.bounced = span < cycle;
+                self.frame = ip + if self.bounced { span * 2. - cycle } else { cycle };
+                !(0. ..span * 2.).contains(&phase)
+            }
+        };  if completed { self.complete() }
+    }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    fn presented(clock: &mut Playback, count: usize) -> Vec<f32> {
+        (0..count).filter_map(|_| clock.advance(1.)).collect()
+    }
+
+    #[test] fn loops_forwards_and_backwards() {
+        let mut clock = Playback::new(0., 3., 1.);
+        assert_eq!(presented(&mut clock, 5), [0., 1., 2., 0., 1.]);
+
+        clock.set_speed(-1.);
+        assert_eq!(presented(&mut clock, 4), [2., 1., 0., 2.]);
+    }
+
+    #[test] fn plays_once_and_stops_at_either_end() {
+        let mut clock = Playback::new(0., 3., 1.);
+        clock.set_loop_mode(LoopMode::Once);
+        assert_eq!(presented(&mut clock, 5), [0., 1., 2.]);
+        assert!(clock.is_finished());
+        assert_eq!(clock.frame(), 2.);
+
+        clock.set_speed(-2.);   clock.seek(2.);
+        assert_eq!(presented(&mut clock, 3), [2., 0.]);
+        assert!(clock.is_finished());
+    }
+
+    #[test] fn ping_pong_bounces_at_both_ends() {
+        let mut clock = Playback::new(10., 13., 1.);
+        clock.set_loop_mode(LoopMode::PingPong);
+        assert_eq!(presented(&mut clock, 7), [10., 11., 12., 11., 10., 11., 12.]);
+    }
+
+    #[test] fn queued_segments_take_over_on_completion() {
+        let mut clock = Playback::new(0., 30., 1.);
+        clock.set_loop_mode(LoopMode::Once);
+        clock.play_segment(Segment::new(0., 2.));
+        clock.queue_segment(Segment { name: Some("idle".into()), start: 10., end: 12. });
+        assert_eq!(presented(&mut clock, 6), [0., 1., 10., 11.]);
+        assert!(clock.is_finished());
+
+        let events = clock.drain_events().collect::<Vec<_>>();
+        assert_eq!(events, [PlaybackEvent::SegmentComplete(Segment::new(0., 2.)),
+            PlaybackEvent::SegmentComplete(clock.segment().clone())]);
+        assert_eq!(clock.drain_events().count(), 0);
+
+        clock.queue_segment(Segment::new(28., 40.));    clock.set_loop_mode(LoopMode::Loop);
+        assert_eq!(presented(&mut clock, 3), [28., 29., 28.]);
+        assert_eq!(clock.drain_events().count(), 1);
+    }
+
+    #[test] fn pause_seek_and_partial_frames() {
+        let mut clock = Playback::new(0., 60., 2.);
+        clock.pause();
+        assert_eq!(clock.advance(1.), None);
+        clock.resume();
+
+        clock.seek_time(10.);
+        assert_eq!(clock.advance(0.), Some(20.));
+        assert_eq!(clock.advance(0.25), None);
+        assert_eq!(clock.advance(0.25), Some(21.));
+
+        clock.set_speed(0.5);   clock.seek(100.);
+        assert_eq!(clock.frame(), 59.);
+        assert_eq!(clock.advance(0.5), Some(59.));
+        assert_eq!(clock.advance(0.5), None);
+        assert_eq!(clock.advance(0.5), Some(0.));
+    }
+}
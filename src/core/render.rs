@@ -10,9 +10,13 @@ use std::{collections::HashMap, rc::Rc};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
 use super::{composite::{self, CompositeContext},
     helpers::{Vec2D, RGBA, IntBool, ACCURACY_TOLERANCE},
-    path_ops::MeasuredPath, style::{StyleConv, MatrixConv, TM2DwO, FSOpts},
-    pathm::{BezPath, PathBuilder, PathFactory, trim_kurbo, round_kurbo, offset_kurbo},
-    schema::{Animation, AssetItem, LayerItem, ShapeItem, TrimPath, TrimMultiple, FillRule},
+    path_ops::MeasuredPath, playback::{Playback, LoopMode, Segment, PlaybackEvent},
+    style::{StyleConv, MatrixConv, TM2DwO, FSOpts, Matrix3D, Camera},
+    pathm::{BezPath, PathBuilder, PathFactory, trim_kurbo, round_kurbo, offset_kurbo,
+        merge_kurbo, pucker_kurbo, twist_kurbo, zigzag_kurbo},
+    schema::{Animation, AssetItem, LayerItem, ShapeItem, ShapePrecomp,
+        TrimPath, TrimMultiple, MergeMode, FillRule, MotionBlur},
+    text::{self, FontCache, FontProvider},
 };
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)] enum Parent { Root, Layer(u32), Invalid }
@@ -22,6 +26,8 @@ struct CompositionState {
      parents: Vec<Parent>, stack: Vec<usize>,
     precomps: Vec<Option<PrecompState>>,
     path_mod: Vec<bool>,
+    /// Has 3D layers or cameras, and the composition center for the camera projection
+    three_d: bool, center: Vec2D,
 }
 
 struct PrecompState { asset: usize, composition: Box<CompositionState> }
@@ -31,13 +37,13 @@ impl CompositionState {
         let _: u32 = layers.len().try_into().expect("too many composition layers");
         let mut indices = HashMap::<u32, Option<u32>>::with_capacity(layers.len());
         for (index, layer) in layers.iter().enumerate() {
-            if let Some(id) = layer.visual_layer().and_then(|vl| vl.base.ind) {
+            if let Some(id) = layer.parentable().and_then(|base| base.ind) {
                 indices.entry(id).and_modify(|index| *index = None)
                     .or_insert(Some(index as u32));
             }
         }
-        let parents: Vec<_> = layers.iter().map(|layer| layer.visual_layer().and_then(|vl|
-            vl.base.parent.and_then(|id| indices.get(&id).copied().flatten()))).collect();
+        let parents: Vec<_> = layers.iter().map(|layer| layer.parentable().and_then(|base|
+            base.parent.and_then(|id| indices.get(&id).copied().flatten()))).collect();
         let (mut states, mut stack) = (vec![0u8; layers.len()], Vec::new());
 
         for root in 0..layers.len() {
@@ -46,7 +52,7 @@ impl CompositionState {
             let resolved = loop { match states[index] {
                 0 => {
                     states[index] = 1;  stack.push(index);
-                    if layers[index].visual_layer().is_none() { break false }
+                    if layers[index].parentable().is_none() { break false }
                     let Some(parent) = parents[index] else { break true };
                     index = parent as usize;
                 }
@@ -69,12 +75,15 @@ impl CompositionState {
             if state != 2 { Parent::Invalid }
             else { parent.map_or(Parent::Root, Parent::Layer) }
         }).collect();
-        Self { parents, stack, precomps, path_mod }
+        let three_d = layers.iter().any(|layer| matches!(layer, LayerItem::Camera(_)) ||
+            layer.visual_layer().is_some_and(|vl| vl.base.ddd.as_bool()));
+        Self { parents, stack, precomps, path_mod, three_d, center: Vec2D { x: 0., y: 0. } }
     }
 
-    fn with_precomps<'a>(layers: &[LayerItem], animation: &'a Animation,
+    fn with_precomps<'a>(layers: &[LayerItem], (w, h): (u32, u32), animation: &'a Animation,
         assets: &HashMap<&'a str, usize>, ancestors: &mut Vec<&'a str>) -> Self {
         let mut runtime = Self::new(layers);
+        runtime.center = Vec2D { x: w as f32 / 2., y: h as f32 / 2. };
         for (index, layer) in layers.iter().enumerate() {
             let LayerItem::PrecompLayer(layer) = layer else { continue };
             let Some(&asset) = assets.get(layer.rid.as_str()) else { continue };
@@ -82,8 +91,8 @@ impl CompositionState {
             if ancestors.contains(&precomp.base.id.as_str()) { continue }
 
             ancestors.push(&precomp.base.id);
-            let composition =
-                Self::with_precomps(&precomp.layers, animation, assets, ancestors);
+            let composition = Self::with_precomps(&precomp.layers,
+                (layer.w, layer.h), animation, assets, ancestors);
             ancestors.pop();
             runtime.precomps[index] =
                 Some(PrecompState { asset, composition: Box::new(composition) });
@@ -94,6 +103,7 @@ impl CompositionState {
     fn evaluate<MC: MatrixConv>(&mut self, layers: &[LayerItem], global: f32,
         mut required: impl FnMut(&LayerItem) -> bool) -> Vec<WorldState<MC>> {
         debug_assert_eq!(layers.len(), self.parents.len());
+        if self.three_d { return self.evaluate_3d(layers, global, required) }
         let mut worlds = Vec::with_capacity(layers.len());
         worlds.resize_with(layers.len(), || WorldState::Pending);
         for (index, layer) in layers.iter().enumerate() {
@@ -104,6 +114,31 @@ impl CompositionState {
         }   worlds
     }
 
+    /// World transforms at sub-frames of the shutter window of a motion-blurred layer,
+    /// each with its share of the opacity; empty if the layer stays still within it.
+    fn motion_samples<MC: MatrixConv>(&mut self, layers: &[LayerItem], index: usize,
+        global: f32, blur: Option<&MotionBlur>, ptm: &TM2DwO<MC>) -> Vec<TM2DwO<MC>> {
+        let Some(blur) = blur.filter(|blur| 0. < blur.sa) else { return Vec::new() };
+        if !layers[index].visual_layer().is_some_and(|vl| vl.mb) { return Vec::new() }
+        let mut world = |time: f32| match self.evaluate(layers, time,
+            |layer| core::ptr::eq(layer, &layers[index])).swap_remove(index) {
+            WorldState::Ready(world) => Some(world.compose(ptm)),
+            WorldState::Pending | WorldState::Invalid => None,
+        };
+        let (start, span) = (global + blur.sp / 360., blur.sa.min(720.) / 360.);
+        let (Some(first), Some(last)) = (world(start), world(start + span))
+            else { return Vec::new() };
+        let (first, last) = (first.0.coeffs(), last.0.coeffs());
+        if first.iter().zip(&last).all(|(a, b)| (a - b).abs() < 1e-6) { return Vec::new() }
+
+        // adaptive sample count by the travelled pixels, from `spf` up to `asl`
+        let (min, max) = (blur.spf.max(2.), blur.asl.max(blur.spf).max(2.));
+        let count = ((last[4] - first[4]).hypot(last[5] - first[5]) as f32)
+            .ceil().clamp(min, max) as usize;
+        (0..count).filter_map(|i| world(start + span * (i as f32 + 0.5) / count as f32))
+            .map(|mut world| { world.1 /= count as f32;     world }).collect()
+    }
+
     fn resolve<MC: MatrixConv>(parents: &[Parent], root: usize,
         layers: &[LayerItem], global: f32,
         worlds: &mut [WorldState<MC>], stack: &mut Vec<usize>) {
@@ -121,6 +156,7 @@ impl CompositionState {
         }
 
         while let Some(index) = stack.pop() {
+            #[cfg(feature = "expression")] super::expression::select_layer(index);
             let Some(vl) = layers[index].visual_layer() else { unreachable!() };
             let Some(local) = vl.base.local_frame(global) else {
                 worlds[index] = WorldState::Invalid; continue
@@ -135,29 +171,118 @@ impl CompositionState {
             worlds[index] = WorldState::Ready(world);
         }
     }
+
+    /// 3D layers go through the active camera (the first one in range), if any;
+    /// 2D layers, even parented to 3D ones, are projected orthographically.
+    fn evaluate_3d<MC: MatrixConv>(&mut self, layers: &[LayerItem], global: f32,
+        mut required: impl FnMut(&LayerItem) -> bool) -> Vec<WorldState<MC>> {
+        let mut spaces = vec![None; layers.len()];
+        let camera = layers.iter().enumerate().find_map(|(index, layer)| {
+            let LayerItem::Camera(camera) = layer else { return None };
+            let base = &camera.base;
+            if base.hd || global < base.ip || base.op <= global { return None }
+            let local = base.local_frame(global)?;
+            let (world, _) = self.resolve_3d(index, layers, global, &mut spaces)?;
+            Camera::new(&world, camera.pe.get_value(local), self.center)
+        });
+
+        layers.iter().enumerate().map(|(index, layer)| {
+            let Some(vl) = layer.visual_layer() else { return WorldState::Pending };
+            if  self.parents[index] == Parent::Invalid || !required(layer) {
+                return WorldState::Pending
+            }
+            let Some((world, opacity)) = self.resolve_3d(index, layers, global, &mut spaces)
+                else { return WorldState::Invalid };
+            let local = vl.base.local_frame(global).unwrap_or(global);
+            let anchor = vl.ks.anchor.as_ref().map_or(Vec2D { x: 0., y: 0. }, |anchor|
+                anchor.get_value(local));
+            let camera = camera.as_ref().filter(|_| vl.base.ddd.as_bool());
+            let world = MC::from_coeffs(world.project(anchor, camera));
+            WorldState::Ready(TM2DwO(world, opacity))
+        }).collect()
+    }
+
+    fn resolve_3d(&mut self, root: usize, layers: &[LayerItem], global: f32,
+        spaces: &mut [Option<Option<(Matrix3D, f32)>>]) -> Option<(Matrix3D, f32)> {
+        if self.parents[root] == Parent::Invalid { return None }
+        self.stack.clear();
+        let mut index = root;
+        while spaces[index].is_none() {
+            self.stack.push(index);
+            match self.parents[index] {
+                Parent::Layer(parent) => index = parent as usize,
+                Parent::Invalid => unreachable!(),
+                Parent::Root => break,
+            }
+        }
+
+        while let Some(index) = self.stack.pop() {
+            #[cfg(feature = "expression")] super::expression::select_layer(index);
+            let parent = match self.parents[index] {
+                Parent::Layer(parent) => match spaces[parent as usize] {
+                    Some(Some((parent, _))) => Some(parent),
+                    _ => { spaces[index] = Some(None); continue }
+                },  _ => None,
+            };
+            spaces[index] = Some(layer_space(&layers[index], global).map(|(space, opacity)|
+                (parent.map_or(space, |parent| space.then(&parent)), opacity)));
+        }   spaces[root].flatten()
+    }
+}
+
+/// Transform of a (3D or flat) layer or camera relative to its parent, with its opacity
+fn layer_space(layer: &LayerItem, global: f32) -> Option<(Matrix3D, f32)> {
+    if let LayerItem::Camera(camera) = layer {
+        return Some((camera.to_matrix3d(camera.base.local_frame(global)?), 1.))
+    }
+    let vl = layer.visual_layer()?;
+    let local = vl.base.local_frame(global)?;
+    Some(if vl.base.ddd.as_bool() { vl.ks.to_matrix3d(local, vl.ao) } else {
+        let TM2DwO(matrix, opacity) = vl.ks.to_matrix::<kurbo::Affine>(local, vl.ao);
+        (Matrix3D::from_coeffs(matrix.as_coeffs()), opacity)
+    })
 }
 
 pub struct LottieRuntime {
-    elapsed: f32, fnth: f32,
-    animation: Animation,
+    clock: Playback, view: [f64; 6],
+    animation: Rc<Animation>,
     root: CompositionState,
+    res: Resources,
+}
+
+/// Decoded images, loaded fonts and `CharacterPrecomp` glyph compositions
+/// (by index of `Animation::chars`) shared by all compositions of a runtime.
+#[derive(Default)] struct Resources {
     images: HashMap<String, (Box<[u8]>, f32, f32)>,
+    fonts: FontCache, glyphs: Vec<Option<PrecompState>>,
 }
 
 impl LottieRuntime {
     pub fn from_reader<R: std::io::Read>(reader: R) -> Result<Self, serde_json::Error> {
-        let animation = Animation::from_reader(reader)?;
-        let root = {
+        Animation::from_reader(reader).map(Self::from_animation)
+    }
+
+    pub fn from_animation(animation: Animation) -> Self {
+        let (root, glyphs) = {
             let mut assets = HashMap::with_capacity(animation.assets.len());
             for (index, asset) in animation.assets.iter().enumerate() {
                 if let AssetItem::Precomp(precomp) = asset {
                     assets.entry(precomp.base.id.as_str()).or_insert(index);
                 }
             }
-            CompositionState::with_precomps(
-                &animation.layers, &animation, &assets, &mut Vec::new())
+            let glyphs = animation.chars.iter().map(|data| {
+                let ShapePrecomp::Precomp(glyph) = &data.data else { return None };
+                let &asset = assets.get(glyph.rid.as_str())?;
+                let AssetItem::Precomp(precomp) =
+                    &animation.assets[asset] else { unreachable!() };
+                let composition = CompositionState::with_precomps(&precomp.layers,
+                    (0, 0), &animation, &assets, &mut vec![precomp.base.id.as_str()]);
+                Some(PrecompState { asset, composition: Box::new(composition) })
+            }).collect();
+            (CompositionState::with_precomps(&animation.layers,
+                (animation.w, animation.h), &animation, &assets, &mut Vec::new()), glyphs)
         };
-        let mut images = HashMap::new();
+        let mut res = Resources { glyphs, ..Default::default() };
         for asset in &animation.assets {
             let AssetItem::Image(image) = asset else { continue };
             let Some((metadata, payload)) = image.file.url.strip_prefix("data:")
@@ -166,36 +291,72 @@ impl LottieRuntime {
                 continue
             }
             let Ok(data) = STANDARD.decode(payload) else { continue };
-            images.entry(image.file.base.id.clone())
+            res.images.entry(image.file.base.id.clone())
                 .or_insert((data.into_boxed_slice(), image.w, image.h));
         }
-        let fnth = animation.ip;
-        Ok(Self { animation, images, elapsed: 0., fnth, root })
+        let clock = Playback::new(animation.ip, animation.op, animation.fr);
+        Self { animation: Rc::new(animation), res, clock, root, view: [1., 0., 0., 1., 0., 0.] }
     }
 
     pub fn animation(&self) -> &Animation { &self.animation }
-    pub fn frame(&self) -> f32 { self.fnth }
+    pub fn playback(&self) -> &Playback { &self.clock }
+    /// Frame to be rendered next by `render_next_frame`.
+    pub fn frame(&self) -> f32 { self.clock.frame() }
+
+    pub fn seek(&mut self, frame: f32) { self.clock.seek(frame) }
+    pub fn seek_time(&mut self, seconds: f32) { self.clock.seek_time(seconds) }
+    pub fn set_speed(&mut self, speed: f32) { self.clock.set_speed(speed) }
+    pub fn set_loop_mode(&mut self, mode: LoopMode) { self.clock.set_loop_mode(mode) }
+    pub fn pause (&mut self) { self.clock.pause()  }
+    pub fn resume(&mut self) { self.clock.resume() }
+
+    pub fn play_segment (&mut self, start: f32, end: f32) {
+        self.clock.play_segment (Segment::new(start, end))
+    }
+    pub fn queue_segment(&mut self, start: f32, end: f32) {
+        self.clock.queue_segment(Segment::new(start, end))
+    }
+
+    /// Play the section of the named marker, returns `false` if there is no such marker.
+    pub fn play_marker (&mut self, name: &str) -> bool {
+        self.marker_segment(name).map(|segment| self.clock.play_segment (segment)).is_some()
+    }
+    pub fn queue_marker(&mut self, name: &str) -> bool {
+        self.marker_segment(name).map(|segment| self.clock.queue_segment(segment)).is_some()
+    }
+
+    fn marker_segment(&self, name: &str) -> Option<Segment> {
+        self.animation.marker(name).map(|marker| Segment { name: Some(name.to_owned()),
+            start: marker.tm, end: marker.tm + marker.dr })
+    }
+
+    /// Take the playback events raised since the last call, e.g. completed segments.
+    pub fn drain_events(&mut self) -> impl Iterator<Item = PlaybackEvent> + '_ {
+        self.clock.drain_events()
+    }
+
+    /// Map the composition into the render target by the affine `coeffs`
+    /// (`[a, b, c, d, e, f]` as in `kurbo::Affine`), identity by default.
+    pub fn set_view_transform(&mut self, coeffs: [f64; 6]) { self.view = coeffs }
+    pub fn view_transform(&self) -> [f64; 6] { self.view }
 
+    /// Replace the source of font files for text layers, system fonts by default.
+    pub fn set_font_provider(&mut self, provider: impl FontProvider + 'static) {
+        self.res.fonts.set_provider(Box::new(provider))
+    }
+
+    /// Render the frame due after `elapsed` seconds of playback, returns `false` if none is.
     /// `clear` selects a frame background; `None` preserves the current render target.
     pub fn render_next_frame<RC: CompositeContext>(&mut self, rctx: &mut RC,
         elapsed: f32, clear: Option<RGBA>) -> Result<bool, RC::Error> {
-        //debug_assert!(0. < self.fr && 0. <= self.ip && 1. < self.op - self.ip);
-        let animation = &self.animation;
-
-        if  self.fnth < animation.ip || animation.op <= self.fnth {
-            self.fnth = animation.ip;
-        }   self.elapsed += elapsed * animation.fr;
-        if  self.elapsed < 1. && animation.ip < self.fnth { return Ok(false) }
-
-        if  2. <= self.elapsed {    // advance/skip elapsed frames
-            let elapsed = (self.elapsed - 1.).floor();
-            let duration =  animation.op - animation.ip;
-            if 0. < duration {
-                self.fnth = animation.ip +
-                    (self.fnth -  animation.ip + elapsed).rem_euclid(duration);
-            }   self.elapsed -= elapsed;
-        }
+        let Some(fnth) = self.clock.advance(elapsed) else { return Ok(false) };
+        self.render_frame(rctx, fnth, clear).map(|_| true)
+    }
 
+    /// Render an arbitrary frame without touching the playback clock.
+    pub fn render_frame<RC: CompositeContext>(&mut self, rctx: &mut RC,
+        fnth: f32, clear: Option<RGBA>) -> Result<(), RC::Error> {
+        let animation = &self.animation;
         // Preserve the caller's complete backend state once per rendered frame. Shape traversal
         // explicitly installs every transform and opacity it uses, so per-layer saves are redundant.
         let state = rctx.save_state()?;
@@ -205,33 +366,50 @@ impl LottieRuntime {
                 let (width, height) = rctx.get_size();
                 rctx.clear_rect_with(0, 0, width, height, color)?;
             }
-            Self::render_layers(animation, &self.images, rctx, &TM2DwO::default(),
-                &animation.layers, self.fnth, &mut self.root)
+            Self::render_layers(animation, &mut self.res, rctx,
+                &TM2DwO(RC::TM2D::from_coeffs(self.view), 1.), None, fnth, &mut self.root)
         })();
         let restored = rctx.restore_state(state);
-        rendered.and(restored)?;
-
-        self.elapsed -= 1.;       self.fnth += 1.;
-        if animation.op <= self.fnth { self.fnth = animation.ip; }    Ok(true)
+        rendered.and(restored)
     }
 
     /// The render order goes from the last element to the first,
     /// items in list coming first will be rendered on top.
-    fn render_layers<RC: CompositeContext>(animation: &Animation,
-        images: &HashMap<String, (Box<[u8]>, f32, f32)>, rctx: &mut RC,
+    fn render_layers<RC: CompositeContext>(animation: &Rc<Animation>,
+        res: &mut Resources, rctx: &mut RC,
+        ptm: &TM2DwO<RC::TM2D>, asset: Option<usize>, fnth: f32,
+        runtime: &mut CompositionState) -> Result<(), RC::Error> {
+        let layers = match asset.map(|asset| &animation.assets[asset]) {
+            Some(AssetItem::Precomp(precomp)) => &precomp.layers,
+            Some(_) => unreachable!(),  None => &animation.layers,
+        };
+        #[cfg(feature = "expression")] {
+            let size = ((runtime.center.x * 2.) as _, (runtime.center.y * 2.) as _);
+            super::expression::with_composition(animation, asset, size, ||
+                Self::render_composition(animation, res, rctx, ptm, layers, fnth, runtime))
+        }
+        #[cfg(not(feature = "expression"))]
+        Self::render_composition(animation, res, rctx, ptm, layers, fnth, runtime)
+    }
+
+    fn render_composition<RC: CompositeContext>(animation: &Rc<Animation>,
+        res: &mut Resources, rctx: &mut RC,
         ptm: &TM2DwO<RC::TM2D>, layers: &[LayerItem], fnth: f32,
         runtime: &mut CompositionState) -> Result<(), RC::Error> {
         let mut composite = composite::Compositor::default();
+        let blur = animation.mb.as_ref();
         let worlds = runtime.evaluate(layers, fnth, |layer| match layer {
             LayerItem::Shape(layer) => !layer.vl.should_hide(fnth),
             LayerItem::PrecompLayer(layer) => !layer.vl.should_hide(fnth),
             LayerItem::SolidColor(layer) => !layer.vl.should_hide(fnth),
             LayerItem::Image(layer) => !layer.vl.should_hide(fnth),
+            LayerItem::Text(layer) => !layer.vl.should_hide(fnth),
             _ => false,
         });
 
         // Capture `?` errors so pending matte images are discarded before returning.
         let rendered = (|| { for (index, layer) in layers.iter().enumerate().rev() {
+        #[cfg(feature = "expression")] super::expression::select_layer(index);
         let mut handled = false; match layer {
             LayerItem::Shape(shpl) =>
             if let WorldState::Ready(ltm) = &worlds[index] {
@@ -241,15 +419,16 @@ impl LottieRuntime {
                 let (draws, ctm) = convert_shapes_known(&shpl.shapes, local,
                     shpl.vl.ao, runtime.path_mod[index]);
                 let ltm = ltm.clone().compose(ptm);
+                let samples = runtime.motion_samples(layers, index, fnth, blur, ptm);
 
                 composite.render(rctx, &shpl.vl, &ltm, fnth, |rctx|
-                    rctx.render_shapes(&ctm.compose(&ltm), &draws))?;
+                    composite::accumulate(rctx, &ltm, &samples, |rctx, ltm|
+                        rctx.render_shapes(&ctm.clone().compose(ltm), &draws)))?;
             }
             LayerItem::PrecompLayer(pcl) =>
             if let WorldState::Ready(ltm) = &worlds[index] {
+                let samples = runtime.motion_samples(layers, index, fnth, blur, ptm);
                 if let Some(child) = &mut runtime.precomps[index] {
-                    let AssetItem::Precomp(pcomp) =
-                        &animation.assets[child.asset] else { unreachable!() };
                     let Some(local) = pcl.vl.base.local_frame(fnth) else {
                         composite.skip(rctx, &pcl.vl);  continue
                     };  handled = true;
@@ -257,9 +436,13 @@ impl LottieRuntime {
                         |tm| tm.get_value(local) * animation.fr);
                     let ltm = ltm.clone().compose(ptm);
 
-                    composite.render(rctx, &pcl.vl, &ltm, fnth, |rctx|
-                        Self::render_layers(animation, images, rctx, &ltm,
-                            &pcomp.layers, child_fnth, &mut child.composition))?;
+                    composite.render(rctx, &pcl.vl, &ltm, fnth, |rctx| {
+                        rctx.begin_group(&pcl.vl)?;
+                        composite::accumulate(rctx, &ltm, &samples, |rctx, ltm|
+                            Self::render_layers(animation, res, rctx, ltm,
+                                Some(child.asset), child_fnth, &mut child.composition))
+                            .and_then(|_| rctx.end_group())
+                    })?;
                 }   // XXX: clipping(pcl.w, pcl.h)?
             }
             LayerItem::SolidColor(scl) =>
@@ -270,22 +453,42 @@ impl LottieRuntime {
                 let opts = FSOpts::Fill(FillRule::NonZero);
                 let mut style = RC::VGStyle::solid_color(scl.sc);
                 style.configure(&opts);     handled = true;
+                let draws = [DrawItem::Shape(path),
+                    DrawItem::Style(Rc::new((style, opts)))];
+                let samples = runtime.motion_samples(layers, index, fnth, blur, ptm);
 
                 composite.render(rctx, &scl.vl, &ltm, fnth, |rctx|
-                    rctx.render_shapes(&ltm, &[DrawItem::Shape(path),
-                        DrawItem::Style(Rc::new((style, opts)))]))?;
+                    composite::accumulate(rctx, &ltm, &samples, |rctx, ltm|
+                        rctx.render_shapes(ltm, &draws)))?;
             }
             LayerItem::Image(layer) =>
             if let (WorldState::Ready(ltm), Some((image, width, height))) =
-                (&worlds[index], images.get(&layer.rid)) {
+                (&worlds[index], res.images.get(&layer.rid)) {
                 let ltm = ltm.clone().compose(ptm); handled = true;
-                composite.render(rctx, &layer.vl, &ltm, fnth, |rctx| {
-                    rctx.apply_transform(&ltm.0, Some(ltm.1))?;
-                    rctx.draw_image(image, *width, *height)
-                })?;
+                let samples = runtime.motion_samples(layers, index, fnth, blur, ptm);
+                composite.render(rctx, &layer.vl, &ltm, fnth, |rctx|
+                    composite::accumulate(rctx, &ltm, &samples, |rctx, ltm| {
+                        rctx.apply_transform(&ltm.0, Some(ltm.1))?;
+                        rctx.draw_image(image, *width, *height)
+                    }))?;
             }
-            LayerItem::Text(_)  | LayerItem::Data(_)  |
-            LayerItem::Audio(_) | LayerItem::Camera(_) => dbg!(),     // TODO:
+            LayerItem::Text(txtl) =>
+            if let WorldState::Ready(ltm) = &worlds[index] {
+                let Some(local) = txtl.vl.base.local_frame(fnth) else {
+                    composite.skip(rctx, &txtl.vl);     continue
+                };  handled = true;
+                let (draws, glyphs) = text::convert_text(&txtl.t,
+                    &txtl.vl.masks, animation, &mut res.fonts, local);
+                let (ltm, center) = (ltm.clone().compose(ptm), runtime.center);
+                let samples = runtime.motion_samples(layers, index, fnth, blur, ptm);
+
+                composite.render(rctx, &txtl.vl, &ltm, fnth, |rctx|
+                    composite::accumulate(rctx, &ltm, &samples, |rctx, ltm| {
+                        rctx.render_shapes(ltm, &draws)?;
+                        Self::render_glyphs(animation, res, rctx, ltm, &glyphs, local, center)
+                    }))?;
+            }
+            LayerItem::Data(_) | LayerItem::Audio(_) => (),   // nothing visual to render
 
             //LayerItem::Null(_) => (),    // used as a parent, nothing to do
             _ => (),
@@ -296,6 +499,30 @@ impl LottieRuntime {
         }   Ok(()) })();
         composite.finish(rctx);     rendered
     }
+
+    /// Render `CharacterPrecomp` glyphs of a text layer at its local frame `fnth`,
+    /// a glyph referenced from within its own composition is skipped. Glyph
+    /// compositions have no size, so cameras project around the text layer's `center`.
+    fn render_glyphs<RC: CompositeContext>(animation: &Rc<Animation>,
+        res: &mut Resources, rctx: &mut RC, ltm: &TM2DwO<RC::TM2D>,
+        glyphs: &[(usize, TM2DwO<RC::TM2D>)], fnth: f32, center: Vec2D) ->
+        Result<(), RC::Error> {
+        for (index, gtm) in glyphs {
+            let ShapePrecomp::Precomp(glyph) =
+                &animation.chars[*index].data else { unreachable!() };
+            if fnth < glyph.ip || glyph.op <= fnth || glyph.sr == 0. { continue }
+            let Some(mut child) = res.glyphs[*index].take() else { continue };
+            child.composition.center = center;
+
+            let local = fnth / glyph.sr - glyph.st;
+            let ctm = glyph.ks.as_ref().map_or_else(TM2DwO::default,
+                |ks| ks.to_matrix(local, IntBool::default()));
+            let ctm = ctm.compose(gtm).compose(ltm);
+            let rendered = Self::render_layers(animation, res, rctx, &ctm,
+                Some(child.asset), local, &mut child.composition);
+            res.glyphs[*index] = Some(child);   rendered?;
+        }   Ok(())
+    }
 }
 
 pub trait RenderContext {
@@ -422,6 +649,17 @@ impl<P: PathBuilder> PathBuilder for PendingPath<P> {
         if amount == 0. { return }
         offset_kurbo(self.kurbo_mut(), amount, join, miter_limit, ACCURACY_TOLERANCE)
     }
+    fn pucker_bloat(&mut self, amount: f32) {
+        if amount != 0. { pucker_kurbo(self.kurbo_mut(), amount) }
+    }
+    fn twist(&mut self, angle: f32, center: Vec2D) {
+        if angle != 0. { twist_kurbo(self.kurbo_mut(), angle, center) }
+    }
+    fn zig_zag(&mut self, ridges: f32, amplitude: f32, smooth: bool) {
+        if amplitude != 0. || 1. <= ridges {
+            zigzag_kurbo(self.kurbo_mut(), ridges, amplitude, smooth)
+        }
+    }
 }
 
 /// calculate transform matrix, convert shapes to paths, modify/change the paths,
@@ -449,6 +687,10 @@ fn has_path_modifier(shapes: &[ShapeItem]) -> bool {
         ShapeItem::Trim(modifier) => !modifier.elem.hd,
         ShapeItem::RoundedCorners(modifier) => !modifier.elem.hd,
         ShapeItem::OffsetPath(modifier) => !modifier.elem.hd,
+        ShapeItem::Merge(modifier) => !modifier.elem.hd,
+        ShapeItem::PuckerBloat(modifier) => !modifier.elem.hd,
+        ShapeItem::Twist(modifier) => !modifier.elem.hd,
+        ShapeItem::ZigZag(modifier) => !modifier.elem.hd,
         ShapeItem::Group(group) => !group.elem.hd && has_path_modifier(&group.shapes),
         _ => false,
     })
@@ -479,7 +721,7 @@ fn convert_shapes_inner<Path: PathBuilder, VGPaint: StyleConv,
             draws.push(DrawItem::Style(Rc::new(grad.to_style(fnth)))),
         ShapeItem::GradientStroke(grad) if !grad.elem.hd =>
             draws.push(DrawItem::Style(Rc::new(grad.to_style(fnth)))),
-        ShapeItem::NoStyle(_) => eprintln!("Nothing to do here?"),
+        ShapeItem::NoStyle(_) => (),    // paints nothing by itself
 
         ShapeItem::Group(group) if !group.elem.hd => {
             let (grp, ctm) =
@@ -505,8 +747,24 @@ fn convert_shapes_inner<Path: PathBuilder, VGPaint: StyleConv,
                 &mut |path| path.offset_path(amount, mdfr.lj, limit));
         }
 
-        ShapeItem::Merge (_) | ShapeItem::PuckerBloat(_) |
-        ShapeItem::Twist (_) | ShapeItem::ZigZag(_) => dbg!(),  // TODO:
+        ShapeItem::Merge(mdfr) if !mdfr.elem.hd => merge_shapes(mdfr.mm, &mut draws),
+        ShapeItem::PuckerBloat(mdfr) if !mdfr.elem.hd => {
+            let amount = mdfr.amount.as_ref().map_or(0., |amount| amount.get_value(fnth));
+            for_each_path_mut(&mut draws, &mut |path| path.pucker_bloat(amount));
+        }
+        ShapeItem::Twist(mdfr) if !mdfr.elem.hd => {
+            let angle  = mdfr.angle .as_ref().map_or(0., |angle| angle.get_value(fnth));
+            let center = mdfr.center.as_ref().map_or((0., 0.).into(),
+                |center| center.get_value(fnth));
+            for_each_path_mut(&mut draws, &mut |path| path.twist(angle, center));
+        }
+        ShapeItem::ZigZag(mdfr) if !mdfr.elem.hd => {
+            let ridges = mdfr.freq.as_ref().map_or(0., |freq| freq.get_value(fnth));
+            let amplitude = mdfr.ampl.as_ref().map_or(0., |ampl| ampl.get_value(fnth));
+            let smooth = mdfr.pt.as_ref().is_some_and(|pt| pt.get_value(fnth) == 2.);
+            for_each_path_mut(&mut draws,
+                &mut |path| path.zig_zag(ridges, amplitude, smooth));
+        }
 
         ShapeItem::Transform(ts) if !ts.elem.hd => ctm = ts.trfm.to_matrix(fnth, ao),
 
@@ -514,6 +772,53 @@ fn convert_shapes_inner<Path: PathBuilder, VGPaint: StyleConv,
     } }     (draws, ctm)
 }
 
+/// Replaces the preceding shapes no style has painted yet, including those of nested
+/// groups under their transforms, with their merge placed at the modifier.
+fn merge_shapes<VGPath: PathBuilder, VGPaint: StyleConv, TM2D: MatrixConv>(
+    mode: MergeMode, draws: &mut Vec<DrawItem<VGPath, VGPaint, TM2D>>) {
+    let mut paths = Vec::new();
+    take_unpainted_paths(draws, kurbo::Affine::IDENTITY, &mut paths);
+    if !paths.is_empty() {
+        draws.push(DrawItem::Shape(VGPath::from_kurbo(merge_kurbo(&paths, mode))));
+    }
+}
+
+/// Moves out the shapes after the last style in stacking order, mapped by `trfm`,
+/// dropping the nested groups left empty.
+fn take_unpainted_paths<VGPath: PathBuilder, VGPaint: StyleConv, TM2D: MatrixConv>(
+    draws: &mut Vec<DrawItem<VGPath, VGPaint, TM2D>>, trfm: kurbo::Affine,
+    paths: &mut Vec<BezPath>) {
+    let start = draws.iter().rposition(|draw|
+        matches!(draw, DrawItem::Style(_))).map_or(0, |idx| idx + 1);
+    let affine = |gtm: &TM2DwO<TM2D>| trfm * kurbo::Affine::new(gtm.0.coeffs());
+    let mut idx = start;
+    while idx < draws.len() {
+        match &mut draws[idx] {
+            DrawItem::Shape(path) => {
+                paths.push(trfm * mem::replace(path, VGPath::new(0)).into_kurbo());
+                draws.remove(idx);  continue
+            }
+            DrawItem::Group(group, transforms) => {
+                let mut local = Vec::new();
+                take_unpainted_paths(group, kurbo::Affine::IDENTITY, &mut local);
+                for gtm in transforms.iter() {
+                    let gtm = affine(gtm);
+                    paths.extend(local.iter().map(|path| gtm * path.clone()));
+                }
+                if group.is_empty() { draws.remove(idx);  continue }
+            }
+            DrawItem::Copies(copies) => {
+                for (group, gtm) in copies.iter_mut() {
+                    take_unpainted_paths(group, affine(gtm), paths);
+                }
+                copies.retain(|(group, _)| !group.is_empty());
+                if copies.is_empty() { draws.remove(idx);  continue }
+            }
+            DrawItem::Style(_) => (),
+        }   idx += 1;
+    }
+}
+
 fn materialize_draws<VGPath: PathBuilder, VGPaint: StyleConv, TM2D: MatrixConv>(
     draws: Vec<DrawItem<PendingPath<VGPath>, VGPaint, TM2D>>) ->
     Vec<DrawItem<VGPath, VGPaint, TM2D>> {
@@ -1,5 +1,5 @@
 use super::*;
-use crate::core::schema::{MatteMode, VisualLayer};
+use crate::core::{schema::{BlendMode, MatteMode, VisualLayer}, LayerEffect, StyleStage};
 use kurbo::ParamCurveArclen;
 
 fn layer_world_matrices<MC: MatrixConv>(
@@ -24,8 +24,8 @@ impl StyleConv for TestStyle {
     current: kurbo::Affine, transforms: Vec<kurbo::Affine>,
     fills: Vec<(kurbo::Affine, Option<kurbo::Affine>)>,
     offscreens: u32, aborts: u32, masks: u32, mattes: u32, presents: u32,
-    opacity: f32, drawn: Vec<f32>, discards: u32,
-    images: Vec<(Vec<u8>, f32, f32)>,
+    opacity: f32, drawn: Vec<f32>, discards: u32, blends: Vec<u8>, blurs: Vec<f32>,
+    styles: usize, images: Vec<(Vec<u8>, f32, f32)>,
 }
 impl RenderContext for TestContext {
     type VGPath = BezPath;
@@ -83,6 +83,20 @@ impl CompositeContext for TestContext {
     fn present(&mut self, _: Self::Image) -> Result<(), Self::Error> {
         self.presents += 1; Ok(())
     }
+    fn present_blended(&mut self, _: Self::Image,
+        mode: BlendMode) -> Result<(), Self::Error> {
+        self.blends.push(mode as u8); Ok(())
+    }
+    fn apply_effects(&mut self, image: Self::Image,
+        effects: &[LayerEffect]) -> Result<Self::Image, Self::Error> {
+        self.blurs.extend(effects.iter().filter_map(|effect| match effect {
+            LayerEffect::GaussianBlur { sigma, .. } => Some(sigma.x), _ => None,
+        }));    Ok(image)
+    }
+    fn apply_styles(&mut self, image: Self::Image,
+        styles: &[StyleStage]) -> Result<Self::Image, Self::Error> {
+        self.styles += styles.len();   Ok(image)
+    }
     fn discard(&mut self, _: Self::Image) { self.discards += 1 }
 }
 
@@ -153,6 +167,50 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
     assert_eq!((context.offscreens, context.aborts, context.presents), (1, 1, 0));
 }
 
+#[test] fn blended_layers_are_presented_offscreen_with_the_content_blend_mode() {
+    let layers: Vec<VisualLayer> = [
+        r#"{"ind":1,"ip":0,"op":1,"ks":{},"bm":1}"#,
+        r#"{"ind":2,"ip":0,"op":1,"ks":{},"bm":2,"tt":1}"#,
+        r#"{"ind":3,"ip":0,"op":1,"ks":{},"bm":3,"td":1}"#,
+        r#"{"ind":4,"ip":0,"op":1,"ks":{}}"#,
+    ].iter().map(|json| serde_json::from_str(json).unwrap()).collect();
+    let (mut context, mut state) =
+        (TestContext::default(), crate::core::composite::Compositor::default());
+
+    for layer in &layers {
+        state.render(&mut context, layer, &TM2DwO::default(), 0., |_| Ok(())).unwrap();
+    }
+    assert_eq!((context.offscreens, context.mattes, context.presents), (3, 1, 0));
+    assert_eq!(context.blends, [BlendMode::Multiply as u8, BlendMode::Screen as u8]);
+}
+
+#[test] fn enabled_effects_run_offscreen_in_device_pixels() {
+    let layers: Vec<VisualLayer> = [
+        r#"{"ind":1,"ip":0,"op":1,"ks":{},"ef":[{"ty":29,"ef":[{"ty":0,"v":{"k":10}}]},
+            {"ty":20,"en":0,"ef":[]}]}"#,
+        r#"{"ind":2,"ip":0,"op":1,"ks":{},"ef":[{"ty":29,"en":0,"ef":[]}]}"#,
+    ].iter().map(|json| serde_json::from_str(json).unwrap()).collect();
+    let (mut context, mut state) =
+        (TestContext::default(), crate::core::composite::Compositor::default());
+
+    for layer in &layers {
+        state.render(&mut context, layer,
+            &TM2DwO(kurbo::Affine::scale(2.), 1.), 0., |_| Ok(())).unwrap();
+    }
+    assert_eq!((context.offscreens, context.presents), (1, 1));
+    assert_eq!(context.blurs, [6.]);
+}
+
+#[test] fn layer_styles_run_offscreen_through_the_hook() {
+    let layer: VisualLayer = serde_json::from_str(
+        r#"{"ind":1,"ip":0,"op":1,"ks":{},"sy":[{"ty":1},{"ty":7}]}"#).unwrap();
+    let (mut context, mut state) =
+        (TestContext::default(), crate::core::composite::Compositor::default());
+
+    state.render(&mut context, &layer, &TM2DwO::default(), 0., |_| Ok(())).unwrap();
+    assert_eq!((context.offscreens, context.styles, context.presents), (1, 2, 1));
+}
+
 #[test] fn skipped_matte_source_does_not_bind_a_later_layer() {
     let target: VisualLayer = serde_json::from_str(
         r#"{"ind":1,"ip":0,"op":1,"ks":{},"tt":1}"#).unwrap();
@@ -192,6 +250,17 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
         [1., 0., 0., 1., 5., 6.]);
 }
 
+#[test] fn layer_world_matrix_applies_parent_transform_after_the_child() {
+    let animation: Animation = serde_json::from_str(r#"{ "layers": [
+        {"ty":3,"ind":1,"st":0,"ip":0,"op":10,"ks":{"r":{"k":90}}},
+        {"ty":3,"ind":2,"parent":1,"st":0,"ip":0,"op":10,"ks":{"p":{"k":[10,0]}}}
+    ] }"#).unwrap();
+
+    let matrices = layer_world_matrices::<kurbo::Affine>(&animation.layers, 0., |_| true);
+    let origin = matrices[1].as_ref().unwrap().0.translation();
+    assert!(origin.x.abs() < 1e-5 && (origin.y - 10.).abs() < 1e-5, "{origin:?}");
+}
+
 #[test] fn layer_world_matrix_skips_parent_cycles_and_their_descendants() {
     let animation: Animation = serde_json::from_str(r#"{ "layers": [
         {"ty":3,"ind":1,"parent":2,"st":0,"ip":0,"op":10,"ks":{}},
@@ -264,6 +333,43 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
     assert_eq!(runtime.frame(), 10.);
 }
 
+#[test] fn render_frame_leaves_the_playback_clock_alone() {
+    let mut runtime = LottieRuntime::from_reader(
+        &br#"{"ip":0,"op":4,"fr":1,"layers":[]}"#[..]).unwrap();
+    let mut context = TestContext::default();
+
+    runtime.seek(2.);   runtime.set_speed(-1.);
+    runtime.render_frame(&mut context, 3., None).unwrap();
+    assert_eq!(runtime.frame(), 2.);
+    assert!(runtime.render_next_frame(&mut context, 0., None).unwrap());
+    assert_eq!(runtime.frame(), 1.);
+
+    runtime.pause();
+    assert!(!runtime.render_next_frame(&mut context, 5., None).unwrap());
+    runtime.resume();   runtime.set_loop_mode(LoopMode::PingPong);
+    assert!(runtime.render_next_frame(&mut context, 2., None).unwrap());
+    assert_eq!(runtime.frame(), 1.);
+}
+
+#[test] fn markers_sequence_segments_and_report_completion() {
+    let mut runtime = LottieRuntime::from_reader(&br#"{"ip":0,"op":30,"fr":1,"layers":[],
+        "markers":[{"cm":"hover-in","tm":0,"dr":2},{"cm":"idle","tm":10,"dr":3}]}"#[..])
+        .unwrap();
+    let mut context = TestContext::default();
+
+    assert!(!runtime.play_marker("missing"));
+    assert!(runtime.play_marker("hover-in") && runtime.queue_marker("idle"));
+    let frames = (0..6).map(|_| {
+        runtime.render_next_frame(&mut context, 1., None).unwrap();     runtime.frame()
+    }).collect::<Vec<_>>();
+    assert_eq!(frames, [1., 10., 11., 12., 10., 11.]);
+
+    let completed = runtime.drain_events().map(|event| match event {
+        PlaybackEvent::SegmentComplete(segment) => segment.name.unwrap(),
+    }).collect::<Vec<_>>();
+    assert_eq!(completed, ["hover-in", "idle"]);
+}
+
 #[test] fn lottie_runtime_reuses_layer_graph_and_precomp_state() {
     let mut runtime = LottieRuntime::from_reader(&br##"{
         "ip":0,"op":10,"fr":1,
@@ -286,6 +392,67 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
         .composition.parents.as_ptr());
 }
 
+#[test] fn motion_blurred_layers_average_sub_frame_samples() {
+    let mut runtime = LottieRuntime::from_reader(&br##"{ "ip":4,"op":10,"fr":1,
+        "mb":{"sa":180,"sp":-90,"spf":4,"asl":8},
+        "layers":[
+            {"ty":1,"ip":0,"op":10,"sw":1,"sh":1,"sc":"#000000","mb":true,"ks":{
+                "p":{"a":1,"k":[{"t":0,"s":[0,0],"o":{"x":[0],"y":[0]},
+                    "i":{"x":[1],"y":[1]}},{"t":10,"s":[100,0]}]}}},
+            {"ty":1,"ip":0,"op":10,"sw":1,"sh":1,"sc":"#000000","mb":true,"ks":{}}
+        ]}"##[..]).unwrap();
+    let mut context = TestContext::default();
+
+    assert!(runtime.render_next_frame(&mut context, 1., None).unwrap());
+    assert_eq!((context.offscreens, context.presents), (6, 1));
+    assert_eq!(context.blends, [BlendMode::Add as u8; 5]);
+    assert_eq!(context.drawn, [1., 0.2, 0.2, 0.2, 0.2, 0.2]);
+    let positions = context.transforms.iter().map(|tm| tm.translation().x)
+        .collect::<Vec<_>>();
+    assert!(positions[1..].windows(2).all(|pair| (pair[1] - pair[0] - 1.).abs() < 1e-4));
+    assert!((positions[1] - 38.).abs() < 1e-4);
+}
+
+#[test] fn three_d_layers_are_projected_through_the_active_camera() {
+    let animation: Animation = serde_json::from_str(r#"{ "w":100,"h":100,"layers": [
+        {"ty":13,"ind":1,"ip":0,"op":10,"pe":{"k":100},"ks":{"p":{"k":[50,50,-100]}}},
+        {"ty":3,"ddd":1,"ip":0,"op":10,"ks":{"p":{"k":[50,50,0]}}},
+        {"ty":3,"ddd":1,"ip":0,"op":10,"ks":{"p":{"k":[50,50,100]}}},
+        {"ty":3,"ddd":1,"parent":1,"ip":0,"op":10,"ks":{"p":{"k":[0,0,200]}}},
+        {"ty":3,"ip":0,"op":10,"ks":{"p":{"k":[10,0]}}},
+        {"ty":3,"ddd":1,"ip":20,"op":30,"pe":{"k":1},"ks":{}}
+    ] }"#).unwrap();
+    let layers = &animation.layers;
+    let mut state = CompositionState::new(layers);
+    state.center = Vec2D { x: 50., y: 50. };
+
+    let matrices = state.evaluate::<kurbo::Affine>(layers, 0., |_| true)
+        .into_iter().map(|world| match world {
+            WorldState::Ready(world) => world.0.as_coeffs().map(|v| (v * 1e6).round() / 1e6),
+            WorldState::Pending | WorldState::Invalid => [f64::NAN; 6],
+        }).collect::<Vec<_>>();
+    assert_eq!(matrices[1], [1., 0., 0., 1., 50., 50.]);
+    assert_eq!(matrices[2], [0.5, 0., 0., 0.5, 50., 50.]);
+    assert_eq!(matrices[3], [0.5, 0., 0., 0.5, 50., 50.]);    // parented to the camera
+    assert_eq!(matrices[4], [1., 0., 0., 1., 10., 0.]);       // 2D layers stay flat
+
+    let flat: Animation = serde_json::from_str(r#"{ "layers": [
+        {"ty":3,"ddd":1,"ip":0,"op":10,
+            "ks":{"rx":{"k":0},"ry":{"k":60},"rz":{"k":0},"p":{"k":[5,6,70]}}}] }"#).unwrap();
+    let matrices = layer_world_matrices::<kurbo::Affine>(&flat.layers, 0., |_| true);
+    let coeffs = matrices[0].as_ref().unwrap().0.as_coeffs();
+    assert!((coeffs[0] - 0.5).abs() < 1e-6 && coeffs[3] == 1. && coeffs[4..] == [5., 6.]);
+
+    let mut runtime = LottieRuntime::from_reader(&br##"{ "ip":0,"op":10,"ddd":1,"layers":[
+        {"ty":13,"ip":0,"op":10,"pe":{"k":500},"ks":{"a":{"k":[0,0,0]},"p":{"k":[0,0,-500]},
+            "or":{"k":[0,0,0]},"rx":{"k":0},"ry":{"k":0},"rz":{"k":0}}},
+        {"ty":1,"ddd":1,"ip":0,"op":10,"sw":10,"sh":10,"sc":"#ff0000","ks":{
+            "rx":{"k":30},"ry":{"k":45},"rz":{"k":0},"or":{"k":[10,20,30]},
+            "p":{"a":1,"k":[{"t":0,"s":[0,0,0]},{"t":10,"s":[10,20,-40]}]}}}
+    ]}"##[..]).unwrap();
+    assert!(runtime.render_next_frame(&mut TestContext::default(), 1., None).unwrap());
+}
+
 #[test] fn lottie_runtime_renders_embedded_image_assets() {
     let mut runtime = LottieRuntime::from_reader(&br##"{ "ip":0,"op":2,"fr":1,
         "assets":[{"id":"picture","p":"data:image/png;base64,AQID","e":1,
@@ -301,6 +468,17 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
     assert_eq!(context.transforms.last().unwrap().as_coeffs()[4..], [4., 5.]);
 }
 
+#[test] fn view_transform_maps_the_composition_into_the_target() {
+    let mut runtime = LottieRuntime::from_reader(&br##"{"ip":0,"op":2,"fr":1,
+        "layers":[{"ty":1,"st":0,"ip":0,"op":2,"sw":1,"sh":1,"sc":"#000000",
+            "ks":{"p":{"k":[4,5]}}}]}"##[..]).unwrap();
+    let mut context = TestContext::default();
+
+    runtime.set_view_transform([2., 0., 0., 2., 1., 1.]);
+    runtime.render_frame(&mut context, 0., None).unwrap();
+    assert_eq!(context.transforms.last().unwrap().as_coeffs(), [2., 0., 0., 2., 9., 11.]);
+}
+
 #[test] fn precomp_time_remap_uses_root_fps_after_layer_time_mapping() {
     let json = br##"{
         "fr":24,"ip":20,"op":40,
@@ -343,6 +521,79 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
     assert!(b.composition.precomps[0].is_none());
 }
 
+#[test] fn text_layer_uses_embedded_character_shapes_and_precomps() {
+    let mut runtime = LottieRuntime::from_reader(&br##"{ "ip":0,"op":1,"fr":1,"w":200,"h":100,
+        "fonts":{"list":[{"fName":"Test-Regular","fFamily":"Test","fStyle":"Regular"}]},
+        "chars":[
+            {"ch":"A","fFamily":"Test","style":"Regular","size":50,"w":60,"data":{"shapes":[
+                {"ty":"rc","s":{"k":[40,70]},"p":{"k":[20,-35]},"r":{"k":0}}]}},
+            {"ch":"B","fFamily":"Test","style":"Regular","size":50,"w":40,"data":{
+                "refId":"glyph","ip":0,"op":1,"ks":{}}},
+            {"ch":"A","fFamily":"Other","style":"Regular","size":50,"w":90,"data":{}}
+        ],
+        "assets":[{"id":"glyph","layers":[{"ty":1,"st":0,"ip":0,"op":1,
+            "sw":40,"sh":70,"sc":"#000000","ks":{}}]}],
+        "layers":[{"ty":5,"st":0,"ip":0,"op":1,"ks":{},"t":{"a":[],"m":{},"p":{},
+            "d":{"k":[{"t":0,"s":{"t":"AB A","f":"Test-Regular","s":50,"fc":[0,0,0]}}]}}}]
+    }"##[..]).unwrap();
+    runtime.set_font_provider(NoFonts);
+    let mut context = TestContext::default();
+
+    assert!(runtime.render_next_frame(&mut context, 1., None).unwrap());
+    let fills: Vec<_> = context.fills.iter().map(|(tm, _)| tm.as_coeffs()).collect();
+    assert_eq!(fills, [[0.5, 0., 0., 0.5, 62.5, 0.], [0.5, 0., 0., 0.5, 0., 0.],
+        [0.5, 0., 0., 0.5, 30., 0.]]);     // the precomp glyph comes after shapes
+    // cameras in the glyph project around the center of the text layer's composition
+    let center = runtime.res.glyphs[1].as_ref().unwrap().composition.center;
+    assert_eq!((center.x, center.y), (100., 50.));
+}
+
+#[test] fn text_range_selector_animates_glyph_opacity_and_position() {
+    let mut runtime = LottieRuntime::from_reader(&br##"{ "ip":0,"op":1,"fr":1,
+        "chars":[{"ch":"A","fFamily":"Test","style":"Regular","size":100,"w":100,
+            "data":{"shapes":[{"ty":"rc","s":{"k":[80,70]},"p":{"k":[50,-35]},"r":{"k":0}}]}}],
+        "layers":[{"ty":5,"st":0,"ip":0,"op":1,"ks":{},"t":{"m":{},"p":{},
+            "d":{"k":[{"t":0,"s":{"t":"AAAA","f":"Test","s":100,"fc":[0,0,0]}}]},
+            "a":[{"s":{"t":0,"a":{"k":100},"b":1,"sh":1,"s":{"k":0},"e":{"k":50}},
+                  "a":{"o":{"k":0},"p":{"k":[0,-10]}}}]}}]
+    }"##[..]).unwrap();
+    let mut context = TestContext::default();
+
+    assert!(runtime.render_next_frame(&mut context, 1., None).unwrap());
+    assert_eq!(context.drawn, [1., 1., 0., 0.]);
+    let origins: Vec<_> = context.fills.iter().map(|(tm, _)|
+        (tm.as_coeffs()[4], tm.as_coeffs()[5])).collect();
+    assert_eq!(origins, [(300., 0.), (200., 0.), (100., -10.), (0., -10.)]);
+}
+
+#[test] fn text_follows_the_mask_path_with_perpendicular_glyphs() {
+    let mut runtime = LottieRuntime::from_reader(&br##"{ "ip":0,"op":1,"fr":1,
+        "chars":[{"ch":"A","fFamily":"Test","style":"Regular","size":100,"w":10,
+            "data":{"shapes":[{"ty":"rc","s":{"k":[8,7]},"p":{"k":[5,-4]},"r":{"k":0}}]}}],
+        "layers":[{"ty":5,"st":0,"ip":0,"op":1,"ks":{},"hasMask":true,
+            "masksProperties":[{"mode":"n","pt":{"k":{"c":false,"v":[[0,0],[0,100]],
+                "i":[[0,0],[0,0]],"o":[[0,0],[0,0]]}}}],
+            "t":{"a":[],"m":{},"p":{"m":0,"f":{"k":5}},
+            "d":{"k":[{"t":0,"s":{"t":"AA","f":"Test","s":100,"fc":[0,0,0]}}]}}}]
+    }"##[..]).unwrap();
+    let mut context = TestContext::default();
+
+    assert!(runtime.render_next_frame(&mut context, 1., None).unwrap());
+    let expected = [[0., 1., -1., 0., 0., 15.], [0., 1., -1., 0., 0., 5.]];
+    assert_eq!(context.fills.len(), expected.len());
+    for ((tm, _), expected) in context.fills.iter().zip(expected) {    // arc length tolerance
+        assert!(tm.as_coeffs().iter().zip(expected).all(|(a, b)| (a - b).abs() < 1e-2),
+            "{:?}", tm.as_coeffs());
+    }
+}
+
+struct NoFonts;
+impl text::FontProvider for NoFonts {
+    fn load_font(&mut self, _: &crate::core::schema::Font) -> Option<(Vec<u8>, u32)> {
+        panic!("glyphs are embedded")
+    }
+}
+
 #[test] fn frame_clear_supports_transparent_color_and_preserve_modes() {
     let mut runtime = LottieRuntime::from_reader(
         &br#"{"ip":0,"op":10,"fr":1,"layers":[]}"#[..]).unwrap();
@@ -414,6 +665,46 @@ fn fill_style() -> DrawItem<BezPath, TestStyle, kurbo::Affine> {
     assert!(!path.into_native().is_empty());
 }
 
+#[test] fn merge_paths_replaces_preceding_shapes_but_not_nested_groups() {
+    let shapes: Vec<ShapeItem> = serde_json::from_str(r#"[
+        {"ty":"rc","s":{"k":[10,10]},"p":{"k":[5,5]},"r":{"k":0}},
+        {"ty":"gr","it":[{"ty":"el","s":{"k":[4,4]},"p":{"k":[0,0]}}]},
+        {"ty":"rc","s":{"k":[10,10]},"p":{"k":[10,10]},"r":{"k":0}},
+        {"ty":"mm","mm":4},
+        {"ty":"fl","c":{"k":[1,0,0,1]},"o":{"k":100}}
+    ]"#).unwrap();
+    let (draws, _) = convert_shapes::<BezPath, TestStyle, kurbo::Affine>(
+        &shapes, 0., IntBool::default());
+
+    // the nested ellipse joins the union that intersects the last rectangle
+    assert!(matches!(draws.as_slice(), [DrawItem::Shape(_), DrawItem::Style(_)]));
+    let DrawItem::Shape(merged) = &draws[0] else { unreachable!() };
+    use kurbo::Shape;
+    assert_eq!(merged.bounding_box(), kurbo::Rect::new(5., 5., 10., 10.));
+}
+
+#[test] fn merge_paths_skips_painted_shapes_and_maps_nested_groups() {
+    let shapes: Vec<ShapeItem> = serde_json::from_str(r#"[
+        {"ty":"rc","s":{"k":[10,10]},"p":{"k":[5,5]},"r":{"k":0}},
+        {"ty":"fl","c":{"k":[1,0,0,1]},"o":{"k":100}},
+        {"ty":"gr","it":[{"ty":"rc","s":{"k":[4,4]},"p":{"k":[0,0]},"r":{"k":0}},
+            {"ty":"tr","p":{"k":[1,1]}}]},
+        {"ty":"rc","s":{"k":[10,10]},"p":{"k":[10,10]},"r":{"k":0}},
+        {"ty":"mm","mm":2},
+        {"ty":"fl","c":{"k":[0,0,1,1]},"o":{"k":100}}
+    ]"#).unwrap();
+    let (draws, _) = convert_shapes::<BezPath, TestStyle, kurbo::Affine>(
+        &shapes, 0., IntBool::default());
+
+    assert!(matches!(draws.as_slice(), [DrawItem::Shape(_), DrawItem::Style(_),
+        DrawItem::Shape(_), DrawItem::Style(_)]));
+    let (DrawItem::Shape(painted), DrawItem::Shape(merged)) = (&draws[0], &draws[2])
+        else { unreachable!() };
+    use kurbo::Shape;
+    assert_eq!(painted.bounding_box(), kurbo::Rect::new(0., 0., 10., 10.));
+    assert_eq!(merged.bounding_box(), kurbo::Rect::new(-1., -1., 15., 15.));
+}
+
 #[test] fn sequential_trim_keeps_both_wrapped_parts_of_one_shape() {
     let trim = trim(0., 50., 270., 2);
     let mut draws: Vec<DrawItem<BezPath, TestStyle, kurbo::Affine>> =
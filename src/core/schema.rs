@@ -4,7 +4,7 @@ use serde_repr::{Serialize_repr, Deserialize_repr}; // for the underlying repr o
 use super::helpers::{IntBool, RGBA, Vec2D, AnyAsset, defaults,
     str_to_rgba, str_from_rgba,
 };
-use super::schema_impl::{des_nonempty_vec, des_strarray, des_static_value};
+use super::schema_impl::{des_nonempty_vec, des_strarray, des_static_value, des_transform};
 
 /// Top level object, describing the animation.
 ///
@@ -122,7 +122,7 @@ type DataLayer =  ImageLayer;   // refId of the data source in assets
 
 #[derive(Deserialize, Serialize)] pub struct CameraLayer { // 3D Camera
     #[serde(flatten)] pub base: LayerInfo,
-    pub ks: Transform, // Layer transform
+    #[serde(deserialize_with = "des_transform")] pub ks: Transform, // Layer transform
     /// Distance from the (Z=0) plane. Small values yield a higher perspective effect.
     pub pe: Value,     // Perspective
 }
@@ -130,7 +130,7 @@ type DataLayer =  ImageLayer;   // refId of the data source in assets
 /// Layer used to affect visual elements
 #[derive(Deserialize, Serialize)] pub struct VisualLayer {
     #[serde(flatten)] pub base: LayerInfo,
-    pub ks: Transform, // Layer transform
+    #[serde(deserialize_with = "des_transform")] pub ks: Transform, // Layer transform
 
     //#[serde(skip_serializing)] pub cp: Option<bool>, // This is deprecated in favour of `ct`
     /// Collapse Transform, Marks that transforms should be applied before masks
@@ -165,7 +165,8 @@ type DataLayer =  ImageLayer;   // refId of the data source in assets
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     /** Styling effects for this layer */ pub sy: Vec<LayerStyleItem>,
 
-    #[serde(flatten, skip_serializing_if = "Option::is_none")] extra: Option<Box<SVGProp>>,
+    #[serde(flatten, skip_serializing_if = "Option::is_none")]
+    pub(super) extra: Option<Box<SVGProp>>,
 }
 
 /// (tag name, `id` attribute, CSS class list) used by the SVG renderer
@@ -203,8 +204,8 @@ type DataLayer =  ImageLayer;   // refId of the data source in assets
 }
 
 #[derive(Deserialize, Serialize)] pub struct VisualObject {
-    //#[serde(default, skip_serializing_if = "String::is_empty")]
-    //* Name, as seen from editors and the like */ pub nm: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    /** Name, as seen from editors and the like */ pub nm: String,
     #[serde(default, skip_serializing_if = "String::is_empty")] #[cfg(feature = "expression")]
     /** Match name, used in expressions */ pub mn: String,
 }
@@ -234,8 +235,13 @@ type DataLayer =  ImageLayer;   // refId of the data source in assets
     /** Skew amount as an angle in degrees    */ pub skew: Option<Value>,
 
     #[serde(flatten)] pub extra: TransRotation,
+    /// Z components of `a` and `p`, only kept for layer transforms
+    #[serde(skip)] pub depth: Option<Box<TransformDepth>>,
 }
 
+/// Z components (`Value`s) of the anchor and position of a 3D layer transform
+pub struct TransformDepth { pub anchor: Option<Value>, pub position: Option<Value> }
+
 #[derive(Deserialize, Serialize)] #[serde(untagged)]
 pub enum Translation { /** Position / Translation */ Normal(Position),
     /** Position / Translation with split components */ Split(Box<SplitVector>),
@@ -250,11 +256,11 @@ pub enum TransRotation {    Split3D(Box<SplitRotation>),
 
 /// XXX: Split rotation component, X/Y/Z (3D) Rotation and Orientation
 #[derive(Deserialize, Serialize)] pub struct SplitRotation {
-    #[serde(skip_serializing_if = "Option::is_none")] rx: Option<Value>,
-    #[serde(skip_serializing_if = "Option::is_none")] ry: Option<Value>,
-    /** equivalent to `r` when not split */ rz: Value,
+    #[serde(skip_serializing_if = "Option::is_none")] pub rx: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")] pub ry: Option<Value>,
+    /** equivalent to `r` when not split */ pub rz: Value,
     #[serde(skip_serializing_if = "Option::is_none")]
-    /** Orientation, MultiDimensional    */ or: Option<MultiD>,
+    /** Orientation, MultiDimensional    */ pub or: Option<MultiD>,
 }
 
 /// (In/Out) tangent for values (eg: moving position around a curved path)
@@ -313,6 +319,9 @@ pub enum AnimatedValue<T> {
     /// truncated or expanded to match this length when accessed from expressions.
     #[serde(skip_serializing_if = "Option::is_none", rename = "l")]
     /** Length, for Position and MultiDimensional */ pub len: Option<u32>,
+    /// Parsed on first evaluation, `None` if it's beyond the supported subset
+    #[cfg(feature = "expression")] #[serde(skip)]
+    pub(crate) program: std::sync::OnceLock<Option<super::expression::Program>>,
 }
 
 /// A Keyframes specifies the value at a specific time and
@@ -925,8 +934,9 @@ type Sound = FileAsset; // External sound, from the LottieFiles/Bodymovin asset
     pub  size: f32,
 }
 
+/// `Precomp` goes first, as any object deserializes into (optional) `Shapes`.
 #[derive(Deserialize, Serialize)] #[serde(untagged)]
-pub enum ShapePrecomp { Shapes(CharacterShapes), Precomp(Box<CharacterPrecomp>), }
+pub enum ShapePrecomp { Precomp(Box<CharacterPrecomp>), Shapes(CharacterShapes), }
 
 /// Shapes forming the character
 #[derive(Deserialize, Serialize)] pub struct CharacterShapes {
@@ -1045,7 +1055,8 @@ type SlotDict = std::collections::HashMap<String, SlotProp>;
     #[serde(flatten)] pub vo: VisualObject,
 }
 
-#[derive(Serialize)] #[serde(untagged)] pub enum LayerStyleItem {
+#[derive(Serialize)] #[serde(untagged)] #[allow(clippy::large_enum_variant)]
+pub enum LayerStyleItem {
     /* 2 */InnerShadow(InnerShadowStyle),
     /* 1 */DropShadow  (DropShadowStyle),
     /* 3 */OuterGlow(OuterGlowStyle),
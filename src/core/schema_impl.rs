@@ -33,8 +33,12 @@ impl<'de> Deserialize<'de> for TextGrouping {
 
 impl Animation {
     pub fn from_reader<R: std::io::Read>(r: R) -> Result<Self, serde_json::Error> {
-        let mut value = serde_json::Value::deserialize(
-            &mut serde_json::Deserializer::from_reader(r))?;
+        Self::from_value(serde_json::Value::deserialize(
+            &mut serde_json::Deserializer::from_reader(r))?)
+    }
+
+    /// Deserialize from a parsed JSON document, resolving slot references on the way.
+    pub fn from_value(mut value: serde_json::Value) -> Result<Self, serde_json::Error> {
         let slots = value.as_object_mut().and_then(|animation| animation.remove("slots"));
         if let Some(serde_json::Value::Object(slots)) = &slots {
             if let Some(animation) = value.as_object_mut() {
@@ -48,6 +52,23 @@ impl Animation {
         }
         Self::deserialize(value)
     }
+
+    pub fn marker(&self, name: &str) -> Option<&Marker> {
+        self.markers.iter().find(|marker| marker.name() == name)
+    }
+}
+
+impl Marker {
+    /// Bodymovin may export the comment as a JSON payload, naming the marker by its `name`.
+    pub fn name(&self) -> Cow<'_, str> {
+        if self.cm.starts_with('{') {
+            if let Ok(serde_json::Value::Object(mut payload)) = serde_json::from_str(&self.cm) {
+                if let Some(serde_json::Value::String(name)) = payload.remove("name") {
+                    return Cow::Owned(name)
+                }
+            }
+        }   Cow::Borrowed(&self.cm)
+    }
 }
 
 fn resolve_slot_refs(value: &mut serde_json::Value, slots: &serde_json::Map<String,
@@ -83,6 +104,40 @@ pub(crate) fn des_nonempty_vec<'de, D, T>(d: D) -> Result<Vec<T>, D::Error>
     else { Ok(values) }
 }
 
+/// Layer transforms keep the non-zero z of 3-element anchor and position values
+/// aside, since `Position` only holds x and y.
+pub(crate) fn des_transform<'de, D: Deserializer<'de>>(d: D) -> Result<Transform, D::Error> {
+    use serde_json::{Value as Json, json};
+    let value = Json::deserialize(d)?;
+    let depth = |key: &str| -> Option<Value> {
+        let z = |value: &Json| value.as_array()
+            .and_then(|value| value.get(2)).and_then(Json::as_f64);
+        let k = value.get(key)?.get("k")?;
+        let json = if let Some(z) = z(k) {
+            if z == 0. { return None }  json!({ "a": 0, "k": z })
+        } else {
+            let frames = k.as_array()?;
+            if !frames.iter().any(|frame|
+                frame.get("s").and_then(z).is_some_and(|z| z != 0.)) { return None }
+            json!({ "a": 1, "k": frames.iter().map(|frame| {
+                let mut frame = frame.clone();
+                if let Some(frame) = frame.as_object_mut() {
+                    frame.remove("to");     frame.remove("ti");
+                    for key in ["s", "e"] { if let Some(value) = frame.get_mut(key) {
+                        *value = json!([z(value).unwrap_or(0.)]);
+                    } }
+                }   frame
+            }).collect::<Vec<_>>() })
+        };  Value::deserialize(json).ok()
+    };
+
+    let (anchor, position) = (depth("a"), depth("p"));
+    let mut transform = Transform::deserialize(value).map_err(D::Error::custom)?;
+    if anchor.is_some() || position.is_some() {
+        transform.depth = Some(Box::new(TransformDepth { anchor, position }));
+    }   Ok(transform)
+}
+
 pub(crate) fn des_strarray<'de, D: Deserializer<'de>>(d: D) ->
     Result<Vec<String>, D::Error> {
     let value = serde_json::Value::deserialize(d)?;
@@ -375,6 +430,16 @@ impl<T> AnimatedProperty<T> {
 impl<T: Clone + math::Tween> AnimatedProperty<T> {
     pub(crate) fn try_get_value_cow(&self, fnth: f32) ->
         Result<Cow<'_, T>, UnresolvedSlot<'_>> {
+        let value = self.keyframed_cow(fnth)?;
+        #[cfg(feature = "expression")] if let Some(expr) = &self.expr {
+            if let Some(value) = super::expression::evaluate(self, expr, fnth, &value) {
+                return Ok(Cow::Owned(value))
+            }
+        }   Ok(value)
+    }
+
+    /// Value from the keyframes (or static value) only, before any expression
+    pub(crate) fn keyframed_cow(&self, fnth: f32) -> Result<Cow<'_, T>, UnresolvedSlot<'_>> {
         let keyframes = match &self.source {
             PropertySource::Inline(value) |
             PropertySource::Slot { fallback: Some(value), .. } => value,
@@ -458,12 +523,20 @@ impl LayerItem {
             Self::Audio(_) | Self::Camera(_) => return None,
         })
     }
+
+    /// Properties of the layers that take part in parenting, visual layers and cameras
+    pub fn parentable(&self) -> Option<&LayerInfo> {
+        if let Self::Camera(camera) = self { return Some(&camera.base) }
+        self.visual_layer().map(|vl| &vl.base)
+    }
 }
 
 impl VisualLayer {
     pub fn should_hide(&self, fnth: f32) -> bool {
         self.base.hd || fnth < self.base.ip || self.base.op <= fnth
     }
+    /// Tag name, `id` and CSS classes for SVG output, if any is given.
+    pub fn svg_prop(&self) -> Option<&SVGProp> { self.extra.as_deref() }
 }
 
 impl LayerInfo {
@@ -477,6 +550,16 @@ impl LayerInfo {
     use serde::ser::SerializeSeq;
     use serde_test::{assert_tokens, Token};
 
+    #[test] fn markers_are_found_by_comment_or_json_payload_name() {
+        let animation: Animation = serde_json::from_str(r#"{"layers":[],"markers":[
+            {"cm":"intro","tm":0,"dr":10},
+            {"cm":"{\"name\":\"idle\",\"loop\":true}","tm":10,"dr":20}]}"#).unwrap();
+
+        assert_eq!(animation.marker("intro").map(|marker| marker.dr), Some(10.));
+        assert_eq!(animation.marker("idle").map(|marker| marker.tm), Some(10.));
+        assert!(animation.marker("outro").is_none());
+    }
+
     #[test] fn layer_local_frame_applies_stretch_before_start_time() {
         let layer: LayerItem = serde_json::from_str(
             r#"{"ty":3,"st":10,"sr":2,"ip":0,"op":100,"ks":{}}"#).unwrap();
@@ -500,6 +583,25 @@ impl LayerInfo {
         }
     }
 
+    #[test] fn layer_transform_keeps_z_of_anchor_and_position() {
+        let layer: LayerItem = serde_json::from_str(r#"{"ty":3,"ddd":1,"ip":0,"op":10,
+            "ks":{"a":{"k":[1,2,3]},"rx":{"k":10},"rz":{"k":30},"or":{"k":[0,90,0]},
+                "p":{"a":1,"k":[{"t":0,"s":[0,0,0],"to":[1,1,1],"ti":[0,0,0]},
+                    {"t":10,"s":[10,20,-40]}]}}}"#).unwrap();
+        let ks = &layer.visual_layer().unwrap().ks;
+        let depth = ks.depth.as_deref().unwrap();
+
+        let anchor = ks.anchor.as_ref().unwrap().get_value(0.);
+        assert_eq!((anchor.x, anchor.y), (1., 2.));
+        assert_eq!(depth.anchor.as_ref().unwrap().get_value(0.), 3.);
+        assert_eq!(depth.position.as_ref().unwrap().get_value(5.), -20.);
+        assert!(matches!(ks.extra, TransRotation::Split3D(_)));
+
+        let flat: LayerItem = serde_json::from_str(
+            r#"{"ty":3,"ip":0,"op":10,"ks":{"p":{"k":[5,6,0]}}}"#).unwrap();
+        assert!(flat.visual_layer().unwrap().ks.depth.is_none());
+    }
+
     #[test] fn enum_deserialization_rejects_unknown_types() {
         assert!(serde_json::from_str::<LayerItem>(r#"{"ty":99}"#).is_err());
         assert!(serde_json::from_str::<EffectValueItem>(r#"{"ty":99}"#).is_err());
@@ -6,9 +6,9 @@
  ****************************************************************/
 
 use super::{helpers::{Vec2D, RGBA, IntBool, math},
-    schema::{Transform, Translation, TransRotation, FillStrokeGrad, ColorGrad,
-        FillStroke, FillRule, GradientType, GradientColors, Repeater, Composite,
-        LineJoin, LineCap, StrokeDashType}
+    schema::{Transform, Translation, TransRotation, CameraLayer, Value,
+        FillStrokeGrad, ColorGrad, FillStroke, FillRule, GradientType, GradientColors,
+        Repeater, Composite, LineJoin, LineCap, StrokeDashType}
 };
 
 impl MatrixConv for kurbo::Affine {
@@ -22,7 +22,9 @@ impl MatrixConv for kurbo::Affine {
     fn scale(&mut self, sl: Vec2D) {      // Affine didn't do tan() inside
         *self = self.then_scale_non_uniform(sl.x as _, sl.y as _)
     }
-    fn premul(&mut self, tm: &Self) { *self *= *tm }
+    fn premul(&mut self, tm: &Self) { *self = *tm * *self }
+    fn coeffs(&self) -> [f64; 6] { self.as_coeffs() }
+    fn from_coeffs(coeffs: [f64; 6]) -> Self { Self::new(coeffs) }
 }
 
 #[cfg(feature = "vello")] impl StyleConv for peniko::Brush {
@@ -92,11 +94,26 @@ impl MatrixConv for kurbo::Affine {
  ``` */
 pub trait MatrixConv {
     fn identity() -> Self;
+    /// Apply `tm` after this transform, e.g. a parent's one after its child's.
     fn premul(&mut self, tm: &Self);
     fn rotate(&mut self, angle: f32);
     fn translate(&mut self, pos: Vec2D);
     fn skew_x(&mut self, sk: f32);
     fn scale(&mut self, sl: Vec2D);
+    /// Coefficients in the order of `[a, b, c, d, e, f]`
+    fn coeffs(&self) -> [f64; 6];
+    /// Composes the coefficients by scale, skew, rotation and then translation.
+    fn from_coeffs([a, b, c, d, e, f]: [f64; 6]) -> Self where Self: Sized {
+        let (sx, angle) = (a.hypot(b), b.atan2(a));
+        let (skew, sy) = if sx == 0. { (0., d) } else {
+            ((a * c + b * d) / sx, (a * d - b * c) / sx)
+        };
+        let mut matrix = Self::identity();
+        matrix.scale(Vec2D { x: sx as _, y: sy as _ });
+        if sy != 0. { matrix.skew_x((skew / sy).atan() as _) }
+        matrix.rotate(angle as _);
+        matrix.translate(Vec2D { x: e as _, y: f as _ });   matrix
+    }
 }
 
 #[derive(Clone)] pub struct TM2DwO<MC: MatrixConv>(pub MC, pub f32);
@@ -121,14 +138,54 @@ impl Transform {
     /// perform LEFT multiplication instead. Perform the following operations on a
     /// matrix starting from the identity matrix (or the parent object's transform matrix):
     pub fn to_matrix<MC: MatrixConv>(&self, fnth: f32, ao: IntBool) -> TM2DwO<MC> {
-        let opacity = self.opacity.as_ref().map_or(1.,
-            |o| o.get_value(fnth) / 100.); // FIXME: for canvas global?
-
         let mut trfm = MC::identity();
         if  let Some(anchor) = &self.anchor {
             trfm.translate(-anchor.get_value(fnth));
+        }   self.scale_skew(fnth, &mut trfm);
+
+        match &self.extra {
+            TransRotation::Normal2D { rotation: Some(rdeg) } =>
+                trfm.rotate(rdeg.get_value(fnth).to_radians()),
+            // flattened 3D rotation, only the Z axis is visible on the layer plane
+            TransRotation::Split3D(rot) => trfm.rotate(rot.rz.get_value(fnth).to_radians()),
+            _ => (),
         }
 
+        if  let Some((pos, orient)) = self.translation(fnth, ao) {
+            if let Some(orient) = orient { trfm.rotate(orient); }
+            trfm.translate(pos);
+        }   TM2DwO(trfm, self.opacity(fnth))
+    }
+
+    /// Same steps as `to_matrix` for 3D layers (`ddd`), with X/Y/Z rotations,
+    /// orientation and the Z components of anchor and position.
+    pub fn to_matrix3d(&self, fnth: f32, ao: IntBool) -> (Matrix3D, f32) {
+        let depth = |value: Option<&Value>| value.map_or(0., |z| z.get_value(fnth) as f64);
+        let (az, pz) = self.depth.as_deref().map_or((0., 0.), |dp|
+            (depth(dp.anchor.as_ref()), depth(dp.position.as_ref())));
+
+        let anchor = self.anchor.as_ref().map_or(Vec2D { x: 0., y: 0. }, |anchor|
+            anchor.get_value(fnth));
+        let mut planar = kurbo::Affine::IDENTITY;   self.scale_skew(fnth, &mut planar);
+        let mut trfm = Matrix3D::translate(-anchor.x as _, -anchor.y as _, -az)
+            .then(&Matrix3D::from_coeffs(planar.as_coeffs())).then(&self.rotation3d(fnth));
+
+        if  let Some((pos, orient)) = self.translation(fnth, ao) {
+            if let Some(orient) = orient {
+                trfm = trfm.then(&Matrix3D::rotate_z(orient as _));
+            }
+            let pz = match &self.position {
+                Some(Translation::Split(sv)) => depth(sv.z.as_ref()), _ => pz,
+            };  trfm = trfm.then(&Matrix3D::translate(pos.x as _, pos.y as _, pz));
+        }   (trfm, self.opacity(fnth))
+    }
+
+    fn opacity(&self, fnth: f32) -> f32 {
+        self.opacity.as_ref().map_or(1.,
+            |o| o.get_value(fnth) / 100.) // FIXME: for canvas global?
+    }
+
+    fn scale_skew<MC: MatrixConv>(&self, fnth: f32, trfm: &mut MC) {
         if  let Some(scale) = &self.scale {
             let scale = scale.get_value(fnth) / 100.;
             //if scale.x == 0. { scale.x = f32::EPSILON; } // workaround for some lottie file?
@@ -146,33 +203,149 @@ impl Transform {
 
             if let Some(axis) = axis { trfm.rotate( axis); }
         }
+    }
+
+    /// Position and the auto-orient angle along the motion path
+    fn translation(&self, fnth: f32, ao: IntBool) -> Option<(Vec2D, Option<f32>)> {
+        let (pos, prev) = match self.position.as_ref()? {
+            Translation::Normal(apos) => (apos.get_value(fnth),
+                (ao.as_bool() && apos.is_animated()).then(|| apos.get_value(fnth - 1.))),
 
+            Translation::Split(sv) => {   debug_assert!(sv.split);
+                let at = |fnth| Vec2D { x: sv.x.get_value(fnth), y: sv.y.get_value(fnth) };
+                (at(fnth), ao.as_bool().then(|| at(fnth - 1.)))
+            }
+        };  Some((pos, prev.map(|prev| {
+            let orient = pos - prev;   math::fast_atan2(orient.y, orient.x)
+        })))
+    }
+
+    /// Z, Y, X rotations followed by the orientation (also applied Z, Y, X)
+    fn rotation3d(&self, fnth: f32) -> Matrix3D {
+        let angle = |value: Option<&Value>| value.map_or(0., |rdeg|
+            (rdeg.get_value(fnth) as f64).to_radians());
         match &self.extra {
-            TransRotation::Normal2D { rotation: Some(rdeg) } =>
-                trfm.rotate(rdeg.get_value(fnth).to_radians()),
-            TransRotation::Split3D(_) => unimplemented!(), //debug_assert!(ddd),
-            _ => (),
+            TransRotation::Normal2D { rotation } =>
+                Matrix3D::rotate_z(angle(rotation.as_ref())),
+            TransRotation::Split3D(rot) => {
+                let or = rot.or.as_ref().map_or_else(Vec::new, |or| or.get_value(fnth));
+                let or = |i: usize| or.get(i).map_or(0., |&v| (v as f64).to_radians());
+                Matrix3D::rotate_z(angle(Some(&rot.rz)))
+                    .then(&Matrix3D::rotate_y(angle(rot.ry.as_ref())))
+                    .then(&Matrix3D::rotate_x(angle(rot.rx.as_ref())))
+                    .then(&Matrix3D::rotate_z(or(2))).then(&Matrix3D::rotate_y(or(1)))
+                    .then(&Matrix3D::rotate_x(or(0)))
+            }
         }
+    }
+}
 
-        match &self.position {
-            Some(Translation::Normal(apos)) => {
-                let pos  = apos.get_value(fnth);
-                if  ao.as_bool() && apos.is_animated() {
-                    let orient = pos - apos.get_value(fnth - 1.);
-                    trfm.rotate(math::fast_atan2(orient.y, orient.x));
-                }   trfm.translate(pos);
+impl CameraLayer {
+    /// Camera to parent space: rotations, then aiming at the point of interest
+    /// (the anchor, if any) and moving to the position.
+    pub fn to_matrix3d(&self, fnth: f32) -> Matrix3D {
+        let trfm = &self.ks;
+        let depth = |value: Option<&Value>| value.map_or(0., |z| z.get_value(fnth) as f64);
+        let dp = trfm.depth.as_deref();
+        let pos = trfm.translation(fnth, IntBool::default())
+            .map_or(Vec2D { x: 0., y: 0. }, |(pos, _)| pos);
+        let pos = [pos.x as f64, pos.y as f64, match &trfm.position {
+            Some(Translation::Split(sv)) => depth(sv.z.as_ref()),
+            _ => depth(dp.and_then(|dp| dp.position.as_ref())),
+        }];
+
+        let mut world = trfm.rotation3d(fnth);
+        if  let Some(anchor) = &trfm.anchor {
+            let poi = anchor.get_value(fnth);
+            let dir = [poi.x as f64 - pos[0], poi.y as f64 - pos[1],
+                depth(dp.and_then(|dp| dp.anchor.as_ref())) - pos[2]];
+            let len = dir.iter().map(|v| v * v).sum::<f64>().sqrt();
+            if  f64::EPSILON < len {
+                world = world.then(&Matrix3D::rotate_x((-dir[1] / len).asin()))
+                    .then(&Matrix3D::rotate_y(dir[0].atan2(dir[2])));
             }
+        }   world.then(&Matrix3D::translate(pos[0], pos[1], pos[2]))
+    }
+}
 
-            Some(Translation::Split(sv)) => {   debug_assert!(sv.split);
-                let pos = Vec2D { x: sv.x.get_value(fnth), y: sv.y.get_value(fnth) };
-                if  ao.as_bool() {
-                    let orient = pos -
-                        Vec2D { x: sv.x.get_value(fnth - 1.), y: sv.y.get_value(fnth - 1.) };
-                    trfm.rotate(math::fast_atan2(orient.y, orient.x));
-                }   trfm.translate(pos);
-                if sv.z.is_some() { unimplemented!(); }
-            }   _ => (),
-        }   TM2DwO(trfm, opacity)
+/// 4x4 matrix (column vectors) in After Effects axes: X right, Y down, Z into the screen.
+#[derive(Clone, Copy, Debug, PartialEq)] pub struct Matrix3D(pub [[f64; 4]; 4]);
+
+impl Matrix3D {
+    pub const IDENTITY: Self = Self([[1., 0., 0., 0.], [0., 1., 0., 0.],
+                                     [0., 0., 1., 0.], [0., 0., 0., 1.]]);
+
+    /// Embeds a 2D affine (`[a, b, c, d, e, f]`) into the Z = 0 plane
+    pub fn from_coeffs([a, b, c, d, e, f]: [f64; 6]) -> Self {
+        Self([[a, c, 0., e], [b, d, 0., f], [0., 0., 1., 0.], [0., 0., 0., 1.]])
+    }
+    pub fn translate(x: f64, y: f64, z: f64) -> Self {
+        let mut tm = Self::IDENTITY;    tm.0[0][3] = x;  tm.0[1][3] = y;  tm.0[2][3] = z;  tm
+    }
+    pub fn rotate_x(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self([[1., 0., 0., 0.], [0., c, -s, 0.], [0., s, c, 0.], [0., 0., 0., 1.]])
+    }
+    pub fn rotate_y(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self([[c, 0., s, 0.], [0., 1., 0., 0.], [-s, 0., c, 0.], [0., 0., 0., 1.]])
+    }
+    pub fn rotate_z(angle: f64) -> Self {
+        let (s, c) = angle.sin_cos();
+        Self([[c, -s, 0., 0.], [s, c, 0., 0.], [0., 0., 1., 0.], [0., 0., 0., 1.]])
+    }
+
+    /// `other * self`, i.e. applies `other` after `self`
+    pub fn then(&self, other: &Self) -> Self {
+        Self(core::array::from_fn(|i| core::array::from_fn(|j|
+            (0..4).map(|k| other.0[i][k] * self.0[k][j]).sum())))
+    }
+
+    /// Inverse of a rigid/affine matrix (last row `0 0 0 1`), `None` if singular
+    pub fn invert(&self) -> Option<Self> {
+        let m = &self.0;
+        let cof = |r0: usize, r1: usize, c0: usize, c1: usize|
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0];
+        let inv = [[cof(1, 2, 1, 2), -cof(0, 2, 1, 2), cof(0, 1, 1, 2)],
+                   [-cof(1, 2, 0, 2), cof(0, 2, 0, 2), -cof(0, 1, 0, 2)],
+                   [cof(1, 2, 0, 1), -cof(0, 2, 0, 1), cof(0, 1, 0, 1)]];
+        let det = m[0][0] * inv[0][0] + m[0][1] * inv[1][0] + m[0][2] * inv[2][0];
+        if det.abs() < f64::EPSILON { return None }
+
+        let mut tm = Self::IDENTITY;
+        for (row, inv) in tm.0.iter_mut().zip(inv) {
+            for (v, inv) in row.iter_mut().zip(inv) { *v = inv / det; }
+            row[3] = -(0..3).map(|k| row[k] * m[k][3]).sum::<f64>();
+        }   Some(tm)
+    }
+
+    /// Flattens to a 2D affine `[a, b, c, d, e, f]`: orthographic without a camera,
+    /// otherwise the perspective projection linearized around layer point `at`.
+    pub(crate) fn project(&self, at: Vec2D, camera: Option<&Camera>) -> [f64; 6] {
+        let Some(camera) = camera else { let m = &self.0;
+            return [m[0][0], m[1][0], m[0][1], m[1][1], m[0][3], m[1][3]]
+        };
+        let m = self.then(&camera.view).0;
+        let (u, v) = (at.x as f64, at.y as f64);
+        let [x, y, z] = [m[0], m[1], m[2]].map(|row| row[0] * u + row[1] * v + row[3]);
+        if z <= f64::EPSILON { return [0.; 6] }   // behind the camera
+
+        let (zoom, z2) = (camera.zoom, z * z);
+        let (a, c) = (zoom * (m[0][0] * z - x * m[2][0]) / z2,
+                      zoom * (m[0][1] * z - x * m[2][1]) / z2);
+        let (b, d) = (zoom * (m[1][0] * z - y * m[2][0]) / z2,
+                      zoom * (m[1][1] * z - y * m[2][1]) / z2);
+        let (px, py) = (camera.center.x as f64 + zoom * x / z,
+                        camera.center.y as f64 + zoom * y / z);
+        [a, b, c, d, px - a * u - c * v, py - b * u - d * v]
+    }
+}
+
+/// Active camera of a composition
+pub(crate) struct Camera { pub view: Matrix3D, pub zoom: f64, pub center: Vec2D }
+impl Camera {
+    pub fn new(world: &Matrix3D, zoom: f32, center: Vec2D) -> Option<Self> {
+        Some(Self { view: world.invert()?, zoom: zoom as _, center })
     }
 }
 
@@ -202,7 +375,7 @@ impl Repeater {
         let rot = match &trfm.extra {
             TransRotation::Normal2D { rotation } =>
                 rotation.as_ref().map(|rdeg| rdeg.get_value(fnth).to_radians()),
-            TransRotation::Split3D(_) => unimplemented!(), //debug_assert!(ddd),
+            TransRotation::Split3D(rot) => Some(rot.rz.get_value(fnth).to_radians()),
         };
 
         let pos = match &trfm.position {
@@ -257,7 +430,7 @@ pub enum FSOpts {   Fill(FillRule),     // XXX: use SmallVec for dash?
 }
 
 impl GradientColors {
-    fn resolve(&self, fnth: f32, opacity: f32) -> Vec<(f32, RGBA)> {
+    pub(crate) fn resolve(&self, fnth: f32, opacity: f32) -> Vec<(f32, RGBA)> {
         let data = self.cl.get_value_cow(fnth);
         let color_count = self.cnt as usize;
         let color_len = color_count * 4;
@@ -404,6 +577,24 @@ impl FillStrokeGrad {
         repeater
     }
 
+    #[test] fn matrix_from_coefficients_defaults_to_composed_operations() {
+        use super::{MatrixConv, Vec2D};
+        struct Operations(kurbo::Affine);
+        impl MatrixConv for Operations {
+            fn identity() -> Self { Self(kurbo::Affine::IDENTITY) }
+            fn premul(&mut self, tm: &Self) { self.0.premul(&tm.0) }
+            fn rotate(&mut self, angle: f32) { self.0.rotate(angle) }
+            fn translate(&mut self, pos: Vec2D) { MatrixConv::translate(&mut self.0, pos) }
+            fn skew_x(&mut self, sk: f32) { self.0.skew_x(sk) }
+            fn scale(&mut self, sl: Vec2D) { MatrixConv::scale(&mut self.0, sl) }
+            fn coeffs(&self) -> [f64; 6] { self.0.as_coeffs() }
+        }
+
+        let coeffs = [2., 1., -0.5, 3., 4., 5.];
+        let matrix = Operations::from_coeffs(coeffs).coeffs();
+        assert!(matrix.iter().zip(coeffs).all(|(a, b)| (a - b).abs() < 1e-5), "{matrix:?}");
+    }
+
     #[test] fn repeater_ceil_copies_and_interpolates_opacity_by_copy_index() {
         let below = repeater(2.5, 1, 0., r#""so":{"k":20},"eo":{"k":80}"#);
         let matrices = below.get_matrix::<kurbo::Affine>(0.);
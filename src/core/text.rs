@@ -0,0 +1,582 @@
+//! Text layer layout: document keyframes, font resolution and glyph shaping.
+
+use std::{collections::HashMap, rc::Rc};
+use rustybuzz::{ttf_parser, UnicodeBuffer};
+use kurbo::{Affine, BezPath};
+use super::{helpers::{Vec2D, IntBool, ACCURACY_TOLERANCE}, render::{DrawItem, convert_shapes},
+    pathm::{PathBuilder, PathFactory, MeasuredPath},
+    style::{StyleConv, MatrixConv, TM2DwO, FSOpts},
+    schema::{AnimatedTextDoc, TextDocument, TextData, TextJustify, TextCaps,
+        TextAlignmentOptions, TextGrouping, TextFollowPath, Animation, CharacterData,
+        ShapePrecomp, Font, FontList, Mask, Value, FillRule, LineJoin, LineCap},
+    text_range::{self, GlyphStyle},
+};
+
+/// Source of font files for text layers, plug in a custom one to bundle or fetch fonts.
+pub trait FontProvider {
+    /// Font file data and face index (in a collection) for a font of `Animation::fonts`.
+    fn load_font(&mut self, font: &Font) -> Option<(Vec<u8>, u32)>;
+    /// Face used when a text document references a missing or unavailable font.
+    fn fallback(&mut self) -> Option<(Vec<u8>, u32)> { None }
+}
+
+/// Default provider, looks up installed fonts by family and style, and first the
+/// `fPath` file only if opted in, as animation files may come from untrusted sources.
+pub struct SystemFonts { fontdb: usvg::fontdb::Database, local_paths: bool }
+
+impl Default for SystemFonts {
+    fn default() -> Self {
+        let mut fontdb = usvg::fontdb::Database::new();
+        fontdb.load_system_fonts();     Self::with_database(fontdb)
+    }
+}
+
+impl SystemFonts {
+    pub fn with_database(fontdb: usvg::fontdb::Database) -> Self {
+        Self { fontdb, local_paths: false }
+    }
+    pub fn database_mut(&mut self) -> &mut usvg::fontdb::Database { &mut self.fontdb }
+
+    /// Allow reading the local font files at `fPath` of trusted animations.
+    pub fn with_local_paths(mut self, enable: bool) -> Self { self.local_paths = enable; self }
+
+    fn query(&self, families: &[usvg::fontdb::Family], font: Option<&Font>) ->
+        Option<(Vec<u8>, u32)> {
+        use usvg::fontdb::{Query, Style, Weight};
+        let style = font.map_or("", |font| font.style.as_str()).to_ascii_lowercase();
+        let weight = font.and_then(|font| font.weight.parse().ok()).unwrap_or_else(||
+            [("thin", 100), ("extralight", 200), ("light", 300), ("medium", 500),
+             ("semibold", 600), ("extrabold", 800), ("bold", 700), ("black", 900)]
+            .into_iter().find(|(name, _)| style.replace([' ', '-'], "").contains(name))
+            .map_or(400, |(_, weight)| weight));
+        let style = if style.contains("italic") { Style::Italic
+        } else if style.contains("oblique") { Style::Oblique } else { Style::Normal };
+
+        let id = self.fontdb.query(&Query { families, weight: Weight(weight),
+            style, ..Default::default() })?;
+        self.fontdb.with_face_data(id, |data, index| (data.to_vec(), index))
+    }
+}
+
+impl FontProvider for SystemFonts {
+    fn load_font(&mut self, font: &Font) -> Option<(Vec<u8>, u32)> {
+        use usvg::fontdb::Family;
+        if self.local_paths && !font.path.is_empty() {
+            if let Ok(data) = std::fs::read(&font.path) { return Some((data, 0)) }
+        }
+        self.query(&[Family::Name(&font.family), Family::Name(&font.name)], Some(font))
+    }
+    fn fallback(&mut self) -> Option<(Vec<u8>, u32)> {
+        self.query(&[usvg::fontdb::Family::SansSerif], None)
+    }
+}
+
+/// Loaded faces of a runtime, keyed by the font name used in text documents.
+#[derive(Default)] pub(crate) struct FontCache {
+    provider: Option<Box<dyn FontProvider>>,
+    faces: Vec<FontFace>, names: HashMap<String, Option<usize>>,
+    fallback: Option<Option<usize>>,
+}
+
+impl FontCache {
+    pub fn set_provider(&mut self, provider: Box<dyn FontProvider>) {
+        self.provider = Some(provider);     self.faces.clear();
+        self.names.clear();    self.fallback = None;
+    }
+
+    fn face(&mut self, fonts: &FontList, name: &str) -> Option<&mut FontFace> {
+        let provider = self.provider.get_or_insert_with(|| Box::new(SystemFonts::default()));
+        let index = match self.names.get(name) { Some(&index) => index,
+            None => {
+                let index = fonts.list.iter().find(|font| font.name == name)
+                    .and_then(|font| provider.load_font(font))
+                    .and_then(|(data, index)| FontFace::new(data, index))
+                    .map(|face| { self.faces.push(face); self.faces.len() - 1 });
+                self.names.insert(name.to_owned(), index);  index
+            }
+        }.or_else(|| *self.fallback.get_or_insert_with(|| provider.fallback()
+            .and_then(|(data, index)| FontFace::new(data, index))
+            .map(|face| { self.faces.push(face); self.faces.len() - 1 })))?;
+        self.faces.get_mut(index)
+    }
+}
+
+/// Produces positioned glyph outlines for a line of text at a given font size.
+pub(crate) trait Shaper {
+    fn shape(&mut self, line: &str, size: f32) -> Vec<ShapedGlyph>;
+    fn ascent(&self, size: f32) -> f32;
+}
+
+/// `cluster` is the char index in the shaped line, `shape` is drawn scaled by `scale`
+/// into text units (y down).
+pub(crate) struct ShapedGlyph { pub cluster: usize,
+    pub shape: GlyphShape, pub scale: f32, pub advance: f32, pub offset: Vec2D,
+}
+
+/// Glyph content, either a font outline or an entry of `Animation::chars` by index.
+pub(crate) enum GlyphShape { Outline(BezPath), Shapes(usize), Precomp(usize) }
+
+pub(crate) struct FontFace {
+    data: Vec<u8>, index: u32, upem: f32, ascender: f32,
+    outlines: HashMap<u16, BezPath>,
+}
+
+impl FontFace {
+    fn new(data: Vec<u8>, index: u32) -> Option<Self> {
+        let face = ttf_parser::Face::parse(&data, index).ok()?;
+        let (upem, ascender) = (face.units_per_em() as f32, face.ascender() as f32);
+        Some(Self { data, index, upem, ascender, outlines: HashMap::new() })
+    }
+}
+
+impl Shaper for FontFace {
+    fn shape(&mut self, line: &str, size: f32) -> Vec<ShapedGlyph> {
+        struct Outline(BezPath);
+        impl ttf_parser::OutlineBuilder for Outline {
+            fn move_to(&mut self, x: f32, y: f32) { self.0.move_to((x as f64, y as f64)) }
+            fn line_to(&mut self, x: f32, y: f32) { self.0.line_to((x as f64, y as f64)) }
+            fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+                self.0.quad_to((x1 as f64, y1 as f64), (x as f64, y as f64))
+            }
+            fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+                self.0.curve_to((x1 as f64, y1 as f64),
+                    (x2 as f64, y2 as f64), (x as f64, y as f64))
+            }
+            fn close(&mut self) { self.0.close_path() }
+        }
+
+        let Self { data, index, upem, outlines, .. } = self;
+        let Some(face) = rustybuzz::Face::from_slice(data, *index) else { return Vec::new() };
+        let mut buffer = UnicodeBuffer::new();  buffer.push_str(line);
+        let glyphs = rustybuzz::shape(&face, &[], buffer);
+
+        let scale = size / *upem;
+        let clusters: Vec<_> = line.char_indices().map(|(offset, _)| offset).collect();
+        glyphs.glyph_infos().iter().zip(glyphs.glyph_positions()).map(|(info, pos)| {
+            let id = info.glyph_id as u16;
+            let outline = outlines.entry(id).or_insert_with(|| {
+                let mut outline = Outline(BezPath::new());
+                face.outline_glyph(ttf_parser::GlyphId(id), &mut outline);  outline.0
+            });
+            ShapedGlyph { cluster: clusters.partition_point(|&offset|
+                    offset < info.cluster as usize),
+                shape: GlyphShape::Outline(Affine::scale_non_uniform(scale as _,
+                    -scale as f64) * &*outline), scale: 1., advance: pos.x_advance as f32 * scale,
+                offset: Vec2D { x: pos.x_offset as f32 * scale,
+                                y: -pos.y_offset as f32 * scale },
+            }
+        }).collect()
+    }
+
+    fn ascent(&self, size: f32) -> f32 { self.ascender * size / self.upem }
+}
+
+/// Glyphs embedded in `Animation::chars` for one font, no font file needed.
+pub(crate) struct CharShaper<'a> { chars: HashMap<char, (usize, &'a CharacterData)> }
+
+impl<'a> CharShaper<'a> {
+    /// Match glyphs by the family/style of the named font, or by the name as family.
+    pub fn new(chars: &'a [CharacterData], fonts: &FontList, name: &str) -> Option<Self> {
+        let font = fonts.list.iter().find(|font| font.name == name);
+        let (family, style) = font.map_or((name, None),
+            |font| (font.family.as_str(), Some(font.style.as_str())));
+        let chars: HashMap<_, _> = chars.iter().enumerate().filter(|(_, data)|
+            data.family == family && style.is_none_or(|style| data.style == style))
+            .filter_map(|(index, data)| {
+                let mut iter = data.ch.chars();
+                let ch = iter.next().filter(|_| iter.next().is_none())?;
+                Some((ch, (index, data)))
+            }).collect();
+        (!chars.is_empty()).then_some(Self { chars })
+    }
+}
+
+impl Shaper for CharShaper<'_> {
+    /// Glyph shapes are defined at a font size of 100, missing chars take no room
+    /// except for whitespace.
+    fn shape(&mut self, line: &str, size: f32) -> Vec<ShapedGlyph> {
+        let scale = size / 100.;
+        line.chars().enumerate().map(|(cluster, ch)| {
+            let (shape, advance) = match self.chars.get(&ch) {
+                Some(&(index, data)) => (match data.data {
+                    ShapePrecomp::Shapes(_) => GlyphShape::Shapes(index),
+                    ShapePrecomp::Precomp(_) => GlyphShape::Precomp(index),
+                }, data.width * scale),
+                None => (GlyphShape::Outline(BezPath::new()),
+                    if ch.is_whitespace() { size / 4. } else { 0. }),
+            };
+            ShapedGlyph { cluster, shape, scale, advance, offset: Vec2D { x: 0., y: 0. } }
+        }).collect()
+    }
+
+    fn ascent(&self, size: f32) -> f32 { size * 0.75 }   // AE default without font metrics
+}
+
+impl AnimatedTextDoc {
+    /// Text documents are always animated in discrete steps (hold keyframes).
+    pub fn get_value(&self, fnth: f32) -> Option<&TextDocument> {
+        self.k.iter().rev().find(|kf| kf.start <= fnth).or(self.k.first()).map(|kf| &kf.value)
+    }
+}
+
+/// A laid out glyph, `pos` is its origin on the baseline in text layer space.
+pub(crate) struct Glyph {
+    pub shape: GlyphShape, pub scale: f32, pub pos: Vec2D, pub advance: f32,
+    /// Indices of the glyph, of the glyph excluding spaces, of the word and of the line
+    /// it belongs to, as counted by range selectors
+    pub index: usize, pub visible: usize, pub word: usize, pub line: usize,
+    pub space: bool,
+}
+
+const SMALL_CAPS_SCALE: f32 = 0.7;
+
+/// Lay out a text document into lines of glyphs: caps, tracking, line height,
+/// baseline shift, box wrapping and justification, following lottie-web/AE conventions.
+pub(crate) fn layout_text(doc: &TextDocument, ascent: f32,
+    shaper: &mut impl Shaper) -> Vec<Glyph> {
+    let (size, caps) = (doc.fs, doc.ca.unwrap_or(TextCaps::Regular));
+    let tracking = doc.tr.unwrap_or(0.) * size / 1000.;
+    let line_height = doc.lh.unwrap_or(size * 1.2);
+    let boxed = doc.sz.filter(|sz| 0. < sz.x);
+
+    let mut lines = Vec::new();
+    for text in doc.ts.split(['\r', '\n', '\u{3}']) {
+        let (mut line, mut small) = (String::with_capacity(text.len()), Vec::new());
+        for ch in text.chars() {
+            if matches!(caps, TextCaps::Regular) {
+                line.push(ch);  small.push(false);   continue
+            }
+            for upper in ch.to_uppercase() {
+                line.push(upper);
+                small.push(matches!(caps, TextCaps::SmallCaps) && ch.is_lowercase());
+            }
+        }
+
+        let (mut glyphs, chars) = (Vec::new(), line.chars().collect::<Vec<_>>());
+
+        for glyph in shaper.shape(&line, size) {
+            let cluster = glyph.cluster.min(chars.len().saturating_sub(1));
+            let scale = if small.get(cluster) == Some(&true) { SMALL_CAPS_SCALE } else { 1. };
+            glyphs.push(Glyph { shape: glyph.shape,
+                scale: glyph.scale * scale, pos: glyph.offset * scale,
+                advance: glyph.advance * scale + tracking,
+                index: 0, visible: 0, word: 0, line: 0,
+                space: chars.get(cluster).is_some_and(|ch| ch.is_whitespace()),
+            });
+        }
+
+        if let Some(sz) = boxed { wrap_line(glyphs, sz.x, &mut lines)
+        } else { lines.push(glyphs) }
+    }
+
+    let (origin, width) = boxed.map_or((Vec2D { x: 0., y: 0. }, 0.), |sz| {
+        let ps = doc.ps.unwrap_or(Vec2D { x: 0., y: 0. });
+        (Vec2D { x: ps.x, y: ps.y + ascent }, sz.x)
+    });
+    let (count, shift) = (lines.len(), doc.ls.unwrap_or(0.));
+    let mut glyphs: Vec<_> = lines.into_iter().enumerate().flat_map(|(nth, mut glyphs)| {
+        let trailing = glyphs.iter().rev().take_while(|glyph| glyph.space).count();
+        let used = glyphs.len() - trailing;
+        let extent: f32 = glyphs[..used].iter().map(|glyph| glyph.advance).sum();
+        let gaps = glyphs[..used].iter().filter(|glyph| glyph.space).count();
+
+        let last = nth + 1 == count;
+        let (mut x, mut spread) = (0., 0.);
+        match doc.justify {
+            TextJustify::Left => (),
+            TextJustify::Right  => x = width - extent,
+            TextJustify::Center => x = (width - extent) / 2.,
+            justify => if boxed.is_some() && 0 < gaps && (!last ||
+                matches!(justify, TextJustify::JustifyWithLastLineFull)) {
+                spread = (width - extent) / gaps as f32;
+            } else if last { match justify {
+                TextJustify::JustifyWithLastLineRight  => x = width - extent,
+                TextJustify::JustifyWithLastLineCenter => x = (width - extent) / 2.,
+                _ => (),
+            } }
+        }
+
+        let y = origin.y + nth as f32 * line_height - shift;
+        x += origin.x;
+        for glyph in &mut glyphs {
+            glyph.pos = glyph.pos + Vec2D { x, y };     glyph.line = nth;
+            x += glyph.advance;  if glyph.space { x += spread }
+        }   glyphs
+    }).collect();
+
+    let (mut visible, mut word, mut line, mut gap) = (0, 0, 0, false);
+    for (index, glyph) in glyphs.iter_mut().enumerate() {
+        if glyph.line != line { line = glyph.line;  gap = true }
+        if !glyph.space && gap && 0 < index { word += 1 }
+        gap = glyph.space;  glyph.index = index;
+        glyph.visible = visible;    glyph.word = word;
+        if !glyph.space { visible += 1 }
+    }   glyphs
+}
+
+/// Greedy word wrapping of a shaped paragraph into the width of the text box.
+fn wrap_line(glyphs: Vec<Glyph>, width: f32, lines: &mut Vec<Vec<Glyph>>) {
+    let (mut line, mut extent, mut brk) = (Vec::new(), 0., None);
+    for glyph in glyphs {
+        if glyph.space { brk = Some(line.len() + 1) }   // trailing spaces may overhang
+        else if width < extent + glyph.advance && !line.is_empty() {
+            let rest = line.split_off(brk.take().unwrap_or(line.len()));
+            lines.push(line);   line = rest;
+            extent = line.iter().map(|glyph: &Glyph| glyph.advance).sum();
+        }   extent += glyph.advance;    line.push(glyph);
+    }   lines.push(line);
+}
+
+/// Glyph draw items of a text layer, and `CharacterPrecomp` glyphs to be rendered
+/// as nested compositions, by index of `Animation::chars` with their transform.
+pub(crate) type TextDraws<VGPath, VGPaint, TM2D> =
+    (Vec<DrawItem<VGPath, VGPaint, TM2D>>, Vec<(usize, TM2DwO<TM2D>)>);
+
+/// Convert a text layer at the given frame into draw items, one group per glyph.
+/// Glyphs embedded in `Animation::chars` take precedence over font files,
+/// `masks` of the layer provide the path of `TextFollowPath`.
+pub(crate) fn convert_text<VGPath: PathBuilder, VGPaint: StyleConv, TM2D: MatrixConv + Clone>(
+    text: &TextData, masks: &[Mask], animation: &Animation, cache: &mut FontCache,
+    fnth: f32) ->
+    TextDraws<VGPath, VGPaint, TM2D> {
+    let Some(doc) = text.doc.get_value(fnth) else { return Default::default() };
+    let ascent = animation.fonts.list.iter().find(|font| font.name == doc.ff)
+        .map(|font| font.ascent).filter(|&ascent| ascent != 0.)
+        .map(|ascent| ascent * doc.fs / 100.);
+    let glyphs = if let Some(mut shaper) =
+        CharShaper::new(&animation.chars, &animation.fonts, &doc.ff) {
+        layout_text(doc, ascent.unwrap_or_else(|| shaper.ascent(doc.fs)), &mut shaper)
+    } else if let Some(face) = cache.face(&animation.fonts, &doc.ff) {
+        layout_text(doc, ascent.unwrap_or_else(|| face.ascent(doc.fs)), face)
+    } else { return Default::default() };
+
+    let base = GlyphStyle::new(doc.fc, doc.sc, doc.sw);
+    let above = doc.of.unwrap_or(false);
+    let styles = text_styles(&base, above);
+    let animated = (!text.ranges.is_empty()).then(|| {
+        let styles = text_range::animate_glyphs(&text.ranges, &glyphs, &base, doc.fs, fnth);
+        let anchors = anchor_points(&glyphs, &text.align, doc.fs, fnth);
+        let shifts = tracking_shifts(&glyphs, &styles, doc.justify);
+        styles.into_iter().zip(anchors).zip(shifts)
+    });
+    let mut animated = animated.into_iter().flatten();
+    let along = TextPath::new(&text.follow, masks, &glyphs, doc.justify, fnth);
+
+    let (mut draws, mut precomps) = (Vec::new(), Vec::new());
+    for (nth, glyph) in glyphs.into_iter().enumerate() {
+        let mut trfm = TM2D::identity();
+        trfm.scale(Vec2D { x: glyph.scale, y: glyph.scale });
+        let (mut opacity, mut shift) = (1., 0.);
+        let styles = if let Some(((style, anchor), tracking)) = animated.next() {
+            trfm.translate(-(anchor + style.anchor));   trfm.scale(style.scale);
+            if style.skew != 0. {
+                let axis = style.skew_axis.to_radians();
+                trfm.rotate(-axis);
+                trfm.skew_x(-style.skew.clamp(-85., 85.).to_radians());
+                trfm.rotate( axis);
+            }   trfm.rotate(style.rotation.to_radians());
+            trfm.translate(anchor + style.position);
+            (opacity, shift) = (style.opacity, tracking);  text_styles(&style, above)
+        } else { styles.clone() };
+
+        if let Some(along) = &along {
+            let Some((point, tangent)) =
+                along.path.sample(along.centers[nth] + shift as f64) else { continue };
+            trfm.translate(Vec2D { x: -glyph.advance / 2., y: glyph.pos.y });
+            if along.perpendicular { trfm.rotate(tangent.atan2() as _) }
+            trfm.translate(Vec2D { x: point.x as _, y: point.y as _ });
+        } else { trfm.translate(glyph.pos + Vec2D { x: shift, y: 0. }) }
+        let mut group = match glyph.shape {
+            GlyphShape::Outline(outline) if !outline.elements().is_empty() =>
+                vec![DrawItem::Shape(VGPath::from_kurbo(outline))],
+            GlyphShape::Shapes(index) => {
+                let ShapePrecomp::Shapes(data) =
+                    &animation.chars[index].data else { unreachable!() };
+                let (grp, ctm) = convert_shapes(&data.shapes, fnth, IntBool::default());
+                vec![DrawItem::Group(grp, vec![ctm])]
+            }
+            GlyphShape::Precomp(index) => {
+                precomps.push((index, TM2DwO(trfm, opacity)));  continue
+            }
+            GlyphShape::Outline(_) => continue,
+        };
+        group.extend(styles.iter().map(|style| DrawItem::Style(Rc::clone(style))));
+        draws.push(DrawItem::Group(group, vec![TM2DwO(trfm, opacity)]));
+    }   (draws, precomps)
+}
+
+/// Glyphs placed along a layer mask path, by the arc length of their centers.
+struct TextPath { path: MeasuredPath, centers: Vec<f64>, perpendicular: bool }
+
+impl TextPath {
+    /// Lines start from the first margin, or end at the last margin when right justified;
+    /// forced alignment spreads the glyphs of each line between both margins.
+    fn new(follow: &TextFollowPath, masks: &[Mask], glyphs: &[Glyph],
+        justify: TextJustify, fnth: f32) -> Option<Self> {
+        let mask = masks.get(follow.mask? as usize)?;
+        let value = |value: &Option<Value>, default: f32|
+            value.as_ref().map_or(default, |value| value.get_value(fnth));
+        let mut path: BezPath = mask.shape.to_path(fnth);
+        if value(&follow.reverse, 0.) != 0. { path = path.reverse_subpaths() }
+        let path = MeasuredPath::new(path, ACCURACY_TOLERANCE);
+
+        let (length, first, last) = (path.length as f32,
+            value(&follow.fm, 0.), value(&follow.lm, 0.));
+        let force = value(&follow.align, 0.) != 0.;
+        let mut centers = Vec::with_capacity(glyphs.len());
+        for line in glyphs.chunk_by(|a, b| a.line == b.line) {
+            let (head, tail) = (&line[0], &line[line.len() - 1]);
+            let extent = tail.pos.x + tail.advance - head.pos.x;
+            let (offset, spread) = if force && 1 < line.len() {
+                (first, (length - first - last - extent) / (line.len() - 1) as f32)
+            } else { (match justify {
+                TextJustify::Right  => length - last - extent,
+                TextJustify::Center => (length + first - last - extent) / 2.,
+                _ => first,
+            }, 0.) };
+            centers.extend(line.iter().enumerate().map(|(index, glyph)|
+                (offset + glyph.pos.x - head.pos.x + glyph.advance / 2. +
+                    spread * index as f32) as f64));
+        }
+        Some(Self { path, centers, perpendicular: value(&follow.perp, 1.) != 0. })
+    }
+}
+
+/// Anchor points of animated glyphs relative to their origin: the center of the glyph,
+/// word, line or whole text on the baseline, moved by the grouping alignment.
+fn anchor_points(glyphs: &[Glyph], align: &TextAlignmentOptions,
+    size: f32, fnth: f32) -> Vec<Vec2D> {
+    let offset = align.align.as_ref().map_or(Vec::new(), |align| align.get_value(fnth));
+    let offset = Vec2D { x: offset.first().copied().unwrap_or(0.),
+                         y: offset.get(1).copied().unwrap_or(0.) };
+    let group = align.group.unwrap_or(TextGrouping::Characters);
+    let key = |glyph: &Glyph| match group {
+        TextGrouping::Characters => glyph.index,
+        TextGrouping::Word => glyph.word,
+        TextGrouping::Line => glyph.line,
+        TextGrouping::All  => 0,
+    };
+
+    // horizontal extents of each group, where spaces don't extend words
+    let mut spans = HashMap::<usize, (f32, f32)>::new();
+    let word = matches!(group, TextGrouping::Word);
+    for glyph in glyphs.iter().filter(|glyph| !(word && glyph.space)) {
+        let (lo, hi) = spans.entry(key(glyph)).or_insert((f32::MAX, f32::MIN));
+        *lo = lo.min(glyph.pos.x);  *hi = hi.max(glyph.pos.x + glyph.advance);
+    }
+    glyphs.iter().map(|glyph| {
+        let (lo, hi) = spans.get(&key(glyph)).copied()
+            .unwrap_or((glyph.pos.x, glyph.pos.x));
+        Vec2D { x: (lo + hi) / 2. - glyph.pos.x + offset.x * (hi - lo) / 200.,
+                y: offset.y * size / 100. }
+    }).collect()
+}
+
+/// Horizontal shifts from animated tracking, accumulated along each line and
+/// balanced for right and center justification.
+fn tracking_shifts(glyphs: &[Glyph], styles: &[GlyphStyle], justify: TextJustify) -> Vec<f32> {
+    let balance = match justify {
+        TextJustify::Right  => 1.,  TextJustify::Center => 0.5,  _ => 0.,
+    };
+    let (mut shifts, mut start) = (Vec::with_capacity(glyphs.len()), 0);
+    for line in glyphs.chunk_by(|a, b| a.line == b.line) {
+        let styles = &styles[start..start + line.len()];
+        let mut sum = -styles.iter().map(|style| style.tracking).sum::<f32>() * balance;
+        for style in styles { shifts.push(sum);   sum += style.tracking }
+        start += line.len();
+    }   shifts
+}
+
+/// Fill and stroke of a glyph style, ordered for `render_shapes` (last drawn first).
+fn text_styles<VGPaint: StyleConv>(style: &GlyphStyle,
+    above: bool) -> Vec<Rc<(VGPaint, FSOpts)>> {
+    let mut fill = VGPaint::solid_color(style.fill);
+    let opts = FSOpts::Fill(FillRule::NonZero);
+    fill.configure(&opts);
+    let mut styles = vec![Rc::new((fill, opts))];
+
+    if let Some(color) = style.stroke.filter(|_| 0. < style.stroke_width) {
+        let opts = FSOpts::Stroke { width: style.stroke_width, limit: 4.,
+            join: LineJoin::Miter, cap: LineCap::Butt, dash: (0., Vec::new()) };
+        let mut stroke = VGPaint::solid_color(color);
+        stroke.configure(&opts);
+        let stroke = Rc::new((stroke, opts));
+        if above { styles.insert(0, stroke) } else { styles.push(stroke) }
+    }   styles
+}
+
+#[cfg(test)] mod tests { use super::*;
+    /// Monospaced box glyphs, half an em wide, for layout checks without font files.
+    struct Mono;
+    impl Shaper for Mono {
+        fn shape(&mut self, line: &str, size: f32) -> Vec<ShapedGlyph> {
+            line.chars().enumerate().map(|(cluster, ch)| {
+                let mut outline = BezPath::new();
+                if !ch.is_whitespace() {
+                    outline.move_to((0., 0.));  outline.line_to((0., -size as f64));
+                    outline.line_to((size as f64 / 2., 0.));    outline.close_path();
+                }
+                ShapedGlyph { cluster, shape: GlyphShape::Outline(outline), scale: 1.,
+                    advance: size / 2., offset: Vec2D { x: 0., y: 0. } }
+            }).collect()
+        }
+        fn ascent(&self, size: f32) -> f32 { size * 0.8 }
+    }
+
+    fn document(json: &str) -> TextDocument {
+        serde_json::from_str(&format!(r#"{{"f":"Mono","fc":[0,0,0],"s":10,{json}}}"#)).unwrap()
+    }
+
+    #[test] fn text_document_keyframes_hold_until_the_next_one() {
+        let doc: AnimatedTextDoc = serde_json::from_str(r#"{"k":[
+            {"t":0,"s":{"t":"one","f":"Mono","fc":[0,0,0]}},
+            {"t":5,"s":{"t":"two","f":"Mono","fc":[0,0,0]}}
+        ]}"#).unwrap();
+        assert_eq!(doc.get_value(-1.).unwrap().ts, "one");
+        assert_eq!(doc.get_value(4.9).unwrap().ts, "one");
+        assert_eq!(doc.get_value(5.).unwrap().ts, "two");
+    }
+
+    #[test] fn layout_breaks_lines_and_justifies_point_text() {
+        let glyphs = layout_text(&document(r#""t":"ab\rcdef","j":2,"lh":20"#), 8., &mut Mono);
+        let pos: Vec<_> = glyphs.iter().map(|glyph| (glyph.pos.x, glyph.pos.y)).collect();
+        assert_eq!(pos, [(-5., 0.), (0., 0.), (-10., 20.), (-5., 20.), (0., 20.), (5., 20.)]);
+        assert_eq!(glyphs.iter().map(|glyph| glyph.line).collect::<Vec<_>>(), [0, 0, 1, 1, 1, 1]);
+    }
+
+    #[test] fn layout_applies_tracking_caps_and_baseline_shift() {
+        let glyphs = layout_text(&document(r#""t":"aB c","tr":100,"ca":2,"ls":3"#), 8., &mut Mono);
+        let pos: Vec<_> = glyphs.iter().map(|glyph| glyph.pos.x).collect();
+        assert_eq!(pos, [0., 4.5, 10.5, 16.5]);
+        assert!(glyphs.iter().all(|glyph| glyph.pos.y == -3.));
+    }
+
+    #[test] fn layout_counts_words_lines_and_visible_glyphs_for_selectors() {
+        let glyphs = layout_text(&document(r#""t":"ab  c\rde""#), 8., &mut Mono);
+        let units: Vec<_> = glyphs.iter().map(|glyph|
+            (glyph.index, glyph.visible, glyph.word, glyph.line)).collect();
+        assert_eq!(units, [(0, 0, 0, 0), (1, 1, 0, 0), (2, 2, 0, 0), (3, 2, 0, 0),
+            (4, 2, 1, 0), (5, 3, 2, 1), (6, 4, 2, 1)]);
+    }
+
+    #[test] fn box_text_wraps_words_and_spreads_full_justification() {
+        let glyphs = layout_text(&document(
+            r#""t":"ab cd ef","sz":[32,100],"ps":[1,2],"j":3"#), 8., &mut Mono);
+        let pos: Vec<_> = glyphs.iter().map(|glyph| (glyph.pos.x, glyph.pos.y)).collect();
+        assert_eq!(pos, [(1., 10.), (6., 10.), (11., 10.), (23., 10.), (28., 10.),
+            (33., 10.), (1., 22.), (6., 22.)]);
+    }
+
+    #[test] fn system_fonts_read_font_paths_only_when_opted_in() {
+        let path = std::env::temp_dir().join("inlottie-font-path.ttf");
+        std::fs::write(&path, b"font").unwrap();
+        let font: Font = serde_json::from_value(serde_json::json!({ "fName": "None",
+            "fFamily": "None", "fStyle": "Regular", "fPath": path })).unwrap();
+
+        let mut fonts = SystemFonts::with_database(Default::default());
+        assert!(fonts.load_font(&font).is_none());
+        fonts = fonts.with_local_paths(true);
+        assert_eq!(fonts.load_font(&font), Some((b"font".to_vec(), 0)));
+        std::fs::remove_file(path).unwrap();
+    }
+}
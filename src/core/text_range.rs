@@ -0,0 +1,265 @@
+//! Text animators: range selector amounts and per-glyph style deltas.
+
+use super::{helpers::{Vec2D, RGBA, math::{CubicBezierEasing, Tween}},
+    schema::{TextRange, TextRangeSelector, TextStyle, TextBased, TextShape,
+        TextRangeUnits, Translation, TransRotation},
+    text::Glyph,
+};
+
+/// Animated properties of one glyph, transform deltas apply around its anchor point.
+#[derive(Clone)] pub(crate) struct GlyphStyle {
+    pub anchor: Vec2D, pub position: Vec2D, pub scale: Vec2D,
+    /// Rotation, skew and skew axis in degrees
+    pub rotation: f32, pub skew: f32, pub skew_axis: f32,
+    pub opacity: f32, pub tracking: f32,
+    pub fill: RGBA, pub stroke: Option<RGBA>, pub stroke_width: f32,
+}
+
+impl GlyphStyle {
+    pub fn new(fill: RGBA, stroke: Option<RGBA>, stroke_width: f32) -> Self {
+        let zero = Vec2D { x: 0., y: 0. };
+        Self { anchor: zero, position: zero, scale: Vec2D { x: 1., y: 1. },
+            rotation: 0., skew: 0., skew_axis: 0., opacity: 1., tracking: 0.,
+            fill, stroke, stroke_width,
+        }
+    }
+
+    /// Accumulate the style of an animator weighted by the selector amount,
+    /// `size` is the font size for the tracking (in 1/1000 em) and blur is not supported.
+    fn apply(&mut self, style: &TextStyle, amount: f32, size: f32, fnth: f32) {
+        let trfm = &style.trfm;
+        if let Some(anchor) = &trfm.anchor {
+            self.anchor = self.anchor + anchor.get_value(fnth) * amount;
+        }
+        match &trfm.position {
+            Some(Translation::Normal(pos)) =>
+                self.position = self.position + pos.get_value(fnth) * amount,
+            Some(Translation::Split(sv)) => self.position = self.position +
+                Vec2D { x: sv.x.get_value(fnth), y: sv.y.get_value(fnth) } * amount,
+            None => (),
+        }
+        if let Some(scale) = &trfm.scale {
+            let scale = scale.get_value(fnth) / 100.;
+            self.scale.x *= 1. + (scale.x - 1.) * amount;
+            self.scale.y *= 1. + (scale.y - 1.) * amount;
+        }
+        if let TransRotation::Normal2D { rotation: Some(rdeg) } = &trfm.extra {
+            self.rotation += rdeg.get_value(fnth) * amount;
+        }
+        if let Some(skew) = &trfm.skew {
+            self.skew += skew.get_value(fnth) * amount;
+            if let Some(axis) = &trfm.skew_axis { self.skew_axis = axis.get_value(fnth) }
+        }
+        if let Some(opacity) = &trfm.opacity {
+            self.opacity = self.opacity.lerp(&(opacity.get_value(fnth) / 100.), amount);
+        }
+        if let Some(spacing) = &style.spacing {
+            self.tracking += spacing.get_value(fnth) * size / 1000. * amount;
+        }
+
+        let hsb = |value: &Option<_>| value.as_ref()
+            .map_or(0., |value: &super::schema::Value| value.get_value(fnth) * amount);
+        self.fill = animate_color(self.fill, style.fc.as_ref().map(|fc| fc.get_value(fnth)),
+            [hsb(&style.fh), hsb(&style.fs), hsb(&style.fb)],
+            style.fo.as_ref().map(|fo| fo.get_value(fnth) / 100.), amount);
+        if let Some(stroke) = &mut self.stroke {
+            *stroke = animate_color(*stroke, style.sc.as_ref().map(|sc| sc.get_value(fnth)),
+                [hsb(&style.sh), hsb(&style.ss), hsb(&style.sb)],
+                style.so.as_ref().map(|so| so.get_value(fnth) / 100.), amount);
+        }
+        if let Some(width) = &style.sw {
+            self.stroke_width = self.stroke_width.lerp(&width.get_value(fnth), amount);
+        }
+    }
+}
+
+/// Blend toward the target color and alpha, then offset hue (in degrees),
+/// saturation and brightness (in percent) already weighted by the amount.
+fn animate_color(mut color: RGBA, target: Option<RGBA>,
+    [hue, saturation, brightness]: [f32; 3], alpha: Option<f32>, amount: f32) -> RGBA {
+    if let Some(target) = target {
+        color = color.lerp(&RGBA { a: color.a, ..target }, amount);
+    }
+    if hue != 0. || saturation != 0. || brightness != 0. {
+        let (r, g, b) = (color.r as f32 / 255., color.g as f32 / 255., color.b as f32 / 255.);
+        let (max, min) = (r.max(g).max(b), r.min(g).min(b));
+        let delta = max - min;
+        let h = if delta == 0. { 0.
+        } else if max == r { 60. * ((g - b) / delta).rem_euclid(6.)
+        } else if max == g { 60. * ((b - r) / delta + 2.)
+        } else { 60. * ((r - g) / delta + 4.) };
+        let s = if max == 0. { 0. } else { delta / max };
+
+        let h = (h + hue).rem_euclid(360.);
+        let s = (s + saturation / 100.).clamp(0., 1.);
+        let v = (max + brightness / 100.).clamp(0., 1.);
+        let channel = |n: f32| {
+            let k = (n + h / 60.) % 6.;
+            v - v * s * k.min(4. - k).clamp(0., 1.)
+        };
+        color = RGBA::new_f32(channel(5.), channel(3.), channel(1.), color.a as f32 / 255.);
+    }
+    if let Some(alpha) = alpha {
+        color.a = ((color.a as f32).lerp(&(alpha * 255.), amount).clamp(0., 255.) + 0.5) as _;
+    }   color
+}
+
+/// Evaluate the text animators for the laid out glyphs,
+/// `base` is the style of the text document.
+pub(crate) fn animate_glyphs(ranges: &[TextRange], glyphs: &[Glyph],
+    base: &GlyphStyle, size: f32, fnth: f32) -> Vec<GlyphStyle> {
+    let mut styles = vec![base.clone(); glyphs.len()];
+    for (nth, range) in ranges.iter().enumerate() {
+        let Some(style) = &range.style else { continue };
+        let Some(select) = &range.select else {
+            styles.iter_mut().for_each(|glyph| glyph.apply(style, 1., size, fnth));
+            continue
+        };
+
+        let units: Vec<_> = glyphs.iter().map(|glyph| match select.based {
+            TextBased::Characters => glyph.index,
+            TextBased::ExcludingSpaces => glyph.visible,
+            TextBased::Words => glyph.word,
+            TextBased::Lines => glyph.line,
+        }).collect();
+        let total = glyphs.iter().zip(&units).filter(|(glyph, _)|
+            !matches!(select.based, TextBased::ExcludingSpaces) || !glyph.space)
+            .map(|(_, &unit)| unit + 1).max().unwrap_or(0);
+        let order = if select.rn.is_some_and(|rn| rn.as_bool()) {
+            shuffle(total, nth as u32)
+        } else { (0..total).collect() };
+
+        let selector = Selector::new(select, total, fnth);
+        for ((glyph, unit), styled) in glyphs.iter().zip(units).zip(&mut styles) {
+            if glyph.space && matches!(select.based, TextBased::ExcludingSpaces) { continue }
+            let Some(&unit) = order.get(unit) else { continue };
+            let amount = selector.amount(unit as f32);
+            if amount != 0. { styled.apply(style, amount, size, fnth) }
+        }
+    }   styles
+}
+
+/// Deterministic permutation of the selector units for `rn`, stable across frames.
+fn shuffle(count: usize, seed: u32) -> Vec<usize> {
+    let mut order: Vec<_> = (0..count).collect();
+    let mut state = 0x9E37_79B9u32 ^ seed.wrapping_mul(0x85EB_CA6B) ^ count as u32;
+    for index in (1..count).rev() {     // xorshift32 driven Fisher-Yates
+        state ^= state << 13;   state ^= state >> 17;   state ^= state << 5;
+        order.swap(index, state as usize % (index + 1));
+    }   order
+}
+
+/// Range selector resolved at a frame, following lottie-web's `TextSelectorProperty`.
+struct Selector { start: f32, end: f32, shape: TextShape,
+    smoothness: f32, amount: f32, easing: Option<CubicBezierEasing>,
+}
+
+impl Selector {
+    fn new(select: &TextRangeSelector, total: usize, fnth: f32) -> Self {
+        let value = |value: &Option<super::schema::Value>, default: f32|
+            value.as_ref().map_or(default, |value| value.get_value(fnth));
+        let index = matches!(select.unit, Some(TextRangeUnits::Index));
+        let divisor = if index { 1. } else { 100. / total.max(1) as f32 };
+
+        let offset = value(&select.offset, 0.) / divisor;
+        let start = value(&select.start, 0.) / divisor + offset;
+        let end = value(&select.end, if index { total as f32 } else { 100. }) / divisor + offset;
+
+        let (ne, xe) = (value(&select.ne, 0.) / 100., value(&select.xe, 0.) / 100.);
+        let easing = (ne != 0. || xe != 0.).then(|| {
+            let (x1, y1) = if 0. < ne { (ne, 0.) } else { (0., -ne) };
+            let (x2, y2) = if 0. < xe { (1. - xe, 1.) } else { (1., 1. + xe) };
+            CubicBezierEasing::new((x1, y1), (x2, y2))
+        });
+
+        Self { start: start.min(end), end: start.max(end), shape: select.sh,
+            smoothness: value(&select.sm, 100.) / 100.,
+            amount: select.max_amount.get_value(fnth) / 100., easing,
+        }
+    }
+
+    /// Selected amount of the unit at `index`, scaled by the max amount.
+    fn amount(&self, index: f32) -> f32 {
+        let (s, e) = (self.start, self.end);
+        let ramp = || ((0.5 + index - s) / (e - s)).clamp(0., 1.);
+        let mut mult = match self.shape {
+            TextShape::Square => {
+                let mut mult = if index < s.floor() { 0.
+                } else if index < s { (e.min(1.) - (s - index)).clamp(0., 1.)
+                } else { (e - index).clamp(0., 1.) };
+                if self.smoothness < 1. {   // only the square shape has smoothness
+                    let smoothness = self.smoothness.max(1e-8);
+                    let threshold = 0.5 - smoothness * 0.5;
+                    mult = if mult < threshold { 0.
+                    } else { ((mult - threshold) / smoothness).min(1.) };
+                }   mult
+            }
+            _ if e == s => match self.shape {
+                TextShape::RampUp   if e <= index => 1.,
+                TextShape::RampDown if index <  e => 1.,
+                _ => 0.,
+            },
+            TextShape::RampUp   => ramp(),
+            TextShape::RampDown => 1. - ramp(),
+            TextShape::Triangle => {
+                let mult = ramp();
+                if mult < 0.5 { mult * 2. } else { 1. - 2. * (mult - 0.5) }
+            }
+            TextShape::Round => {
+                let (a, x) = ((e - s) / 2., (index + 0.5 - s).clamp(0., e - s));
+                let x = x - a;  (1. - x * x / (a * a)).max(0.).sqrt()
+            }
+            TextShape::Smooth => {
+                let x = (index + 0.5 - s).clamp(0., e - s);
+                (1. + (core::f32::consts::PI * (1. + 2. * x / (e - s))).cos()) / 2.
+            }
+        };
+        if let Some(easing) = &self.easing { mult = easing.get_y(mult) }
+        mult * self.amount
+    }
+}
+
+#[cfg(test)] mod tests { use super::*;
+    fn selector(json: &str, total: usize) -> Selector {
+        let select: TextRangeSelector = serde_json::from_str(&format!(
+            r#"{{"t":0,"b":1,{json}}}"#)).unwrap();
+        Selector::new(&select, total, 0.)
+    }
+
+    #[test] fn square_selector_covers_partial_units_by_percent_or_index() {
+        let select = selector(r#""sh":1,"a":{"k":100},"s":{"k":0},"e":{"k":50}"#, 4);
+        let amounts: Vec<_> = (0..4).map(|index| select.amount(index as _)).collect();
+        assert_eq!(amounts, [1., 1., 0., 0.]);
+
+        let select = selector(
+            r#""sh":1,"a":{"k":100},"r":2,"s":{"k":0.5},"e":{"k":2.5},"o":{"k":1}"#, 5);
+        let amounts: Vec<_> = (0..5).map(|index| select.amount(index as _)).collect();
+        assert_eq!(amounts, [0., 0.5, 1., 0.5, 0.]);
+    }
+
+    #[test] fn ramp_and_triangle_shapes_interpolate_across_the_range() {
+        let select = selector(r#""sh":2,"a":{"k":100},"s":{"k":0},"e":{"k":100}"#, 4);
+        let amounts: Vec<_> = (0..4).map(|index| select.amount(index as _)).collect();
+        assert_eq!(amounts, [0.125, 0.375, 0.625, 0.875]);
+
+        let select = selector(r#""sh":4,"s":{"k":0},"e":{"k":100},"a":{"k":50}"#, 4);
+        let amounts: Vec<_> = (0..4).map(|index| select.amount(index as _)).collect();
+        assert_eq!(amounts, [0.125, 0.375, 0.375, 0.125]);
+    }
+
+    #[test] fn random_order_is_a_stable_permutation() {
+        let mut order = shuffle(10, 1);
+        assert_eq!(order, shuffle(10, 1));
+        assert_ne!(order, (0..10).collect::<Vec<_>>());
+        order.sort();   assert_eq!(order, (0..10).collect::<Vec<_>>());
+    }
+
+    #[test] fn color_deltas_blend_and_offset_hue() {
+        let red = RGBA::new_u8(255, 0, 0, 255);
+        let color = animate_color(red, Some(RGBA::new_u8(0, 0, 255, 255)),
+            [0.; 3], Some(0.), 0.5);
+        assert_eq!((color.r, color.g, color.b, color.a), (127, 0, 127, 128));
+        let color = animate_color(red, None, [120., 0., 0.], None, 1.);
+        assert_eq!((color.r, color.g, color.b, color.a), (0, 255, 0, 255));
+    }
+}
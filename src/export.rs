@@ -0,0 +1,302 @@
+/****************************************************************
+ * $ID: export.rs  	Sun 18 Oct 2026 16:05:12+0800               *
+ *                                                              *
+ * Maintainer: 范美辉 (MeiHui FAN) <mhfan@ustc.edu>              *
+ * Copyright (c) 2026 M.H.Fan, All rights reserved.             *
+ ****************************************************************/
+
+//! Encodes a whole Lottie animation rendered by tiny-skia into an animated
+//! GIF, APNG or WebP file, for previews outside of any Lottie player.
+
+use std::{error::Error as StdError, fmt, io::{self, Write}};
+use crate::{backend::tiny_skia::{SkiaContext, SkiaError},
+    core::{helpers::{RGBA, fit_viewport}, render::LottieRuntime}};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)] pub enum Format { Gif, Apng, WebP }
+
+impl Format {
+    /// Guess the format by a file extension, where APNG takes `.png` and `.apng`.
+    pub fn from_extension(ext: &str) -> Option<Self> {
+        match ext.to_ascii_lowercase().as_str() {
+            "gif" => Some(Self::Gif), "png" | "apng" => Some(Self::Apng),
+            "webp" => Some(Self::WebP), _ => None,
+        }
+    }
+}
+
+#[derive(Clone)] pub struct ExportOptions {
+    /// Output size with the composition fitted and centered, or its own size by default.
+    pub size: Option<(u32, u32)>,
+    /// Fixed scale of the composition instead of fitting it into `size`.
+    pub scale: Option<f32>,
+    /// Opaque backgrounds suit GIF best, which has only 1-bit transparency.
+    pub background: RGBA,
+    /// Keep every `decimation`-th frame, each shown that much longer.
+    pub decimation: u32,
+    /// Times to play the animation, zero for forever.
+    pub loop_count: u16,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self { size: None, scale: None, background: RGBA::new_u8(0, 0, 0, 0),
+            decimation: 1, loop_count: 0 }
+    }
+}
+
+#[derive(Debug)] pub enum ExportError {
+    Io(io::Error), Render(SkiaError), Encode(String), TooLarge((u32, u32)),
+}
+
+impl fmt::Display for ExportError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { match self {
+        Self::Io(error) => error.fmt(f),
+        Self::Render(error) => error.fmt(f),
+        Self::Encode(error) => write!(f, "failed to encode animation: {error}"),
+        Self::TooLarge((w, h)) => write!(f, "{w}x{h} exceeds the format's size limit"),
+    } }
+}
+
+impl StdError for ExportError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self { Self::Io(error) => Some(error), Self::Render(error) => Some(error), _ => None }
+    }
+}
+
+impl From<io::Error> for ExportError { fn from(error: io::Error) -> Self { Self::Io(error) } }
+impl From<SkiaError> for ExportError { fn from(error: SkiaError) -> Self { Self::Render(error) } }
+impl From<gif::EncodingError> for ExportError {
+    fn from(error: gif::EncodingError) -> Self { Self::Encode(error.to_string()) }
+}
+impl From<png::EncodingError> for ExportError {
+    fn from(error: png::EncodingError) -> Self { Self::Encode(error.to_string()) }
+}
+impl From<image::ImageError> for ExportError {
+    fn from(error: image::ImageError) -> Self { Self::Encode(error.to_string()) }
+}
+
+/// Renders frames `ip..op` of the animation at its `fr` and writes them to `writer`,
+/// returning the number of frames encoded. Display durations are rounded from the
+/// composition time of each frame, so the total one doesn't drift from `(op - ip) / fr`.
+/// The view transform of `runtime` is left fitted to the output size.
+pub fn export_animation<W: Write>(runtime: &mut LottieRuntime, writer: W,
+    format: Format, options: &ExportOptions) -> Result<usize, ExportError> {
+    let animation = runtime.animation();
+    let (ip, op, fr) = (animation.ip, animation.op, animation.fr.max(f32::EPSILON));
+    let (size, view) = fit_viewport((animation.w as _, animation.h as _),
+        options.size, options.scale);
+    runtime.set_view_transform(view);
+
+    let step = options.decimation.max(1) as f32;
+    let count = ((op - ip) / step).ceil().max(1.) as usize;
+    let millis = |frame: f32| ((frame.min(op) - ip) / fr * 1000.).round() as u32;
+
+    let mut context = SkiaContext::new(size.0, size.1)?;
+    let mut sink = FrameSink::new(writer, format, size, count, options)?;
+    for index in 0..count {
+        let frame = ip + index as f32 * step;
+        runtime.render_frame(&mut context, frame, Some(options.background))?;
+        let delay = millis(frame + step).saturating_sub(millis(frame)).max(1);
+        sink.push(straight_rgba(context.pixmap()), delay)?;
+    }   sink.finish()?;     Ok(count)
+}
+
+/// Un-premultiplies the pixmap into 8-bit RGBA.
+fn straight_rgba(pixmap: &tiny_skia::Pixmap) -> Vec<u8> {
+    pixmap.pixels().iter().flat_map(|px| {
+        let px = px.demultiply();   [px.red(), px.green(), px.blue(), px.alpha()]
+    }).collect()
+}
+
+enum FrameSink<W: Write> {
+    Gif(gif::Encoder<W>, (u16, u16), u32),  // with the elapsed time in milliseconds
+    Apng(png::Writer<W>),
+    WebP(W, WebPMuxer),
+}
+
+impl<W: Write> FrameSink<W> {
+    fn new(writer: W, format: Format, size: (u32, u32), count: usize,
+        options: &ExportOptions) -> Result<Self, ExportError> {
+        Ok(match format {
+            Format::Gif => {
+                let (Ok(w), Ok(h)) = (u16::try_from(size.0), u16::try_from(size.1))
+                    else { return Err(ExportError::TooLarge(size)) };
+                let mut encoder = gif::Encoder::new(writer, w, h, &[])?;
+                // a Netscape loop count repeats after the first play, and is absent for once
+                match options.loop_count {
+                    0 => encoder.set_repeat(gif::Repeat::Infinite)?,
+                    1 => (), n => encoder.set_repeat(gif::Repeat::Finite(n - 1))?,
+                }   Self::Gif(encoder, (w, h), 0)
+            }
+            Format::Apng => {
+                let mut encoder = png::Encoder::new(writer, size.0, size.1);
+                encoder.set_color(png::ColorType::Rgba);
+                encoder.set_depth(png::BitDepth::Eight);
+                encoder.set_animated(count as _, options.loop_count as _)?;
+                Self::Apng(encoder.write_header()?)
+            }
+            Format::WebP => {
+                // lossless bitstreams store 14-bit dimensions
+                if 16384 < size.0 || 16384 < size.1 {
+                    return Err(ExportError::TooLarge(size))
+                }   Self::WebP(writer, WebPMuxer::new(size, options))
+            }
+        })
+    }
+
+    fn push(&mut self, mut rgba: Vec<u8>, delay: u32) -> Result<(), ExportError> {
+        match self {
+            Self::Gif(encoder, (w, h), elapsed) => {
+                // GIF delays are in centiseconds, rounded from the elapsed time as well
+                let centis = ((*elapsed + delay + 5) / 10).saturating_sub((*elapsed + 5) / 10);
+                *elapsed += delay;
+                let mut frame = gif::Frame::from_rgba_speed(*w, *h, &mut rgba, 10);
+                frame.delay = centis.min(u16::MAX as _) as _;
+                // clear to transparency, or the previous frame shows through
+                frame.dispose = gif::DisposalMethod::Background;
+                encoder.write_frame(&frame)?;
+            }
+            Self::Apng(writer) => {
+                writer.set_frame_delay(delay.min(u16::MAX as _) as _, 1000)?;
+                writer.write_image_data(&rgba)?;
+            }
+            Self::WebP(_, muxer) => muxer.push(&rgba, delay)?,
+        }   Ok(())
+    }
+
+    fn finish(self) -> Result<(), ExportError> {
+        match self {
+            Self::Gif(encoder, ..) => { encoder.into_inner()?.flush()?; }
+            Self::Apng(writer) => writer.finish()?,
+            Self::WebP(mut writer, muxer) => {
+                writer.write_all(&muxer.finish())?;     writer.flush()?;
+            }
+        }   Ok(())
+    }
+}
+
+/// Assembles lossless still frames into the extended WebP container, as `ANMF` chunks
+/// following the `VP8X` and `ANIM` headers.
+struct WebPMuxer { size: (u32, u32), background: RGBA, loops: u16, frames: Vec<u8> }
+
+impl WebPMuxer {
+    fn new(size: (u32, u32), options: &ExportOptions) -> Self {
+        Self { size, background: options.background, loops: options.loop_count,
+            frames: Vec::new() }
+    }
+
+    fn push(&mut self, rgba: &[u8], delay: u32) -> Result<(), ExportError> {
+        let mut still = Vec::new();
+        image::codecs::webp::WebPEncoder::new_lossless(&mut still).encode(rgba,
+            self.size.0, self.size.1, image::ExtendedColorType::Rgba8)?;
+        self.push_still(&still, delay)
+    }
+
+    /// Wraps the bitstream chunks of a simple-format still WebP into an `ANMF` chunk.
+    fn push_still(&mut self, still: &[u8], delay: u32) -> Result<(), ExportError> {
+        let mut frame = Vec::with_capacity(16 + still.len());
+        frame.extend(u24(0));   frame.extend(u24(0));   // offset in pairs of pixels
+        frame.extend(u24(self.size.0 - 1));     frame.extend(u24(self.size.1 - 1));
+        frame.extend(u24(delay.min(0xff_ffff)));
+        frame.push(0x02);   // without alpha-blending over nor disposing the previous frame
+
+        let invalid = || ExportError::Encode("malformed still WebP".into());
+        if still.len() < 12 || &still[..4] != b"RIFF" || &still[8..12] != b"WEBP" {
+            return Err(invalid())
+        }
+        let mut rest = &still[12..];
+        while 8 <= rest.len() {
+            let len = u32::from_le_bytes(rest[4..8].try_into().unwrap()) as usize;
+            let end = 8 + len + (len & 1);
+            if rest.len() < 8 + len { return Err(invalid()) }
+            if matches!(&rest[..4], b"ALPH" | b"VP8 " | b"VP8L") {
+                frame.extend_from_slice(&rest[..end.min(rest.len())]);
+                if rest.len() < end { frame.push(0) }
+            }   rest = &rest[end.min(rest.len())..];
+        }
+        write_chunk(&mut self.frames, b"ANMF", &frame);     Ok(())
+    }
+
+    fn finish(self) -> Vec<u8> {
+        let mut body = b"WEBP".to_vec();
+        let mut header = vec![0x10 | 0x02, 0, 0, 0];    // alpha and animation flags
+        header.extend(u24(self.size.0 - 1));    header.extend(u24(self.size.1 - 1));
+        write_chunk(&mut body, b"VP8X", &header);
+
+        let RGBA { r, g, b, a } = self.background;
+        let mut anim = vec![b, g, r, a];    anim.extend(self.loops.to_le_bytes());
+        write_chunk(&mut body, b"ANIM", &anim);
+        body.extend(self.frames);
+
+        let mut file = b"RIFF".to_vec();
+        file.extend((body.len() as u32).to_le_bytes());     file.extend(body);  file
+    }
+}
+
+fn u24(value: u32) -> [u8; 3] { let [b0, b1, b2, _] = value.to_le_bytes(); [b0, b1, b2] }
+
+fn write_chunk(output: &mut Vec<u8>, fourcc: &[u8; 4], payload: &[u8]) {
+    output.extend(fourcc);  output.extend((payload.len() as u32).to_le_bytes());
+    output.extend(payload);     if payload.len() % 2 == 1 { output.push(0) }
+}
+
+#[cfg(test)] mod tests {
+    use super::*;
+
+    fn runtime() -> LottieRuntime {
+        LottieRuntime::from_reader(&br#"{"ip":0,"op":5,"fr":3,"w":8,"h":4,"layers":[
+            {"ty":4,"ind":1,"ip":0,"op":5,"st":0,"ks":{},"shapes":[
+                {"ty":"rc","p":{"a":0,"k":[4,2]},"s":{"a":0,"k":[8,4]},"r":{"a":0,"k":0}},
+                {"ty":"fl","c":{"a":0,"k":[0,0,1,1]},"o":{"a":0,"k":100}}]}]}"#[..]).unwrap()
+    }
+
+    #[test] fn apng_keeps_the_timeline_duration_across_decimated_frames() {
+        let (mut output, options) = (Vec::new(),
+            ExportOptions { decimation: 2, loop_count: 3, ..Default::default() });
+        let count = export_animation(&mut runtime(), &mut output,
+            Format::Apng, &options).unwrap();
+        assert_eq!(count, 3);
+
+        let mut reader = png::Decoder::new(&output[..]).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (3, 3));
+        assert_eq!(reader.info().size(), (8, 4));
+
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut delays = Vec::new();
+        for _ in 0..count {
+            let info = reader.next_frame(&mut buffer).unwrap();
+            assert_eq!(&buffer[..4], [0, 0, 255, 255]);
+            assert_eq!(info.width, 8);
+            let fc = reader.info().frame_control.unwrap();
+            delays.push((fc.delay_num, fc.delay_den));
+        }   // frames 0, 2 and 4 at 3 fps, the last one only until the out point
+        assert_eq!(delays, [(667, 1000), (666, 1000), (334, 1000)]);
+    }
+
+    #[test] fn webp_frames_are_muxed_into_an_animated_container() {
+        let options = ExportOptions { loop_count: 2,
+            background: RGBA::new_u8(1, 2, 3, 4), ..Default::default() };
+        let mut muxer = WebPMuxer::new((3, 2), &options);
+        let mut still = b"RIFF\x12\0\0\0WEBPVP8L\x05\0\0\0abcde\0".to_vec();
+        muxer.push_still(&still, 40).unwrap();
+        still[20] = b'x';   muxer.push_still(&still, 0x1234567).unwrap();
+        assert!(muxer.push_still(b"RIFF\0\0\0\0WAVE", 1).is_err());
+
+        let file = muxer.finish();
+        assert_eq!(&file[..4], b"RIFF");
+        assert_eq!(u32::from_le_bytes(file[4..8].try_into().unwrap()) as usize, file.len() - 8);
+        assert_eq!(&file[8..16], b"WEBPVP8X");
+        assert_eq!(&file[20..30], [0x12, 0, 0, 0, 2, 0, 0, 1, 0, 0]);
+        assert_eq!(&file[30..44], b"ANIM\x06\0\0\0\x03\x02\x01\x04\x02\0");
+
+        let frame = &file[44..];
+        assert_eq!(&frame[..8], b"ANMF\x1e\0\0\0");
+        assert_eq!(&frame[8..24], [0, 0, 0, 0, 0, 0, 2, 0, 0, 1, 0, 0, 40, 0, 0, 2]);
+        assert_eq!(&frame[24..38], b"VP8L\x05\0\0\0abcde\0");
+        let frame = &frame[38..];
+        assert_eq!(&frame[20..23], [0xff, 0xff, 0xff]);     // duration is 24-bit
+        assert_eq!(&frame[24..33], b"VP8L\x05\0\0\0x");
+        assert_eq!(frame.len(), 38);
+    }
+}
@@ -4,3 +4,4 @@ pub mod backend;
 pub mod rive;
 
 #[cfg(feature = "vello")] pub mod vello_svg;
+#[cfg(feature = "export")] pub mod export;
@@ -102,6 +102,13 @@ impl ApplicationHandler for WinitApp {
             }
             WindowEvent::MouseInput { state, button: MouseButton::Left, .. } => {
                 self.dragging = matches!(state, ElementState::Pressed);
+                let point = self.artboard_point();
+                if let (AnimGraph::Rive(rive), Some((x, y))) = (&mut self.graph, point) {
+                    match state {
+                        ElementState::Pressed  => rive.runtime.pointer_down(x, y),
+                        ElementState::Released => rive.runtime.pointer_up  (x, y),
+                    };
+                }
                 #[cfg(feature = "rive-rs")]
                 if let AnimGraph::RiveRs((scene, viewport)) = &mut self.graph {
                     match state {
@@ -131,6 +138,11 @@ impl ApplicationHandler for WinitApp {
                     }
                 }   self.mouse_pos = (position.x as _, position.y as _);
 
+                let point = self.artboard_point();
+                if let (AnimGraph::Rive(rive), Some((x, y))) = (&mut self.graph, point) {
+                    rive.runtime.pointer_move(x, y);
+                }
+
                 #[cfg(feature = "rive-rs")]
                 if let AnimGraph::RiveRs((scene, viewport)) = &mut self.graph {
                     scene.pointer_move(self.mouse_pos.0, self.mouse_pos.1, viewport);
@@ -180,9 +192,10 @@ struct WinitApp {
     backend::femtovg::FemtovgContext,
     backend::femtovg::ImageCache,
 };
+#[cfg(feature = "dotlottie")] use inlottie::core::dotlottie::DotLottie;
 #[cfg(feature = "rive-rs")] use inlottie::rive::rscpp_nvg::RiveNVG;
 use inlottie::rive::{RenderContext as _, decode::RiveFile,
-    display_list::DisplayList, runtime::Runtime as RiveRuntime,
+    display_list::DisplayList, runtime::{EventKind, Runtime as RiveRuntime},
     render_nvg::ImageCache as NvgImageCache,
 };
 #[cfg(feature = "b2d")] use inlottie::rive::render_b2d::ImageCache as B2dImageCache;
@@ -203,6 +216,19 @@ struct NativeRive {
     #[cfg(feature = "b2d")] b2d_images: B2dImageCache,
 }
 
+impl NativeRive {
+    fn log_events(&mut self) {
+        for fired in self.runtime.drain_events() {
+            let name = String::from_utf8_lossy(&fired.event.name);
+            match &fired.event.kind {
+                EventKind::OpenUrl { url, .. } => eprintln!("Rive event {name}: open {}",
+                    String::from_utf8_lossy(url)),
+                _ => eprintln!("Rive event {name} at +{:.3}s", fired.time),
+            }
+        }
+    }
+}
+
 enum AnimGraph {
     #[cfg(feature =  "lottie")] Lottie(Box<LottieRuntime>),
     Rive(Box<NativeRive>),
@@ -233,6 +259,22 @@ impl WinitApp {
         if let Some(window) = &self.window { window.request_redraw(); }
     }
 
+    /// Map the mouse position from window to the artboard space of the current view.
+    fn artboard_point(&self) -> Option<(f32, f32)> {
+        if let Some(ctx2d) = &self.ctx2d {
+            return Some(ctx2d.transform().inverse()
+                .transform_point(self.mouse_pos.0, self.mouse_pos.1))
+        }
+        #[cfg(feature = "b2d")] if let (Some((blctx, scale)), Some(surface)) =
+            (&self.blctx, &self.surface) {
+            let (wsize, size) = (surface.window().inner_size(), blctx.get_target_size());
+            let loff = wsize.width .saturating_sub(size.width())  / 2;
+            let topl = wsize.height.saturating_sub(size.height()) / 2;
+            return Some(((self.mouse_pos.0 - loff as f32) / scale,
+                         (self.mouse_pos.1 - topl as f32) / scale))
+        }   None
+    }
+
     #[cfg(feature = "b2d")] fn init_blctx(&mut self, event_loop: &ActiveEventLoop,
         title: &str) -> Result<(), Box<dyn Error>> {
         let mut wsize = event_loop.primary_monitor().unwrap().size();
@@ -304,6 +346,7 @@ impl WinitApp {
                 if  rive.runtime.advance(_elapsed.as_secs_f32()) {
                     rive.runtime.write_display_list(&mut rive.list);
                 }
+                rive.log_events();
                 blctx.fill_all_rgba32((99, 99, 99, 255).into())?;
                 blctx.render_animation(&rive.list, &mut rive.b2d_images)
             })(),
@@ -508,6 +551,10 @@ impl WinitApp {
             #[cfg(feature =  "lottie")] Some("json") => {
                 AnimGraph::Lottie(Box::new(LottieRuntime::from_reader(fs::File::open(path)?)?))
             }
+            #[cfg(feature = "dotlottie")] Some("lottie") =>
+                AnimGraph::Lottie(Box::new(DotLottie::open(path)?.runtime()?)),
+            #[cfg(not(feature = "dotlottie"))] Some("lottie") => return Err(
+                "reading .lottie packages needs a rebuild with `-F dotlottie`".into()),
 
             Some("riv") => self.load_rive(path)?,
 
@@ -534,7 +581,9 @@ impl WinitApp {
                 .map(ToString::to_string).collect::<Vec<_>>().join(", ");
             eprintln!("Rive file uses unsupported native-runtime features: {features}");
         }
-        if 0 < runtime.animation_count() { runtime.set_animation(0)?; }
+        if let Some(machine) = runtime.default_state_machine() {
+            runtime.set_state_machine(machine)?;
+        } else if 0 < runtime.animation_count() { runtime.set_animation(0)?; }
         let mut list = DisplayList::default();
         runtime.write_display_list(&mut list);
         Ok(AnimGraph::Rive(Box::new(NativeRive {
@@ -592,6 +641,7 @@ impl WinitApp {
                 if rive.runtime.advance(_elapsed.as_secs_f32()) {
                     rive.runtime.write_display_list(&mut rive.list);
                 }
+                rive.log_events();
                 ctx2d.clear_rect(0, 0, ctx2d.width(), ctx2d.height(),
                     femtovg::Color::rgbf(0.4, 0.4, 0.4));
                 if let Err(error) =
@@ -0,0 +1,224 @@
+/****************************************************************
+ * $ID: render.rs  	Sun 18 Oct 2026 10:32:05+0800               *
+ *                                                              *
+ * Maintainer: 范美辉 (MeiHui FAN) <mhfan@ustc.edu>              *
+ * Copyright (c) 2026 M.H.Fan, All rights reserved.             *
+ ****************************************************************/
+
+//! Headless renderer of Lottie/Rive frames into numbered PNG files, rasterized
+//! on the CPU without any display or GPU: Lottie by tiny-skia in pure Rust, Rive by
+//! Blend2D, which needs the `b2d` feature and so the C++ library through intvg.
+
+use std::{env, error::Error, fs, path::PathBuf};
+use inlottie::{backend::tiny_skia::SkiaContext,
+    core::{helpers::{RGBA, fit_viewport}, render::LottieRuntime}};
+#[cfg(feature = "dotlottie")] use inlottie::core::dotlottie::DotLottie;
+#[cfg(feature = "b2d")] use intvg::blend2d::{BLContext, BLErr, BLFormat, BLMatrix2D};
+#[cfg(feature = "b2d")] use inlottie::{core::render::RenderContext as _,
+    rive::{RenderContext as _, decode::RiveFile, display_list::DisplayList,
+        render_b2d::ImageCache, runtime::Runtime as RiveRuntime}};
+
+const USAGE: &str = "[--size <W>x<H>] [--scale <S>] [--frames <start>:<end>]
+    [--background <RRGGBB[AA]>] [--animation <name>] [--output <dir>]
+    [--format gif|apng|webp [--step <N>] [--loops <N>]] <path-to-lottie/rive>
+Rive files render by Blend2D only, which needs a build with `-F b2d` (not pure Rust).";
+
+struct Options {
+    path: PathBuf, output: PathBuf,
+    size: Option<(u32, u32)>, scale: Option<f32>,
+    frames: Option<(f32, f32)>, background: RGBA,
+    #[cfg_attr(not(feature = "b2d"), allow(dead_code))]
+    animation: Option<String>,     // of Rive
+    format: Option<String>,        // of an animated image instead of PNG frames
+    #[cfg(feature = "export")] step: u32,
+    #[cfg(feature = "export")] loops: u16,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut args = env::args();
+    let program = args.next().unwrap();
+    let Some(opts) = Options::parse(args)? else {
+        println!("Usage: {program} {USAGE}");   return Ok(())
+    };
+
+    fs::create_dir_all(&opts.output)?;
+    if let Some(format) = &opts.format {
+        #[cfg(feature = "export")] return export_lottie(&opts, format);
+        #[cfg(not(feature = "export"))]
+        return Err(format!("exporting {format} needs the `export` feature").into())
+    }
+    let count = match opts.path.extension().and_then(|ext| ext.to_str()) {
+        Some("json" | "lottie") => render_lottie(&opts)?,
+        #[cfg(feature = "b2d")] Some("riv")  => render_rive  (&opts)?,
+        #[cfg(not(feature = "b2d"))]
+        Some("riv") => return Err(
+            "rendering Rive files needs a rebuild with `-F b2d` (Blend2D in C++)".into()),
+        _ => return Err(format!("unsupported file: {}", opts.path.display()).into()),
+    };  eprintln!("{count} frames written to {}", opts.output.display());     Ok(())
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Option<Self>, Box<dyn Error>> {
+        let mut opts = Self { path: PathBuf::new(), output: PathBuf::from("."),
+            size: None, scale: None, frames: None,
+            background: RGBA::new_u8(0, 0, 0, 0), animation: None, format: None,
+            #[cfg(feature = "export")] step: 1, #[cfg(feature = "export")] loops: 0 };
+        let mut path = None;
+
+        while let Some(arg) = args.next() {
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) if name.starts_with("--") =>
+                    (name.to_owned(), Some(value.to_owned())),
+                _ => (arg, None),
+            };
+            let mut value = || inline.clone().or_else(|| args.next())
+                .ok_or_else(|| format!("missing value after {name}"));
+            match name.as_str() {
+                "-h" | "--help" => return Ok(None),
+                "-o" | "--output" => opts.output = value()?.into(),
+                "--size" => opts.size = Some(value()?.split_once(['x', 'X'])
+                    .and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)))
+                    .filter(|&(w, h)| 0 < w && 0 < h).ok_or("invalid --size")?),
+                "--scale" => opts.scale = Some(value()?.parse::<f32>().ok()
+                    .filter(|scale| 0. < *scale && scale.is_finite()).ok_or("invalid --scale")?),
+                "--frames" => opts.frames = Some(value()?.split_once(':')
+                    .and_then(|(start, end)| Some((start.parse().ok()?, end.parse().ok()?)))
+                    .ok_or("invalid --frames")?),
+                "--background" => opts.background = parse_color(&value()?)
+                    .ok_or("invalid --background")?,
+                "--animation" => opts.animation = Some(value()?),
+                "--format" => opts.format = Some(value()?),
+                #[cfg(feature = "export")] "--step" => opts.step = value()?.parse().ok()
+                    .filter(|&step| 0 < step).ok_or("invalid --step")?,
+                #[cfg(feature = "export")]
+                "--loops" => opts.loops = value()?.parse().map_err(|_| "invalid --loops")?,
+                _ if !name.starts_with('-') && path.is_none() => path = Some(name),
+                _ => return Err(format!("unexpected argument: {name}").into()),
+            }
+        }
+        if opts.format.is_some() && opts.frames.is_some() {
+            return Err("--frames doesn't apply to an animated image of the whole timeline".into())
+        }
+        opts.path = path.ok_or("missing input file")?.into();   Ok(Some(opts))
+    }
+
+    fn frame_path(&self, frame: f32) -> PathBuf {
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        self.output.join(format!("{stem}_{:05}.png", frame as i64))
+    }
+}
+
+fn parse_color(hex: &str) -> Option<RGBA> {
+    let hex = hex.trim_start_matches('#');
+    let value = u32::from_str_radix(hex, 16).ok()?;
+    let [r, g, b, a] = match hex.len() {
+        6 => (value << 8 | 0xff).to_be_bytes(), 8 => value.to_be_bytes(), _ => return None,
+    };  Some(RGBA::new_u8(r, g, b, a))
+}
+
+/// Whole frames from `start` up to the (exclusive) `end`.
+fn frames(start: f32, end: f32) -> impl Iterator<Item = f32> {
+    (0..).map(move |index| start + index as f32).take_while(move |&frame| frame < end)
+}
+
+#[cfg(feature = "b2d")]
+fn b2d_error(error: BLErr) -> Box<dyn Error> { format!("Blend2D: {error:?}").into() }
+
+fn load_lottie(path: &std::path::Path) -> Result<LottieRuntime, Box<dyn Error>> {
+    if path.extension().is_some_and(|ext| ext == "lottie") {
+        #[cfg(feature = "dotlottie")] return Ok(DotLottie::open(path)?.runtime()?);
+        #[cfg(not(feature = "dotlottie"))]
+        return Err("reading .lottie packages needs the `dotlottie` feature".into())
+    }   Ok(LottieRuntime::from_reader(fs::File::open(path)?)?)
+}
+
+fn render_lottie(opts: &Options) -> Result<usize, Box<dyn Error>> {
+    let mut lottie = load_lottie(&opts.path)?;
+    let animation  = lottie.animation();
+    let (size, view) = fit_viewport((animation.w as _, animation.h as _), opts.size, opts.scale);
+    let (start, end) = opts.frames.unwrap_or((animation.ip, animation.op));
+    lottie.set_view_transform(view);
+
+    let mut context = SkiaContext::new(size.0, size.1)?;
+    let mut count = 0;
+    for frame in frames(start, end) {
+        lottie.render_frame(&mut context, frame, Some(opts.background))?;
+        context.pixmap().save_png(opts.frame_path(frame))?;     count += 1;
+    }   Ok(count)
+}
+
+/// Encodes the whole Lottie timeline into `{stem}.{ext}` of the output directory.
+#[cfg(feature = "export")]
+fn export_lottie(opts: &Options, format: &str) -> Result<(), Box<dyn Error>> {
+    use inlottie::export::{export_animation, ExportOptions, Format};
+    let kind = Format::from_extension(format).ok_or("invalid --format")?;
+    let mut lottie = load_lottie(&opts.path)?;
+
+    let stem = opts.path.file_stem().unwrap_or_default().to_string_lossy();
+    let path = opts.output.join(format!("{stem}.{}", format.to_ascii_lowercase()));
+    let writer = std::io::BufWriter::new(fs::File::create(&path)?);
+    let count = export_animation(&mut lottie, writer, kind, &ExportOptions {
+        size: opts.size, scale: opts.scale, background: opts.background,
+        decimation: opts.step, loop_count: opts.loops })?;
+    eprintln!("{count} frames encoded into {}", path.display());    Ok(())
+}
+
+#[cfg(feature = "b2d")] fn render_rive(opts: &Options) -> Result<usize, Box<dyn Error>> {
+    let mut runtime = RiveRuntime::from_file(
+        RiveFile::read(&mut fs::File::open(&opts.path)?)?)?;
+    if !runtime.is_fully_supported() {
+        let features = runtime.unsupported_features().iter()
+            .map(ToString::to_string).collect::<Vec<_>>().join(", ");
+        eprintln!("Rive file uses unsupported native-runtime features: {features}");
+    }
+    if let Some(name) = &opts.animation {
+        runtime.set_animation_by_name(name.as_bytes())?;
+    } else if let Some(machine) = runtime.default_state_machine() {
+        runtime.set_state_machine(machine)?;
+    } else if 0 < runtime.animation_count() { runtime.set_animation(0)?; }
+    let active = (0..runtime.animation_count()).filter_map(|index|
+        runtime.animation(index)).find(|info| opts.animation.as_ref()
+            .is_none_or(|name| info.name == name.as_bytes()));
+    // a still artboard renders a single frame
+    let (fps, duration) = active.map_or((60., 1.),
+        |info| (info.fps.max(1) as f32, info.duration.max(1) as f32));
+
+    let (size, view) = fit_viewport(runtime.artboard_size(), opts.size, opts.scale);
+    let (start, end) = opts.frames.unwrap_or((0., duration));
+    let mut blctx = BLContext::new(size.0 as _, size.1 as _,
+        BLFormat::BL_FORMAT_PRGB32).map_err(b2d_error)?;
+    let (mut list, mut cache) = (DisplayList::default(), ImageCache::default());
+    let (mut elapsed, mut count) = (0., 0);
+
+    for frame in frames(start, end) {
+        let time = frame / fps;
+        runtime.advance(time - elapsed);    elapsed = time;
+        runtime.write_display_list(&mut list);
+
+        blctx.reset_transform(None);
+        blctx.clear_rect_with(0, 0, size.0, size.1, opts.background).map_err(b2d_error)?;
+        blctx.reset_transform(Some(&BLMatrix2D::new(view)));
+        blctx.render_animation(&list, &mut cache).map_err(b2d_error)?;
+        write_png(&mut blctx, &opts.frame_path(frame))?;    count += 1;
+    }   Ok(count)
+}
+
+/// Copies the PRGB32 target (little-endian BGRA in memory) into a premultiplied
+/// RGBA pixmap, which un-premultiplies it on encoding the PNG.
+#[cfg(feature = "b2d")]
+fn write_png(blctx: &mut BLContext, path: &std::path::Path) -> Result<(), Box<dyn Error>> {
+    blctx.flush().map_err(b2d_error)?;
+    let image = blctx.get_target_image();
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    let pixels = image.pixels().ok_or("Blend2D image has no pixel data")?;
+
+    let mut rgba = Vec::with_capacity(width * height * 4);
+    for row in pixels.chunks(image.stride() as _).take(height) {
+        for bgra in row[..width * 4].chunks_exact(4) {
+            rgba.extend([bgra[2], bgra[1], bgra[0], bgra[3]]);
+        }
+    }
+    let size = tiny_skia::IntSize::from_wh(width as _, height as _).ok_or("empty image")?;
+    tiny_skia::Pixmap::from_vec(rgba, size).ok_or("mismatched image buffer")?
+        .save_png(path)?;   Ok(())
+}
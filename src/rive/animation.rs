@@ -6,10 +6,20 @@ use super::{decode::{Object, RiveFile, core_color_default, object_ids, property_
 };
 use crate::core::helpers::math::CubicBezierEasing;
 
-#[derive(Debug, Clone, Copy)] enum Interpolation {
+#[derive(Debug, Clone, Copy)] pub(super) enum Interpolation {
     Hold, Linear, Cubic { x1: f32, y1: f32, x2: f32, y2: f32 },
 }
 
+impl Interpolation {
+    /// Map a linear progress in `[0, 1]` through this curve.
+    pub fn ease(self, factor: f32) -> f32 { match self {
+        Self::Hold => if factor < 1.0 { 0.0 } else { 1.0 },
+        Self::Linear => factor,
+        Self::Cubic { x1, y1, x2, y2 } =>
+            CubicBezierEasing::new((x1, y1), (x2, y2)).get_y(factor),
+    } }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)] pub(super) enum TrackValue {
     Scalar(f32), Color(u32), Bool(bool), Uint(u32),
 }
@@ -40,6 +50,8 @@ impl RawTrack {
     pub speed: f32, pub loop_mode: u32,
     pub tracks: Vec<T>,
     pub geometries: Vec<u32>, pub gradients: Vec<u32>,
+    /// Callback keyframes as (frame, keyed component), sorted by frame.
+    pub callbacks: Vec<(u32, u32)>,
 }
 
 pub(super) type RawAnimation = Animation<RawTrack>;
@@ -62,7 +74,7 @@ pub(super) fn build_animations(file: &RiveFile, context_start: usize, context_en
                 fps: uint(object, property_ids::FPS)?,
                 speed: float(object, property_ids::LINEARANIMATION_SPEED)?,
                 loop_mode: uint(object, property_ids::LOOPVALUE)?,
-                tracks: Vec::new(),
+                tracks: Vec::new(), callbacks: Vec::new(),
                 geometries: Vec::new(), gradients: Vec::new(),
             });
             current_animation = Some(animations.len() - 1);
@@ -134,11 +146,18 @@ pub(super) fn build_animations(file: &RiveFile, context_start: usize, context_en
                 value: TrackValue::Uint(value),
                 interp: Interpolation::Hold,
             });
+        }
+        // Callback keyframes carry no value; they report their keyed event when passed.
+        object_ids::KEY_FRAME_CALLBACK => if let Some((animation, track)) = current_track {
+            let component = animations[animation].tracks[track].component;
+            animations[animation].callbacks.push(
+                (uint(object, property_ids::FRAME)?, component));
         }   _ => {}
     }}
     // Normalize once at load time so frame evaluation only touches animated components.
     for animation in &mut animations {
         animation.tracks.retain(|track| !track.keyframes.is_empty());
+        animation.callbacks.sort_by_key(|callback| callback.0);
         for track in &mut animation.tracks {
             track.keyframes.sort_by_key(|keyframe| keyframe.frame);
         }
@@ -147,12 +166,18 @@ pub(super) fn build_animations(file: &RiveFile, context_start: usize, context_en
 
 fn keyframe_interpolation(file: &RiveFile, context_start: usize,
     keyframe: &Object) -> Result<Interpolation> {
-    let kind = uint(keyframe, property_ids::INTERPOLATINGKEYFRAME_INTERPOLATIONTYPE)?;
+    interpolation(file, context_start,
+        uint(keyframe, property_ids::INTERPOLATINGKEYFRAME_INTERPOLATIONTYPE)?,
+        uint(keyframe, property_ids::INTERPOLATINGKEYFRAME_INTERPOLATORID)?)
+}
+
+/// Resolve an interpolation type and its artboard-relative cubic interpolator reference.
+pub(super) fn interpolation(file: &RiveFile, context_start: usize,
+    kind: u32, id: u32) -> Result<Interpolation> {
     if  kind == 0 { return Ok(Interpolation::Hold) }
     if  kind == 1 { return Ok(Interpolation::Linear) }
     if  kind != 2 { return Err(RuntimeError::InvalidInterpolation(kind)) }
 
-    let id = uint(keyframe, property_ids::INTERPOLATINGKEYFRAME_INTERPOLATORID)?;
     let interpolator = context_start.checked_add(id as usize)
         .and_then(|index| file.ocoll.get(index))
         .ok_or(RuntimeError::InvalidInterpolator(id))?;
@@ -189,11 +214,8 @@ fn evaluate(keyframes: &[Keyframe], frame: f32) -> Option<TrackValue> {
     if matches!(current.interp, Interpolation::Hold) || next.frame == current.frame {
         return Some(current.value)
     }
-    let mut factor = ((frame - current.frame as f32) /
-        (next.frame - current.frame) as f32).clamp(0.0, 1.0);
-    if let Interpolation::Cubic { x1, y1, x2, y2 } = current.interp {
-        factor = CubicBezierEasing::new((x1, y1), (x2, y2)).get_y(factor);
-    }
+    let factor = current.interp.ease(((frame - current.frame as f32) /
+        (next.frame - current.frame) as f32).clamp(0.0, 1.0));
     Some(match (current.value, next.value) {
         (TrackValue::Scalar(from), TrackValue::Scalar(to)) =>
             TrackValue::Scalar(from + (to - from) * factor),
@@ -447,7 +447,7 @@ fn compose(parts: Parts) -> Affine {
     }   matrix
 }
 
-fn inverse(matrix: Affine) -> Option<Affine> {
+pub(super) fn inverse(matrix: Affine) -> Option<Affine> {
     let determinant = matrix.xx * matrix.yy - matrix.yx * matrix.xy;
     if determinant == 0.0 { return None }
     let inverse = determinant.recip();
@@ -114,6 +114,15 @@ pub enum FillRule { NonZero, EvenOdd, Clockwise }
     pub data: Arc<[u8]>,
     pub trfm: Affine,
     pub origin: Point,
+    /// Textured triangles drawn instead of the whole image, ignoring `origin`.
+    pub mesh: Option<ImageMesh>,
+}
+
+#[derive(Debug, Clone, PartialEq)] pub struct ImageMesh {
+    /// Positions under the image `trfm`, with normalized texture coordinates.
+    pub vertices: Arc<[Point]>, pub uvs: Arc<[Point]>,
+    /// Vertex indices, three per triangle.
+    pub indices: Arc<[u16]>,
 }
 
 #[derive(Debug, Clone, PartialEq)] pub struct DrawItem {
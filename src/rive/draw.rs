@@ -2,9 +2,12 @@
 
 use std::{mem, sync::Arc};
 
-use super::{ComponentPaint, DrawGroup, Result, Runtime, RuntimeError, uint, Shape,
-    object_ids, property_ids, Affine, Brush, Clip, DisplayList, DrawItem, Image, Paint,
+use super::{ComponentGeom, ComponentPaint, DrawGroup, Result, Runtime, RuntimeError, uint,
+    Shape, object_ids, property_ids, Affine, Brush, Clip, DisplayList, DrawItem, FillRule,
+    Image, Paint, Point,
 };
+use crate::rive::display_list::ImageMesh;
+use crate::rive::contains;
 
 impl Runtime {
     pub fn write_display_list(&self, list: &mut DisplayList) {
@@ -91,19 +94,46 @@ impl Runtime {
         }));
     }
 
+    /// Whether artboard-space `point` hits a shape at or under component `target` within its
+    /// clips; opacity is ignored since invisible shapes commonly serve as hit areas.
+    pub(super) fn hit_test(&self, target: u32, point: Point) -> bool {
+        self.draw_groups.iter().filter(|group| group.image.is_none() && group.nested.is_none() &&
+            is_descendant(&self.components, group.opacity_component, target)).any(|group| {
+            let rule = group.paints.iter().find_map(|&index|
+                match self.components[index as usize].paint()?.value {
+                    Paint::Fill { rule, .. } => Some(rule), _ => None,
+                }).unwrap_or(FillRule::NonZero);
+            contains(&self.snapshot_shapes(&group.components), rule, point) &&
+                group.clips.iter().all(|&index| {
+                    let clip = self.components[index as usize].clip().unwrap();
+                    !clip.visible || contains(&self.snapshot_shapes(&clip.shapes), clip.rule, point)
+                })
+        })
+    }
+
     fn snapshot_image(&self, index: u32) -> Image {
         let component = &self.components[index as usize];
         let image = component.image().unwrap();
-        Image { asset_id: image.asset_id, data: image.data.clone(),
-            trfm: component.world, origin: image.origin }
+        // Skinned mesh vertices are already in world space.
+        let mesh = image.mesh.as_ref().map(|mesh| ImageMesh {
+            vertices: mesh.deformed.as_ref().unwrap_or(&mesh.vertices)[..].into(),
+            uvs: mesh.uvs.clone(), indices: mesh.indices.clone(),
+        });
+        let skinned = image.mesh.as_ref().is_some_and(|mesh| mesh.deformed.is_some());
+        Image { asset_id: image.asset_id, data: image.data.clone(), mesh,
+            trfm: if skinned { Affine::default() } else { component.world },
+            origin: image.origin }
     }
 
     fn snapshot_shapes(&self, indices: &[u32]) -> Arc<[Shape]> {
         indices.iter().map(|&index| {
                 let component = &self.components[index as usize];
+                let geom = component.geom().unwrap();
+                // Skinned paths are already deformed into world space.
+                let skinned = matches!(geom, ComponentGeom::Points { skinned: true, .. });
                 Shape { obj_idx: component.obj_idx, is_hole: component.is_hole,
-                    trfm: component.world,
-                    geom: component.geom().unwrap().geometry().clone() }
+                    trfm: if skinned { Affine::default() } else { component.world },
+                    geom: geom.geometry().clone() }
         }).collect()
     }
 
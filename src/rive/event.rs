@@ -0,0 +1,121 @@
+
+//! Rive event decoding and the reports fired by animations, state machines and listeners.
+
+use std::sync::Arc;
+
+use super::{Component, Runtime, boolean, float, object_ids, property_ids, uint};
+use crate::rive::decode::{self, RiveFile};
+
+#[derive(Debug, Clone, PartialEq)] pub enum EventKind {
+    General,
+    /// `target` is the OpenUrlTarget: 0 blank, 1 parent, 2 self, 3 top.
+    OpenUrl { url: Vec<u8>, target: u32 },
+    Audio { asset_id: u32 },
+}
+
+#[derive(Debug, Clone, PartialEq)] pub enum PropertyValue {
+    Number(f32), Bool(bool), String(Vec<u8>),
+}
+
+/// An Event object with its custom properties, in file order.
+#[derive(Debug, Clone, PartialEq)] pub struct Event {
+    pub name: Vec<u8>, pub kind: EventKind,
+    pub properties: Vec<(Vec<u8>, PropertyValue)>,
+    component: u32,
+}
+
+/// An event report; `time` is the offset in seconds into the `advance` that fired it,
+/// or 0 for events fired by pointer listeners between advances.
+#[derive(Debug, Clone, PartialEq)] pub struct FiredEvent { pub event: Arc<Event>, pub time: f32 }
+
+pub(super) fn build_events(file: &RiveFile,
+    components: &[Component]) -> decode::Result<Vec<Arc<Event>>> {
+    let mut events = Vec::new();
+    for (index, component) in components.iter().enumerate() {
+        let object = &file.ocoll[component.obj_idx as usize];
+        let kind = match object.type_id.0 {
+            object_ids::EVENT => EventKind::General,
+            object_ids::OPEN_URL_EVENT => EventKind::OpenUrl {
+                url: object.bytes(property_ids::URL)?.unwrap_or_default().to_vec(),
+                target: uint(object, property_ids::TARGETVALUE)?,
+            },
+            object_ids::AUDIO_EVENT => EventKind::Audio {
+                asset_id: uint(object, property_ids::AUDIOEVENT_ASSETID)?,
+            },  _ => continue,
+        };
+        let mut properties = Vec::new();
+        for child in components.iter().filter(|child| child.parent == Some(index as u32)) {
+            let object = &file.ocoll[child.obj_idx as usize];
+            properties.push((object.bytes(property_ids::COMPONENT_NAME)?
+                .unwrap_or_default().to_vec(), match object.type_id.0 {
+                object_ids::CUSTOM_PROPERTY_NUMBER => PropertyValue::Number(
+                    float(object, property_ids::CUSTOMPROPERTYNUMBER_PROPERTYVALUE)?),
+                object_ids::CUSTOM_PROPERTY_BOOLEAN => PropertyValue::Bool(
+                    boolean(object, property_ids::CUSTOMPROPERTYBOOLEAN_PROPERTYVALUE)?),
+                object_ids::CUSTOM_PROPERTY_STRING => PropertyValue::String(
+                    object.bytes(property_ids::CUSTOMPROPERTYSTRING_PROPERTYVALUE)?
+                        .unwrap_or_default().to_vec()),
+                _ => continue,
+            }));
+        }
+        events.push(Arc::new(Event { component: index as u32, kind, properties,
+            name: object.bytes(property_ids::COMPONENT_NAME)?.unwrap_or_default().to_vec(),
+        }));
+    }   Ok(events)
+}
+
+/// Fired events kept for the host to drain, dropping the oldest ones beyond.
+const MAX_EVENTS: usize = 64;
+
+impl Runtime {
+    pub fn events(&self) -> &[Arc<Event>] { &self.events }
+
+    /// Take the events fired since the last drain, in firing order; only the latest
+    /// `MAX_EVENTS` are kept for hosts that never drain them.
+    pub fn drain_events(&mut self) -> std::vec::Drain<'_, FiredEvent> { self.fired.drain(..) }
+
+    /// Report the event at component index `component`; other components are ignored.
+    pub(super) fn report_event(&mut self, component: u32, time: f32) {
+        if let Some(event) = self.events.iter().find(|event| event.component == component) {
+            if self.fired.len() == MAX_EVENTS { self.fired.remove(0); }
+            self.fired.push(FiredEvent { event: event.clone(), time });
+        }
+    }
+
+    /// Report the callback keyframes animation `index` passes while playing forward from
+    /// `from` to `to` seconds, stamped relative to `from`; a start at 0 includes frame 0.
+    pub(super) fn report_callbacks(&mut self, index: u32, from: f32, to: f32) {
+        let Some(animation) = self.animations.get(index as usize) else { return };
+        let rate = animation.fps as f32 * animation.speed;
+        if animation.callbacks.is_empty() || rate <= 0.0 || to <= from { return }
+        let (start, end) = (from * rate, to * rate);
+        let duration = animation.duration as f32;
+        let passed = |frame: f32| (start < frame || from == 0.0 && frame == 0.0) && frame <= end;
+
+        // Unroll loops into unbounded frames: a ping-pong cycle also passes each
+        // keyframe strictly inside the animation on its way back.
+        let cycle = match animation.loop_mode {
+            _ if duration <= 0.0 => None,
+            1 => Some(duration), 2 => Some(duration * 2.0), _ => None,
+        };
+        let mut reports = Vec::new();
+        for &(frame, component) in &animation.callbacks {
+            let frame = frame as f32;
+            let Some(cycle) = cycle else {
+                if passed(frame) && (duration <= 0.0 || frame <= duration) {
+                    reports.push((frame, component));
+                }   continue
+            };
+            let mirrored = animation.loop_mode == 2 && 0.0 < frame && frame < duration;
+            for base in (start / cycle).floor() as i64..=(end / cycle).floor() as i64 {
+                let base = base as f32 * cycle;
+                if passed(base + frame) { reports.push((base + frame, component)) }
+                if mirrored && passed(base + cycle - frame) {
+                    reports.push((base + cycle - frame, component));
+                }
+            }
+        }
+        reports.sort_by(|left, right| left.0.total_cmp(&right.0));
+        for (frame, component) in reports { self.report_event(component, frame / rate - from) }
+    }
+}
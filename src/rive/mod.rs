@@ -12,7 +12,8 @@ mod path;
 pub mod runtime;
 
 use kurbo::{Affine, BezPath, Shape as _};
-use display_list::{DisplayList, Geometry, PathCommand, PathEffect, Shape, TrimMode};
+use display_list::{DisplayList, FillRule, Geometry, ImageMesh, PathCommand, PathEffect,
+    Point, Shape, TrimMode};
 use crate::core::pathm::MeasuredPath;
 
 const PATH_TOLERANCE: f64 = 1e-3;
@@ -60,6 +61,47 @@ fn shape_paths(shapes: &[Shape]) -> Vec<RenderPath> {
     }).collect()
 }
 
+/// Whether `point` lies inside the combined contours, with holes wound like the backends do.
+fn contains(shapes: &[Shape], rule: FillRule, point: Point) -> bool {
+    let point = kurbo::Point::new(point.x as _, point.y as _);
+    let winding: i32 = shape_paths(shapes).iter().map(|entry| {
+        let winding = entry.path.winding(point);
+        if rule == FillRule::EvenOdd { return winding }
+        let area = entry.path.area();
+        let hole = entry.hole || rule == FillRule::Clockwise && area < 0.0;
+        if hole == (0.0 < area) { -winding } else { winding }
+    }).sum();
+    if rule == FillRule::EvenOdd { winding % 2 != 0 } else { winding != 0 }
+}
+
+/// Mesh triangles as their corners in image pixels, each with the affine map from those
+/// pixels to mesh space; degenerate triangles and missing vertices are skipped.
+fn mesh_triangles(mesh: &ImageMesh, width: f32, height: f32) ->
+    impl Iterator<Item = ([Point; 3], display_list::Affine)> + '_ {
+    mesh.indices.chunks_exact(3).filter_map(move |triangle| {
+        let corner = |index: u16| {
+            let (uv, position) = (mesh.uvs.get(index as usize)?,
+                mesh.vertices.get(index as usize)?);
+            Some((Point { x: uv.x * width, y: uv.y * height }, *position))
+        };
+        let [(t0, p0), (t1, p1), (t2, p2)] =
+            [corner(triangle[0])?, corner(triangle[1])?, corner(triangle[2])?];
+        let (d1, d2) = (Point { x: t1.x - t0.x, y: t1.y - t0.y },
+                        Point { x: t2.x - t0.x, y: t2.y - t0.y });
+        let (e1, e2) = (Point { x: p1.x - p0.x, y: p1.y - p0.y },
+                        Point { x: p2.x - p0.x, y: p2.y - p0.y });
+        let determinant = d1.x * d2.y - d2.x * d1.y;
+        if  determinant == 0.0 { return None }
+        let inverse = determinant.recip();
+        let (xx, xy) = ((e1.x * d2.y - e2.x * d1.y) * inverse,
+                        (e2.x * d1.x - e1.x * d2.x) * inverse);
+        let (yx, yy) = ((e1.y * d2.y - e2.y * d1.y) * inverse,
+                        (e2.y * d1.x - e1.y * d2.x) * inverse);
+        Some(([t0, t1, t2], display_list::Affine { xx, yx, xy, yy,
+            tx: p0.x - xx * t0.x - xy * t0.y, ty: p0.y - yx * t0.x - yy * t0.y }))
+    })
+}
+
 /// Apply ordered Rive path effects in a backend-independent representation.
 fn apply_effects(mut paths: Vec<RenderPath>, effects: &[PathEffect]) -> Vec<RenderPath> {
     for effect in effects { match effect {
@@ -132,4 +174,21 @@ fn trim_measured(measured: &MeasuredPath, start: f32, trim: f32) -> BezPath {
             .map(|segment| segment.arclen(PATH_TOLERANCE)).sum::<f64>();
         assert!((length - 25.).abs() < PATH_TOLERANCE);
     }
+
+    #[test] fn mesh_triangles_map_image_pixels_onto_their_vertices() {
+        let point = |x, y| Point { x, y };
+        let mesh = ImageMesh {
+            vertices: Arc::from([point(10., 0.), point(30., 10.), point(0., 40.), point(5., 5.)]),
+            uvs: Arc::from([point(0., 0.), point(1., 0.), point(0., 1.), point(1., 1.)]),
+            indices: Arc::from([0, 1, 2, 0, 1, 9, 0, 0, 3]),
+        };
+        let triangles: Vec<_> = mesh_triangles(&mesh, 4., 8.).collect();
+        assert_eq!(triangles.len(), 1);
+        let (corners, map) = triangles[0];
+        assert_eq!(corners, [point(0., 0.), point(4., 0.), point(0., 8.)]);
+        for (corner, vertex) in corners.into_iter().zip(mesh.vertices.iter()) {
+            let mapped = map.transform_point(corner);
+            assert!((mapped.x - vertex.x).abs() < 1e-4 && (mapped.y - vertex.y).abs() < 1e-4);
+        }
+    }
 }
@@ -3,7 +3,7 @@
 
 use core::f32::consts::{FRAC_PI_2, TAU};
 use super::{decode::{self, Object, object_ids, property_ids},
-    display_list::{CornerRadii, Geometry, Path, PathCommand, Point, Rect},
+    display_list::{Affine, CornerRadii, Geometry, Path, PathCommand, Point, Rect},
     runtime::{Result, RuntimeError, boolean, float, uint},
 };
 
@@ -254,6 +254,65 @@ impl VertexParams {
     }
 }
 
+/// Skin weights of a vertex as (values, indices) pairs, each packing four bytes; index 0 is
+/// the identity and index `n` is the skin's `n`th tendon. Cubic weights bind the control
+/// points separately.
+#[derive(Debug, Clone, Copy)] pub(super) struct VertexWeights {
+    pub position: (u32, u32), pub incoming: (u32, u32), pub outgoing: (u32, u32),
+}
+
+impl Default for VertexWeights {
+    fn default() -> Self { Self { position: (255, 0), incoming: (255, 0), outgoing: (255, 0) } }
+}
+
+impl VertexWeights {
+    pub fn from_object(object: &Object) -> decode::Result<Option<Self>> {
+        let weights = |values, indices| Ok::<_, decode::DecodeError>(
+            (uint(object, values)?, uint(object, indices)?));
+        let position = match object.type_id.0 {
+            object_ids::WEIGHT | object_ids::CUBIC_WEIGHT =>
+                weights(property_ids::WEIGHT_VALUES, property_ids::WEIGHT_INDICES)?,
+            _ => return Ok(None),
+        };
+        if object.type_id.0 == object_ids::WEIGHT {
+            return Ok(Some(Self { position, incoming: position, outgoing: position }))
+        }
+        Ok(Some(Self { position,
+            incoming: weights(property_ids::INVALUES,  property_ids::ININDICES)?,
+            outgoing: weights(property_ids::OUTVALUES, property_ids::OUTINDICES)?,
+        }))
+    }
+}
+
+/// Linear-blend skinning: move `point` by `bind` into world space at bind time, then by the
+/// weighted sum of `bones` (each a bone's world transform after its inverse bind).
+pub(super) fn deform_point(point: Point, (values, indices): (u32, u32),
+    bind: Affine, bones: &[Affine]) -> Point {
+    let mut blend = Affine { xx: 0.0, yx: 0.0, xy: 0.0, yy: 0.0, tx: 0.0, ty: 0.0 };
+    for byte in 0..4 {
+        let weight = (values >> (byte * 8)) & 0xFF;
+        if  weight == 0 { continue }
+        let Some(bone) = bones.get(((indices >> (byte * 8)) & 0xFF) as usize) else { continue };
+        let weight = weight as f32 / 255.0;
+        blend.xx += bone.xx * weight;   blend.yx += bone.yx * weight;
+        blend.xy += bone.xy * weight;   blend.yy += bone.yy * weight;
+        blend.tx += bone.tx * weight;   blend.ty += bone.ty * weight;
+    }   blend.transform_point(bind.transform_point(point))
+}
+
+impl Vertex {
+    /// Skin the vertex and its control points; corner radii apply to the deformed path.
+    pub fn deform(self, weights: &VertexWeights, bind: Affine, bones: &[Affine]) -> Self {
+        Self { position: deform_point(self.position, weights.position, bind, bones),
+            incoming: self.incoming.map(|point|
+                deform_point(point, weights.incoming, bind, bones)),
+            outgoing: self.outgoing.map(|point|
+                deform_point(point, weights.outgoing, bind, bones)),
+            radius: self.radius,
+        }
+    }
+}
+
 pub(super) fn build_path(vertices: &[Vertex], closed: bool) -> Path {
     if vertices.len() < 2 { return Path::default() }
     let rendered: Vec<_> = vertices.iter().enumerate()
@@ -5,7 +5,7 @@ use intvg::blend2d::{BLCompOp, BLContext, BLEllipse, BLErr, BLFillRule, BLPath,
     BLGeometryDirection, BLGradient, BLImage, BLLinearGradientValues, BLMatrix2D,
     BLRadialGradientValues, BLRoundRect, BLRgba32, BLStrokeCap, BLStrokeJoin
 };
-use super::{RenderContext, RenderPath, apply_effects, shape_paths,
+use super::{RenderContext, RenderPath, apply_effects, mesh_triangles, shape_paths,
     display_list::{Brush, Clip, DisplayList, DrawItem, FillRule, Geometry, Shape,
         Paint as RivePaint, StrokeCap, StrokeJoin, GradientStop},
 };
@@ -125,6 +125,28 @@ fn draw_image(blctx: &mut BLContext, image: &super::display_list::Image,
     }
     let image_data = &assets[&image.asset_id];
     let (width, height) = (image_data.width(), image_data.height());
+    if let Some(mesh) = &image.mesh {
+        for (corners, map) in mesh_triangles(mesh, width as _, height as _) {
+            // Clip to each triangle in world space, then blit its affine-mapped image.
+            let (trfm, mut path) = (image.trfm.then(map), BLPath::new());
+            let corners = corners.map(|corner| trfm.transform_point(corner));
+            path.move_to((corners[0].x, corners[0].y).into());
+            path.line_to((corners[1].x, corners[1].y).into());
+            path.line_to((corners[2].x, corners[2].y).into());   path.close();
+            let trfm = BLMatrix2D::new([trfm.xx as _, trfm.yx as _,
+                trfm.xy as _, trfm.yy as _, trfm.tx as _, trfm.ty as _]);
+            blctx.set_global_alpha(1.0);
+            blctx.set_fill_rule(BLFillRule::BL_FILL_RULE_NON_ZERO);
+            blctx.clip_to_path(&path, |content| {
+                let previous = content.user_transform();
+                content.apply_transform(&trfm);
+                content.set_global_alpha(opacity.clamp(0.0, 1.0) as _);
+                let result = content.blit_image_d((0.0, 0.0).into(),
+                    image_data, &(0, 0, width, height).into());
+                content.reset_transform(Some(&previous)); result
+            })?;
+        }   return Ok(())
+    }
     let (ox, oy) = (width as f32 * image.origin.x, height as f32 * image.origin.y);
     let trfm = BLMatrix2D::new([
         image.trfm.xx as _, image.trfm.yx as _, image.trfm.xy as _, image.trfm.yy as _,
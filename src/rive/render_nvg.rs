@@ -5,7 +5,7 @@ use femtovg::{Canvas, Color, CompositeOperation, ErrorKind, FillRule as VgFillRu
     ImageFlags, LineCap, LineJoin, Paint, Path, PixelFormat, RenderTarget, Solidity,
     Transform2D, renderer::SurfacelessRenderer
 };
-use super::{RenderContext, RenderPath, apply_effects, shape_paths,
+use super::{RenderContext, RenderPath, apply_effects, mesh_triangles, shape_paths,
     display_list::{Brush, Clip, DisplayList, DrawItem, FillRule,
         Paint as RivePaint, StrokeCap, StrokeJoin},
 };
@@ -160,12 +160,29 @@ impl<T: SurfacelessRenderer> FemtovgRenderer<'_, T> {
         let (width, height) = self.canvas.image_size(image_id)?;
         let canvas = &mut *self.canvas;
         let base = canvas.transform();
+        canvas.set_global_alpha(opacity.clamp(0.0, 1.0));
+        if let Some(mesh) = &image.mesh {
+            let mut paint = Paint::image(
+                image_id, 0.0, 0.0, width as _, height as _, 0.0, 1.0);
+            // Antialiased edges would leave seams between adjacent triangles.
+            paint.set_anti_alias(false);
+            for (corners, map) in mesh_triangles(mesh, width as _, height as _) {
+                let trfm = image.trfm.then(map);
+                canvas.set_transform(&Transform2D::new(
+                    trfm.xx, trfm.yx, trfm.xy, trfm.yy, trfm.tx, trfm.ty));
+                let mut path = Path::new();
+                path.move_to(corners[0].x, corners[0].y);
+                path.line_to(corners[1].x, corners[1].y);
+                path.line_to(corners[2].x, corners[2].y);   path.close();
+                canvas.fill_path(&path, &paint);
+                canvas.reset_transform(); canvas.set_transform(&base);
+            }   return Ok(())
+        }
         canvas.set_transform(&Transform2D::new(
             image.trfm.xx, image.trfm.yx,  image.trfm.xy,
             image.trfm.yy, image.trfm.tx,  image.trfm.ty));
         canvas.translate(-(width as f32) * image.origin.x,
                         -(height as f32) * image.origin.y);
-        canvas.set_global_alpha(opacity.clamp(0.0, 1.0));
 
         let mut path = Path::new();
         path.rect(0.0, 0.0, width as _, height as _);
@@ -90,16 +90,165 @@ fn embedded_image_file(mut objects: Vec<Object>, bytes: &[u8]) -> RiveFile {
     assert!((display_list(&runtime)[0].image.as_ref().unwrap().origin.x - 0.5).abs() < 1e-5);
 }
 
-#[test] fn reports_textured_mesh_images_as_unsupported() {
+#[test] fn reports_nine_slice_images_as_unsupported() {
     let mut image = parented(object_ids::IMAGE, 0);
     uint_prop(&mut image, property_ids::IMAGE_ASSETID, 0);
-    let mesh = parented(object_ids::MESH, 1);
+    let slicer = parented(object_ids::N_SLICER, 1);
     let runtime = Runtime::from_file(
-        embedded_image_file(vec![artboard(), image, mesh], b"image")).unwrap();
+        embedded_image_file(vec![artboard(), image, slicer], b"image")).unwrap();
     assert_eq!(runtime.unsupported_features(), &[UnsupportedFeature::Images]);
     assert!(display_list(&runtime).is_empty());
 }
 
+fn mesh_vertex(mesh: u32, x: f32, y: f32, u: f32, v: f32) -> Object {
+    let mut vertex = parented(object_ids::MESH_VERTEX, mesh);
+    prop(&mut vertex, property_ids::VERTEX_X, x);  prop(&mut vertex, property_ids::VERTEX_Y, y);
+    prop(&mut vertex, property_ids::U, u);         prop(&mut vertex, property_ids::V, v);  vertex
+}
+
+/// An image at x = 30 with a three-vertex mesh(2), vertices 3 to 5 and `extra` objects.
+fn mesh_scene(extra: Vec<Object>) -> Runtime {
+    let mut image = parented(object_ids::IMAGE, 0);
+    uint_prop(&mut image, property_ids::IMAGE_ASSETID, 0);
+    prop(&mut image, property_ids::NODE_X, 30.0);
+    let mut mesh = parented(object_ids::MESH, 1);
+    mesh.add_prop(VarUInt(property_ids::TRIANGLEINDEXBYTES), FieldValue::Bytes(vec![0, 1, 2]));
+    let mut objects = vec![artboard(), image, mesh, mesh_vertex(2, 0.0, 0.0, 0.0, 0.0),
+        mesh_vertex(2, 10.0, 0.0, 1.0, 0.0), mesh_vertex(2, 0.0, 10.0, 0.0, 1.0)];
+    objects.extend(extra);
+    Runtime::from_file(embedded_image_file(objects, b"image")).unwrap()
+}
+
+#[test] fn emits_and_animates_textured_image_meshes() {
+    let mut runtime = mesh_scene(vec![linear_animation(b"stretch", 10, 10, 0),
+        keyed_object(4), keyed_property(property_ids::VERTEX_X),
+        double_keyframe(0, 10.0, 1), double_keyframe(10, 20.0, 1)]);
+    assert!(runtime.is_fully_supported());
+    let list = display_list(&runtime);
+    let image = list[0].image.as_ref().unwrap();
+    let mesh = image.mesh.as_ref().unwrap();
+    assert_eq!((image.trfm.tx, &*mesh.indices), (30.0, &[0, 1, 2][..]));
+    assert_eq!(mesh.uvs[1], Point { x: 1.0, y: 0.0 });
+
+    runtime.set_animation(0).unwrap();
+    runtime.advance(0.5);
+    let list = display_list(&runtime);
+    assert_eq!(list[0].image.as_ref().unwrap().mesh.as_ref().unwrap().vertices[1],
+        Point { x: 15.0, y: 0.0 });
+}
+
+/// A root bone(1) at x = 10 with length 10 and its child bone(2) at the tip; `skin` binds
+/// to tendons(6, 7) of both bones and deforms its parent.
+fn skeleton(skin: u32) -> Vec<Object> {
+    let mut root = parented(object_ids::ROOT_BONE, 0);
+    prop(&mut root, property_ids::ROOTBONE_X, 10.0);
+    prop(&mut root, property_ids::BONE_LENGTH, 10.0);
+    let mut bone = parented(object_ids::BONE, 1);
+    prop(&mut bone, property_ids::BONE_LENGTH, 5.0);
+    let tendon = |bone_id, x| {
+        let mut tendon = parented(object_ids::TENDON, skin);
+        uint_prop(&mut tendon, property_ids::TENDON_BONEID, bone_id);
+        prop(&mut tendon, property_ids::TENDON_TX, x);  tendon
+    };  vec![root, bone, tendon(1, 10.0), tendon(2, 20.0)]
+}
+
+fn weight(vertex: u32, values: u32, indices: u32) -> Object {
+    let mut weight = parented(object_ids::WEIGHT, vertex);
+    uint_prop(&mut weight, property_ids::WEIGHT_VALUES, values);
+    uint_prop(&mut weight, property_ids::WEIGHT_INDICES, indices); weight
+}
+
+#[test] fn skins_points_paths_with_weighted_bones() {
+    // The path sits at x = 100; its second vertex weighs 128 on the root and 127 on the child.
+    let mut path = parented(object_ids::POINTS_PATH, 3);
+    prop(&mut path, property_ids::NODE_X, 100.0);
+    let mut second = parented(object_ids::STRAIGHT_VERTEX, 4);
+    prop(&mut second, property_ids::VERTEX_X, 10.0);
+    let mut skin = parented(object_ids::SKIN, 4);
+    prop(&mut skin, property_ids::SKIN_TX, 100.0);
+    let mut tendons = skeleton(9);
+    let mut objects = vec![artboard()];
+    objects.extend(tendons.drain(..2));
+    objects.extend([parented(object_ids::SHAPE, 0), path,
+        parented(object_ids::STRAIGHT_VERTEX, 4), weight(5, 255, 1),
+        second, weight(7, 128 | 127 << 8, 1 | 2 << 8), skin]);
+    objects.extend(tendons);
+    objects.extend([linear_animation(b"bend", 10, 10, 0),
+        keyed_object(2), keyed_property(property_ids::TRANSFORMCOMPONENT_ROTATION),
+        double_keyframe(0, 0.0, 1), double_keyframe(10, f32::consts::FRAC_PI_2, 1),
+        linear_animation(b"grow", 10, 10, 0),
+        keyed_object(1), keyed_property(property_ids::BONE_LENGTH),
+        double_keyframe(0, 10.0, 1), double_keyframe(10, 20.0, 1)]);
+    let mut runtime = Runtime::from_file(file(objects)).unwrap();
+    assert!(runtime.is_fully_supported());
+    let points = |runtime: &Runtime| {
+        let list = display_list(runtime);
+        assert_eq!(list[0].shapes[0].trfm, Affine::default());
+        let Geometry::Path(path) = &list[0].shapes[0].geom else { panic!() };
+        let [PathCommand::MoveTo(first), PathCommand::LineTo(second)] = path.cmd[..] else {
+            panic!()
+        };  (first, second)
+    };
+    assert_eq!(points(&runtime), (Point { x: 100.0, y: 0.0 }, Point { x: 110.0, y: 0.0 }));
+
+    // Rotating the child bone about its head at x = 20 swings half of the second vertex.
+    runtime.set_animation(0).unwrap();
+    runtime.advance(1.0);
+    let (first, second) = points(&runtime);
+    assert_eq!(first, Point { x: 100.0, y: 0.0 });
+    let expected = Point { x: (128.0 * 110.0 + 127.0 * 20.0) / 255.0, y: 127.0 * 90.0 / 255.0 };
+    assert!((second.x - expected.x).abs() < 1e-3 && (second.y - expected.y).abs() < 1e-3);
+
+    // Lengthening the root bone moves the child bone's head along with it.
+    runtime.set_animation(1).unwrap();
+    runtime.advance(1.0);
+    let (_, second) = points(&runtime);
+    assert!((second.x - (110.0 + 10.0 * 127.0 / 255.0)).abs() < 1e-3 && second.y.abs() < 1e-3);
+}
+
+#[test] fn skins_image_meshes_and_animates_root_bones() {
+    // The mesh binds at its image's x = 30, with vertices on the root bone only.
+    let mut skin = parented(object_ids::SKIN, 2);
+    prop(&mut skin, property_ids::SKIN_TX, 30.0);
+    let mut extra = vec![weight(3, 255, 1), weight(4, 255, 1), weight(5, 255, 1), skin];
+    extra.extend(skeleton(9).into_iter().map(|mut object| {
+        // Shift bone parents and tendon bone ids past the mesh objects.
+        for (id, value) in &mut object.props {
+            if let FieldValue::VarUInt(value) = value {
+                if matches!(id.0, property_ids::COMPONENT_PARENTID | property_ids::TENDON_BONEID)
+                    && value.0 != 0 && value.0 != 9 { value.0 += 9 }
+            }
+        }   object
+    }));
+    extra.extend([linear_animation(b"slide", 10, 10, 0),
+        keyed_object(10), keyed_property(property_ids::ROOTBONE_X),
+        double_keyframe(0, 10.0, 1), double_keyframe(10, 15.0, 1)]);
+    let mut runtime = mesh_scene(extra);
+    let vertices = |runtime: &Runtime| {
+        let list = display_list(runtime);
+        let image = list[0].image.clone().unwrap();
+        assert_eq!(image.trfm, Affine::default());
+        image.mesh.unwrap().vertices
+    };
+    assert_eq!(vertices(&runtime)[1], Point { x: 40.0, y: 0.0 });
+    runtime.set_animation(0).unwrap();
+    runtime.advance(1.0);
+    assert_eq!(vertices(&runtime)[..], [Point { x: 35.0, y: 0.0 },
+        Point { x: 45.0, y: 0.0 }, Point { x: 35.0, y: 10.0 }]);
+}
+
+#[test] fn rejects_invalid_skin_owners_and_tendon_bones() {
+    assert!(matches!(Runtime::from_file(file(vec![artboard(), parented(object_ids::SKIN, 0)])),
+        Err(RuntimeError::InvalidSkinOwner(1))));
+    let mut objects = vec![artboard(),
+        parented(object_ids::POINTS_PATH, 0), parented(object_ids::SKIN, 1)];
+    let mut tendon = parented(object_ids::TENDON, 2);
+    uint_prop(&mut tendon, property_ids::TENDON_BONEID, 1);
+    objects.push(tendon);
+    assert!(matches!(Runtime::from_file(file(objects)),
+        Err(RuntimeError::InvalidTendonBone(1))));
+}
+
 fn parented(type_id: u32, parent: u32) -> Object {
     let mut object = Object::new_simple(type_id);
     object.add_prop(VarUInt(property_ids::COMPONENT_PARENTID),
@@ -364,15 +513,15 @@ fn nested_scene(controller: Option<Object>) -> RiveFile {
 
 #[test] fn reports_unsupported_rive_subsystems_once() {
     let runtime = Runtime::from_file(file(vec![artboard(),
-        parented(object_ids::BONE,  0), parented(object_ids::SKIN, 0),
         parented(object_ids::IMAGE, 0), parented(object_ids::NESTED_ARTBOARD, 0),
-        parented(object_ids::STATE_MACHINE,  0), parented(object_ids::TEXT, 0),
+        Object::new_simple(object_ids::LISTENER_ALIGN_TARGET),
+        parented(object_ids::TEXT, 0),
         parented(object_ids::I_K_CONSTRAINT, 0),
     ])).unwrap();
     assert_eq!(runtime.unsupported_features(), &[
-        UnsupportedFeature::BonesAndSkins, UnsupportedFeature::AdvancedConstraints,
-        UnsupportedFeature::Images, UnsupportedFeature::NestedArtboards,
-        UnsupportedFeature::StateMachines, UnsupportedFeature::Text,
+        UnsupportedFeature::AdvancedConstraints, UnsupportedFeature::Images,
+        UnsupportedFeature::NestedArtboards, UnsupportedFeature::StateMachines,
+        UnsupportedFeature::Text,
     ]);
     assert!(!runtime.is_fully_supported());
     assert!(Runtime::from_file(file(vec![artboard()])).unwrap().is_fully_supported());
@@ -780,6 +929,349 @@ fn nested_scene(controller: Option<Object>) -> RiveFile {
     assert_eq!((transform.tx, transform.ty), (0.0, 30.0));
 }
 
+fn named(type_id: u32, prop_id: u32, name: &[u8]) -> Object {
+    let mut object = Object::new_simple(type_id);
+    object.add_prop(VarUInt(prop_id), FieldValue::Bytes(name.to_vec()));  object
+}
+
+fn animation_state(animation: u32) -> Object {
+    let mut state = Object::new_simple(object_ids::ANIMATION_STATE);
+    uint_prop(&mut state, property_ids::ANIMATIONSTATE_ANIMATIONID, animation); state
+}
+
+fn transition(target: u32, flags: u32, duration: u32, exit_time: u32) -> Object {
+    let mut transition = Object::new_simple(object_ids::STATE_TRANSITION);
+    uint_prop(&mut transition, property_ids::STATETOID, target);
+    uint_prop(&mut transition, property_ids::STATETRANSITION_FLAGS, flags);
+    uint_prop(&mut transition, property_ids::STATETRANSITION_DURATION, duration);
+    uint_prop(&mut transition, property_ids::EXITTIME, exit_time);  transition
+}
+
+fn condition(type_id: u32, input: u32, op: u32) -> Object {
+    let mut condition = Object::new_simple(type_id);
+    uint_prop(&mut condition, property_ids::TRANSITIONCONDITION_INPUTID, input);
+    uint_prop(&mut condition, property_ids::OPVALUE, op);   condition
+}
+
+/// An ellipse held at x = 0 by `left` and x = 20 by `right` and a `changed` event(2), with
+/// one machine of `on`, `level` and `fire` inputs whose layer has states
+/// entry(0) -> any(1), left(2), right(3), exit(4).
+fn state_machine_scene(any: Vec<Object>, left: Vec<Object>, right: Vec<Object>) -> RiveFile {
+    let mut objects = vec![artboard(), parented(object_ids::ELLIPSE, 0), event(b"changed", 0),
+        linear_animation(b"left", 10, 10, 1), keyed_object(1),
+        keyed_property(property_ids::NODE_X), double_keyframe(0, 0.0, 1),
+        linear_animation(b"right", 10, 10, 1), keyed_object(1),
+        keyed_property(property_ids::NODE_X), double_keyframe(0, 20.0, 1),
+        named(object_ids::STATE_MACHINE, property_ids::ANIMATION_NAME, b"machine"),
+        named(object_ids::STATE_MACHINE_BOOL, property_ids::STATEMACHINECOMPONENT_NAME, b"on"),
+        named(object_ids::STATE_MACHINE_NUMBER,
+            property_ids::STATEMACHINECOMPONENT_NAME, b"level"),
+        named(object_ids::STATE_MACHINE_TRIGGER,
+            property_ids::STATEMACHINECOMPONENT_NAME, b"fire"),
+        Object::new_simple(object_ids::STATE_MACHINE_LAYER),
+        Object::new_simple(object_ids::ENTRY_STATE), transition(2, 0, 0, 0),
+        Object::new_simple(object_ids::ANY_STATE)];
+    objects.extend(any);
+    objects.push(animation_state(0));   objects.extend(left);
+    objects.push(animation_state(1));   objects.extend(right);
+    objects.push(Object::new_simple(object_ids::EXIT_STATE));   file(objects)
+}
+
+fn shape_x(runtime: &Runtime) -> f32 { display_list(runtime)[0].shapes[0].trfm.tx }
+
+#[test] fn state_machine_follows_bool_and_number_conditions() {
+    let mut level = condition(object_ids::TRANSITION_NUMBER_CONDITION, 1, 3);
+    prop(&mut level, property_ids::TRANSITIONNUMBERCONDITION_VALUE, 5.0);
+    let mut runtime = Runtime::from_file(state_machine_scene(Vec::new(),
+        vec![transition(3, 0, 0, 0), condition(object_ids::TRANSITION_BOOL_CONDITION, 0, 0)],
+        vec![transition(2, 0, 0, 0), level])).unwrap();
+    assert_eq!(runtime.state_machine_count(), 1);
+    assert_eq!(runtime.state_machine_name(0), Some(&b"machine"[..]));
+    runtime.set_state_machine_by_name(b"machine").unwrap();
+    assert!(runtime.is_fully_supported());
+    assert_eq!(shape_x(&runtime), 0.0);
+
+    let machine = runtime.state_machine_mut().unwrap();
+    assert_eq!(machine.input(0), Some((&b"on"[..], InputValue::Bool(false))));
+    machine.set_bool(b"on", true).unwrap();
+    assert!(runtime.advance(0.1));
+    assert_eq!(shape_x(&runtime), 20.0);
+
+    let machine = runtime.state_machine_mut().unwrap();
+    machine.set_bool(b"on", false).unwrap();
+    machine.set_number(b"level", 4.0).unwrap();
+    runtime.advance(0.1);
+    assert_eq!(shape_x(&runtime), 20.0);
+    runtime.state_machine_mut().unwrap().set_number(b"level", 5.0).unwrap();
+    runtime.advance(0.1);
+    assert_eq!(shape_x(&runtime), 0.0);
+
+    let machine = runtime.state_machine_mut().unwrap();
+    assert!(matches!(machine.set_bool(b"level", true), Err(RuntimeError::InputNotFound)));
+    assert!(matches!(machine.fire_trigger(b"none"), Err(RuntimeError::InputNotFound)));
+    assert!(matches!(runtime.set_state_machine(1),
+        Err(RuntimeError::StateMachineNotFound(1))));
+}
+
+#[test] fn state_machine_triggers_fire_once_and_wait_for_exit_time() {
+    let mut runtime = Runtime::from_file(state_machine_scene(
+        vec![transition(3, 0, 0, 0), condition(object_ids::TRANSITION_TRIGGER_CONDITION, 2, 0)],
+        Vec::new(), vec![transition(2, 4 | 8, 0, 50), fire_event(2, 0)])).unwrap();
+    runtime.set_state_machine(0).unwrap();
+    runtime.state_machine_mut().unwrap().fire_trigger(b"fire").unwrap();
+    runtime.advance(0.1);
+    assert_eq!(shape_x(&runtime), 20.0);
+    assert_eq!(runtime.state_machine().unwrap().input(2),
+        Some((&b"fire"[..], InputValue::Trigger(false))));
+
+    // The exit time is 50% of the one-second `right` animation, passed 0.1 seconds
+    // into the last advance.
+    assert!(runtime.advance(0.4));
+    assert_eq!(shape_x(&runtime), 20.0);
+    assert!(fired_events(&mut runtime).is_empty());
+    runtime.advance(0.2);
+    assert_eq!(shape_x(&runtime), 0.0);
+    let fired = fired_events(&mut runtime);
+    assert!(fired.len() == 1 && (fired[0].1 - 0.1).abs() < 1e-5);
+}
+
+#[test] fn state_machine_crossfades_transitions_through_the_mix() {
+    let mut runtime = Runtime::from_file(state_machine_scene(Vec::new(),
+        vec![transition(3, 0, 1000, 0), condition(object_ids::TRANSITION_BOOL_CONDITION, 0, 0)],
+        Vec::new())).unwrap();
+    runtime.set_state_machine(0).unwrap();
+    runtime.state_machine_mut().unwrap().set_bool(b"on", true).unwrap();
+    runtime.advance(0.01);
+    assert_eq!(shape_x(&runtime), 0.0);
+    assert!(runtime.advance(0.5));
+    assert!((shape_x(&runtime) - 10.0).abs() < 1e-4);
+    runtime.advance(0.5);
+    assert_eq!(shape_x(&runtime), 20.0);
+
+    // Switching back to a plain animation drops the machine and restores its targets.
+    runtime.set_animation(0).unwrap();
+    assert!(runtime.state_machine().is_none());
+    assert_eq!(shape_x(&runtime), 0.0);
+}
+
+fn event(name: &[u8], parent: u32) -> Object {
+    let mut event = parented(object_ids::EVENT, parent);
+    event.add_prop(VarUInt(property_ids::COMPONENT_NAME), FieldValue::Bytes(name.to_vec()));
+    event
+}
+
+fn fire_event(event: u32, occurs: u32) -> Object {
+    let mut fire = Object::new_simple(object_ids::STATE_MACHINE_FIRE_EVENT);
+    uint_prop(&mut fire, property_ids::STATEMACHINEFIREEVENT_EVENTID, event);
+    uint_prop(&mut fire, property_ids::OCCURSVALUE, occurs);  fire
+}
+
+fn fired_events(runtime: &mut Runtime) -> Vec<(Vec<u8>, f32)> {
+    runtime.drain_events().map(|fired| (fired.event.name.clone(), fired.time)).collect()
+}
+
+#[test] fn events_decode_properties_and_fire_from_callback_keyframes() {
+    let mut link = parented(object_ids::OPEN_URL_EVENT, 0);
+    link.add_prop(VarUInt(property_ids::COMPONENT_NAME), FieldValue::Bytes(b"link".to_vec()));
+    link.add_prop(VarUInt(property_ids::URL), FieldValue::Bytes(b"https://rive.app".to_vec()));
+    uint_prop(&mut link, property_ids::TARGETVALUE, 2);
+    let mut volume = event(b"volume", 1);
+    volume.type_id = VarUInt(object_ids::CUSTOM_PROPERTY_NUMBER);
+    prop(&mut volume, property_ids::CUSTOMPROPERTYNUMBER_PROPERTYVALUE, 0.5);
+    let mut label = event(b"label", 1);
+    label.type_id = VarUInt(object_ids::CUSTOM_PROPERTY_STRING);
+    label.add_prop(VarUInt(property_ids::CUSTOMPROPERTYSTRING_PROPERTYVALUE),
+        FieldValue::Bytes(b"left".to_vec()));
+    let callback = |frame| {
+        let mut keyframe = Object::new_simple(object_ids::KEY_FRAME_CALLBACK);
+        uint_prop(&mut keyframe, property_ids::FRAME, frame);   keyframe
+    };
+    // 395 is Event.trigger, the callback property keyed by event timelines.
+    let mut runtime = Runtime::from_file(file(vec![artboard(), event(b"step", 0), volume,
+        label, link, linear_animation(b"walk", 10, 10, 1),
+        keyed_object(1), keyed_property(395), callback(5),
+        keyed_object(4), keyed_property(395), callback(0)])).unwrap();
+    assert_eq!(runtime.events().len(), 2);
+    assert_eq!(runtime.events()[0].properties, [
+        (b"volume".to_vec(), PropertyValue::Number(0.5)),
+        (b"label".to_vec(), PropertyValue::String(b"left".to_vec()))]);
+    assert_eq!(runtime.events()[1].kind,
+        EventKind::OpenUrl { url: b"https://rive.app".to_vec(), target: 2 });
+
+    runtime.set_animation(0).unwrap();
+    runtime.advance(0.4);
+    assert_eq!(fired_events(&mut runtime), [(b"link".to_vec(), 0.0)]);
+    runtime.advance(0.2);
+    let fired = fired_events(&mut runtime);
+    assert_eq!(fired.len(), 1);
+    assert!(fired[0].0 == b"step" && (fired[0].1 - 0.1).abs() < 1e-5);
+
+    // Looping passes frame 0 again at one second, before frame 5 at 1.5 seconds.
+    runtime.advance(1.0);
+    let fired = fired_events(&mut runtime);
+    assert_eq!(fired.iter().map(|fired| &fired.0[..]).collect::<Vec<_>>(),
+        [&b"link"[..], b"step"]);
+    assert!((fired[0].1 - 0.4).abs() < 1e-5 && (fired[1].1 - 0.9).abs() < 1e-5);
+    assert!(runtime.drain_events().next().is_none());
+
+    // Undrained events are capped, keeping the latest.
+    for _ in 0..100 { runtime.advance(1.0); }
+    let fired = fired_events(&mut runtime);
+    assert!(fired.len() == 64 && fired[63].0 == b"step");
+}
+
+#[test] fn state_machine_fires_state_and_transition_events() {
+    let mut runtime = Runtime::from_file(state_machine_scene(Vec::new(),
+        vec![fire_event(2, 1), transition(3, 0, 500, 0),
+            condition(object_ids::TRANSITION_BOOL_CONDITION, 0, 0),
+            fire_event(2, 0), fire_event(2, 1)],
+        vec![fire_event(2, 0)])).unwrap();
+    runtime.set_state_machine(0).unwrap();
+    runtime.advance(0.1);
+    assert!(fired_events(&mut runtime).is_empty());
+
+    // Leaving `left`, starting the transition and entering `right` all report at the start
+    // of the advance the input was set before; the transition's end waits for its crossfade,
+    // completing 0.2 seconds into the last advance.
+    runtime.state_machine_mut().unwrap().set_bool(b"on", true).unwrap();
+    runtime.advance(0.1);
+    assert_eq!(fired_events(&mut runtime), vec![(b"changed".to_vec(), 0.0); 3]);
+    runtime.advance(0.3);
+    assert!(fired_events(&mut runtime).is_empty());
+    runtime.advance(0.25);
+    let fired = fired_events(&mut runtime);
+    assert!(fired.len() == 1 && (fired[0].1 - 0.2).abs() < 1e-5);
+}
+
+fn blend_animation(type_id: u32, animation: u32, prop_id: u32, value: f32) -> Object {
+    let mut blend = Object::new_simple(type_id);
+    uint_prop(&mut blend, property_ids::BLENDANIMATION_ANIMATIONID, animation);
+    prop(&mut blend, prop_id, value);   blend
+}
+
+/// The state machine scene with a blend state(4) of `blend` animations after `right`,
+/// entered from `left` while `on` is set.
+fn blend_scene(state: Object, blend: Vec<Object>) -> Runtime {
+    let mut right = vec![state];    right.extend(blend);
+    let mut runtime = Runtime::from_file(state_machine_scene(Vec::new(),
+        vec![transition(4, 0, 0, 0), condition(object_ids::TRANSITION_BOOL_CONDITION, 0, 0)],
+        right)).unwrap();
+    runtime.set_state_machine(0).unwrap();
+    runtime.state_machine_mut().unwrap().set_bool(b"on", true).unwrap();
+    runtime
+}
+
+#[test] fn state_machine_blends_1d_by_the_input_value() {
+    let mut state = Object::new_simple(object_ids::BLEND_STATE1_D);
+    uint_prop(&mut state, property_ids::BLENDSTATE1D_INPUTID, 1);
+    let mut runtime = blend_scene(state, vec![
+        blend_animation(object_ids::BLEND_ANIMATION1_D, 0,
+            property_ids::BLENDANIMATION1D_VALUE, 0.0),
+        blend_animation(object_ids::BLEND_ANIMATION1_D, 1,
+            property_ids::BLENDANIMATION1D_VALUE, 10.0)]);
+    assert!(runtime.is_fully_supported());
+    for (level, x) in [(2.5, 5.0), (10.0, 20.0), (25.0, 20.0), (-5.0, 0.0), (5.0, 10.0)] {
+        runtime.state_machine_mut().unwrap().set_number(b"level", level).unwrap();
+        assert!(runtime.advance(0.1));
+        assert!((shape_x(&runtime) - x).abs() < 1e-4, "level {level}");
+    }
+}
+
+#[test] fn state_machine_blends_direct_by_inputs_and_mix_values() {
+    let mut fixed = blend_animation(object_ids::BLEND_ANIMATION_DIRECT, 1,
+        property_ids::MIXVALUE, 25.0);
+    uint_prop(&mut fixed, property_ids::BLENDSOURCE, 1);
+    let mut driven = blend_animation(object_ids::BLEND_ANIMATION_DIRECT, 1,
+        property_ids::MIXVALUE, 100.0);
+    uint_prop(&mut driven, property_ids::BLENDANIMATIONDIRECT_INPUTID, 1);
+    let mut runtime = blend_scene(Object::new_simple(object_ids::BLEND_STATE_DIRECT),
+        vec![fixed, driven]);
+    runtime.advance(0.1);
+    assert!((shape_x(&runtime) - 5.0).abs() < 1e-4);
+
+    // Input percentages are clamped to a full mix.
+    for (level, x) in [(50.0, 12.5), (150.0, 20.0)] {
+        runtime.state_machine_mut().unwrap().set_number(b"level", level).unwrap();
+        runtime.advance(0.1);
+        assert!((shape_x(&runtime) - x).abs() < 1e-4, "level {level}");
+    }
+}
+
+fn listener(target: u32, kind: u32, action: u32, input: u32) -> [Object; 2] {
+    let mut listener = Object::new_simple(object_ids::STATE_MACHINE_LISTENER);
+    uint_prop(&mut listener, property_ids::STATEMACHINELISTENER_TARGETID, target);
+    uint_prop(&mut listener, property_ids::LISTENERTYPEVALUE, kind);
+    let mut action = Object::new_simple(action);
+    uint_prop(&mut action, property_ids::LISTENERINPUTCHANGE_INPUTID, input);
+    [listener, action]
+}
+
+/// The clipped scene's 40x40 target rectangle, visible only where the clip ellipse spans
+/// x = 2..22, with `on`, `level` and `fire` inputs changed by `listeners`.
+fn listener_scene(listeners: Vec<[Object; 2]>) -> Result<Runtime> {
+    let mut objects = clipped_scene();
+    prop(&mut objects[6], property_ids::PARAMETRICPATH_WIDTH,  40.0);
+    prop(&mut objects[6], property_ids::PARAMETRICPATH_HEIGHT, 40.0);
+    objects.extend([
+        named(object_ids::STATE_MACHINE, property_ids::ANIMATION_NAME, b"buttons"),
+        named(object_ids::STATE_MACHINE_BOOL, property_ids::STATEMACHINECOMPONENT_NAME, b"on"),
+        named(object_ids::STATE_MACHINE_NUMBER,
+            property_ids::STATEMACHINECOMPONENT_NAME, b"level"),
+        named(object_ids::STATE_MACHINE_TRIGGER,
+            property_ids::STATEMACHINECOMPONENT_NAME, b"fire")]);
+    objects.extend(listeners.into_iter().flatten());
+    let mut runtime = Runtime::from_file(file(objects))?;
+    runtime.set_state_machine(0)?;  Ok(runtime)
+}
+
+#[test] fn pointer_listeners_hit_test_clipped_shapes_and_change_inputs() {
+    let [down, mut toggle] = listener(5, 2, object_ids::LISTENER_BOOL_CHANGE, 0);
+    uint_prop(&mut toggle, property_ids::LISTENERBOOLCHANGE_VALUE, 2);
+    let [enter, mut level] = listener(3, 0, object_ids::LISTENER_NUMBER_CHANGE, 1);
+    prop(&mut level, property_ids::LISTENERNUMBERCHANGE_VALUE, 7.0);
+    let mut runtime = listener_scene(vec![[down, toggle], [enter, level],
+        listener(5, 1, object_ids::LISTENER_TRIGGER_CHANGE, 2)]).unwrap();
+    let input = |runtime: &Runtime, index| runtime.state_machine().unwrap().input(index).unwrap().1;
+
+    // The rectangle covers x = -10 but its clip does not.
+    assert!(!runtime.pointer_move(-10.0, 0.0));
+    assert!(!runtime.pointer_down(30.0, 0.0));
+    assert!( runtime.pointer_move(10.0, 0.0));
+    assert_eq!(input(&runtime, 1), InputValue::Number(7.0));
+    assert!( runtime.pointer_down(10.0, 0.0));
+    assert_eq!(input(&runtime, 0), InputValue::Bool(true));
+    assert!(!runtime.pointer_up  (10.0, 0.0));
+    runtime.pointer_down(10.0, 0.0);
+    assert_eq!(input(&runtime, 0), InputValue::Bool(false));
+
+    // Leaving the clip ellipse (x = 12 ± 10, y = ±5) fires the exit listener once.
+    assert_eq!(input(&runtime, 2), InputValue::Trigger(false));
+    assert!( runtime.pointer_move(10.0, 5.0));
+    assert_eq!(input(&runtime, 2), InputValue::Trigger(true));
+    assert!(!runtime.pointer_move(30.0, 0.0));
+
+    assert!(matches!(listener_scene(vec![listener(99, 0, object_ids::LISTENER_BOOL_CHANGE, 0)]),
+        Err(RuntimeError::InvalidListenerTarget(99))));
+    assert!(matches!(listener_scene(vec![listener(5, 0, object_ids::LISTENER_BOOL_CHANGE, 4)]),
+        Err(RuntimeError::InvalidInput(4))));
+}
+
+#[test] fn rejects_invalid_state_machine_references() {
+    let scene = |right| Runtime::from_file(state_machine_scene(Vec::new(), Vec::new(), right));
+    assert!(matches!(scene(vec![transition(9, 0, 0, 0)]), Err(RuntimeError::InvalidState(9))));
+    assert!(matches!(scene(vec![transition(2, 0, 0, 0),
+        condition(object_ids::TRANSITION_BOOL_CONDITION, 7, 0)]),
+        Err(RuntimeError::InvalidInput(7))));
+    assert!(matches!(scene(vec![animation_state(5)]), Err(RuntimeError::AnimationNotFound(5))));
+    assert!(matches!(scene(vec![fire_event(99, 0)]), Err(RuntimeError::InvalidEvent(99))));
+    let mut blend = Object::new_simple(object_ids::BLEND_STATE1_D);
+    uint_prop(&mut blend, property_ids::BLENDSTATE1D_INPUTID, 6);
+    assert!(matches!(scene(vec![blend]), Err(RuntimeError::InvalidInput(6))));
+    assert!(matches!(scene(vec![Object::new_simple(object_ids::BLEND_STATE_DIRECT),
+        blend_animation(object_ids::BLEND_ANIMATION_DIRECT, 8, property_ids::MIXVALUE, 0.0)]),
+        Err(RuntimeError::AnimationNotFound(8))));
+}
+
 #[test] fn rejects_unknown_keyframe_interpolation_and_cubic_reference() {
     let animation_file = |interpolation, interpolator| file(vec![
         artboard(), parented(object_ids::ELLIPSE, 0),
@@ -1360,6 +1852,9 @@ fn nested_scene(controller: Option<Object>) -> RiveFile {
     assert!(0 < runtime.animation_count());
     runtime.set_animation(0).unwrap();
     assert!(runtime.advance(1.0 / 60.0));
+    let machine = runtime.default_state_machine().unwrap();
+    runtime.set_state_machine(machine).unwrap();
+    runtime.advance(1.0 / 60.0);
     let list =  display_list(&runtime);
     assert!(list.iter().flat_map(|item| item.shapes.iter())
         .any(|geometry| matches!(&geometry.geom, Geometry::Path(_))));
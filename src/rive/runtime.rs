@@ -10,7 +10,7 @@ use super::{animation::{LinearAnimation, TrackValue, build_animations},
     },
     decode::{self, DecodeError, Object, RiveFile, object_ids, property_ids,
         core_boolean_default, core_color_default, core_float_default, core_varuint_default,
-        core_is_component, core_is_transform_component,
+        core_is_component, core_is_transform_component, objects,
     }, path::{GeomParams, Vertex, VertexParams, build_path},
 };
 
@@ -20,6 +20,14 @@ use shape::fill_rule;
 #[path = "track.rs"] pub(super) mod track;
 #[path = "constraint.rs"] mod constraint;
 use constraint::{Constraint, apply_constraints, sort_constraints};
+#[path = "state_machine.rs"] mod state_machine;
+use state_machine::{PointerEvent, StateMachine, build_state_machines};
+pub use state_machine::{InputValue, StateMachineInstance};
+#[path = "event.rs"] mod event;
+use event::build_events;
+pub use event::{Event, EventKind, FiredEvent, PropertyValue};
+#[path = "skin.rs"] mod skin;
+use skin::{ComponentBone, ComponentMesh, Skin, deform_skins, link_bones};
 
 pub type Result<T> = std::result::Result<T, RuntimeError>;
 
@@ -29,6 +37,9 @@ pub type Result<T> = std::result::Result<T, RuntimeError>;
     InvalidTrimMode(u32), ParentCycle(u32), TooManyObjects, TooManyVertices(u32),
     NestedArtboardCycle(u32), ConstraintCycle(u32), InvalidConstraintOwner(u32),
     ArtboardNotFound(u32), InvalidConstraintTarget(u32),
+    StateMachineNotFound(u32), StateMachineNameNotFound, InputNotFound,
+    InvalidState(u32), InvalidInput(u32), InvalidListenerTarget(u32), InvalidEvent(u32),
+    InvalidSkinOwner(u32), InvalidTendonBone(u32),
     InvalidClipSource { comp_id: u32, source_id: u32 },
     InvalidParent { comp_id: u32, parent_id: u32 },
 }
@@ -39,6 +50,20 @@ impl fmt::Display for RuntimeError {
         Self::AnimationNameNotFound => f.write_str("Rive animation name does not exist"),
         Self::AnimationNotFound(index) => write!(f, "Rive animation {index} does not exist"),
         Self::ArtboardNotFound(index) => write!(f, "Rive artboard {index} does not exist"),
+        Self::StateMachineNotFound(index) =>
+            write!(f, "Rive state machine {index} does not exist"),
+        Self::StateMachineNameNotFound =>
+            f.write_str("Rive state machine name does not exist"),
+        Self::InputNotFound =>
+            f.write_str("Rive state-machine input does not exist with that type"),
+        Self::InvalidState(index) =>
+            write!(f, "Rive transition targets missing layer state {index}"),
+        Self::InvalidInput(index) =>
+            write!(f, "Rive state machine references missing input {index}"),
+        Self::InvalidListenerTarget(target_id) =>
+            write!(f, "Rive listener references invalid target {target_id}"),
+        Self::InvalidEvent(event_id) =>
+            write!(f, "Rive state machine references invalid event {event_id}"),
         Self::NestedArtboardCycle(index) =>
             write!(f, "Rive nested-artboard cycle at artboard {index}"),
         Self::DrawOrderCycle(obj_idx) =>
@@ -59,6 +84,10 @@ impl fmt::Display for RuntimeError {
             write!(f, "constraint {obj_idx} has no transform-component owner"),
         Self::InvalidConstraintTarget(target_id) =>
             write!(f, "constraint references invalid target {target_id}"),
+        Self::InvalidSkinOwner(obj_idx) =>
+            write!(f, "skin {obj_idx} is not owned by a points path or mesh"),
+        Self::InvalidTendonBone(bone_id) =>
+            write!(f, "tendon references invalid bone {bone_id}"),
         Self::ConstraintCycle(obj_idx) =>
             write!(f, "Rive constraint dependency cycle at object {obj_idx}"),
         Self::ParentCycle(comp_id) => write!(f, "component parent cycle at {comp_id}"),
@@ -77,7 +106,8 @@ impl From<DecodeError> for RuntimeError {
 
 #[derive(Debug)] enum ComponentGeom {
     Parametric { cached: Geometry, params: GeomParams, dirty: bool },
-    Points { cached: Geometry, vertices: Vec<Vertex>, closed: bool, dirty: bool },
+    /// A skinned path caches world-space geometry, rebuilt by its skin.
+    Points { cached: Geometry, vertices: Vec<Vertex>, closed: bool, dirty: bool, skinned: bool },
 }
 
 impl ComponentGeom {
@@ -126,7 +156,7 @@ impl ComponentGeom {
                 *cached = params.geometry();
                 *dirty = false;
             }
-            Self::Points { cached, vertices, closed, dirty } if *dirty => {
+            Self::Points { cached, vertices, closed, dirty, skinned: false } if *dirty => {
                 *cached = Geometry::Path(build_path(vertices, *closed));
                 *dirty = false;
             }   _ => {}
@@ -140,7 +170,9 @@ impl ComponentGeom {
     source: u32, rule: FillRule, visible: bool, shapes: Vec<u32>,
 }
 
-#[derive(Debug)] struct ComponentImage { asset_id: u32, data: Arc<[u8]>, origin: Point }
+#[derive(Debug)] struct ComponentImage {
+    asset_id: u32, data: Arc<[u8]>, origin: Point, mesh: Option<ComponentMesh>,
+}
 
 impl ComponentImage {
     fn set(&mut self, prop_id: u32, value: f32) -> bool {
@@ -233,6 +265,8 @@ impl ComponentNestedAnimation {
     NestedHost(ComponentNestedHost),
     NestedOrigin(ComponentNestedOrigin),
     NestedAnimation(ComponentNestedAnimation),
+    Bone(ComponentBone),
+    Skin(Skin),
 }
 
 #[derive(Debug)] struct GradientState {
@@ -366,6 +400,12 @@ impl Component {
     fn nested_origin_mut(&mut self) -> Option<&mut ComponentNestedOrigin> {
         if let ComponentData::NestedOrigin(value) = &mut self.data { Some(value) } else { None }
     }
+    fn bone(&self) -> Option<&ComponentBone> {
+        if let ComponentData::Bone(value) = &self.data { Some(value) } else { None }
+    }
+    fn bone_mut(&mut self) -> Option<&mut ComponentBone> {
+        if let ComponentData::Bone(value) = &mut self.data { Some(value) } else { None }
+    }
 }
 
 #[derive(Debug)] struct DrawGroup {
@@ -396,6 +436,7 @@ impl Component {
     Color(ColorTarget),
     Effect(EffectTarget),
     Vertex { path: u32, slot: u32 },
+    MeshVertex { image: u32, slot: u32 },
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)] pub struct AnimationInfo<'a> {
@@ -405,16 +446,15 @@ impl Component {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
 pub enum UnsupportedFeature {
-    BonesAndSkins, AdvancedConstraints, Images, NestedArtboards, StateMachines, Text,
+    AdvancedConstraints, Images, NestedArtboards, StateMachines, Text,
 }
 
 impl fmt::Display for UnsupportedFeature {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str(match self {
-        Self::BonesAndSkins => "bones/skins",
         Self::AdvancedConstraints => "advanced constraints",
         Self::Images => "images",
         Self::NestedArtboards => "nested artboards",
-        Self::StateMachines => "state machines",
+        Self::StateMachines => "state-machine align-target listeners",
         Self::Text => "text",
     }) }
 }
@@ -429,9 +469,10 @@ impl TransformValues {
             return Ok(Self { x: 0.0, y: 0.0, rotation: 0.0,
                 scale_x: 1.0, scale_y: 1.0, opacity: 1.0 })
         }
-        Ok(Self {
-            x: float(object, property_ids::NODE_X)?,
-            y: float(object, property_ids::NODE_Y)?,
+        let (x, y) = if object.type_id.0 == object_ids::ROOT_BONE {
+            (property_ids::ROOTBONE_X, property_ids::ROOTBONE_Y)
+        } else { (property_ids::NODE_X, property_ids::NODE_Y) };
+        Ok(Self { x: float(object, x)?, y: float(object, y)?,
             rotation: float(object, property_ids::TRANSFORMCOMPONENT_ROTATION)?,
             scale_x: float(object, property_ids::TRANSFORMCOMPONENT_SCALEX)?,
             scale_y: float(object, property_ids::TRANSFORMCOMPONENT_SCALEY)?,
@@ -440,8 +481,8 @@ impl TransformValues {
     }
 
     fn set(&mut self, prop_id: u32, value: f32) -> bool { match prop_id {
-        property_ids::NODE_X => replace_changed(&mut self.x, value),
-        property_ids::NODE_Y => replace_changed(&mut self.y, value),
+        property_ids::NODE_X | property_ids::ROOTBONE_X => replace_changed(&mut self.x, value),
+        property_ids::NODE_Y | property_ids::ROOTBONE_Y => replace_changed(&mut self.y, value),
         property_ids::TRANSFORMCOMPONENT_ROTATION =>
             replace_changed(&mut self.rotation, value),
         property_ids::TRANSFORMCOMPONENT_SCALEX =>
@@ -464,7 +505,7 @@ impl TransformValues {
 /// points-path geometry with solid or gradient paint. Animation, constraints,
 /// text and state machines can update this retained state without changing the display-list API.
 ///
-/// TODO: Add text, state machines, skins/deformers, and advanced nested-artboard layout.
+/// TODO: Add text and advanced nested-artboard layout.
 #[derive(Debug)] pub struct Runtime {
     file: Arc<RiveFile>, artboard_obj: u32, artboard_size: (f32, f32), elapsed: f32,
     components: Vec<Component>,
@@ -472,11 +513,16 @@ impl TransformValues {
       gradients: Vec<u32>,
     constraints: Vec<u32>,
     constraint_dirty: Vec<bool>,
+    skins: Vec<u32>,
     draw_groups: Vec<DrawGroup>,
      animations: Vec<LinearAnimation>,
+       mix_base: Vec<Option<TrackValue>>,
+    state_machines: Vec<Arc<StateMachine>>,
     unsupported: Vec<UnsupportedFeature>,
     nested: Vec<NestedRuntime>,
     active_animation: Option<u32>,
+    state_machine: Option<StateMachineInstance>,
+    events: Vec<Arc<Event>>, fired: Vec<FiredEvent>,
 }
 
 impl Runtime {
@@ -551,7 +597,7 @@ impl Runtime {
                 let asset_id = uint(object, property_ids::IMAGE_ASSETID)?;
                 if let Some(data) = image_assets.get(asset_id as usize)
                     .and_then(|data| data.clone()) {
-                    ComponentData::Image(ComponentImage { asset_id, data,
+                    ComponentData::Image(ComponentImage { asset_id, data, mesh: None,
                         origin: Point {
                             x: float(object, property_ids::IMAGE_ORIGINX)?,
                             y: float(object, property_ids::IMAGE_ORIGINY)?,
@@ -562,6 +608,10 @@ impl Runtime {
                         unsupported.push(UnsupportedFeature::Images);
                     }   ComponentData::None
                 }
+            } else if matches!(object.type_id.0, object_ids::BONE | object_ids::ROOT_BONE) {
+                ComponentData::Bone(ComponentBone {
+                    length: float(object, property_ids::BONE_LENGTH)?,
+                })
             } else if object.type_id.0 == object_ids::CLIPPING_SHAPE {
                 ComponentData::Clip(ComponentClip {
                     source: uint(object, property_ids::SOURCEID)?, shapes: Vec::new(),
@@ -596,11 +646,11 @@ impl Runtime {
                     comp_id: components[index].obj_idx + 1, parent_id })
             };  components[index].parent = Some(parent);
         }
-        // Mesh and nine-slice images need textured geometry, not a flat image quad.
+        link_bones(&mut components);
+        // Nine-slice images need stretched texture regions, not a flat image quad.
         let advanced_images: Vec<_> = components.iter().filter_map(|component| {
-            matches!(file.ocoll[component.obj_idx as usize].type_id.0,
-                object_ids::MESH | object_ids::N_SLICER)
-                    .then_some(component.parent).flatten()
+            (file.ocoll[component.obj_idx as usize].type_id.0 == object_ids::N_SLICER)
+                .then_some(component.parent).flatten()
         }).collect();
         for owner in advanced_images {
             if  components[owner as usize].image().is_some() {
@@ -637,25 +687,34 @@ impl Runtime {
         }
 
         let animations = build_animations(&file, context_start, context_end, &obj_comps)?;
+        let state_machines = build_state_machines(&file, context_start, context_end,
+            &obj_comps, animations.len() as u32)?.into_iter().map(Arc::new).collect();
+        let events = build_events(&file, &components)?;
         let constraint_dirty = if constraints.is_empty() { Vec::new()
         } else { vec![false; components.len()] };
         unsupported.sort();
         let mut runtime = Self { file, artboard_obj: context_start as u32, artboard_size,
             components, update_order: Vec::new(), gradients: Vec::new(), elapsed: 0.0,
-            constraint_dirty, constraints, unsupported, draw_groups: Vec::new(),
-            animations: Vec::new(), nested: Vec::new(), active_animation: None,
+            constraint_dirty, constraints, skins: Vec::new(), unsupported,
+            draw_groups: Vec::new(),
+            animations: Vec::new(), mix_base: Vec::new(), state_machines,
+            nested: Vec::new(), active_animation: None, state_machine: None,
+            events, fired: Vec::new(),
         };
-        // Construction order matters: world transforms feed gradients, then shape content feeds
-        // draw grouping and finally draw rules reorder those completed groups.
+        // Construction order matters: world transforms feed gradients and skins, then shape
+        // content feeds draw grouping and finally draw rules reorder those completed groups.
         runtime.validate_hierarchy()?;
         sort_constraints(&runtime.components, &mut runtime.constraints)?;
         runtime.update_world_state();
         runtime.apply_constraints();
-        let targets = runtime.build_shape_content()?;
+        let mut targets = runtime.build_shape_content()?;
+        runtime.build_meshes(&mut targets)?;
+        runtime.build_skins(&targets, &obj_comps)?;
+        runtime.deform_skins();
         runtime.gradients = runtime.components.iter().enumerate()
             .filter_map(|(index, component)|
                 component.gradient().is_some().then_some(index as u32)).collect();
-        runtime.animations = runtime.bind_animations(animations, &targets);
+        runtime.bind_animations(animations, &targets);
         runtime.build_nested(stack)?;
         runtime.advance_nested(0.0);
         runtime.build_draw_groups();
@@ -683,8 +742,8 @@ impl Runtime {
         if index as usize >= self.animations.len() {
             return Err(RuntimeError::AnimationNotFound(index))
         }
-        if let Some(active) = self.active_animation { self.reset_animation(active); }
-        self.active_animation = Some(index); self.elapsed = 0.0;
+        self.stop_playback();
+        self.active_animation = Some(index);
         self.apply_animation();
         self.advance_nested(0.0);
         Ok(())
@@ -695,14 +754,77 @@ impl Runtime {
         self.set_animation(index)
     }
 
+    pub fn state_machine_count(&self) -> u32 { self.state_machines.len() as u32 }
+    pub fn state_machine_name(&self, index: u32) -> Option<&[u8]> {
+        self.state_machines.get(index as usize).map(|machine| &machine.name[..])
+    }
+    /// The state machine the artboard is set to play by default, if any.
+    pub fn default_state_machine(&self) -> Option<u32> {
+        objects::Artboard::try_from(&self.file.ocoll[self.artboard_obj as usize]).ok()?
+            .default_state_machine_id().ok()
+            .filter(|&index| (index as usize) < self.state_machines.len())
+    }
+    /// Play a state machine instead of a single animation, settling it into its first state.
+    pub fn set_state_machine(&mut self, index: u32) -> Result<()> {
+        let mut machine = StateMachineInstance::new(self, index)?;
+        self.stop_playback();
+        machine.advance(self, 0.0);
+        self.state_machine = Some(machine);
+        self.advance_nested(0.0);
+        Ok(())
+    }
+    pub fn set_state_machine_by_name(&mut self, name: &[u8]) -> Result<()> {
+        let index = self.state_machines.iter().position(|machine| machine.name == name)
+            .ok_or(RuntimeError::StateMachineNameNotFound)? as u32;
+        self.set_state_machine(index)
+    }
+    pub fn state_machine(&self) -> Option<&StateMachineInstance> { self.state_machine.as_ref() }
+    pub fn state_machine_mut(&mut self) -> Option<&mut StateMachineInstance> {
+        self.state_machine.as_mut()
+    }
+
+    /// Pointer input in artboard space for the listeners of the active state machine;
+    /// returns whether any listener fired.
+    pub fn pointer_down(&mut self, x: f32, y: f32) -> bool {
+        self.pointer_event(PointerEvent::Down, Point { x, y })
+    }
+    pub fn pointer_up  (&mut self, x: f32, y: f32) -> bool {
+        self.pointer_event(PointerEvent::Up,   Point { x, y })
+    }
+    pub fn pointer_move(&mut self, x: f32, y: f32) -> bool {
+        self.pointer_event(PointerEvent::Move, Point { x, y })
+    }
+
+    fn pointer_event(&mut self, event: PointerEvent, point: Point) -> bool {
+        let Some(mut machine) = self.state_machine.take() else { return false };
+        let fired = machine.pointer_event(self, event, point);
+        self.state_machine = Some(machine);     fired
+    }
+
     pub fn advance(&mut self, delta_seconds: f32) -> bool {
         if delta_seconds <= 0.0 { return false }
-        if  self.active_animation.is_some() {
+        let mut playing = self.active_animation.is_some();
+        if let Some(active) = self.active_animation {
+            let from = self.elapsed;
             self.elapsed += delta_seconds;
             self.apply_animation();
+            self.report_callbacks(active, from, self.elapsed);
+        }
+        if let Some(mut machine) = self.state_machine.take() {
+            self.elapsed += delta_seconds;
+            playing |= machine.advance(self, delta_seconds);
+            self.state_machine = Some(machine);
         }
         let nested = self.advance_nested(delta_seconds);
-        self.active_animation.is_some() || nested
+        playing || nested
+    }
+
+    fn stop_playback(&mut self) {
+        if let Some(active) = self.active_animation.take() { self.reset_animation(active); }
+        // A state machine may have left any of the animations applied.
+        if self.state_machine.take().is_some() {
+            for index in 0..self.animation_count() { self.reset_animation(index); }
+        }   self.elapsed = 0.0;
     }
 
     fn validate_hierarchy(&mut self) -> Result<()> {
@@ -729,6 +851,8 @@ impl Runtime {
         update_world_state(&mut self.components, &self.update_order);
     }
 
+    fn deform_skins(&mut self) { deform_skins(&mut self.components, &self.skins) }
+
     pub(super) fn apply_constraints(&mut self) {
         apply_constraints(&mut self.components, &self.update_order, &self.constraints,
             &mut self.constraint_dirty);
@@ -769,7 +893,7 @@ impl Runtime {
             let Some(delta_seconds) = self.components[nested.host as usize]
                 .nested_host_mut().and_then(|host| host.elapsed(delta_seconds)) else {
                 continue
-            };
+            };  nested.runtime.begin_mix();
             for &component in &nested.animations {
                 let animation = self.components[component as usize]
                     .nested_animation_mut().unwrap();
@@ -796,12 +920,9 @@ fn collect_unsupported(objects: &[Object]) -> Vec<UnsupportedFeature> {
     let mut features = Vec::new();
     for object in objects {
         let feature = match object.type_id.0 {
-            object_ids::BONE | object_ids::ROOT_BONE | object_ids::SKIN |
-            object_ids::TENDON | object_ids::WEIGHT => UnsupportedFeature::BonesAndSkins,
             object_ids::I_K_CONSTRAINT | object_ids::FOLLOW_PATH_CONSTRAINT =>
                 UnsupportedFeature::AdvancedConstraints,
-            object_ids::STATE_MACHINE | object_ids::STATE_MACHINE_LAYER |
-            object_ids::ANIMATION_STATE => UnsupportedFeature::StateMachines,
+            object_ids::LISTENER_ALIGN_TARGET => UnsupportedFeature::StateMachines,
             object_ids::TEXT | object_ids::TEXT_VALUE_RUN | object_ids::TEXT_STYLE_PAINT |
             object_ids::FONT_ASSET => UnsupportedFeature::Text,
             _ => continue,
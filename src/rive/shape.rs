@@ -127,7 +127,7 @@ impl Runtime {
                     self.components[index].data =
                         super::ComponentData::Geometry(ComponentGeom::Points {
                         cached: Geometry::Path(build_path(&vertices, closed)),
-                        vertices, closed, dirty: false,
+                        vertices, closed, dirty: false, skinned: false,
                     });
                 }
                 object_ids::FILL => {
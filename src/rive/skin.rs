@@ -0,0 +1,185 @@
+
+//! Bones, skins and image meshes, with linear-blend deformation of skinned vertices.
+
+use std::{mem, sync::Arc};
+
+use super::{Affine, Component, ComponentData, ComponentGeom, ComponentImage, ComponentTarget,
+    Geometry, Point, Result, Runtime, RuntimeError, constraint::inverse, float,
+    object_ids, property_ids, replace_changed, uint,
+};
+use crate::rive::{decode::VarUInt, path::{VertexWeights, build_path, deform_point}};
+
+#[derive(Debug)] pub(super) struct ComponentBone { pub length: f32 }
+
+#[derive(Debug)] pub(super) struct ComponentMesh {
+    pub vertices: Vec<Point>, pub uvs: Arc<[Point]>, pub indices: Arc<[u16]>,
+    /// World-space vertices once a skin deforms the mesh.
+    pub deformed: Option<Vec<Point>>,
+}
+
+#[derive(Debug)] pub(super) struct Skin {
+    /// The skinned points path, or the image owning the skinned mesh.
+    target: u32,
+    /// World transform of the target at bind time.
+    bind: Affine,
+    /// Bone components with their inverse bind transforms, in tendon order.
+    tendons: Vec<(u32, Affine)>,
+    weights: Vec<VertexWeights>,
+}
+
+/// A bone sits at the tip of its parent bone, so its x follows the parent's length.
+pub(super) fn link_bones(components: &mut [Component]) {
+    for index in 0..components.len() {
+        if components[index].bone().is_none() { continue }
+        let Some(length) = components[index].parent.and_then(|parent|
+            components[parent as usize].bone()).map(|bone| bone.length) else { continue };
+        components[index].transform.x = length;
+    }
+}
+
+pub(super) fn set_bone_length(components: &mut [Component], component: u32, value: f32) -> bool {
+    let Some(bone) = components[component as usize].bone_mut() else { return false };
+    if !replace_changed(&mut bone.length, value) { return false }
+    for child in components.iter_mut()
+        .filter(|child| child.parent == Some(component) && child.bone().is_some()) {
+        child.transform.x = value;
+    }   true
+}
+
+pub(super) fn deform_skins(components: &mut [Component], skins: &[u32]) {
+    for &index in skins {
+        let ComponentData::Skin(skin) =
+            mem::take(&mut components[index as usize].data) else { continue };
+        let mut bones = Vec::with_capacity(skin.tendons.len() + 1);
+        bones.push(Affine::default());
+        bones.extend(skin.tendons.iter().map(|&(bone, inverse_bind)|
+            components[bone as usize].world.then(inverse_bind)));
+
+        match &mut components[skin.target as usize].data {
+            ComponentData::Geometry(ComponentGeom::Points {
+                cached, vertices, closed, dirty, .. }) => {
+                let deformed: Vec<_> = vertices.iter().zip(&skin.weights).map(|(vertex, weights)|
+                    vertex.deform(weights, skin.bind, &bones)).collect();
+                *cached = Geometry::Path(build_path(&deformed, *closed));
+                *dirty = false;
+            }
+            ComponentData::Image(ComponentImage { mesh: Some(mesh), .. }) =>
+                mesh.deformed = Some(mesh.vertices.iter().zip(&skin.weights)
+                    .map(|(&point, weights)|
+                        deform_point(point, weights.position, skin.bind, &bones)).collect()),
+            _ => {}
+        }   components[index as usize].data = ComponentData::Skin(skin);
+    }
+}
+
+impl Runtime {
+    /// Gather mesh vertices and triangles into the images that own them.
+    pub(super) fn build_meshes(&mut self, targets: &mut [ComponentTarget]) -> Result<()> {
+        for index in 0..self.components.len() {
+            let object = &self.file.ocoll[self.components[index].obj_idx as usize];
+            if object.type_id.0 != object_ids::MESH { continue }
+            let Some(image) = self.components[index].parent.filter(|&image|
+                self.components[image as usize].image().is_some()) else { continue };
+
+            let mut bytes = object.bytes(property_ids::TRIANGLEINDEXBYTES)?.unwrap_or_default();
+            let mut indices = Vec::new();
+            // Out-of-range indices saturate; backends skip triangles with missing vertices.
+            while !bytes.is_empty() {
+                indices.push(u16::try_from(VarUInt::read(&mut bytes)?.0).unwrap_or(u16::MAX));
+            }
+            let (mut vertices, mut uvs) = (Vec::new(), Vec::new());
+            for (child, component) in self.components.iter().enumerate() {
+                let object = &self.file.ocoll[component.obj_idx as usize];
+                if component.parent != Some(index as u32) ||
+                    object.type_id.0 != object_ids::MESH_VERTEX { continue }
+                targets[child] = ComponentTarget::MeshVertex {
+                    image, slot: vertices.len() as u32 };
+                vertices.push(Point { x: float(object, property_ids::VERTEX_X)?,
+                    y: float(object, property_ids::VERTEX_Y)? });
+                uvs.push(Point { x: float(object, property_ids::U)?,
+                    y: float(object, property_ids::V)? });
+            }
+            self.components[image as usize].image_mut().unwrap().mesh = Some(ComponentMesh {
+                vertices, uvs: uvs.into(), indices: indices.into(), deformed: None,
+            });
+        }   Ok(())
+    }
+
+    /// Bind skins to their tendon bones and the weights of the vertices they deform.
+    pub(super) fn build_skins(&mut self, targets: &[ComponentTarget],
+        obj_comps: &[Option<u32>]) -> Result<()> {
+        for index in 0..self.components.len() {
+            let component = &self.components[index];
+            let object = &self.file.ocoll[component.obj_idx as usize];
+            if object.type_id.0 != object_ids::SKIN { continue }
+            let owner = component.parent.filter(|&owner|
+                matches!(self.file.ocoll[self.components[owner as usize].obj_idx as usize]
+                    .type_id.0, object_ids::POINTS_PATH | object_ids::MESH))
+                .ok_or(RuntimeError::InvalidSkinOwner(component.obj_idx))?;
+            let (target, count) = match &self.components[owner as usize].data {
+                ComponentData::Geometry(ComponentGeom::Points { vertices, .. }) =>
+                    (owner, vertices.len()),
+                // The image of a mesh may be missing and already reported unsupported.
+                _ => match self.components[owner as usize].parent.and_then(|image|
+                    self.components[image as usize].image()?.mesh.as_ref()
+                        .map(|mesh| (image, mesh.vertices.len()))) {
+                    Some(target) => target, None => continue,
+                },
+            };
+            let bind = Affine {
+                xx: float(object, property_ids::SKIN_XX)?,
+                yx: float(object, property_ids::SKIN_YX)?,
+                xy: float(object, property_ids::SKIN_XY)?,
+                yy: float(object, property_ids::SKIN_YY)?,
+                tx: float(object, property_ids::SKIN_TX)?,
+                ty: float(object, property_ids::SKIN_TY)?,
+            };
+
+            let mut tendons = Vec::new();
+            for child in self.components.iter()
+                .filter(|child| child.parent == Some(index as u32)) {
+                let object = &self.file.ocoll[child.obj_idx as usize];
+                if object.type_id.0 != object_ids::TENDON { continue }
+                let bone_id = uint(object, property_ids::TENDON_BONEID)?;
+                let bone = (self.artboard_obj as usize).checked_add(bone_id as usize)
+                    .and_then(|bone_obj| obj_comps.get(bone_obj).copied().flatten())
+                    .filter(|&bone| self.components[bone as usize].bone().is_some())
+                    .ok_or(RuntimeError::InvalidTendonBone(bone_id))?;
+                let tendon = Affine {
+                    xx: float(object, property_ids::TENDON_XX)?,
+                    yx: float(object, property_ids::TENDON_YX)?,
+                    xy: float(object, property_ids::TENDON_XY)?,
+                    yy: float(object, property_ids::TENDON_YY)?,
+                    tx: float(object, property_ids::TENDON_TX)?,
+                    ty: float(object, property_ids::TENDON_TY)?,
+                };
+                // A degenerate bind pose has no inverse; the bone then applies as posed.
+                tendons.push((bone, inverse(tendon).unwrap_or_default()));
+            }
+
+            // Unweighted vertices only follow the bind transform.
+            let mut weights = vec![VertexWeights::default(); count];
+            for component in &self.components {
+                let object = &self.file.ocoll[component.obj_idx as usize];
+                let Some(value) = VertexWeights::from_object(object)? else { continue };
+                let slot = match component.parent.map(|vertex| targets[vertex as usize]) {
+                    Some(ComponentTarget::Vertex { path, slot }) if path == target => slot,
+                    Some(ComponentTarget::MeshVertex { image, slot }) if image == target => slot,
+                    _ => continue,
+                };
+                if let Some(weight) = weights.get_mut(slot as usize) { *weight = value }
+            }
+
+            match &mut self.components[target as usize].data {
+                ComponentData::Geometry(ComponentGeom::Points { skinned, .. }) =>
+                    *skinned = true,
+                ComponentData::Image(ComponentImage { mesh: Some(mesh), .. }) =>
+                    mesh.deformed = Some(Vec::new()),
+                _ => {}
+            }
+            self.components[index].data =
+                ComponentData::Skin(Skin { target, bind, tendons, weights });
+            self.skins.push(index as u32);
+        }   Ok(())
+    }
+}
@@ -0,0 +1,643 @@
+
+//! State-machine decoding and layered state/transition evaluation.
+
+use std::sync::Arc;
+
+use super::{Result, Runtime, RuntimeError, boolean, float, object_ids, property_ids, uint};
+use crate::rive::{animation::{Interpolation, interpolation}, decode::RiveFile,
+    display_list::Point};
+
+/// Bounds the chained state changes one layer may take within a single advance.
+const MAX_STATE_CHANGES: usize = 100;
+
+// StateTransitionFlags in rive-runtime.
+const DISABLED: u32 = 1 << 0;
+const DURATION_IS_PERCENTAGE: u32 = 1 << 1;
+const ENABLE_EXIT_TIME: u32 = 1 << 2;
+const EXIT_TIME_IS_PERCENTAGE: u32 = 1 << 3;
+const PAUSE_ON_EXIT: u32 = 1 << 4;
+
+/// Value of a state-machine input; a trigger carries whether it fired since the last advance.
+#[derive(Debug, Clone, Copy, PartialEq)] pub enum InputValue {
+    Bool(bool), Number(f32), Trigger(bool),
+}
+
+#[derive(Debug)] struct Input { name: Vec<u8>, value: InputValue }
+
+#[derive(Debug, Clone, Copy)] enum Condition {
+    Trigger { input: u32 },
+    Bool { input: u32, op: u32 },
+    Number { input: u32, op: u32, value: f32 },
+}
+
+impl Condition {
+    fn input(self) -> u32 { match self {
+        Self::Trigger { input } | Self::Bool { input, .. } | Self::Number { input, .. } => input,
+    } }
+
+    fn evaluate(self, inputs: &[InputValue]) -> bool {
+        match (self, inputs.get(self.input() as usize)) {
+            (Self::Trigger { .. }, Some(&InputValue::Trigger(fired))) => fired,
+            (Self::Bool { op, .. }, Some(&InputValue::Bool(value))) =>
+                match op { 0 => value, 1 => !value, _ => false },
+            (Self::Number { op, value: rhs, .. }, Some(&InputValue::Number(lhs))) =>
+                match op {
+                    0 => lhs == rhs, 1 => lhs != rhs, 2 => lhs <= rhs,
+                    3 => lhs >= rhs, 4 => lhs <  rhs, 5 => lhs >  rhs, _ => false,
+                },
+            _ => false,
+        }
+    }
+}
+
+/// A StateMachineFireEvent reporting the event component when its owner starts or ends.
+#[derive(Debug, Clone, Copy)] struct FireEvent { event: u32, at_end: bool }
+
+fn fire(runtime: &mut Runtime, events: &[FireEvent], at_end: bool, time: f32) {
+    for event in events.iter().filter(|event| event.at_end == at_end) {
+        runtime.report_event(event.event, time);
+    }
+}
+
+#[derive(Debug)] struct Transition {
+    target: u32, flags: u32, duration: u32, exit_time: u32,
+    interp: Interpolation, conditions: Vec<Condition>, events: Vec<FireEvent>,
+    exit_blend: u32, // blend animation timing the exit of a blend state
+}
+
+impl Transition {
+    fn flag(&self, flag: u32) -> bool { self.flags & flag != 0 }
+
+    /// Crossfade length in seconds; percentages are of the `from` animation duration.
+    fn mix_seconds(&self, from_duration: f32) -> f32 {
+        if self.flag(DURATION_IS_PERCENTAGE) {
+            self.duration as f32 / 100.0 * from_duration
+        } else { self.duration as f32 / 1000.0 }
+    }
+
+    fn exit_seconds(&self, from_duration: f32) -> f32 {
+        if self.flag(EXIT_TIME_IS_PERCENTAGE) {
+            self.exit_time as f32 / 100.0 * from_duration
+        } else { self.exit_time as f32 / 1000.0 }
+    }
+}
+
+/// An animation of a blend state: `value` is its position on a 1D blend state's input axis,
+/// or the fixed mix percentage of a direct blend animation that isn't driven by `input`.
+#[derive(Debug)] struct BlendAnimation { animation: u32, value: f32, input: Option<u32> }
+
+#[derive(Debug)] enum StateKind {
+    Entry, Any, Exit, Animation(u32),
+    Blend1D { input: u32, animations: Vec<BlendAnimation> },
+    BlendDirect(Vec<BlendAnimation>),
+}
+
+#[derive(Debug)] struct LayerState {
+    kind: StateKind, transitions: Vec<Transition>, events: Vec<FireEvent>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)] pub(super) enum PointerEvent { Down, Up, Move }
+
+#[derive(Debug, Clone, Copy)] enum ListenerAction {
+    Trigger { input: u32 },
+    Bool { input: u32, value: u32 }, // 0 sets false, 1 sets true, 2 toggles
+    Number { input: u32, value: f32 },
+    Event { event: u32 },
+}
+
+impl ListenerAction {
+    fn input(self) -> Option<u32> { match self {
+        Self::Trigger { input } | Self::Bool { input, .. } |
+        Self::Number { input, .. } => Some(input),
+        Self::Event { .. } => None,
+    } }
+
+    fn perform(self, inputs: &mut [InputValue], runtime: &mut Runtime) {
+        if let Self::Event { event } = self { return runtime.report_event(event, 0.0) }
+        match (self, self.input().and_then(|input| inputs.get_mut(input as usize))) {
+            (Self::Trigger { .. }, Some(InputValue::Trigger(fired))) => *fired = true,
+            (Self::Bool { value, .. }, Some(InputValue::Bool(current))) =>
+                *current = match value { 0 => false, 1 => true, _ => !*current },
+            (Self::Number { value, .. }, Some(InputValue::Number(current))) =>
+                *current = value,
+            _ => {}
+        }
+    }
+}
+
+/// Pointer listener on the shapes at or under `target` (a component index);
+/// `kind` is the ListenerType: 0 enter, 1 exit, 2 down, 3 up, 4 move.
+#[derive(Debug)] struct Listener { target: u32, kind: u32, actions: Vec<ListenerAction> }
+
+#[derive(Debug)] struct Layer { states: Vec<LayerState>, entry: Option<u32>, any: Option<u32> }
+
+#[derive(Debug)] pub(super) struct StateMachine {
+    pub name: Vec<u8>, inputs: Vec<Input>, layers: Vec<Layer>, listeners: Vec<Listener>,
+}
+
+pub(super) fn build_state_machines(file: &RiveFile, context_start: usize, context_end: usize,
+    obj_comps: &[Option<u32>], animation_count: u32) -> Result<Vec<StateMachine>> {
+    let mut machines: Vec<StateMachine> = Vec::new();
+    let mut on_transition = false; // whether fire events belong to a transition or a state
+    // Event and listener target IDs are artboard-relative object IDs, like component parents.
+    let component = |id: u32| context_start.checked_add(id as usize)
+        .and_then(|obj| obj_comps.get(obj).copied().flatten());
+
+    // Like keyed animation data, machine components follow their owner in a flat stream:
+    // inputs and layers after the machine, states after a layer, conditions after a transition.
+    for object in &file.ocoll[context_start..context_end] {
+        let type_id = object.type_id.0;
+        if  type_id == object_ids::STATE_MACHINE {
+            machines.push(StateMachine {
+                inputs: Vec::new(), layers: Vec::new(), listeners: Vec::new(),
+                name: object.bytes(property_ids::ANIMATION_NAME)?.unwrap_or_default().to_vec(),
+            }); continue
+        }
+        let Some(machine) = machines.last_mut() else { continue };
+        match type_id {
+            object_ids::STATE_MACHINE_NUMBER | object_ids::STATE_MACHINE_BOOL |
+            object_ids::STATE_MACHINE_TRIGGER => machine.inputs.push(Input {
+                name: object.bytes(property_ids::STATEMACHINECOMPONENT_NAME)?
+                    .unwrap_or_default().to_vec(),
+                value: match type_id {
+                    object_ids::STATE_MACHINE_NUMBER => InputValue::Number(
+                        float(object, property_ids::STATEMACHINENUMBER_VALUE)?),
+                    object_ids::STATE_MACHINE_BOOL => InputValue::Bool(
+                        boolean(object, property_ids::STATEMACHINEBOOL_VALUE)?),
+                    _ => InputValue::Trigger(false),
+                },
+            }),
+            object_ids::STATE_MACHINE_LAYER =>
+                machine.layers.push(Layer { states: Vec::new(), entry: None, any: None }),
+            object_ids::ENTRY_STATE | object_ids::ANY_STATE | object_ids::EXIT_STATE |
+            object_ids::ANIMATION_STATE | object_ids::BLEND_STATE_DIRECT |
+            object_ids::BLEND_STATE1_D => {
+                let Some(layer) = machine.layers.last_mut() else { continue };
+                let index = layer.states.len() as u32;
+                let kind = match type_id {
+                    object_ids::ENTRY_STATE => {
+                        layer.entry.get_or_insert(index);   StateKind::Entry
+                    }
+                    object_ids::ANY_STATE => {
+                        layer.any.get_or_insert(index);     StateKind::Any
+                    }
+                    object_ids::EXIT_STATE => StateKind::Exit,
+                    object_ids::ANIMATION_STATE => {
+                        let animation = uint(object, property_ids::ANIMATIONSTATE_ANIMATIONID)?;
+                        if animation_count <= animation {
+                            return Err(RuntimeError::AnimationNotFound(animation))
+                        }   StateKind::Animation(animation)
+                    }
+                    object_ids::BLEND_STATE1_D => StateKind::Blend1D { animations: Vec::new(),
+                        input: uint(object, property_ids::BLENDSTATE1D_INPUTID)? },
+                    _ => StateKind::BlendDirect(Vec::new()),
+                };
+                layer.states.push(LayerState { kind,
+                    transitions: Vec::new(), events: Vec::new() });
+                on_transition = false;
+            }
+            object_ids::BLEND_ANIMATION1_D | object_ids::BLEND_ANIMATION_DIRECT => {
+                let Some(state) = machine.layers.last_mut()
+                    .and_then(|layer| layer.states.last_mut()) else { continue };
+                let animation = uint(object, property_ids::BLENDANIMATION_ANIMATIONID)?;
+                if animation_count <= animation {
+                    return Err(RuntimeError::AnimationNotFound(animation))
+                }
+                match (&mut state.kind, type_id) {
+                    (StateKind::Blend1D { animations, .. }, object_ids::BLEND_ANIMATION1_D) =>
+                        animations.push(BlendAnimation { animation, input: None,
+                            value: float(object, property_ids::BLENDANIMATION1D_VALUE)? }),
+                    (StateKind::BlendDirect(animations),
+                        object_ids::BLEND_ANIMATION_DIRECT) => {
+                        // BlendSource: 0 mixes by the number input, 1 by the fixed mix value.
+                        let input = uint(object, property_ids::BLENDANIMATIONDIRECT_INPUTID)?;
+                        animations.push(BlendAnimation { animation,
+                            value: float(object, property_ids::MIXVALUE)?,
+                            input: (uint(object, property_ids::BLENDSOURCE)? == 0)
+                                .then_some(input),
+                        })
+                    }   _ => {}
+                }
+            }
+            object_ids::STATE_TRANSITION | object_ids::BLEND_STATE_TRANSITION => {
+                let Some(state) = machine.layers.last_mut()
+                    .and_then(|layer| layer.states.last_mut()) else { continue };
+                state.transitions.push(Transition {
+                    target: uint(object, property_ids::STATETOID)?,
+                    flags:  uint(object, property_ids::STATETRANSITION_FLAGS)?,
+                    duration:  uint(object, property_ids::STATETRANSITION_DURATION)?,
+                    exit_time: uint(object, property_ids::EXITTIME)?,
+                    interp: interpolation(file, context_start,
+                        uint(object, property_ids::STATETRANSITION_INTERPOLATIONTYPE)?,
+                        uint(object, property_ids::STATETRANSITION_INTERPOLATORID)?)?,
+                    conditions: Vec::new(), events: Vec::new(),
+                    exit_blend: if type_id == object_ids::BLEND_STATE_TRANSITION {
+                        uint(object, property_ids::EXITBLENDANIMATIONID)?
+                    } else { u32::MAX },
+                });
+                on_transition = true;
+            }
+            object_ids::TRANSITION_TRIGGER_CONDITION | object_ids::TRANSITION_BOOL_CONDITION |
+            object_ids::TRANSITION_NUMBER_CONDITION => {
+                let Some(transition) = machine.layers.last_mut()
+                    .and_then(|layer| layer.states.last_mut())
+                    .and_then(|state| state.transitions.last_mut()) else { continue };
+                let input = uint(object, property_ids::TRANSITIONCONDITION_INPUTID)?;
+                transition.conditions.push(match type_id {
+                    object_ids::TRANSITION_TRIGGER_CONDITION => Condition::Trigger { input },
+                    object_ids::TRANSITION_BOOL_CONDITION => Condition::Bool { input,
+                        op: uint(object, property_ids::OPVALUE)? },
+                    _ => Condition::Number { input, op: uint(object, property_ids::OPVALUE)?,
+                        value: float(object, property_ids::TRANSITIONNUMBERCONDITION_VALUE)? },
+                });
+            }
+            object_ids::STATE_MACHINE_FIRE_EVENT => {
+                let Some(state) = machine.layers.last_mut()
+                    .and_then(|layer| layer.states.last_mut()) else { continue };
+                let event_id = uint(object, property_ids::STATEMACHINEFIREEVENT_EVENTID)?;
+                let event = FireEvent {
+                    event: component(event_id).ok_or(RuntimeError::InvalidEvent(event_id))?,
+                    at_end: uint(object, property_ids::OCCURSVALUE)? == 1,
+                };
+                match state.transitions.last_mut().filter(|_| on_transition) {
+                    Some(transition) => transition.events.push(event),
+                    None => state.events.push(event),
+                }
+            }
+            object_ids::STATE_MACHINE_LISTENER => {
+                let target_id = uint(object, property_ids::STATEMACHINELISTENER_TARGETID)?;
+                let target = component(target_id)
+                    .ok_or(RuntimeError::InvalidListenerTarget(target_id))?;
+                machine.listeners.push(Listener { target, actions: Vec::new(),
+                    kind: uint(object, property_ids::LISTENERTYPEVALUE)? });
+            }
+            object_ids::LISTENER_TRIGGER_CHANGE | object_ids::LISTENER_BOOL_CHANGE |
+            object_ids::LISTENER_NUMBER_CHANGE => {
+                let Some(listener) = machine.listeners.last_mut() else { continue };
+                let input = uint(object, property_ids::LISTENERINPUTCHANGE_INPUTID)?;
+                listener.actions.push(match type_id {
+                    object_ids::LISTENER_TRIGGER_CHANGE => ListenerAction::Trigger { input },
+                    object_ids::LISTENER_BOOL_CHANGE => ListenerAction::Bool { input,
+                        value: uint(object, property_ids::LISTENERBOOLCHANGE_VALUE)? },
+                    _ => ListenerAction::Number { input,
+                        value: float(object, property_ids::LISTENERNUMBERCHANGE_VALUE)? },
+                });
+            }
+            object_ids::LISTENER_FIRE_EVENT => {
+                let Some(listener) = machine.listeners.last_mut() else { continue };
+                let event_id = uint(object, property_ids::LISTENERFIREEVENT_EVENTID)?;
+                listener.actions.push(ListenerAction::Event {
+                    event: component(event_id).ok_or(RuntimeError::InvalidEvent(event_id))?,
+                });
+            }   _ => {}
+        }
+    }
+
+    // State and input references are machine-relative; check them once at load time.
+    // A missing (u32::MAX) blend input is allowed and reads as zero, like in rive-runtime.
+    let valid_input = |machine: &StateMachine, input: u32|
+        input == u32::MAX || (input as usize) < machine.inputs.len();
+    if let Some(input) = machines.iter().find_map(|machine| machine.listeners.iter()
+        .flat_map(|listener| &listener.actions).filter_map(|action| action.input())
+        .find(|&input| machine.inputs.len() <= input as usize)) {
+        return Err(RuntimeError::InvalidInput(input))
+    }
+    for machine in &machines { for layer in &machine.layers {
+        for state in &layer.states {
+            let input = match &state.kind {
+                StateKind::Blend1D { input, .. } => Some(*input),
+                StateKind::BlendDirect(animations) => animations.iter()
+                    .filter_map(|blend| blend.input).find(|&input| !valid_input(machine, input)),
+                _ => None,
+            };
+            if let Some(input) = input.filter(|&input| !valid_input(machine, input)) {
+                return Err(RuntimeError::InvalidInput(input))
+            }
+        }
+        for transition in layer.states.iter().flat_map(|state| &state.transitions) {
+            if layer.states.len() <= transition.target as usize {
+                return Err(RuntimeError::InvalidState(transition.target))
+            }
+            if let Some(condition) = transition.conditions.iter()
+                .find(|condition| machine.inputs.len() <= condition.input() as usize) {
+                return Err(RuntimeError::InvalidInput(condition.input()))
+            }
+        }
+    } } Ok(machines)
+}
+
+/// Whether a transition may be taken, and since when into the advance of its source state.
+#[derive(Debug, Clone, Copy, PartialEq)] enum Allowed { Yes(f32), No, WaitingForExit }
+
+#[derive(Debug, Clone, Copy)] struct StateInstance { state: u32, seconds: f32, last: f32 }
+
+impl StateInstance {
+    fn new(state: u32) -> Self { Self { state, seconds: 0.0, last: 0.0 } }
+    fn advance(&mut self, delta: f32) { self.last = self.seconds; self.seconds += delta }
+}
+
+#[derive(Debug, Clone)] struct LayerInstance {
+    current: Option<StateInstance>, from: Option<StateInstance>,
+    transition: Option<(u32, u32)>, // (source state, transition index) of the last change
+    mix: f32, mix_from: f32, hold_from: bool, waiting_for_exit: bool,
+}
+
+impl LayerInstance {
+    fn new(layer: &Layer) -> Self {
+        Self { current: layer.entry.map(StateInstance::new), from: None, transition: None,
+            mix: 1.0, mix_from: 1.0, hold_from: false, waiting_for_exit: false }
+    }
+
+    fn advance(&mut self, layer: &Layer, inputs: &[InputValue],
+        runtime: &mut Runtime, delta: f32) -> bool {
+        if let Some(current) = &mut self.current {
+            current.advance(delta);     report_callbacks(layer, runtime, *current);
+        }
+        self.update_mix(layer, runtime, delta);
+        if self.mix < 1.0 && !self.hold_from {
+            if let Some(from) = &mut self.from {
+                from.advance(delta);    report_callbacks(layer, runtime, *from);
+            }
+        }
+
+        let (mut changed, mut time) = (false, 0.0);
+        for iteration in 0..MAX_STATE_CHANGES {
+            // Triggers are consumed by the first change, never by the chained ones.
+            let Some(at) = self.update_state(layer, inputs, runtime,
+                0 < iteration, time) else { break };
+            changed = true;     time = at;
+        }
+
+        self.apply(layer, inputs, runtime);
+        changed || self.mix < 1.0 || self.waiting_for_exit ||
+            self.current.is_some_and(|current| keep_going(layer, runtime, current))
+    }
+
+    fn transition<'a>(&self, layer: &'a Layer) -> Option<&'a Transition> {
+        self.transition.map(|(state, index)|
+            &layer.states[state as usize].transitions[index as usize])
+    }
+
+    fn update_mix(&mut self, layer: &Layer, runtime: &mut Runtime, delta: f32) {
+        let (Some(transition), Some(from)) = (self.transition(layer), self.from) else {
+            self.mix = 1.0; return
+        };
+        let from_duration = timing(layer, runtime, from.state, transition)
+            .map_or(0.0, |timing| timing.0);
+        let seconds = transition.mix_seconds(from_duration);
+        let (mixing, time) = (self.mix < 1.0, ((1.0 - self.mix) * seconds).clamp(0.0, delta));
+        self.mix = if 0.0 < seconds { (self.mix + delta / seconds).min(1.0) } else { 1.0 };
+        if mixing && 1.0 <= self.mix { fire(runtime, &transition.events, true, time) }
+    }
+
+    /// Change the state no earlier than `time` into the advance, returning when it changed.
+    fn update_state(&mut self, layer: &Layer, inputs: &[InputValue],
+        runtime: &mut Runtime, ignore_triggers: bool, time: f32) -> Option<f32> {
+        // An ongoing crossfade must finish before the layer may change state again.
+        if self.from.is_some() && self.mix < 1.0 &&
+            self.transition(layer).is_some_and(|transition| transition.duration != 0) {
+            return None
+        }   self.waiting_for_exit = false;
+        layer.any.and_then(|any|
+            self.try_change_state(layer, inputs, runtime, any, ignore_triggers, time))
+        .or_else(|| self.current.and_then(|current| self.try_change_state(layer, inputs,
+            runtime, current.state, ignore_triggers, time)))
+    }
+
+    /// Take the first allowed transition out of `source`, reporting the fire events of the
+    /// left state, the transition and the entered state at the offset into the advance
+    /// it became allowed, no earlier than `time`, which is returned.
+    fn try_change_state(&mut self, layer: &Layer, inputs: &[InputValue],
+        runtime: &mut Runtime, source: u32, ignore_triggers: bool, time: f32) -> Option<f32> {
+        // Exit times only apply to transitions leaving the current (animated) state.
+        let exit_from = self.current.filter(|current| current.state == source);
+        for (index, transition) in layer.states[source as usize].transitions.iter().enumerate() {
+            let time = match allowed(transition, layer, runtime,
+                exit_from, inputs, ignore_triggers) {
+                Allowed::Yes(at) => time.max(at),
+                Allowed::WaitingForExit => { self.waiting_for_exit = true; continue }
+                Allowed::No => continue,
+            };
+            if self.current.is_some_and(|current| current.state == transition.target) {
+                continue
+            }
+
+            self.from = self.current.replace(StateInstance::new(transition.target));
+            self.transition = Some((source, index as u32));
+            self.hold_from = false;
+            if transition.flag(ENABLE_EXIT_TIME) && transition.flag(PAUSE_ON_EXIT) {
+                if let Some(from) = &mut self.from {
+                    if let Some((duration, speed, _)) =
+                        timing(layer, runtime, from.state, transition)
+                        .filter(|timing| timing.1 != 0.0) {
+                        from.seconds = transition.exit_seconds(duration) / speed;
+                        self.hold_from = true;
+                    }
+                }
+            }
+            self.mix_from = self.mix;
+            self.mix = if transition.duration == 0 { 1.0 } else { 0.0 };
+
+            if let Some(from) = self.from {
+                fire(runtime, &layer.states[from.state as usize].events, true, time);
+            }
+            fire(runtime, &transition.events, false, time);
+            fire(runtime, &layer.states[transition.target as usize].events, false, time);
+            if transition.duration == 0 { fire(runtime, &transition.events, true, time) }
+            return Some(time)
+        }   None
+    }
+
+    fn apply(&self, layer: &Layer, inputs: &[InputValue], runtime: &mut Runtime) {
+        if let Some(from) = self.from.filter(|_| self.mix < 1.0) {
+            apply_state(layer, inputs, runtime, from, self.mix_from);
+        }
+        if let Some(current) = self.current {
+            let mix = self.transition(layer)
+                .map_or(self.mix, |transition| transition.interp.ease(self.mix));
+            apply_state(layer, inputs, runtime, current, mix);
+        }
+    }
+}
+
+/// Duration in seconds, speed and loop mode of an animation.
+fn animation_timing(runtime: &Runtime, index: u32) -> Option<(f32, f32, u32)> {
+    runtime.animations.get(index as usize).map(|animation|
+        (animation.duration as f32 / animation.fps.max(1) as f32,
+            animation.speed, animation.loop_mode))
+}
+
+/// Timing of the animation a transition measures its exit and percentage durations against:
+/// the one played by an animation state, or the exit blend animation of a blend state.
+fn timing(layer: &Layer, runtime: &Runtime, state: u32,
+    transition: &Transition) -> Option<(f32, f32, u32)> {
+    let index = match &layer.states[state as usize].kind {
+        StateKind::Animation(index) => *index,
+        StateKind::Blend1D { animations, .. } | StateKind::BlendDirect(animations) =>
+            animations.get(transition.exit_blend as usize)?.animation,
+        _ => return None,
+    };  animation_timing(runtime, index)
+}
+
+fn report_callbacks(layer: &Layer, runtime: &mut Runtime, state: StateInstance) {
+    match &layer.states[state.state as usize].kind {
+        StateKind::Animation(index) =>
+            runtime.report_callbacks(*index, state.last, state.seconds),
+        StateKind::Blend1D { animations, .. } | StateKind::BlendDirect(animations) =>
+            for blend in animations {
+                runtime.report_callbacks(blend.animation, state.last, state.seconds);
+            },
+        _ => {}
+    }
+}
+
+fn keep_going(layer: &Layer, runtime: &Runtime, state: StateInstance) -> bool {
+    let playing = |index| animation_timing(runtime, index).is_some_and(
+        |(duration, speed, loop_mode)| loop_mode != 0 || state.seconds * speed < duration);
+    match &layer.states[state.state as usize].kind {
+        StateKind::Animation(index) => playing(*index),
+        StateKind::Blend1D { animations, .. } | StateKind::BlendDirect(animations) =>
+            animations.iter().any(|blend| playing(blend.animation)),
+        _ => false,
+    }
+}
+
+fn allowed(transition: &Transition, layer: &Layer, runtime: &Runtime,
+    exit_from: Option<StateInstance>, inputs: &[InputValue], ignore_triggers: bool) -> Allowed {
+    if transition.flag(DISABLED) { return Allowed::No }
+    if transition.conditions.iter().any(|condition| !condition.evaluate(inputs) ||
+        ignore_triggers && matches!(condition, Condition::Trigger { .. })) {
+        return Allowed::No
+    }
+    if !transition.flag(ENABLE_EXIT_TIME) { return Allowed::Yes(0.0) }
+    let Some((from, (duration, speed, _))) = exit_from.and_then(|from|
+        timing(layer, runtime, from.state, transition).map(|timing| (from, timing))) else {
+        return Allowed::Yes(0.0)
+    };
+
+    // An exit time within one loop applies to whichever loop the animation is in.
+    let mut exit = transition.exit_seconds(duration);
+    if 0.0 < duration && exit <= duration {
+        exit += (from.last * speed / duration).floor() * duration;
+    }
+    if from.seconds * speed < exit { return Allowed::WaitingForExit }
+    Allowed::Yes(if speed == 0.0 { 0.0 } else {
+        (exit / speed - from.last).clamp(0.0, (from.seconds - from.last).max(0.0))
+    })
+}
+
+fn number(inputs: &[InputValue], input: u32) -> f32 {
+    match inputs.get(input as usize) { Some(&InputValue::Number(value)) => value, _ => 0.0 }
+}
+
+fn apply_state(layer: &Layer, inputs: &[InputValue], runtime: &mut Runtime,
+    state: StateInstance, mix: f32) {
+    let mut apply = |animation, weight: f32| if 0.0 < weight {
+        runtime.apply_animation_sample(animation, state.seconds, mix * weight);
+    };
+    match &layer.states[state.state as usize].kind {
+        StateKind::Animation(animation) => {
+            runtime.apply_animation_sample(*animation, state.seconds, mix);
+        }
+        StateKind::Blend1D { input, animations } => {
+            // As in rive-runtime, the animations at the nearest positions below and at/above
+            // the input value share the weight; outside the axis the end animation gets it all.
+            let value = number(inputs, *input);
+            let to = animations.iter().map(|blend| blend.value)
+                .filter(|&position| value <= position).reduce(f32::min);
+            let from = animations.iter().map(|blend| blend.value)
+                .filter(|&position| position < value).reduce(f32::max);
+            let to_mix = match (from, to) {
+                (Some(from), Some(to)) => (value - from) / (to - from), _ => 1.0,
+            };
+            for blend in animations {
+                apply(blend.animation, if Some(blend.value) == to { to_mix }
+                    else if Some(blend.value) == from { 1.0 - to_mix } else { 0.0 });
+            }
+        }
+        StateKind::BlendDirect(animations) => for blend in animations {
+            let value = blend.input.map_or(blend.value, |input| number(inputs, input));
+            apply(blend.animation, (value / 100.0).clamp(0.0, 1.0));
+        },  _ => {}
+    }
+}
+
+/// Playback state of one state machine: its input values and the active state of every layer.
+///
+/// Every [`advance`](Self::advance) evaluates the layers in order, each crossfading its
+/// transition source into the current state and mixing on top of the layers before it.
+#[derive(Debug, Clone)] pub struct StateMachineInstance {
+    machine: Arc<StateMachine>, inputs: Vec<InputValue>, layers: Vec<LayerInstance>,
+    hovered: Vec<bool>, // whether each listener's target was under the last pointer
+}
+
+impl StateMachineInstance {
+    pub fn new(runtime: &Runtime, index: u32) -> Result<Self> {
+        let machine = runtime.state_machines.get(index as usize).cloned()
+            .ok_or(RuntimeError::StateMachineNotFound(index))?;
+        Ok(Self { inputs: machine.inputs.iter().map(|input| input.value).collect(),
+            layers: machine.layers.iter().map(LayerInstance::new).collect(),
+            hovered: vec![false; machine.listeners.len()], machine })
+    }
+
+    pub fn name(&self) -> &[u8] { &self.machine.name }
+    pub fn input_count(&self) -> u32 { self.inputs.len() as u32 }
+    pub fn input(&self, index: u32) -> Option<(&[u8], InputValue)> {
+        self.machine.inputs.get(index as usize)
+            .map(|input| (&input.name[..], self.inputs[index as usize]))
+    }
+
+    pub fn set_bool(&mut self, name: &[u8], value: bool) -> Result<()> {
+        let Some(InputValue::Bool(target)) = self.input_mut(name) else {
+            return Err(RuntimeError::InputNotFound)
+        };  *target = value;    Ok(())
+    }
+    pub fn set_number(&mut self, name: &[u8], value: f32) -> Result<()> {
+        let Some(InputValue::Number(target)) = self.input_mut(name) else {
+            return Err(RuntimeError::InputNotFound)
+        };  *target = value;    Ok(())
+    }
+    pub fn fire_trigger(&mut self, name: &[u8]) -> Result<()> {
+        let Some(InputValue::Trigger(fired)) = self.input_mut(name) else {
+            return Err(RuntimeError::InputNotFound)
+        };  *fired = true;      Ok(())
+    }
+
+    fn input_mut(&mut self, name: &[u8]) -> Option<&mut InputValue> {
+        let index = self.machine.inputs.iter().position(|input| input.name == name)?;
+        self.inputs.get_mut(index)
+    }
+
+    /// Hit-test the listener targets at artboard-space `point`, performing the actions of
+    /// every listener the event fires; input changes take effect on the next advance.
+    pub(super) fn pointer_event(&mut self, runtime: &mut Runtime,
+        event: PointerEvent, point: Point) -> bool {
+        let mut fired = false;
+        for (listener, hovered) in self.machine.listeners.iter().zip(&mut self.hovered) {
+            let hit = runtime.hit_test(listener.target, point);
+            let fire = match listener.kind {
+                0 => hit && !*hovered, 1 => !hit && *hovered,
+                2 => hit && event == PointerEvent::Down,
+                3 => hit && event == PointerEvent::Up,
+                4 => hit && event == PointerEvent::Move, _ => false,
+            };  *hovered = hit;
+            if !fire { continue }
+            for action in &listener.actions { action.perform(&mut self.inputs, runtime) }
+            fired = true;
+        }   fired
+    }
+
+    /// Advance all layers and apply their animations to `runtime`; returns whether the
+    /// machine changed state or still has something to play, mix or wait for.
+    pub fn advance(&mut self, runtime: &mut Runtime, delta_seconds: f32) -> bool {
+        runtime.begin_mix();
+        let mut playing = false;
+        for (instance, layer) in self.layers.iter_mut().zip(&self.machine.layers) {
+            playing |= instance.advance(layer, &self.inputs, runtime, delta_seconds);
+        }
+        // A trigger lives for exactly the one advance that observes it.
+        for input in &mut self.inputs {
+            if let InputValue::Trigger(fired) = input { *fired = false }
+        }   playing
+    }
+}
@@ -4,14 +4,17 @@
 use super::{Brush, ColorTarget, Component, ComponentPaint, ComponentTarget, EffectTarget,
     Paint, Runtime, TrackValue, core_boolean_default, core_color_default, core_float_default,
     core_is_transform_component, core_varuint_default, float, property_ids,
-    apply_constraints, shape::{set_effect, set_paint}, update_world_state,
+    apply_constraints, deform_skins, shape::{set_effect, set_paint},
+    skin::set_bone_length, update_world_state,
 };
 use crate::rive::animation::{
-    Animation, LinearAnimation, RawAnimation, evaluate_track, mix_value
+    Animation, RawAnimation, evaluate_track, mix_value
 };
 
 #[derive(Debug, Clone, Copy)] pub(super) enum TrackTarget {
     Vertex { component: u32, path: u32, slot: u32, prop_id: u32 },
+    MeshVertex { image: u32, slot: u32, prop_id: u32 },
+    BoneLength { component: u32 },
     Transform { component: u32, prop_id: u32 },
     Geometry { component: u32, prop_id: u32 },
     Gradient { component: u32, prop_id: u32 },
@@ -30,14 +33,17 @@ use crate::rive::animation::{
 }
 
 #[derive(Debug, Clone, Copy)] pub(in crate::rive) struct TrackBinding {
-    target: TrackTarget, default: TrackValue,
+    // Tracks of different animations keying the same property share one mix slot.
+    target: TrackTarget, default: TrackValue, slot: u32,
 }
 
 impl Runtime {
-    pub(super) fn bind_animations(&self, animations: Vec<RawAnimation>,
-        bindings: &[ComponentTarget]) -> Vec<LinearAnimation> {
-        animations.into_iter().map(|animation| {
-            let Animation { name, duration, fps, speed, loop_mode, tracks, .. } = animation;
+    pub(super) fn bind_animations(&mut self, animations: Vec<RawAnimation>,
+        bindings: &[ComponentTarget]) {
+        let mut slots = Vec::new();
+        self.animations = animations.into_iter().map(|animation| {
+            let Animation { name, duration, fps, speed, loop_mode,
+                tracks, callbacks, .. } = animation;
             let (mut geometries, mut gradients) = (Vec::new(), Vec::new());
             let tracks = tracks.into_iter().filter_map(|track| {
                 let component = track.component;
@@ -66,19 +72,29 @@ impl Runtime {
                         push_unique(&mut geometries, component),
                     TrackTarget::Vertex { path, .. } =>
                         push_unique(&mut geometries, path),
+                    TrackTarget::MeshVertex { image, .. } =>
+                        push_unique(&mut geometries, image),
                     TrackTarget::Gradient { component, .. } |
                     TrackTarget::GradientStopPos { component, .. } |
                     TrackTarget::GradientStopColor { component, .. } =>
                         push_unique(&mut gradients, component),
                     _ => {}
                 }
-                Some(track.bind(TrackBinding { target, default }))
+                let key = (component, track.prop_id);
+                let slot = slots.iter().position(|&slot| slot == key)
+                    .unwrap_or_else(|| { slots.push(key); slots.len() - 1 }) as u32;
+                Some(track.bind(TrackBinding { target, default, slot }))
             }).collect();
             Animation { name, duration, fps, speed, loop_mode,
-                tracks, geometries, gradients }
-        }).collect()
+                tracks, geometries, gradients, callbacks }
+        }).collect();
+        self.mix_base = vec![None; slots.len()];
     }
 
+    /// Start a new mixing pass: partial mixes blend from the property defaults again
+    /// instead of from the values applied by a previous pass.
+    pub(super) fn begin_mix(&mut self) { self.mix_base.fill(None) }
+
     pub(super) fn apply_animation(&mut self) {
         let Some(index) = self.active_animation else { return };
         let animation = &self.animations[index as usize];
@@ -128,9 +144,11 @@ impl Runtime {
         let animation = &self.animations[index as usize];
         let mut transform_dirty = false;
         for track in &animation.tracks {
+            let TrackBinding { default, slot, .. } = track.binding;
+            let base = &mut self.mix_base[slot as usize];
             let value = frame.and_then(|frame| evaluate_track(track, frame))
-                .map_or(track.binding.default,
-                    |value| mix_value(track.binding.default, value, mix));
+                .map_or(default, |value| mix_value(base.unwrap_or(default), value, mix));
+            *base = frame.map(|_| value);
             transform_dirty |= apply_track(&mut self.components,
                 track.binding.target, value);
         }
@@ -140,6 +158,10 @@ impl Runtime {
             apply_constraints(&mut self.components, &self.update_order, &self.constraints,
                 &mut self.constraint_dirty);
         }
+        // Skins follow the final bone transforms and re-deform any refreshed vertices.
+        if transform_dirty || !animation.geometries.is_empty() {
+            deform_skins(&mut self.components, &self.skins);
+        }
         let gradients = if transform_dirty { &self.gradients } else { &animation.gradients };
         sync_gradients(&mut self.components, gradients);
     }
@@ -151,6 +173,7 @@ fn push_unique(values: &mut Vec<u32>, value: u32) {
 
 fn transform_prop(prop_id: u32) -> bool {
     matches!(prop_id, property_ids::NODE_X | property_ids::NODE_Y |
+        property_ids::ROOTBONE_X | property_ids::ROOTBONE_Y |
         property_ids::TRANSFORMCOMPONENT_ROTATION |
         property_ids::TRANSFORMCOMPONENT_SCALEX |
         property_ids::TRANSFORMCOMPONENT_SCALEY |
@@ -177,9 +200,13 @@ fn resolve_target(components: &[Component], bindings: &[ComponentTarget],
             return Some(TrackTarget::Effect { target, prop_id }),
         (ComponentTarget::Vertex { path, slot }, TrackValue::Scalar(_)) =>
             return Some(TrackTarget::Vertex { component, path, slot, prop_id }),
+        (ComponentTarget::MeshVertex { image, slot }, TrackValue::Scalar(_)) =>
+            return Some(TrackTarget::MeshVertex { image, slot, prop_id }),
         _ => {}
     }
     match value {
+        TrackValue::Scalar(_) if prop_id == property_ids::BONE_LENGTH &&
+            state.bone().is_some() => Some(TrackTarget::BoneLength { component }),
         TrackValue::Scalar(_) | TrackValue::Bool(_) | TrackValue::Uint(_)
             if state.constraint().is_some() =>
             Some(TrackTarget::Constraint { component, prop_id }),
@@ -226,6 +253,17 @@ fn apply_track(components: &mut [Component], target: TrackTarget, value: TrackVa
                 }
             }
         }
+        (TrackTarget::MeshVertex { image, slot, prop_id }, TrackValue::Scalar(value)) => {
+            let Some(point) = components[image as usize].image_mut()
+                .and_then(|image| image.mesh.as_mut()?.vertices.get_mut(slot as usize))
+                else { return false };
+            match prop_id {
+                property_ids::VERTEX_X => point.x = value,
+                property_ids::VERTEX_Y => point.y = value, _ => {}
+            }
+        }
+        (TrackTarget::BoneLength { component }, TrackValue::Scalar(value)) =>
+            return set_bone_length(components, component, value),
         (TrackTarget::Gradient { component, prop_id }, TrackValue::Scalar(value)) => {
             if let Some(gradient) = components[component as usize].gradient_mut() {
                 gradient.set(prop_id, value);